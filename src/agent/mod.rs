@@ -0,0 +1,251 @@
+//! Агент hiho: фоновый процесс, который держит расшифрованное хранилище
+//! в памяти, чтобы команды CLI не спрашивали мастер-пароль и не запускали
+//! Argon2 при каждом вызове (как ssh-agent или агент rbw).
+//!
+//! Агент слушает Unix-сокет в каталоге с правами 0700 и принимает
+//! подключения только от процессов того же пользователя. Запросы и ответы —
+//! по одной строке JSON. Память процесса закреплена через mlockall, дампы
+//! памяти отключены. По истечении таймаута автоблокировки, при переходе
+//! системы в сон и при блокировке экрана агент забывает хранилище.
+//!
+//! Рядом с сокетом агента открывается сокет ssh-agent (`ssh-agent.sock`)
+//! с SSH-ключами из открытого хранилища — см. модуль `ssh`.
+
+pub mod server;
+
+use crate::entry::Entry;
+use crate::vault::UnlockError;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use zeroize::Zeroize;
+
+/// Переменная окружения с путём к сокету агента
+pub const SOCKET_ENV: &str = "HIHO_AGENT_SOCK";
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Request {
+    Status,
+    /// Открыть хранилище; `vault` — абсолютный путь
+    Unlock { vault: PathBuf, password: String },
+    /// Открыть хранилище запомненным ключом (base64), без мастер-пароля
+    UnlockKey { vault: PathBuf, key: String },
+    /// Записи открытого хранилища
+    Entries { vault: PathBuf },
+    /// Заменить записи и сохранить хранилище в файл
+    Store { vault: PathBuf, entries: Vec<Entry> },
+    /// Забыть хранилище
+    Lock,
+    Stop,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentErrorCode {
+    /// Хранилище не открыто
+    Locked,
+    /// Неверный мастер-пароль
+    Auth,
+    /// Открыто другое хранилище
+    WrongVault,
+    BadRequest,
+    Io,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Response {
+    Ok,
+    Status(AgentStatus),
+    Entries { entries: Vec<Entry> },
+    Error { code: AgentErrorCode, message: String },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AgentStatus {
+    pub pid: u32,
+    /// Открытое хранилище; `None` — агент заблокирован
+    pub vault: Option<PathBuf>,
+    /// Таймаут автоблокировки в секундах
+    pub idle_timeout_secs: Option<u64>,
+    /// Сколько секунд осталось до автоблокировки
+    pub locks_in_secs: Option<u64>,
+    /// Сокет ssh-agent для `SSH_AUTH_SOCK`
+    #[serde(default)]
+    pub ssh_socket: Option<PathBuf>,
+}
+
+/// Ошибка, которую вернул агент.
+#[derive(Debug)]
+pub struct AgentError {
+    pub code: AgentErrorCode,
+    pub message: String,
+}
+
+impl fmt::Display for AgentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Агент: {}", self.message)
+    }
+}
+
+impl Error for AgentError {}
+
+/// Путь к сокету: `$HIHO_AGENT_SOCK`, иначе `$XDG_RUNTIME_DIR/hiho/agent.sock`,
+/// иначе `/tmp/hiho-<uid>/agent.sock`.
+pub fn socket_path() -> PathBuf {
+    if let Some(path) = std::env::var_os(SOCKET_ENV) {
+        return PathBuf::from(path);
+    }
+    let directory = match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(runtime) => PathBuf::from(runtime).join("hiho"),
+        None => std::env::temp_dir().join(format!("hiho-{}", nix::unistd::getuid())),
+    };
+    directory.join("agent.sock")
+}
+
+/// Сокет ssh-agent: `ssh-agent.sock` в каталоге сокета агента.
+pub fn ssh_socket_path(agent_socket: &Path) -> PathBuf {
+    agent_socket.with_file_name("ssh-agent.sock")
+}
+
+/// UID процесса на другом конце сокета.
+pub(crate) fn peer_uid(stream: &UnixStream) -> io::Result<u32> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        use nix::sys::socket::{getsockopt, sockopt::PeerCredentials};
+        let credentials = getsockopt(stream, PeerCredentials).map_err(io::Error::from)?;
+        Ok(credentials.uid())
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    {
+        let (uid, _) = nix::unistd::getpeereid(stream).map_err(io::Error::from)?;
+        Ok(uid.as_raw())
+    }
+}
+
+pub struct AgentClient {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+}
+
+impl AgentClient {
+    /// Подключается к запущенному агенту; `None`, если агента нет.
+    pub fn connect() -> Option<Self> {
+        Self::connect_to(&socket_path()).ok()
+    }
+
+    pub fn connect_to(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let stream = UnixStream::connect(path)?;
+        // Пароль отправляем только агенту своего пользователя
+        if peer_uid(&stream)? != nix::unistd::getuid().as_raw() {
+            return Err(format!("Сокет {} принадлежит другому пользователю", path.display()).into());
+        }
+        stream.set_read_timeout(Some(Duration::from_secs(120)))?;
+        Ok(AgentClient {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+        })
+    }
+
+    pub fn call(&mut self, request: &Request) -> Result<Response, Box<dyn Error>> {
+        let mut line = serde_json::to_vec(request)?;
+        line.push(b'\n');
+        self.writer.write_all(&line)?;
+        // В строке запроса может быть мастер-пароль или записи
+        line.zeroize();
+
+        let mut answer = String::new();
+        if self.reader.read_line(&mut answer)? == 0 {
+            return Err("Агент закрыл соединение".into());
+        }
+        Ok(serde_json::from_str(&answer)?)
+    }
+
+    pub fn status(&mut self) -> Result<AgentStatus, Box<dyn Error>> {
+        match self.call(&Request::Status)? {
+            Response::Status(status) => Ok(status),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    pub fn unlock(&mut self, vault: &Path, password: &str) -> Result<(), Box<dyn Error>> {
+        let request = Request::Unlock {
+            vault: vault.to_path_buf(),
+            password: password.to_string(),
+        };
+        let response = self.call(&request);
+        if let Request::Unlock { mut password, .. } = request {
+            password.zeroize();
+        }
+        match response? {
+            Response::Ok => Ok(()),
+            Response::Error { code: AgentErrorCode::Auth, .. } => Err(Box::new(UnlockError)),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    /// Открывает хранилище ключом из `derive_vault_key_for` (см. `remember`).
+    pub fn unlock_with_key(&mut self, vault: &Path, key: &[u8; 32]) -> Result<(), Box<dyn Error>> {
+        let request = Request::UnlockKey {
+            vault: vault.to_path_buf(),
+            key: BASE64.encode(key),
+        };
+        let response = self.call(&request);
+        if let Request::UnlockKey { mut key, .. } = request {
+            key.zeroize();
+        }
+        match response? {
+            Response::Ok => Ok(()),
+            Response::Error { code: AgentErrorCode::Auth, .. } => Err(Box::new(UnlockError)),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    /// Записи хранилища `vault`; `None`, если агент заблокирован или открыл
+    /// другое хранилище.
+    pub fn entries(&mut self, vault: &Path) -> Result<Option<Vec<Entry>>, Box<dyn Error>> {
+        match self.call(&Request::Entries { vault: vault.to_path_buf() })? {
+            Response::Entries { entries } => Ok(Some(entries)),
+            Response::Error {
+                code: AgentErrorCode::Locked | AgentErrorCode::WrongVault,
+                ..
+            } => Ok(None),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    pub fn store(&mut self, vault: &Path, entries: Vec<Entry>) -> Result<(), Box<dyn Error>> {
+        match self.call(&Request::Store { vault: vault.to_path_buf(), entries })? {
+            Response::Ok => Ok(()),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    pub fn lock(&mut self) -> Result<(), Box<dyn Error>> {
+        match self.call(&Request::Lock)? {
+            Response::Ok => Ok(()),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    pub fn stop(&mut self) -> Result<(), Box<dyn Error>> {
+        match self.call(&Request::Stop)? {
+            Response::Ok => Ok(()),
+            other => Err(unexpected(other)),
+        }
+    }
+}
+
+fn unexpected(response: Response) -> Box<dyn Error> {
+    match response {
+        Response::Error { code, message } => Box::new(AgentError { code, message }),
+        // Сам ответ не выводим: в нём могут быть записи с паролями
+        _ => "Неожиданный ответ агента".into(),
+    }
+}
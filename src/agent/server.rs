@@ -0,0 +1,416 @@
+use super::{peer_uid, AgentErrorCode, AgentStatus, Request, Response};
+use crate::auto_lock::{AutoLockManager, IdleTimer};
+use crate::ssh::agent::{self as ssh_agent, Identity};
+use crate::ssh::PrivateKey;
+use crate::system_lock;
+use crate::vault::{UnlockError, Vault};
+use std::error::Error;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use zeroize::{Zeroize, Zeroizing};
+
+// Как часто проверять таймаут автоблокировки
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+// Клиент, который не прислал запрос за это время, отключается
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(30);
+
+struct OpenVault {
+    path: PathBuf,
+    vault: Vault,
+}
+
+struct AgentState {
+    open: Option<OpenVault>,
+    idle: IdleTimer,
+    ssh_socket: Option<PathBuf>,
+}
+
+impl AgentState {
+    fn lock(&mut self) {
+        // Drop у Vault затирает пароль, ключ и секреты записей
+        self.open = None;
+    }
+
+    // Таймаут перечитываем из настроек, чтобы `hiho auto-lock` действовал
+    // без перезапуска агента
+    fn lock_if_idle(&mut self) {
+        self.idle.set_timeout(AutoLockManager::timeout());
+        if self.open.is_some() && self.idle.expired() {
+            self.lock();
+        }
+    }
+}
+
+/// Запускает агент в текущем процессе и обслуживает сокет до команды `stop`.
+pub fn run(socket: &Path) -> Result<(), Box<dyn Error>> {
+    harden_process();
+    let listener = bind(socket)?;
+    let ssh_socket = super::ssh_socket_path(socket);
+    let ssh_listener = bind(&ssh_socket)
+        .map_err(|e| eprintln!("⚠️  Агент SSH не запущен: {}", e))
+        .ok();
+    let state = Arc::new(Mutex::new(AgentState {
+        open: None,
+        idle: IdleTimer::from_config(),
+        ssh_socket: ssh_listener.as_ref().map(|_| ssh_socket.clone()),
+    }));
+
+    if let Some(ssh_listener) = ssh_listener {
+        let ssh_state = Arc::clone(&state);
+        thread::spawn(move || serve_ssh(ssh_listener, ssh_state));
+    }
+
+    let idle_state = Arc::clone(&state);
+    thread::spawn(move || loop {
+        thread::sleep(IDLE_CHECK_INTERVAL);
+        lock_state(&idle_state).lock_if_idle();
+    });
+
+    let signal_state = Arc::clone(&state);
+    system_lock::watch(move |_| lock_state(&signal_state).lock());
+
+    let own_uid = nix::unistd::getuid().as_raw();
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(_) => continue,
+        };
+        // Чужие процессы отключаем, не читая запрос
+        if !peer_uid(&stream).is_ok_and(|uid| uid == own_uid) {
+            continue;
+        }
+        let state = Arc::clone(&state);
+        let socket = socket.to_path_buf();
+        thread::spawn(move || {
+            if let Ok(true) = serve(stream, &state) {
+                let mut state = lock_state(&state);
+                state.lock();
+                if let Some(ssh_socket) = &state.ssh_socket {
+                    let _ = fs::remove_file(ssh_socket);
+                }
+                let _ = fs::remove_file(&socket);
+                std::process::exit(0);
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Агент в фоновом потоке для тестов других модулей: без закрепления
+/// памяти, SSH и слежения за блокировкой экрана.
+#[cfg(test)]
+pub(crate) fn spawn_for_tests(socket: &Path) -> Result<(), Box<dyn Error>> {
+    let listener = bind(socket)?;
+    let state = Arc::new(Mutex::new(AgentState {
+        open: None,
+        idle: IdleTimer::new(None),
+        ssh_socket: None,
+    }));
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let state = Arc::clone(&state);
+            thread::spawn(move || {
+                let _ = serve(stream, &state);
+            });
+        }
+    });
+    Ok(())
+}
+
+fn serve_ssh(listener: UnixListener, state: Arc<Mutex<AgentState>>) {
+    let own_uid = nix::unistd::getuid().as_raw();
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        if !peer_uid(&stream).is_ok_and(|uid| uid == own_uid) {
+            continue;
+        }
+        let state = Arc::clone(&state);
+        thread::spawn(move || {
+            if let Err(e) = ssh_agent::serve(stream, || ssh_identities(&state)) {
+                eprintln!("hiho ssh-agent: {}", e);
+            }
+        });
+    }
+}
+
+// Ключи разбираются из открытого хранилища на каждый запрос: после
+// блокировки агент SSH сразу остаётся без ключей. Подписи не продлевают
+// таймаут автоблокировки — его отсчитывают только действия с хранилищем
+fn ssh_identities(state: &Mutex<AgentState>) -> Vec<Identity> {
+    let mut state = lock_state(state);
+    state.lock_if_idle();
+    let Some(open) = &state.open else {
+        return Vec::new();
+    };
+    open.vault
+        .get_entries()
+        .iter()
+        .filter_map(|entry| {
+            let ssh_key = entry.ssh_key.as_ref()?;
+            match PrivateKey::parse(&ssh_key.private_key) {
+                Ok(key) => Some(Identity {
+                    comment: entry.name.clone(),
+                    key,
+                    confirm: ssh_key.confirm,
+                }),
+                Err(e) => {
+                    eprintln!("hiho ssh-agent: ключ записи '{}' пропущен: {}", entry.name, e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+// Мьютекс мог быть отравлен паникой в другом потоке; состояние при этом
+// остаётся согласованным, поэтому продолжаем работу
+fn lock_state(state: &Mutex<AgentState>) -> MutexGuard<'_, AgentState> {
+    state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+// Закрепляет память процесса в RAM и запрещает дампы памяти. Ошибки не
+// фатальны: например, mlockall упирается в RLIMIT_MEMLOCK
+fn harden_process() {
+    use nix::sys::mman::{mlockall, MlockAllFlags};
+    use nix::sys::resource::{setrlimit, Resource};
+
+    if let Err(e) = mlockall(MlockAllFlags::MCL_CURRENT | MlockAllFlags::MCL_FUTURE) {
+        eprintln!("⚠️  Не удалось закрепить память агента (mlockall): {}", e);
+    }
+    let _ = setrlimit(Resource::RLIMIT_CORE, 0, 0);
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    let _ = nix::sys::prctl::set_dumpable(false);
+}
+
+fn bind(socket: &Path) -> Result<UnixListener, Box<dyn Error>> {
+    if let Some(directory) = socket.parent() {
+        fs::DirBuilder::new().recursive(true).mode(0o700).create(directory)?;
+        fs::set_permissions(directory, fs::Permissions::from_mode(0o700))?;
+    }
+    if socket.exists() {
+        if UnixStream::connect(socket).is_ok() {
+            return Err(format!("Агент уже запущен: {}", socket.display()).into());
+        }
+        // Сокет остался от завершившегося агента
+        fs::remove_file(socket)?;
+    }
+    let listener = UnixListener::bind(socket)?;
+    fs::set_permissions(socket, fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+/// Обслуживает одно подключение. `true` — клиент попросил остановить агент.
+fn serve(stream: UnixStream, state: &Mutex<AgentState>) -> Result<bool, Box<dyn Error>> {
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(false);
+        }
+        let request: Result<Request, _> = serde_json::from_str(&line);
+        line.zeroize();
+
+        let (response, stop) = match request {
+            Ok(Request::Stop) => (Response::Ok, true),
+            Ok(request) => (handle(request, &mut lock_state(state)), false),
+            Err(e) => (error(AgentErrorCode::BadRequest, format!("Некорректный запрос: {}", e)), false),
+        };
+
+        let mut answer = serde_json::to_vec(&response)?;
+        answer.push(b'\n');
+        let written = writer.write_all(&answer);
+        answer.zeroize();
+        written?;
+        if stop {
+            return Ok(true);
+        }
+    }
+}
+
+fn handle(request: Request, state: &mut AgentState) -> Response {
+    // Таймаут проверяем и здесь, а не только в фоновом потоке
+    state.lock_if_idle();
+    if !matches!(request, Request::Status) {
+        state.idle.touch();
+    }
+
+    match request {
+        Request::Status => Response::Status(AgentStatus {
+            pid: std::process::id(),
+            vault: state.open.as_ref().map(|open| open.path.clone()),
+            idle_timeout_secs: state.idle.timeout().map(|t| t.as_secs()),
+            locks_in_secs: state
+                .idle
+                .remaining()
+                .filter(|_| state.open.is_some())
+                .map(|t| t.as_secs()),
+            ssh_socket: state.ssh_socket.clone(),
+        }),
+        Request::Unlock { vault, mut password } => {
+            if !vault.exists() {
+                password.zeroize();
+                return vault_not_found(&vault);
+            }
+            let opened = Vault::open(&vault, &password);
+            password.zeroize();
+            match opened {
+                Ok(opened) => {
+                    state.open = Some(OpenVault { path: vault, vault: opened });
+                    Response::Ok
+                }
+                Err(e) if e.is::<UnlockError>() => error(AgentErrorCode::Auth, e.to_string()),
+                Err(e) => error(AgentErrorCode::Io, e.to_string()),
+            }
+        }
+        Request::UnlockKey { vault, mut key } => {
+            if !vault.exists() {
+                key.zeroize();
+                return vault_not_found(&vault);
+            }
+            let decoded = Zeroizing::new(BASE64.decode(&key).unwrap_or_default());
+            key.zeroize();
+            let Ok(key) = <[u8; 32]>::try_from(decoded.as_slice()) else {
+                return error(AgentErrorCode::BadRequest, "Некорректный ключ хранилища");
+            };
+            match Vault::open_with_key(&vault, key) {
+                Ok(opened) => {
+                    state.open = Some(OpenVault { path: vault, vault: opened });
+                    Response::Ok
+                }
+                Err(e) if e.is::<UnlockError>() => error(AgentErrorCode::Auth, e.to_string()),
+                Err(e) => error(AgentErrorCode::Io, e.to_string()),
+            }
+        }
+        Request::Entries { vault } => match open_vault(state, &vault) {
+            Ok(open) => Response::Entries {
+                entries: open.vault.get_entries().clone(),
+            },
+            Err(response) => response,
+        },
+        Request::Store { vault, entries } => match open_vault(state, &vault) {
+            Ok(open) => {
+                open.vault.replace_entries(entries);
+                match open.vault.save_to_file(&open.path) {
+                    Ok(()) => Response::Ok,
+                    Err(e) => error(AgentErrorCode::Io, e.to_string()),
+                }
+            }
+            Err(response) => response,
+        },
+        Request::Lock => {
+            state.lock();
+            Response::Ok
+        }
+        Request::Stop => Response::Ok,
+    }
+}
+
+fn open_vault<'a>(state: &'a mut AgentState, vault: &Path) -> Result<&'a mut OpenVault, Response> {
+    match state.open.as_mut() {
+        None => Err(error(AgentErrorCode::Locked, "Хранилище не открыто")),
+        Some(open) if open.path != vault => Err(error(
+            AgentErrorCode::WrongVault,
+            format!("Открыто другое хранилище: {}", open.path.display()),
+        )),
+        Some(open) => Ok(open),
+    }
+}
+
+// Vault::open считает отсутствующий файл новым пустым хранилищем: агент
+// «открыл» бы его любым паролем, а следующий Store создал бы файл
+fn vault_not_found(vault: &Path) -> Response {
+    error(AgentErrorCode::Io, format!("Хранилище {} не найдено", vault.display()))
+}
+
+fn error(code: AgentErrorCode, message: impl Into<String>) -> Response {
+    Response::Error {
+        code,
+        message: message.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vault::seal_entries;
+    use crate::Entry;
+
+    const KEY: [u8; 32] = [7; 32];
+
+    fn locked_state() -> AgentState {
+        AgentState {
+            open: None,
+            idle: IdleTimer::new(None),
+            ssh_socket: None,
+        }
+    }
+
+    fn error_code(response: Response) -> Option<AgentErrorCode> {
+        match response {
+            Response::Error { code, .. } => Some(code),
+            _ => None,
+        }
+    }
+
+    fn unlock_key(vault: &Path, key: [u8; 32]) -> Request {
+        Request::UnlockKey {
+            vault: vault.to_path_buf(),
+            key: BASE64.encode(key),
+        }
+    }
+
+    #[test]
+    fn missing_vault_is_not_unlocked() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path().join("vault.enc");
+        let mut state = locked_state();
+
+        let unlock = Request::Unlock {
+            vault: vault.clone(),
+            password: "любой".into(),
+        };
+        assert_eq!(error_code(handle(unlock, &mut state)), Some(AgentErrorCode::Io));
+        assert_eq!(error_code(handle(unlock_key(&vault, KEY), &mut state)), Some(AgentErrorCode::Io));
+        assert!(state.open.is_none());
+
+        // Store в незаблокированное хранилище не создаёт файл
+        let store = Request::Store {
+            vault: vault.clone(),
+            entries: Vec::new(),
+        };
+        assert_eq!(error_code(handle(store, &mut state)), Some(AgentErrorCode::Locked));
+        assert!(!vault.exists());
+    }
+
+    #[test]
+    fn unlock_key_opens_existing_vault() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path().join("vault.enc");
+        let entry = Entry::new("Почта".into(), "ivan".into(), "secret".into());
+        fs::write(&vault, seal_entries(&[entry], &KEY).unwrap()).unwrap();
+        let mut state = locked_state();
+
+        assert_eq!(error_code(handle(unlock_key(&vault, [8; 32]), &mut state)), Some(AgentErrorCode::Auth));
+        assert!(state.open.is_none());
+
+        assert!(matches!(handle(unlock_key(&vault, KEY), &mut state), Response::Ok));
+        match handle(Request::Entries { vault: vault.clone() }, &mut state) {
+            Response::Entries { entries } => assert_eq!(entries[0].name, "Почта"),
+            other => panic!("{:?}", other),
+        }
+        assert!(matches!(handle(Request::Lock, &mut state), Response::Ok));
+        assert!(state.open.is_none());
+    }
+}
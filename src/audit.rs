@@ -0,0 +1,476 @@
+use crate::breach::{check_entries, PwnedRangeSource};
+use crate::entry::{Entry, EntryKind};
+use crate::strength::{estimate_strength, StrengthScore};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::error::Error;
+
+#[derive(Debug, Clone)]
+pub struct AuditConfig {
+    /// Пароли старше этого срока считаются устаревшими
+    pub max_age_days: i64,
+    /// Порог похожести паролей (0.0–1.0) для поиска почти одинаковых
+    pub similarity_threshold: f64,
+    /// Пароли с оценкой ниже этой считаются слабыми
+    pub min_score: StrengthScore,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            max_age_days: 365,
+            similarity_threshold: 0.8,
+            min_score: StrengthScore::Strong,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    Breached,
+    Empty,
+    UsernameAsPassword,
+    Reused,
+    Weak,
+    Similar,
+    Old,
+}
+
+impl IssueKind {
+    pub fn title(&self) -> &'static str {
+        match self {
+            IssueKind::Breached => "Пароли из утечек",
+            IssueKind::Empty => "Пустые пароли",
+            IssueKind::UsernameAsPassword => "Пароль совпадает с логином",
+            IssueKind::Reused => "Повторно используемые пароли",
+            IssueKind::Weak => "Слабые пароли",
+            IssueKind::Similar => "Похожие пароли",
+            IssueKind::Old => "Устаревшие пароли",
+        }
+    }
+
+    pub fn severity(&self) -> Severity {
+        match self {
+            IssueKind::Breached | IssueKind::Empty | IssueKind::UsernameAsPassword => Severity::Critical,
+            IssueKind::Reused | IssueKind::Weak => Severity::High,
+            IssueKind::Similar => Severity::Medium,
+            IssueKind::Old => Severity::Low,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    fn penalty(&self) -> f64 {
+        match self {
+            Severity::Low => 0.2,
+            Severity::Medium => 0.4,
+            Severity::High => 0.7,
+            Severity::Critical => 1.0,
+        }
+    }
+}
+
+/// Ссылка на запись: индекс начинается с 1, как в `hiho list`.
+#[derive(Serialize, Debug, Clone)]
+pub struct EntryRef {
+    pub index: usize,
+    pub name: String,
+    pub username: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct AuditIssue {
+    pub kind: IssueKind,
+    pub severity: Severity,
+    pub entries: Vec<EntryRef>,
+    pub detail: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct AuditGroup {
+    pub kind: IssueKind,
+    pub title: String,
+    pub severity: Severity,
+    pub affected_entries: usize,
+    pub issues: Vec<AuditIssue>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct AuditReport {
+    pub generated_at: DateTime<Utc>,
+    /// Проверенные записи: только логины
+    pub total_entries: usize,
+    pub affected_entries: usize,
+    /// Итоговая оценка хранилища от 0 до 100
+    pub score: u8,
+    pub groups: Vec<AuditGroup>,
+}
+
+impl AuditReport {
+    pub fn issue_count(&self) -> usize {
+        self.groups.iter().map(|g| g.issues.len()).sum()
+    }
+}
+
+/// Проверяет все записи хранилища и группирует найденные проблемы.
+pub fn audit_entries(entries: &[Entry], config: &AuditConfig) -> AuditReport {
+    let now = Utc::now();
+    build_report(audited_count(entries), collect_issues(entries, config, now), now)
+}
+
+/// То же, что `audit_entries`, но дополнительно сверяет пароли с базой утечек.
+pub fn audit_entries_with_breaches(
+    entries: &[Entry],
+    config: &AuditConfig,
+    source: &mut dyn PwnedRangeSource,
+) -> Result<AuditReport, Box<dyn Error>> {
+    let now = Utc::now();
+    let mut issues = collect_issues(entries, config, now);
+    for result in check_entries(entries, source)? {
+        if !is_audited(&entries[result.index]) {
+            continue;
+        }
+        issues.push(AuditIssue {
+            kind: IssueKind::Breached,
+            severity: IssueKind::Breached.severity(),
+            entries: vec![entry_ref(entries, result.index)],
+            detail: format!("Пароль встречался в утечках {} раз", result.count),
+        });
+    }
+    Ok(build_report(audited_count(entries), issues, now))
+}
+
+/// Пароли проверяются только у логинов: у заметок и SSH-ключей их нет.
+fn is_audited(entry: &Entry) -> bool {
+    entry.kind == EntryKind::Login
+}
+
+fn audited_count(entries: &[Entry]) -> usize {
+    entries.iter().filter(|entry| is_audited(entry)).count()
+}
+
+fn entry_ref(entries: &[Entry], index: usize) -> EntryRef {
+    EntryRef {
+        index: index + 1,
+        name: entries[index].name.clone(),
+        username: entries[index].username.clone(),
+    }
+}
+
+fn collect_issues(entries: &[Entry], config: &AuditConfig, now: DateTime<Utc>) -> Vec<AuditIssue> {
+    let mut issues: Vec<AuditIssue> = Vec::new();
+    let entry_ref = |index: usize| entry_ref(entries, index);
+
+    for (index, entry) in entries.iter().enumerate().filter(|(_, entry)| is_audited(entry)) {
+        if entry.password.is_empty() {
+            issues.push(AuditIssue {
+                kind: IssueKind::Empty,
+                severity: IssueKind::Empty.severity(),
+                entries: vec![entry_ref(index)],
+                detail: "Пароль не задан".to_string(),
+            });
+            // Остальные проверки для пустого пароля не имеют смысла
+            continue;
+        }
+
+        if !entry.username.is_empty() && entry.username.to_lowercase() == entry.password.to_lowercase() {
+            issues.push(AuditIssue {
+                kind: IssueKind::UsernameAsPassword,
+                severity: IssueKind::UsernameAsPassword.severity(),
+                entries: vec![entry_ref(index)],
+                detail: "Пароль совпадает с именем пользователя".to_string(),
+            });
+        }
+
+        let report = estimate_strength(&entry.password, &[&entry.name, &entry.username]);
+        if report.score < config.min_score {
+            let mut detail = format!("Оценка: {} ({:.0} бит)", report.score.label(), report.entropy_bits);
+            if let Some(warning) = &report.feedback.warning {
+                detail.push_str(&format!(" — {}", warning));
+            }
+            issues.push(AuditIssue {
+                kind: IssueKind::Weak,
+                severity: IssueKind::Weak.severity(),
+                entries: vec![entry_ref(index)],
+                detail,
+            });
+        }
+
+        // У старых записей даты смены пароля нет: пароль не моложе
+        // последнего изменения записи, а если неизвестно и оно — возраст неизвестен
+        let detail = match (entry.password_changed_at, entry.updated_at.or(entry.created_at)) {
+            (Some(changed_at), _) => {
+                let age_days = (now - changed_at).num_days();
+                (age_days > config.max_age_days).then(|| format!("Пароль не менялся {} дн.", age_days))
+            }
+            (None, Some(touched_at)) => {
+                let age_days = (now - touched_at).num_days();
+                (age_days > config.max_age_days).then(|| format!("Пароль не менялся не меньше {} дн.", age_days))
+            }
+            (None, None) => Some("Возраст пароля неизвестен".to_string()),
+        };
+        if let Some(detail) = detail {
+            issues.push(AuditIssue {
+                kind: IssueKind::Old,
+                severity: IssueKind::Old.severity(),
+                entries: vec![entry_ref(index)],
+                detail,
+            });
+        }
+    }
+
+    // Точные повторы: группируем записи по паролю
+    let mut by_password: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (index, entry) in entries.iter().enumerate().filter(|(_, entry)| is_audited(entry)) {
+        if !entry.password.is_empty() {
+            by_password.entry(entry.password.as_str()).or_default().push(index);
+        }
+    }
+    let reused_groups: Vec<&Vec<usize>> = by_password.values().filter(|group| group.len() > 1).collect();
+    for group in &reused_groups {
+        issues.push(AuditIssue {
+            kind: IssueKind::Reused,
+            severity: IssueKind::Reused.severity(),
+            entries: group.iter().map(|&i| entry_ref(i)).collect(),
+            detail: format!("Один и тот же пароль используется в {} записях", group.len()),
+        });
+    }
+
+    // Почти одинаковые пароли: сравниваем по одному представителю каждого пароля
+    let representatives: Vec<(&str, &Vec<usize>)> = by_password.iter().map(|(p, g)| (*p, g)).collect();
+    let mut parent: Vec<usize> = (0..representatives.len()).collect();
+    for a in 0..representatives.len() {
+        for b in a + 1..representatives.len() {
+            if are_similar(representatives[a].0, representatives[b].0, config.similarity_threshold) {
+                let (root_a, root_b) = (find_root(&mut parent, a), find_root(&mut parent, b));
+                if root_a != root_b {
+                    parent[root_b] = root_a;
+                }
+            }
+        }
+    }
+    let mut clusters: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for i in 0..representatives.len() {
+        let root = find_root(&mut parent, i);
+        clusters.entry(root).or_default().push(i);
+    }
+    for cluster in clusters.values().filter(|cluster| cluster.len() > 1) {
+        let mut indices: Vec<usize> = cluster
+            .iter()
+            .flat_map(|&r| representatives[r].1.iter().copied())
+            .collect();
+        indices.sort_unstable();
+        issues.push(AuditIssue {
+            kind: IssueKind::Similar,
+            severity: IssueKind::Similar.severity(),
+            entries: indices.iter().map(|&i| entry_ref(i)).collect(),
+            detail: format!("Похожих, но не одинаковых паролей: {}", cluster.len()),
+        });
+    }
+
+    issues
+}
+
+fn build_report(total_entries: usize, issues: Vec<AuditIssue>, generated_at: DateTime<Utc>) -> AuditReport {
+    // Для оценки каждая запись учитывается один раз, по самой серьёзной проблеме
+    let mut worst: BTreeMap<usize, Severity> = BTreeMap::new();
+    for issue in &issues {
+        for entry in &issue.entries {
+            let current = worst.entry(entry.index).or_insert(issue.severity);
+            if issue.severity > *current {
+                *current = issue.severity;
+            }
+        }
+    }
+    let penalty: f64 = worst.values().map(Severity::penalty).sum();
+    let score = if total_entries == 0 {
+        100
+    } else {
+        (100.0 * (1.0 - penalty / total_entries as f64)).round().clamp(0.0, 100.0) as u8
+    };
+
+    let mut grouped: BTreeMap<IssueKind, Vec<AuditIssue>> = BTreeMap::new();
+    for issue in issues {
+        grouped.entry(issue.kind).or_default().push(issue);
+    }
+    let groups = grouped
+        .into_iter()
+        .map(|(kind, issues)| {
+            let mut affected: Vec<usize> = issues.iter().flat_map(|i| i.entries.iter().map(|e| e.index)).collect();
+            affected.sort_unstable();
+            affected.dedup();
+            AuditGroup {
+                kind,
+                title: kind.title().to_string(),
+                severity: kind.severity(),
+                affected_entries: affected.len(),
+                issues,
+            }
+        })
+        .collect();
+
+    AuditReport {
+        generated_at,
+        total_entries,
+        affected_entries: worst.len(),
+        score,
+        groups,
+    }
+}
+
+fn find_root(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+/// Пароли похожи, если совпадают без учёта регистра и цифр в конце
+/// («Summer2023» и «summer2024») или близки по расстоянию Левенштейна.
+pub fn are_similar(a: &str, b: &str, threshold: f64) -> bool {
+    if a == b {
+        return false;
+    }
+    let normalize = |s: &str| s.to_lowercase().trim_end_matches(|c: char| c.is_ascii_digit() || c.is_ascii_punctuation()).to_string();
+    let (na, nb) = (normalize(a), normalize(b));
+    if na.chars().count() >= 4 && na == nb {
+        return true;
+    }
+    similarity(&a.to_lowercase(), &b.to_lowercase()) >= threshold
+}
+
+/// Нормированная похожесть строк: 1.0 — совпадают, 0.0 — ничего общего.
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let max_len = a.len().max(b.len());
+    if max_len == 0 {
+        return 1.0;
+    }
+    1.0 - levenshtein(&a, &b) as f64 / max_len as f64
+}
+
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == cb { 0 } else { 1 };
+            current[j + 1] = (previous[j + 1] + 1).min(current[j] + 1).min(previous[j] + cost);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    const STRONG: &str = "vK8#qZ2!mW5$tR9&xL3@pN7";
+
+    fn entry(name: &str, username: &str, password: &str) -> Entry {
+        Entry::new(name.to_string(), username.to_string(), password.to_string())
+    }
+
+    fn kinds(report: &AuditReport) -> Vec<IssueKind> {
+        report.groups.iter().map(|group| group.kind).collect()
+    }
+
+    #[test]
+    fn empty_vault_scores_100() {
+        let report = audit_entries(&[], &AuditConfig::default());
+        assert_eq!(report.score, 100);
+        assert_eq!(report.issue_count(), 0);
+    }
+
+    #[test]
+    fn strong_unique_password_has_no_issues() {
+        let report = audit_entries(&[entry("site", "alice", STRONG)], &AuditConfig::default());
+        assert_eq!(report.score, 100);
+        assert!(report.groups.is_empty(), "{:?}", kinds(&report));
+    }
+
+    #[test]
+    fn reused_weak_passwords_count_once_per_entry() {
+        let entries = [entry("a", "alice", "password"), entry("b", "bob", "password")];
+        let report = audit_entries(&entries, &AuditConfig::default());
+        assert_eq!(kinds(&report), vec![IssueKind::Reused, IssueKind::Weak]);
+        assert_eq!(report.affected_entries, 2);
+        // Обе записи с высокой серьёзностью: 100 · (1 − 2 · 0.7 / 2)
+        assert_eq!(report.score, 30);
+    }
+
+    #[test]
+    fn empty_and_username_passwords_are_critical() {
+        let entries = [entry("a", "alice", ""), entry("b", "Bob.Smith", "bob.smith"), entry("c", "carol", STRONG)];
+        let report = audit_entries(&entries, &AuditConfig::default());
+        assert!(kinds(&report).contains(&IssueKind::Empty));
+        assert!(kinds(&report).contains(&IssueKind::UsernameAsPassword));
+        assert_eq!(report.affected_entries, 2);
+        assert_eq!(report.score, 33);
+    }
+
+    #[test]
+    fn notes_and_ssh_keys_are_not_audited() {
+        let mut note = entry("note", "", "");
+        note.kind = EntryKind::Note;
+        let mut key = entry("id_ed25519", "", "");
+        key.kind = EntryKind::SshKey;
+        key.password_changed_at = None;
+        key.updated_at = None;
+        key.created_at = None;
+        let mut duplicate = entry("copy", "", "");
+        duplicate.kind = EntryKind::Note;
+        let report = audit_entries(&[note, key, duplicate, entry("site", "alice", STRONG)], &AuditConfig::default());
+        assert!(report.groups.is_empty(), "{:?}", kinds(&report));
+        assert_eq!(report.total_entries, 1);
+        assert_eq!(report.score, 100);
+    }
+
+    #[test]
+    fn similar_passwords_are_grouped() {
+        assert!(are_similar("Summer2023!", "summer2024", 0.8));
+        assert!(!are_similar("same", "same", 0.8));
+        let entries = [entry("a", "alice", "Correct-Horse-Battery-2023"), entry("b", "bob", "correct-horse-battery-2024")];
+        let report = audit_entries(&entries, &AuditConfig::default());
+        let similar = report.groups.iter().find(|group| group.kind == IssueKind::Similar).expect("похожие пароли");
+        assert_eq!(similar.affected_entries, 2);
+    }
+
+    #[test]
+    fn old_passwords_fall_back_to_entry_dates() {
+        let long_ago = Utc::now() - Duration::days(400);
+        let mut changed = entry("changed", "a", STRONG);
+        changed.password_changed_at = Some(long_ago);
+        let mut legacy = entry("legacy", "b", "Another-Strong-Passphrase-91!");
+        legacy.password_changed_at = None;
+        legacy.updated_at = Some(long_ago);
+        let mut unknown = entry("unknown", "c", "Third-Distinct-Passphrase-47#");
+        unknown.password_changed_at = None;
+        unknown.updated_at = None;
+        unknown.created_at = None;
+        let mut recent = entry("recent", "d", "Fourth-Unrelated-Secret-63%");
+        recent.password_changed_at = None;
+
+        let report = audit_entries(&[changed, legacy, unknown, recent], &AuditConfig::default());
+        let old = report.groups.iter().find(|group| group.kind == IssueKind::Old).expect("устаревшие пароли");
+        let names: Vec<&str> = old.issues.iter().map(|issue| issue.entries[0].name.as_str()).collect();
+        assert_eq!(names, vec!["changed", "legacy", "unknown"]);
+        assert_eq!(old.issues[2].detail, "Возраст пароля неизвестен");
+    }
+}
@@ -1,20 +1,56 @@
+//! Автоблокировка: настройки и таймер бездействия.
+//!
+//! Блокировка — это не файл-маркер, а закрытие хранилища: процесс, который
+//! держит расшифрованные записи (GUI или агент), отбрасывает `Vault` вместе
+//! с ключом и записями, и для продолжения работы снова нужен мастер-пароль.
+//! Каждый такой процесс ведёт свой `IdleTimer` в памяти.
+
 use std::fs;
 use std::path::Path;
-use std::time::SystemTime;
+use std::time::{Duration, Instant};
 use serde::{Deserialize, Serialize};
+use crate::system_lock::LockSignal;
 
-const ACTIVITY_FILE: &str = "data\\activity.log";
 const CONFIG_FILE: &str = "data\\auto_lock_config.json";
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct AutoLockConfig {
     pub timeout_minutes: Option<u64>, // None = отключено
+    /// Блокировать при переходе системы в сон
+    #[serde(default = "enabled")]
+    pub lock_on_sleep: bool,
+    /// Блокировать при блокировке экрана
+    #[serde(default = "enabled")]
+    pub lock_on_screen_lock: bool,
+}
+
+fn enabled() -> bool {
+    true
 }
 
 impl Default for AutoLockConfig {
     fn default() -> Self {
         Self {
             timeout_minutes: Some(10), // 10 минут по умолчанию
+            lock_on_sleep: true,
+            lock_on_screen_lock: true,
+        }
+    }
+}
+
+impl AutoLockConfig {
+    /// Таймаут бездействия; `None`, если автоблокировка по времени отключена.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout_minutes
+            .filter(|minutes| *minutes > 0)
+            .map(|minutes| Duration::from_secs(minutes * 60))
+    }
+
+    /// Нужно ли блокировать по сигналу системы.
+    pub fn locks_on(&self, signal: LockSignal) -> bool {
+        match signal {
+            LockSignal::Sleep => self.lock_on_sleep,
+            LockSignal::ScreenLock => self.lock_on_screen_lock,
         }
     }
 }
@@ -51,62 +87,54 @@ impl AutoLockManager {
         Ok(())
     }
 
-    pub fn update_activity() -> Result<(), Box<dyn std::error::Error>> {
-        let config = Self::get_config()?;
-        
-        // Если автоблокировка включена
-        if config.timeout_minutes.is_some() {
-            let activity_path = Path::new(ACTIVITY_FILE);
-            fs::write(activity_path, "")?;
-        }
-        
-        Ok(())
+    /// Таймаут из настроек; при ошибке чтения — значение по умолчанию,
+    /// чтобы испорченный файл настроек не отключал блокировку.
+    pub fn timeout() -> Option<Duration> {
+        Self::get_config().unwrap_or_default().timeout()
     }
+}
 
-    pub fn should_lock() -> Result<bool, Box<dyn std::error::Error>> {
-        let config = Self::get_config()?;
-        
-        // Если автоблокировка отключена
-        let timeout_minutes = match config.timeout_minutes {
-            Some(minutes) => minutes,
-            None => return Ok(false),
-        };
-
-        // Проверяем время последней активности
-        let activity_path = Path::new(ACTIVITY_FILE);
-        if activity_path.exists() {
-            let metadata = fs::metadata(activity_path)?;
-            let modified = metadata.modified()?;
-            let now = SystemTime::now();
-            let elapsed = now.duration_since(modified)?.as_secs();
-            let timeout_seconds = timeout_minutes * 60;
-            
-            if elapsed > timeout_seconds {
-                return Ok(true);
-            }
+/// Таймер бездействия. Владелец сбрасывает его при каждом действии
+/// пользователя и закрывает хранилище, когда `expired()` вернёт `true`.
+#[derive(Debug, Clone)]
+pub struct IdleTimer {
+    timeout: Option<Duration>,
+    last_activity: Instant,
+}
+
+impl IdleTimer {
+    pub fn new(timeout: Option<Duration>) -> Self {
+        Self {
+            timeout,
+            last_activity: Instant::now(),
         }
-        
-        Ok(false)
     }
 
-    pub fn is_locked() -> bool {
-        crate::session::SessionManager::is_locked()
+    /// Таймер с таймаутом из настроек автоблокировки.
+    pub fn from_config() -> Self {
+        Self::new(AutoLockManager::timeout())
     }
 
-    pub fn lock_session() -> Result<(), Box<dyn std::error::Error>> {
-        // Создаем файл блокировки сессии
-        fs::write("data\\session.lock", "")?;
-        // Удаляем файл активности
-        let activity_path = Path::new(ACTIVITY_FILE);
-        if activity_path.exists() {
-            fs::remove_file(activity_path)?;
-        }
-        Ok(())
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    /// Отмечает действие пользователя.
+    pub fn touch(&mut self) {
+        self.last_activity = Instant::now();
     }
 
-    // Добавляем метод unlock
-    pub fn unlock() -> Result<(), Box<dyn std::error::Error>> {
-        crate::session::SessionManager::unlock_session()
-            .map_err(|e| format!("Ошибка разблокировки: {}", e).into())
+    /// Сколько осталось до блокировки; `None`, если таймаут отключён.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.timeout
+            .map(|timeout| timeout.saturating_sub(self.last_activity.elapsed()))
     }
-}
\ No newline at end of file
+
+    pub fn expired(&self) -> bool {
+        self.remaining().is_some_and(|remaining| remaining.is_zero())
+    }
+}
@@ -0,0 +1,557 @@
+//! Автонабор: ввод логина и пароля нажатиями клавиш в активное окно.
+//!
+//! Нужен там, где вставка из буфера не работает (RDP-клиенты, старые
+//! Java-программы). Последовательность задаётся в синтаксисе KeePass:
+//! `{USERNAME}{TAB}{PASSWORD}{ENTER}`.
+//!
+//! - `{USERNAME}`, `{PASSWORD}`, `{TITLE}`, `{URL}`, `{NOTES}` — поля записи,
+//!   `{TOTP}` — текущий одноразовый код, `{S:Имя}` — пользовательское поле;
+//! - `{TAB}`, `{ENTER}`, `{SPACE}`, `{BS}`, `{DEL}`, `{ESC}`, стрелки,
+//!   `{HOME}`, `{END}`, `{PGUP}`, `{PGDN}`, `{INSERT}`, `{F1}`…`{F12}`;
+//!   число после имени — повтор: `{TAB 3}`;
+//! - `{DELAY 500}` — пауза в миллисекундах, `{DELAY=50}` — пауза между
+//!   всеми последующими нажатиями;
+//! - `~` — Enter, `+`, `^`, `%` — Shift, Ctrl и Alt для следующей клавиши;
+//!   сами эти символы и скобки набираются как `{+}`, `{^}`, `{%}`, `{~}`,
+//!   `{{}`, `{}}`.
+//!
+//! Значения полей набираются как есть, спецсимволы в них не разбираются.
+//! Разбор и выполнение последовательности не зависят от платформы: клавиши
+//! отправляются в [`KeySink`]. В Linux это XTest (X11) или uinput.
+
+#[cfg(target_os = "linux")]
+mod uinput;
+#[cfg(target_os = "linux")]
+mod xtest;
+
+use crate::entry::Entry;
+use std::error::Error;
+use std::fmt;
+use std::thread;
+use std::time::Duration;
+use zeroize::Zeroizing;
+
+/// Последовательность KeePass по умолчанию
+pub const DEFAULT_SEQUENCE: &str = "{USERNAME}{TAB}{PASSWORD}{ENTER}";
+
+// Пауза между нажатиями по умолчанию: без неё часть программ теряет символы
+const DEFAULT_KEY_DELAY: Duration = Duration::from_millis(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Tab,
+    Enter,
+    Space,
+    Backspace,
+    Delete,
+    Escape,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Insert,
+    /// Функциональная клавиша F1–F12
+    F(u8),
+}
+
+impl Key {
+    fn parse(name: &str) -> Option<Key> {
+        let key = match name {
+            "TAB" => Key::Tab,
+            "ENTER" => Key::Enter,
+            "SPACE" => Key::Space,
+            "BS" | "BKSP" | "BACKSPACE" => Key::Backspace,
+            "DEL" | "DELETE" => Key::Delete,
+            "ESC" => Key::Escape,
+            "UP" => Key::Up,
+            "DOWN" => Key::Down,
+            "LEFT" => Key::Left,
+            "RIGHT" => Key::Right,
+            "HOME" => Key::Home,
+            "END" => Key::End,
+            "PGUP" => Key::PageUp,
+            "PGDN" => Key::PageDown,
+            "INSERT" | "INS" => Key::Insert,
+            _ => {
+                let number: u8 = name.strip_prefix('F')?.parse().ok()?;
+                if !(1..=12).contains(&number) {
+                    return None;
+                }
+                Key::F(number)
+            }
+        };
+        Some(key)
+    }
+
+    fn name(&self) -> String {
+        match self {
+            Key::Char(c) => c.to_string(),
+            Key::Tab => "TAB".to_string(),
+            Key::Enter => "ENTER".to_string(),
+            Key::Space => "SPACE".to_string(),
+            Key::Backspace => "BS".to_string(),
+            Key::Delete => "DEL".to_string(),
+            Key::Escape => "ESC".to_string(),
+            Key::Up => "UP".to_string(),
+            Key::Down => "DOWN".to_string(),
+            Key::Left => "LEFT".to_string(),
+            Key::Right => "RIGHT".to_string(),
+            Key::Home => "HOME".to_string(),
+            Key::End => "END".to_string(),
+            Key::PageUp => "PGUP".to_string(),
+            Key::PageDown => "PGDN".to_string(),
+            Key::Insert => "INSERT".to_string(),
+            Key::F(number) => format!("F{}", number),
+        }
+    }
+}
+
+/// Зажатые модификаторы.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+}
+
+impl Modifiers {
+    pub fn is_empty(&self) -> bool {
+        !(self.shift || self.ctrl || self.alt)
+    }
+
+    fn prefix(&self) -> String {
+        let mut prefix = String::new();
+        if self.shift {
+            prefix.push('+');
+        }
+        if self.ctrl {
+            prefix.push('^');
+        }
+        if self.alt {
+            prefix.push('%');
+        }
+        prefix
+    }
+}
+
+/// Элемент разобранной последовательности.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    /// Текст из самой последовательности
+    Text(String),
+    Key { key: Key, modifiers: Modifiers, repeat: u32 },
+    /// Поле записи: имя в нижнем регистре или `s:Имя`
+    Field(String),
+    Delay(Duration),
+    KeyDelay(Duration),
+}
+
+/// Разобранная последовательность автонабора.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sequence {
+    tokens: Vec<Token>,
+}
+
+impl Sequence {
+    pub fn parse(source: &str) -> Result<Self, Box<dyn Error>> {
+        let mut tokens = Vec::new();
+        let mut modifiers = Modifiers::default();
+        let mut chars = source.chars();
+
+        while let Some(c) = chars.next() {
+            let key = match c {
+                '+' => {
+                    modifiers.shift = true;
+                    continue;
+                }
+                '^' => {
+                    modifiers.ctrl = true;
+                    continue;
+                }
+                '%' => {
+                    modifiers.alt = true;
+                    continue;
+                }
+                '~' => Key::Enter,
+                '(' | ')' => {
+                    return Err("Группы в скобках не поддерживаются; скобку можно набрать как {(} или {)}".into())
+                }
+                '}' => return Err("Лишняя '}' в последовательности; наберите её как {}}".into()),
+                '{' => {
+                    // `{}}` — закрывающая скобка, поэтому первый символ берём всегда
+                    let mut inner = String::new();
+                    if let Some(first) = chars.next() {
+                        inner.push(first);
+                    }
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => inner.push(c),
+                            None => return Err(format!("Не закрыта скобка: {{{}", inner).into()),
+                        }
+                    }
+                    match parse_braced(&inner)? {
+                        Token::Key { key, repeat, .. } => {
+                            tokens.push(Token::Key { key, modifiers, repeat });
+                            modifiers = Modifiers::default();
+                        }
+                        token if modifiers.is_empty() => tokens.push(token),
+                        _ => return Err(format!("Модификаторы +, ^ и % нельзя применить к {{{}}}", inner).into()),
+                    }
+                    continue;
+                }
+                c => Key::Char(c),
+            };
+
+            match key {
+                Key::Char(c) if modifiers.is_empty() => match tokens.last_mut() {
+                    Some(Token::Text(text)) => text.push(c),
+                    _ => tokens.push(Token::Text(c.to_string())),
+                },
+                key => {
+                    tokens.push(Token::Key { key, modifiers, repeat: 1 });
+                    modifiers = Modifiers::default();
+                }
+            }
+        }
+        if !modifiers.is_empty() {
+            return Err("Модификатор в конце последовательности ни к чему не относится".into());
+        }
+        Ok(Self { tokens })
+    }
+
+    pub fn tokens(&self) -> &[Token] {
+        &self.tokens
+    }
+
+    /// Подставляет поля записи. Значения затираются вместе с результатом.
+    pub fn resolve(&self, entry: &Entry) -> Result<Vec<Action>, Box<dyn Error>> {
+        let mut actions = Vec::new();
+        for token in &self.tokens {
+            match token {
+                Token::Text(text) => actions.push(Action::Text(Zeroizing::new(text.clone()))),
+                Token::Key { key, modifiers, repeat } => {
+                    for _ in 0..*repeat {
+                        actions.push(Action::Key(*key, *modifiers));
+                    }
+                }
+                Token::Field(name) => {
+                    let field = name.strip_prefix("s:").unwrap_or(name);
+                    let value = entry
+                        .copy_value(field)?
+                        .ok_or_else(|| format!("У записи '{}' нет поля '{}'", entry.name, field))?;
+                    actions.push(Action::Text(Zeroizing::new(value)));
+                }
+                Token::Delay(duration) => actions.push(Action::Delay(*duration)),
+                Token::KeyDelay(duration) => actions.push(Action::KeyDelay(*duration)),
+            }
+        }
+        Ok(actions)
+    }
+}
+
+impl fmt::Display for Sequence {
+    /// Последовательность в каноническом виде, без значений полей.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for token in &self.tokens {
+            match token {
+                Token::Text(text) => {
+                    for c in text.chars() {
+                        match c {
+                            c if "+^%~(){}".contains(c) => write!(f, "{{{}}}", c)?,
+                            c => write!(f, "{}", c)?,
+                        }
+                    }
+                }
+                Token::Key { key, modifiers, repeat } => {
+                    write!(f, "{}", modifiers.prefix())?;
+                    match (key, repeat) {
+                        (Key::Char(c), 1) if "+^%~(){}".contains(*c) => write!(f, "{{{}}}", c)?,
+                        (Key::Char(c), 1) => write!(f, "{}", c)?,
+                        (key, 1) => write!(f, "{{{}}}", key.name())?,
+                        (key, repeat) => write!(f, "{{{} {}}}", key.name(), repeat)?,
+                    }
+                }
+                Token::Field(name) => match name.strip_prefix("s:") {
+                    Some(custom) => write!(f, "{{S:{}}}", custom)?,
+                    None => write!(f, "{{{}}}", name.to_uppercase())?,
+                },
+                Token::Delay(duration) => write!(f, "{{DELAY {}}}", duration.as_millis())?,
+                Token::KeyDelay(duration) => write!(f, "{{DELAY={}}}", duration.as_millis())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+// Содержимое фигурных скобок
+fn parse_braced(inner: &str) -> Result<Token, Box<dyn Error>> {
+    if inner.chars().count() == 1 {
+        let c = inner.chars().next().unwrap_or_default();
+        return Ok(Token::Key { key: Key::Char(c), modifiers: Modifiers::default(), repeat: 1 });
+    }
+    if let Some(custom) = inner.strip_prefix("S:").or_else(|| inner.strip_prefix("s:")) {
+        return Ok(Token::Field(format!("s:{}", custom)));
+    }
+
+    let upper = inner.to_uppercase();
+    if let Some(millis) = upper.strip_prefix("DELAY=") {
+        return Ok(Token::KeyDelay(parse_millis(millis, inner)?));
+    }
+    if let Some(millis) = upper.strip_prefix("DELAY ") {
+        return Ok(Token::Delay(parse_millis(millis, inner)?));
+    }
+    match upper.as_str() {
+        "USERNAME" | "PASSWORD" | "TITLE" | "URL" | "NOTES" | "TOTP" => return Ok(Token::Field(upper.to_lowercase())),
+        _ => {}
+    }
+
+    let (name, repeat) = match upper.split_once(' ') {
+        Some((name, count)) => {
+            let repeat = count
+                .trim()
+                .parse()
+                .map_err(|_| format!("Некорректное число повторов в {{{}}}", inner))?;
+            (name, repeat)
+        }
+        None => (upper.as_str(), 1),
+    };
+    let key = Key::parse(name).ok_or_else(|| format!("Неизвестный элемент последовательности: {{{}}}", inner))?;
+    Ok(Token::Key { key, modifiers: Modifiers::default(), repeat })
+}
+
+fn parse_millis(value: &str, inner: &str) -> Result<Duration, Box<dyn Error>> {
+    let millis = value
+        .trim()
+        .parse()
+        .map_err(|_| format!("Некорректная пауза в {{{}}}: нужно число миллисекунд", inner))?;
+    Ok(Duration::from_millis(millis))
+}
+
+/// Шаг набора после подстановки полей.
+pub enum Action {
+    Text(Zeroizing<String>),
+    Key(Key, Modifiers),
+    Delay(Duration),
+    KeyDelay(Duration),
+}
+
+/// Получатель нажатий: виртуальная клавиатура платформы или запись для проверки.
+pub trait KeySink {
+    /// Нажимает и отпускает клавишу с модификаторами. Для `Key::Char`
+    /// реализация сама решает, нужен ли Shift или другой уровень раскладки.
+    fn press(&mut self, key: Key, modifiers: Modifiers) -> Result<(), Box<dyn Error>>;
+
+    fn pause(&mut self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+/// Выполняет шаги набора.
+pub fn run(actions: &[Action], sink: &mut dyn KeySink) -> Result<(), Box<dyn Error>> {
+    let mut key_delay = DEFAULT_KEY_DELAY;
+    for action in actions {
+        match action {
+            Action::Text(text) => {
+                for c in text.chars() {
+                    sink.press(Key::Char(c), Modifiers::default())?;
+                    sink.pause(key_delay);
+                }
+            }
+            Action::Key(key, modifiers) => {
+                sink.press(*key, *modifiers)?;
+                sink.pause(key_delay);
+            }
+            Action::Delay(duration) => sink.pause(*duration),
+            Action::KeyDelay(duration) => key_delay = *duration,
+        }
+    }
+    Ok(())
+}
+
+/// Последовательность записи: своя или по умолчанию.
+pub fn sequence_for(entry: &Entry) -> Result<Sequence, Box<dyn Error>> {
+    Sequence::parse(entry.auto_type.as_deref().unwrap_or(DEFAULT_SEQUENCE))
+}
+
+/// Что получил бы [`KeySink`]: для пробного запуска без ввода.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Recorded {
+    Key(Key, Modifiers),
+    Pause(Duration),
+}
+
+/// Получатель, который только запоминает нажатия и паузы.
+#[derive(Default)]
+pub struct RecordingSink {
+    pub events: Vec<Recorded>,
+}
+
+impl RecordingSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Сколько клавиш было бы нажато.
+    pub fn key_count(&self) -> usize {
+        self.events.iter().filter(|e| matches!(e, Recorded::Key(..))).count()
+    }
+
+    /// Общая длительность пауз.
+    pub fn total_pause(&self) -> Duration {
+        self.events
+            .iter()
+            .map(|e| match e {
+                Recorded::Pause(duration) => *duration,
+                Recorded::Key(..) => Duration::ZERO,
+            })
+            .sum()
+    }
+}
+
+impl KeySink for RecordingSink {
+    fn press(&mut self, key: Key, modifiers: Modifiers) -> Result<(), Box<dyn Error>> {
+        self.events.push(Recorded::Key(key, modifiers));
+        Ok(())
+    }
+
+    fn pause(&mut self, duration: Duration) {
+        self.events.push(Recorded::Pause(duration));
+    }
+}
+
+/// Открывает виртуальную клавиатуру текущего сеанса. В Wayland нажатия
+/// идут через uinput (нужен доступ к /dev/uinput), иначе через XTest.
+#[cfg(target_os = "linux")]
+pub fn open() -> Result<Box<dyn KeySink>, Box<dyn Error>> {
+    let has_x11 = std::env::var_os("DISPLAY").is_some();
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() || !has_x11 {
+        match uinput::UinputSink::new() {
+            Ok(sink) => return Ok(Box::new(sink)),
+            // XTest под XWayland достаёт хотя бы до X-программ
+            Err(e) if !has_x11 => return Err(e),
+            Err(_) => {}
+        }
+    }
+    Ok(Box::new(xtest::XTestSink::new()?))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn open() -> Result<Box<dyn KeySink>, Box<dyn Error>> {
+    Err("Автонабор пока поддерживается только в Linux".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entry::CustomField;
+
+    const NONE: Modifiers = Modifiers {
+        shift: false,
+        ctrl: false,
+        alt: false,
+    };
+
+    fn entry() -> Entry {
+        let mut entry = Entry::new("Почта".into(), "ivan".into(), "p+{}".into());
+        entry.custom_fields.push(CustomField {
+            name: "ПИН".into(),
+            value: "42".into(),
+            protected: true,
+        });
+        entry
+    }
+
+    fn record(sequence: &str, entry: &Entry) -> RecordingSink {
+        let actions = Sequence::parse(sequence).unwrap().resolve(entry).unwrap();
+        let mut sink = RecordingSink::new();
+        run(&actions, &mut sink).unwrap();
+        sink
+    }
+
+    fn keys(sink: &RecordingSink) -> Vec<(Key, Modifiers)> {
+        sink.events
+            .iter()
+            .filter_map(|event| match event {
+                Recorded::Key(key, modifiers) => Some((*key, *modifiers)),
+                Recorded::Pause(_) => None,
+            })
+            .collect()
+    }
+
+    fn typed(text: &str) -> Vec<(Key, Modifiers)> {
+        text.chars().map(|c| (Key::Char(c), NONE)).collect()
+    }
+
+    #[test]
+    fn default_sequence_types_fields_literally() {
+        let sink = record(DEFAULT_SEQUENCE, &entry());
+        let mut expected = typed("ivan");
+        expected.push((Key::Tab, NONE));
+        // Спецсимволы в значении поля не разбираются
+        expected.extend(typed("p+{}"));
+        expected.push((Key::Enter, NONE));
+        assert_eq!(keys(&sink), expected);
+        // После каждого нажатия — пауза по умолчанию
+        assert_eq!(sink.total_pause(), DEFAULT_KEY_DELAY * sink.key_count() as u32);
+    }
+
+    #[test]
+    fn modifiers_repeat_and_escapes() {
+        let sink = record("^a{DEL}+{TAB 2}%{F4}{+}{{}~", &entry());
+        let ctrl = Modifiers { ctrl: true, ..NONE };
+        let shift = Modifiers { shift: true, ..NONE };
+        let alt = Modifiers { alt: true, ..NONE };
+        assert_eq!(
+            keys(&sink),
+            vec![
+                (Key::Char('a'), ctrl),
+                (Key::Delete, NONE),
+                (Key::Tab, shift),
+                (Key::Tab, shift),
+                (Key::F(4), alt),
+                (Key::Char('+'), NONE),
+                (Key::Char('{'), NONE),
+                (Key::Enter, NONE),
+            ]
+        );
+    }
+
+    #[test]
+    fn delays_are_recorded() {
+        let sink = record("{DELAY=0}a{DELAY 250}b", &entry());
+        assert_eq!(sink.key_count(), 2);
+        assert_eq!(sink.total_pause(), Duration::from_millis(250));
+        assert!(sink.events.contains(&Recorded::Pause(Duration::from_millis(250))));
+    }
+
+    #[test]
+    fn custom_fields_and_missing_fields() {
+        let sink = record("{S:ПИН}", &entry());
+        assert_eq!(keys(&sink), typed("42"));
+        let sequence = Sequence::parse("{S:Нет такого}").unwrap();
+        assert!(sequence.resolve(&entry()).is_err());
+    }
+
+    #[test]
+    fn display_is_canonical_and_reparses() {
+        for source in [DEFAULT_SEQUENCE, "^a{TAB 3}{DELAY 100}{DELAY=20}{S:ПИН}{+}x~", "{F12}%{UP}"] {
+            let sequence = Sequence::parse(source).unwrap();
+            let canonical = sequence.to_string();
+            assert_eq!(Sequence::parse(&canonical).unwrap(), sequence, "{}", canonical);
+        }
+        assert_eq!(Sequence::parse("~{bs}").unwrap().to_string(), "{ENTER}{BS}");
+    }
+
+    #[test]
+    fn invalid_sequences_are_rejected() {
+        for source in ["{TAB", "}", "(a)", "{NOPE}", "{F13}", "{TAB x}", "{DELAY x}", "+", "^{USERNAME}"] {
+            assert!(Sequence::parse(source).is_err(), "{}", source);
+        }
+    }
+}
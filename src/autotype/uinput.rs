@@ -0,0 +1,240 @@
+use super::{Key, KeySink, Modifiers};
+use nix::libc;
+use std::error::Error;
+use std::fs::{File, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::OpenOptionsExt;
+use std::thread;
+use std::time::Duration;
+
+const UINPUT_PATH: &str = "/dev/uinput";
+
+const EV_SYN: u16 = 0x00;
+const EV_KEY: u16 = 0x01;
+const SYN_REPORT: u16 = 0;
+const BUS_VIRTUAL: u16 = 0x06;
+
+const KEY_ESC: u16 = 1;
+const KEY_BACKSPACE: u16 = 14;
+const KEY_TAB: u16 = 15;
+const KEY_ENTER: u16 = 28;
+const KEY_LEFTCTRL: u16 = 29;
+const KEY_LEFTSHIFT: u16 = 42;
+const KEY_LEFTALT: u16 = 56;
+const KEY_SPACE: u16 = 57;
+const KEY_HOME: u16 = 102;
+const KEY_UP: u16 = 103;
+const KEY_PAGEUP: u16 = 104;
+const KEY_LEFT: u16 = 105;
+const KEY_RIGHT: u16 = 106;
+const KEY_END: u16 = 107;
+const KEY_DOWN: u16 = 108;
+const KEY_PAGEDOWN: u16 = 109;
+const KEY_INSERT: u16 = 110;
+const KEY_DELETE: u16 = 111;
+
+// Сколько ждать, пока композитор подхватит новое устройство
+const DEVICE_SETTLE: Duration = Duration::from_millis(200);
+
+#[repr(C)]
+struct InputId {
+    bustype: u16,
+    vendor: u16,
+    product: u16,
+    version: u16,
+}
+
+#[repr(C)]
+struct UinputSetup {
+    id: InputId,
+    name: [u8; 80],
+    ff_effects_max: u32,
+}
+
+nix::ioctl_none!(ui_dev_create, b'U', 1);
+nix::ioctl_none!(ui_dev_destroy, b'U', 2);
+nix::ioctl_write_ptr!(ui_dev_setup, b'U', 3, UinputSetup);
+nix::ioctl_write_int!(ui_set_evbit, b'U', 100);
+nix::ioctl_write_int!(ui_set_keybit, b'U', 101);
+
+/// Виртуальная клавиатура ядра. Работает и в Wayland, и в консоли, но
+/// отправляет коды клавиш, а не символы, поэтому рассчитана на раскладку US.
+pub struct UinputSink {
+    device: File,
+}
+
+impl UinputSink {
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        let device = OpenOptions::new()
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(UINPUT_PATH)
+            .map_err(|e| match e.kind() {
+                ErrorKind::PermissionDenied => format!(
+                    "Нет доступа к {}: добавьте пользователя в группу input или настройте правило udev",
+                    UINPUT_PATH
+                ),
+                _ => format!("Не удалось открыть {}: {}", UINPUT_PATH, e),
+            })?;
+
+        let mut setup = UinputSetup {
+            id: InputId {
+                bustype: BUS_VIRTUAL,
+                vendor: 0,
+                product: 0,
+                version: 1,
+            },
+            name: [0; 80],
+            ff_effects_max: 0,
+        };
+        let name = b"hiho autotype";
+        setup.name[..name.len()].copy_from_slice(name);
+
+        let fd = device.as_raw_fd();
+        // SAFETY: fd открыт на время вызовов, setup живёт до конца функции
+        unsafe {
+            ui_set_evbit(fd, EV_KEY.into())?;
+            ui_set_evbit(fd, EV_SYN.into())?;
+            for code in KEY_ESC..=KEY_DELETE {
+                ui_set_keybit(fd, code.into())?;
+            }
+            ui_dev_setup(fd, &setup)?;
+            ui_dev_create(fd)?;
+        }
+        thread::sleep(DEVICE_SETTLE);
+        Ok(Self { device })
+    }
+
+    fn emit(&mut self, kind: u16, code: u16, value: i32) -> Result<(), Box<dyn Error>> {
+        let event = libc::input_event {
+            time: libc::timeval { tv_sec: 0, tv_usec: 0 },
+            type_: kind,
+            code,
+            value,
+        };
+        // SAFETY: input_event — простая структура C без указателей
+        let bytes = unsafe {
+            std::slice::from_raw_parts(
+                &event as *const libc::input_event as *const u8,
+                std::mem::size_of::<libc::input_event>(),
+            )
+        };
+        self.device.write_all(bytes)?;
+        Ok(())
+    }
+
+    fn key(&mut self, code: u16, pressed: bool) -> Result<(), Box<dyn Error>> {
+        self.emit(EV_KEY, code, pressed as i32)?;
+        self.emit(EV_SYN, SYN_REPORT, 0)
+    }
+}
+
+impl KeySink for UinputSink {
+    fn press(&mut self, key: Key, modifiers: Modifiers) -> Result<(), Box<dyn Error>> {
+        let (code, shift) = key_code(key)
+            .ok_or("В тексте есть символ, которого нет в раскладке US; через uinput набирается только она")?;
+
+        let mut held = Vec::new();
+        if modifiers.shift || shift {
+            held.push(KEY_LEFTSHIFT);
+        }
+        if modifiers.ctrl {
+            held.push(KEY_LEFTCTRL);
+        }
+        if modifiers.alt {
+            held.push(KEY_LEFTALT);
+        }
+
+        for modifier in &held {
+            self.key(*modifier, true)?;
+        }
+        self.key(code, true)?;
+        self.key(code, false)?;
+        for modifier in held.iter().rev() {
+            self.key(*modifier, false)?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for UinputSink {
+    fn drop(&mut self) {
+        // SAFETY: устройство ещё открыто
+        unsafe {
+            let _ = ui_dev_destroy(self.device.as_raw_fd());
+        }
+    }
+}
+
+// Код клавиши в раскладке US и нужен ли Shift
+fn key_code(key: Key) -> Option<(u16, bool)> {
+    let code = match key {
+        Key::Char(c) => return char_code(c),
+        Key::Tab => KEY_TAB,
+        Key::Enter => KEY_ENTER,
+        Key::Space => KEY_SPACE,
+        Key::Backspace => KEY_BACKSPACE,
+        Key::Delete => KEY_DELETE,
+        Key::Escape => KEY_ESC,
+        Key::Up => KEY_UP,
+        Key::Down => KEY_DOWN,
+        Key::Left => KEY_LEFT,
+        Key::Right => KEY_RIGHT,
+        Key::Home => KEY_HOME,
+        Key::End => KEY_END,
+        Key::PageUp => KEY_PAGEUP,
+        Key::PageDown => KEY_PAGEDOWN,
+        Key::Insert => KEY_INSERT,
+        // F1–F10 идут подряд, F11 и F12 — отдельно
+        Key::F(number @ 1..=10) => 58 + u16::from(number),
+        Key::F(11) => 87,
+        Key::F(12) => 88,
+        Key::F(_) => return None,
+    };
+    Some((code, false))
+}
+
+fn char_code(c: char) -> Option<(u16, bool)> {
+    const ROWS: [(&str, &str, u16); 4] = [
+        ("1234567890-=", "!@#$%^&*()_+", 2),
+        ("qwertyuiop[]", "QWERTYUIOP{}", 16),
+        ("asdfghjkl;'`", "ASDFGHJKL:\"~", 30),
+        ("zxcvbnm,./", "ZXCVBNM<>?", 44),
+    ];
+    match c {
+        ' ' => return Some((KEY_SPACE, false)),
+        '\t' => return Some((KEY_TAB, false)),
+        '\n' => return Some((KEY_ENTER, false)),
+        '\\' => return Some((43, false)),
+        '|' => return Some((43, true)),
+        _ => {}
+    }
+    for (plain, shifted, first) in ROWS {
+        if let Some(i) = plain.chars().position(|p| p == c) {
+            return Some((first + i as u16, false));
+        }
+        if let Some(i) = shifted.chars().position(|p| p == c) {
+            return Some((first + i as u16, true));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn us_layout_codes() {
+        assert_eq!(char_code('a'), Some((30, false)));
+        assert_eq!(char_code('A'), Some((30, true)));
+        assert_eq!(char_code('1'), Some((2, false)));
+        assert_eq!(char_code('!'), Some((2, true)));
+        assert_eq!(char_code('|'), Some((43, true)));
+        assert_eq!(char_code('я'), None);
+        assert_eq!(key_code(Key::F(10)), Some((68, false)));
+        assert_eq!(key_code(Key::F(12)), Some((88, false)));
+        assert_eq!(key_code(Key::Tab), Some((KEY_TAB, false)));
+    }
+}
@@ -0,0 +1,176 @@
+use super::{Key, KeySink, Modifiers};
+use std::error::Error;
+use std::thread;
+use std::time::Duration;
+use x11rb::connection::{Connection, RequestConnection};
+use x11rb::protocol::xproto::{ConnectionExt, Keycode, Keysym, Window, KEY_PRESS_EVENT, KEY_RELEASE_EVENT};
+use x11rb::protocol::xtest::{self, ConnectionExt as _};
+use x11rb::rust_connection::RustConnection;
+use x11rb::CURRENT_TIME;
+
+const XK_SHIFT_L: Keysym = 0xffe1;
+const XK_CONTROL_L: Keysym = 0xffe3;
+const XK_ALT_L: Keysym = 0xffe9;
+
+// Сколько дать программам на то, чтобы заметить смену раскладки
+const REMAP_DELAY: Duration = Duration::from_millis(20);
+
+/// Нажатия через расширение XTEST. Символы, которых нет в текущей
+/// раскладке, набираются через временно переназначенную свободную клавишу
+/// (как это делает xdotool).
+pub struct XTestSink {
+    conn: RustConnection,
+    root: Window,
+    min_keycode: Keycode,
+    keysyms_per_keycode: u8,
+    // Раскладка: по `keysyms_per_keycode` значений на каждый keycode
+    keysyms: Vec<Keysym>,
+    // Keycode без символов, который можно переназначать
+    spare: Option<Keycode>,
+    remapped: bool,
+}
+
+impl XTestSink {
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        let (conn, screen_num) = x11rb::connect(None)
+            .map_err(|e| format!("Не удалось подключиться к X11: {}", e))?;
+        if conn.extension_information(xtest::X11_EXTENSION_NAME)?.is_none() {
+            return Err("Сервер X11 не поддерживает расширение XTEST".into());
+        }
+        let setup = conn.setup();
+        let root = setup.roots[screen_num].root;
+        let (min_keycode, max_keycode) = (setup.min_keycode, setup.max_keycode);
+        let mapping = conn
+            .get_keyboard_mapping(min_keycode, max_keycode - min_keycode + 1)?
+            .reply()?;
+        let per = mapping.keysyms_per_keycode.max(1) as usize;
+        let spare = mapping
+            .keysyms
+            .chunks(per)
+            .rposition(|syms| syms.iter().all(|sym| *sym == 0))
+            .map(|i| min_keycode + i as u8);
+
+        Ok(Self {
+            conn,
+            root,
+            min_keycode,
+            keysyms_per_keycode: per as u8,
+            keysyms: mapping.keysyms,
+            spare,
+            remapped: false,
+        })
+    }
+
+    // Keycode и нужен ли Shift: сначала ищем на первом уровне, потом на втором
+    fn find(&self, keysym: Keysym) -> Option<(Keycode, bool)> {
+        let per = self.keysyms_per_keycode as usize;
+        for (level, shift) in [(0, false), (1, true)] {
+            let found = self
+                .keysyms
+                .chunks(per)
+                .position(|syms| syms.get(level) == Some(&keysym));
+            if let Some(i) = found {
+                return Some((self.min_keycode + i as u8, shift));
+            }
+        }
+        None
+    }
+
+    fn keycode_for(&mut self, keysym: Keysym) -> Result<(Keycode, bool), Box<dyn Error>> {
+        if let Some(found) = self.find(keysym) {
+            return Ok(found);
+        }
+        let spare = self
+            .spare
+            .ok_or("В раскладке нет символа из последовательности и нет свободной клавиши для него")?;
+        let syms = vec![keysym; self.keysyms_per_keycode as usize];
+        self.set_mapping(spare, &syms)?;
+        thread::sleep(REMAP_DELAY);
+        self.remapped = true;
+        Ok((spare, false))
+    }
+
+    fn set_mapping(&mut self, keycode: Keycode, syms: &[Keysym]) -> Result<(), Box<dyn Error>> {
+        self.conn
+            .change_keyboard_mapping(1, keycode, self.keysyms_per_keycode, syms)?;
+        // Ответ на запрос гарантирует, что сервер уже применил раскладку
+        self.conn.get_input_focus()?.reply()?;
+        let start = (keycode - self.min_keycode) as usize * self.keysyms_per_keycode as usize;
+        self.keysyms[start..start + syms.len()].copy_from_slice(syms);
+        Ok(())
+    }
+
+    fn modifier(&self, keysym: Keysym) -> Result<Keycode, Box<dyn Error>> {
+        self.find(keysym)
+            .map(|(keycode, _)| keycode)
+            .ok_or_else(|| "В раскладке X11 нет клавиши-модификатора".into())
+    }
+
+    fn fake(&self, event: u8, keycode: Keycode) -> Result<(), Box<dyn Error>> {
+        self.conn
+            .xtest_fake_input(event, keycode, CURRENT_TIME, self.root, 0, 0, 0)?;
+        Ok(())
+    }
+}
+
+impl KeySink for XTestSink {
+    fn press(&mut self, key: Key, modifiers: Modifiers) -> Result<(), Box<dyn Error>> {
+        let (keycode, shift) = self.keycode_for(keysym(key))?;
+
+        let mut held = Vec::new();
+        if modifiers.shift || shift {
+            held.push(self.modifier(XK_SHIFT_L)?);
+        }
+        if modifiers.ctrl {
+            held.push(self.modifier(XK_CONTROL_L)?);
+        }
+        if modifiers.alt {
+            held.push(self.modifier(XK_ALT_L)?);
+        }
+
+        for modifier in &held {
+            self.fake(KEY_PRESS_EVENT, *modifier)?;
+        }
+        self.fake(KEY_PRESS_EVENT, keycode)?;
+        self.fake(KEY_RELEASE_EVENT, keycode)?;
+        for modifier in held.iter().rev() {
+            self.fake(KEY_RELEASE_EVENT, *modifier)?;
+        }
+        self.conn.flush()?;
+        Ok(())
+    }
+}
+
+impl Drop for XTestSink {
+    fn drop(&mut self) {
+        // Возвращаем свободной клавише пустую раскладку
+        if let (true, Some(spare)) = (self.remapped, self.spare) {
+            let empty = vec![0; self.keysyms_per_keycode as usize];
+            let _ = self.set_mapping(spare, &empty);
+        }
+    }
+}
+
+fn keysym(key: Key) -> Keysym {
+    match key {
+        Key::Char('\n') | Key::Enter => 0xff0d,
+        Key::Char('\t') | Key::Tab => 0xff09,
+        // Latin-1 совпадает с кодами символов, остальное — Unicode-keysym
+        Key::Char(c) if matches!(c as u32, 0x20..=0x7e | 0xa0..=0xff) => c as u32,
+        Key::Char(c) => 0x0100_0000 | c as u32,
+        Key::Space => 0x20,
+        Key::Backspace => 0xff08,
+        Key::Delete => 0xffff,
+        Key::Escape => 0xff1b,
+        Key::Home => 0xff50,
+        Key::Left => 0xff51,
+        Key::Up => 0xff52,
+        Key::Right => 0xff53,
+        Key::Down => 0xff54,
+        Key::PageUp => 0xff55,
+        Key::PageDown => 0xff56,
+        Key::End => 0xff57,
+        Key::Insert => 0xff63,
+        Key::F(number) => 0xffbe + u32::from(number) - 1,
+    }
+}
@@ -1,153 +0,0 @@
-use std::fs;
-use std::path::Path;
-use serde::{Deserialize, Serialize};
-use std::error::Error;
-
-#[allow(dead_code)]
-const BIOMETRIC_CONFIG: &str = "data\\biometric_config.json";
-#[allow(dead_code)]
-const MASTER_KEY_FILE: &str = "data\\master_key.enc";
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct BiometricConfig {
-    pub enabled: bool,
-    pub platform: String, // "windows", "macos", "linux"
-    pub key_id: Option<String>,
-}
-
-impl Default for BiometricConfig {
-    fn default() -> Self {
-        Self {
-            enabled: false,
-            platform: std::env::consts::OS.to_string(),
-            key_id: None,
-        }
-    }
-}
-
-pub struct BiometricManager;
-
-impl BiometricManager {
-    pub fn get_config() -> Result<BiometricConfig, Box<dyn Error>> {
-        let config_path = Path::new(BIOMETRIC_CONFIG);
-        if config_path.exists() {
-            let data = fs::read_to_string(config_path)?;
-            let config: BiometricConfig = serde_json::from_str(&data)?;
-            Ok(config)
-        } else {
-            let config = BiometricConfig::default();
-            Self::save_config(&config)?;
-            Ok(config)
-        }
-    }
-
-    pub fn save_config(config: &BiometricConfig) -> Result<(), Box<dyn Error>> {
-        let config_path = Path::new(BIOMETRIC_CONFIG);
-        std::fs::create_dir_all("data")?;
-        let json_data = serde_json::to_string_pretty(config)?;
-        fs::write(config_path, json_data)?;
-        Ok(())
-    }
-
-    pub fn enable_biometric() -> Result<(), Box<dyn Error>> {
-        let mut config = Self::get_config()?;
-        config.enabled = true;
-        Self::save_config(&config)?;
-        println!("✅ Биометрическая аутентификация включена");
-        Ok(())
-    }
-
-    pub fn disable_biometric() -> Result<(), Box<dyn Error>> {
-        let mut config = Self::get_config()?;
-        config.enabled = false;
-        Self::save_config(&config)?;
-        println!("🔓 Биометрическая аутентификация отключена");
-        Ok(())
-    }
-
-    #[cfg(target_os = "windows")]
-    pub fn authenticate(prompt: &str) -> Result<bool, Box<dyn Error>> {
-        // Заглушка для Windows Hello
-        println!("🔐 Запрос биометрической аутентификации (Windows Hello): {}", prompt);
-        
-        // Здесь должна быть настоящая реализация Windows Hello
-        // Пока возвращаем true для тестирования
-        Ok(true)
-    }
-
-    #[cfg(target_os = "macos")]
-    pub fn authenticate(prompt: &str) -> Result<bool, Box<dyn Error>> {
-        // Заглушка для Touch ID на macOS
-        println!("🔐 Запрос Touch ID аутентификации: {}", prompt);
-        
-        // Здесь должна быть настоящая реализация Touch ID
-        // Пока возвращаем true для тестирования
-        Ok(true)
-    }
-
-    #[cfg(target_os = "linux")]
-    pub fn authenticate(prompt: &str) -> Result<bool, Box<dyn Error>> {
-        // Заглушка для Linux (Fingerprint GUI или pam)
-        println!("🔐 Запрос биометрической аутентификации (Linux): {}", prompt);
-        
-        // Здесь должна быть настоящая реализация Linux биометрии
-        // Пока возвращаем true для тестирования
-        Ok(true)
-    }
-
-    #[allow(dead_code)]
-    pub fn store_master_key(master_password: &str) -> Result<(), Box<dyn Error>> {
-        // Здесь должна быть реализация безопасного хранения мастер-ключа
-        // с использованием платформенного хранилища ключей
-        
-        let key_path = Path::new(MASTER_KEY_FILE);
-        std::fs::create_dir_all("data")?;
-        
-        // В реальной реализации здесь будет шифрование мастер-пароля
-        // с использованием биометрического ключа платформы
-        let encrypted_key = format!("encrypted_{}", master_password);
-        fs::write(key_path, encrypted_key)?;
-        
-        Ok(())
-    }
-
-    #[allow(dead_code)]
-    pub fn retrieve_master_key() -> Result<Option<String>, Box<dyn Error>> {
-        let key_path = Path::new(MASTER_KEY_FILE);
-        if key_path.exists() {
-            let encrypted_key = fs::read_to_string(key_path)?;
-            // В реальной реализации здесь будет расшифровка ключа
-            // с использованием биометрической аутентификации
-            let master_key = encrypted_key.replace("encrypted_", "");
-            Ok(Some(master_key))
-        } else {
-            Ok(None)
-        }
-    }
-
-    pub fn is_available() -> bool {
-        // Проверяем доступность биометрического оборудования
-        #[cfg(target_os = "windows")]
-        {
-            // Проверка наличия Windows Hello
-            true // Заглушка
-        }
-        
-        #[cfg(target_os = "macos")]
-        {
-            // Проверка наличия Touch ID
-            true // Заглушка
-        }
-        
-        #[cfg(target_os = "linux")]
-        {
-            // Проверка наличия биометрического оборудования
-            false // Пока не реализовано
-        }
-        
-        #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
-        {
-            false
-        }
-    }
-}
\ No newline at end of file
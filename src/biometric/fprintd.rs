@@ -0,0 +1,109 @@
+//! Проверка отпечатка через fprintd (`net.reactivated.Fprint` на системной
+//! шине D-Bus) — тот же сервис, что используют pam_fprintd и GNOME.
+
+use super::Authenticator;
+use std::error::Error;
+use zbus::blocking::{Connection, MessageIterator};
+use zbus::zvariant::OwnedObjectPath;
+use zbus::{MatchRule, MessageType};
+
+const BUS_NAME: &str = "net.reactivated.Fprint";
+const MANAGER_PATH: &str = "/net/reactivated/Fprint/Manager";
+const MANAGER: &str = "net.reactivated.Fprint.Manager";
+const DEVICE: &str = "net.reactivated.Fprint.Device";
+
+pub struct FprintdAuthenticator {
+    connection: Connection,
+    device: OwnedObjectPath,
+}
+
+impl FprintdAuthenticator {
+    /// Подключается к сенсору по умолчанию.
+    pub fn connect() -> Result<Self, Box<dyn Error>> {
+        let connection = Connection::system()
+            .map_err(|e| format!("Не удалось подключиться к системной шине D-Bus: {}", e))?;
+        let device = connection
+            .call_method(Some(BUS_NAME), MANAGER_PATH, Some(MANAGER), "GetDefaultDevice", &())
+            .map_err(|e| match e {
+                zbus::Error::MethodError(name, _, _) if name.as_str() == "net.reactivated.Fprint.Error.NoSuchDevice" => {
+                    "Сканер отпечатков не найден".to_string()
+                }
+                e => format!("fprintd недоступен: {}", e),
+            })?
+            .body::<OwnedObjectPath>()?;
+        Ok(Self { connection, device })
+    }
+
+    fn call<B>(&self, method: &str, body: &B) -> zbus::Result<std::sync::Arc<zbus::Message>>
+    where
+        B: serde::Serialize + zbus::zvariant::DynamicType,
+    {
+        self.connection
+            .call_method(Some(BUS_NAME), self.device.as_str(), Some(DEVICE), method, body)
+    }
+
+    fn enrolled(&self) -> Result<Vec<String>, Box<dyn Error>> {
+        // Пустое имя — пользователь, от имени которого идёт вызов
+        match self.call("ListEnrolledFingers", &("",)) {
+            Ok(reply) => Ok(reply.body()?),
+            Err(zbus::Error::MethodError(name, _, _)) if name.as_str() == "net.reactivated.Fprint.Error.NoEnrolledPrints" => {
+                Ok(Vec::new())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Ждёт окончательного результата `VerifyStatus`.
+    fn wait_for_match(&self, statuses: &mut MessageIterator) -> Result<bool, Box<dyn Error>> {
+        for message in statuses {
+            let (result, done): (String, bool) = message?.body()?;
+            match result.as_str() {
+                "verify-match" => return Ok(true),
+                "verify-no-match" => return Ok(false),
+                "verify-retry-scan" => eprintln!("👆 Приложите палец ещё раз"),
+                "verify-swipe-too-short" => eprintln!("👆 Слишком короткое движение, ещё раз"),
+                "verify-finger-not-centered" => eprintln!("👆 Палец не по центру, ещё раз"),
+                "verify-remove-and-retry" => eprintln!("👆 Уберите палец и приложите снова"),
+                "verify-disconnected" => return Err("Сканер отпечатков отключён".into()),
+                other if done => return Err(format!("Проверка отпечатка не удалась: {}", other).into()),
+                _ => {}
+            }
+        }
+        Err("fprintd закрыл соединение".into())
+    }
+}
+
+impl Authenticator for FprintdAuthenticator {
+    fn describe(&self) -> String {
+        "fprintd".to_string()
+    }
+
+    fn is_available(&self) -> bool {
+        self.enrolled().is_ok_and(|fingers| !fingers.is_empty())
+    }
+
+    fn verify(&self, prompt: &str) -> Result<bool, Box<dyn Error>> {
+        if self.enrolled()?.is_empty() {
+            return Err("Нет записанных отпечатков (fprintd-enroll)".into());
+        }
+        self.call("Claim", &("",)).map_err(|e| format!("Сканер занят: {}", e))?;
+
+        let rule = MatchRule::builder()
+            .msg_type(MessageType::Signal)
+            .interface(DEVICE)?
+            .member("VerifyStatus")?
+            .path(self.device.as_str())?
+            .build();
+        let result = MessageIterator::for_match_rule(rule, &self.connection, None)
+            .map_err(Into::into)
+            .and_then(|mut statuses| {
+                self.call("VerifyStart", &("any",))?;
+                eprintln!("👆 {}: приложите палец к сканеру", prompt);
+                let matched = self.wait_for_match(&mut statuses);
+                let _ = self.call("VerifyStop", &());
+                matched
+            });
+        let _ = self.call("Release", &());
+        result
+    }
+}
@@ -0,0 +1,155 @@
+//! Биометрическая проверка перед выдачей запомненного ключа хранилища.
+//!
+//! Сама проверка ничего не расшифровывает: она лишь разрешает взять ключ
+//! обёртки из хранилища ключей ОС (см. `remember`). В Linux отпечаток
+//! проверяет fprintd; в тестах его заменяет `MockAuthenticator`.
+
+#[cfg(target_os = "linux")]
+mod fprintd;
+
+#[cfg(target_os = "linux")]
+pub use fprintd::FprintdAuthenticator;
+
+use crate::keystore;
+use crate::remember::RememberManager;
+use std::fs;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+const BIOMETRIC_CONFIG: &str = "data\\biometric_config.json";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BiometricConfig {
+    pub enabled: bool,
+    pub platform: String, // "windows", "macos", "linux"
+    pub key_id: Option<String>,
+}
+
+impl Default for BiometricConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            platform: std::env::consts::OS.to_string(),
+            key_id: None,
+        }
+    }
+}
+
+/// Способ подтвердить, что за устройством его владелец.
+pub trait Authenticator {
+    /// Название для сообщений, например «fprintd»
+    fn describe(&self) -> String;
+
+    /// Есть ли сканер и записанные отпечатки.
+    fn is_available(&self) -> bool;
+
+    /// Просит пользователя подтвердить личность; `false` — не подтвердил.
+    fn verify(&self, prompt: &str) -> Result<bool, Box<dyn Error>>;
+}
+
+/// Сканер для тестов: всегда отвечает заданным результатом и считает
+/// проверки (счётчик общий у копий).
+#[cfg(test)]
+#[derive(Clone)]
+pub(crate) struct MockAuthenticator {
+    pub matches: bool,
+    pub calls: std::rc::Rc<std::cell::Cell<usize>>,
+}
+
+#[cfg(test)]
+impl MockAuthenticator {
+    pub(crate) fn new(matches: bool) -> Self {
+        Self {
+            matches,
+            calls: Default::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl Authenticator for MockAuthenticator {
+    fn describe(&self) -> String {
+        "мок".to_string()
+    }
+
+    fn is_available(&self) -> bool {
+        true
+    }
+
+    fn verify(&self, _prompt: &str) -> Result<bool, Box<dyn Error>> {
+        self.calls.set(self.calls.get() + 1);
+        Ok(self.matches)
+    }
+}
+
+pub struct BiometricManager;
+
+impl BiometricManager {
+    pub fn get_config() -> Result<BiometricConfig, Box<dyn Error>> {
+        let config_path = Path::new(BIOMETRIC_CONFIG);
+        if config_path.exists() {
+            let data = fs::read_to_string(config_path)?;
+            let config: BiometricConfig = serde_json::from_str(&data)?;
+            Ok(config)
+        } else {
+            let config = BiometricConfig::default();
+            Self::save_config(&config)?;
+            Ok(config)
+        }
+    }
+
+    pub fn save_config(config: &BiometricConfig) -> Result<(), Box<dyn Error>> {
+        let config_path = Path::new(BIOMETRIC_CONFIG);
+        std::fs::create_dir_all("data")?;
+        let json_data = serde_json::to_string_pretty(config)?;
+        fs::write(config_path, json_data)?;
+        Ok(())
+    }
+
+    /// Проверка личности этой системы: fprintd в Linux.
+    pub fn authenticator() -> Result<Box<dyn Authenticator>, Box<dyn Error>> {
+        #[cfg(target_os = "linux")]
+        {
+            Ok(Box::new(FprintdAuthenticator::connect()?))
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            Err("Биометрия пока поддерживается только в Linux (fprintd)".into())
+        }
+    }
+
+    /// Включает вход по биометрии для хранилища `vault` (абсолютный путь):
+    /// после проверки отпечатка ключ хранилища запоминается так, что
+    /// выдаётся только после новой успешной проверки. Ключ должен быть
+    /// уже проверен вызывающим.
+    pub fn enable_biometric(vault: &Path, vault_key: &[u8; 32]) -> Result<(), Box<dyn Error>> {
+        if !Self::authenticate("Подтвердите личность, чтобы включить вход по биометрии")? {
+            return Err("Биометрическая проверка не пройдена".into());
+        }
+        let store = keystore::platform()?;
+        RememberManager::remember(store.as_ref(), vault, vault_key, true)?;
+        let mut config = Self::get_config()?;
+        config.enabled = true;
+        Self::save_config(&config)
+    }
+
+    /// Выключает биометрию и забывает ключ хранилища, выданный под неё.
+    pub fn disable_biometric(vault: &Path) -> Result<(), Box<dyn Error>> {
+        let mut config = Self::get_config()?;
+        config.enabled = false;
+        Self::save_config(&config)?;
+        if RememberManager::find(vault)?.is_some_and(|remembered| remembered.biometric) {
+            RememberManager::forget(keystore::platform()?.as_ref(), vault)?;
+        }
+        Ok(())
+    }
+
+    pub fn authenticate(prompt: &str) -> Result<bool, Box<dyn Error>> {
+        Self::authenticator()?.verify(prompt)
+    }
+
+    pub fn is_available() -> bool {
+        Self::authenticator().is_ok_and(|authenticator| authenticator.is_available())
+    }
+}
@@ -0,0 +1,360 @@
+use crate::entry::Entry;
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// Длина префикса SHA-1, который уходит в источник (как в range API HIBP).
+pub const PREFIX_LEN: usize = 5;
+
+#[derive(Debug, Clone)]
+pub struct RangeEntry {
+    /// Оставшиеся 35 символов SHA-1 в верхнем регистре
+    pub suffix: String,
+    pub count: u64,
+}
+
+/// Источник утёкших хешей, отвечающий на запрос по префиксу SHA-1.
+///
+/// Запрос по префиксу устроен так же, как k-анонимный range API Have I Been
+/// Pwned: источник никогда не видит полный хеш, поэтому онлайн-реализацию
+/// можно добавить без изменений в остальном коде.
+pub trait PwnedRangeSource {
+    fn range(&mut self, prefix: &str) -> Result<Vec<RangeEntry>, Box<dyn Error>>;
+}
+
+/// Один большой файл Pwned Passwords, отсортированный по хешу
+/// (строки вида `SHA1:COUNT`). Поиск — двоичный, по смещениям в файле.
+pub struct SortedHashFile {
+    reader: BufReader<File>,
+    len: u64,
+}
+
+impl SortedHashFile {
+    pub fn open(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let file = File::open(path)
+            .map_err(|e| format!("Не удалось открыть базу утечек {}: {}", path.display(), e))?;
+        let len = file.metadata()?.len();
+        let mut source = Self {
+            reader: BufReader::new(file),
+            len,
+        };
+        source.check_sorted()?;
+        Ok(source)
+    }
+
+    // Файл, отсортированный по частоте, а не по хешу, выдаст ложные «не найдено»
+    fn check_sorted(&mut self) -> Result<(), Box<dyn Error>> {
+        self.reader.seek(SeekFrom::Start(0))?;
+        let mut previous: Option<String> = None;
+        for _ in 0..16 {
+            let mut line = String::new();
+            if self.reader.read_line(&mut line)? == 0 {
+                break;
+            }
+            let hash = parse_line(&line)
+                .ok_or_else(|| format!("Неизвестный формат базы утечек: {:?}", line.trim()))?
+                .0;
+            if let Some(previous) = &previous {
+                if hash < *previous {
+                    return Err("База утечек должна быть отсортирована по хешу (ordered by hash)".into());
+                }
+            }
+            previous = Some(hash);
+        }
+        Ok(())
+    }
+
+    // Начало первой строки, начинающейся в позиции `pos` или позже
+    fn line_start_at_or_after(&mut self, pos: u64) -> Result<Option<u64>, Box<dyn Error>> {
+        if pos == 0 {
+            return Ok(Some(0));
+        }
+        self.reader.seek(SeekFrom::Start(pos - 1))?;
+        let mut skipped = Vec::new();
+        let read = self.reader.read_until(b'\n', &mut skipped)?;
+        let start = pos - 1 + read as u64;
+        if read == 0 || start >= self.len {
+            Ok(None)
+        } else {
+            Ok(Some(start))
+        }
+    }
+
+    fn read_line_at(&mut self, pos: u64) -> Result<String, Box<dyn Error>> {
+        self.reader.seek(SeekFrom::Start(pos))?;
+        let mut line = String::new();
+        self.reader.read_line(&mut line)?;
+        Ok(line)
+    }
+}
+
+impl PwnedRangeSource for SortedHashFile {
+    fn range(&mut self, prefix: &str) -> Result<Vec<RangeEntry>, Box<dyn Error>> {
+        let prefix = prefix.to_ascii_uppercase();
+
+        // Ищем первую строку, хеш которой не меньше префикса
+        let (mut lo, mut hi) = (0u64, self.len);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let is_before = match self.line_start_at_or_after(mid)? {
+                Some(start) => {
+                    let line = self.read_line_at(start)?;
+                    let hash = line.get(..PREFIX_LEN).unwrap_or("").to_ascii_uppercase();
+                    hash < prefix
+                }
+                None => false,
+            };
+            if is_before {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+
+        let mut entries = Vec::new();
+        let start = match self.line_start_at_or_after(lo)? {
+            Some(start) => start,
+            None => return Ok(entries),
+        };
+        self.reader.seek(SeekFrom::Start(start))?;
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if self.reader.read_line(&mut line)? == 0 {
+                break;
+            }
+            let (hash, count) = match parse_line(&line) {
+                Some(parsed) => parsed,
+                None => continue,
+            };
+            if !hash.starts_with(&prefix) {
+                break;
+            }
+            entries.push(RangeEntry {
+                suffix: hash[PREFIX_LEN..].to_string(),
+                count,
+            });
+        }
+        Ok(entries)
+    }
+}
+
+/// Каталог с файлами диапазонов `ABCDE.txt` (формат range API: `SUFFIX:COUNT`),
+/// как его сохраняет официальный загрузчик HIBP.
+pub struct RangeDirectory {
+    dir: PathBuf,
+}
+
+impl RangeDirectory {
+    pub fn open(dir: &Path) -> Result<Self, Box<dyn Error>> {
+        if !dir.is_dir() {
+            return Err(format!("{} не является каталогом", dir.display()).into());
+        }
+        Ok(Self { dir: dir.to_path_buf() })
+    }
+}
+
+impl PwnedRangeSource for RangeDirectory {
+    fn range(&mut self, prefix: &str) -> Result<Vec<RangeEntry>, Box<dyn Error>> {
+        let path = self.dir.join(format!("{}.txt", prefix.to_ascii_uppercase()));
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let mut content = String::new();
+        File::open(&path)?.read_to_string(&mut content)?;
+        Ok(content
+            .lines()
+            .filter_map(parse_line)
+            .map(|(suffix, count)| RangeEntry { suffix, count })
+            .collect())
+    }
+}
+
+/// Открывает базу утечек: файл целиком или каталог с диапазонами.
+pub fn open_source(path: &Path) -> Result<Box<dyn PwnedRangeSource>, Box<dyn Error>> {
+    let metadata = fs::metadata(path)
+        .map_err(|e| format!("База утечек {} недоступна: {}", path.display(), e))?;
+    if metadata.is_dir() {
+        Ok(Box::new(RangeDirectory::open(path)?))
+    } else {
+        Ok(Box::new(SortedHashFile::open(path)?))
+    }
+}
+
+fn parse_line(line: &str) -> Option<(String, u64)> {
+    let (hash, count) = line.trim().split_once(':')?;
+    if hash.is_empty() || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some((hash.to_ascii_uppercase(), count.trim().parse().ok()?))
+}
+
+pub fn sha1_hex(password: &str) -> String {
+    let digest = Sha1::digest(password.as_bytes());
+    digest.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+/// Проверяет пароли через источник, кешируя ответы по префиксам.
+pub struct BreachChecker<'a> {
+    source: &'a mut dyn PwnedRangeSource,
+    cache: HashMap<String, Vec<RangeEntry>>,
+}
+
+impl<'a> BreachChecker<'a> {
+    pub fn new(source: &'a mut dyn PwnedRangeSource) -> Self {
+        Self {
+            source,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Сколько раз пароль встречался в утечках (0 — не найден).
+    pub fn pwned_count(&mut self, password: &str) -> Result<u64, Box<dyn Error>> {
+        let hash = sha1_hex(password);
+        let (prefix, suffix) = hash.split_at(PREFIX_LEN);
+        if !self.cache.contains_key(prefix) {
+            let range = self.source.range(prefix)?;
+            self.cache.insert(prefix.to_string(), range);
+        }
+        Ok(self.cache[prefix]
+            .iter()
+            .find(|entry| entry.suffix == suffix)
+            .map(|entry| entry.count)
+            .unwrap_or(0))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BreachResult {
+    /// Индекс записи, начиная с 0
+    pub index: usize,
+    pub count: u64,
+}
+
+/// Возвращает только те записи, пароли которых найдены в базе утечек.
+pub fn check_entries(
+    entries: &[Entry],
+    source: &mut dyn PwnedRangeSource,
+) -> Result<Vec<BreachResult>, Box<dyn Error>> {
+    let mut checker = BreachChecker::new(source);
+    let mut results = Vec::new();
+    for (index, entry) in entries.iter().enumerate() {
+        if entry.password.is_empty() {
+            continue;
+        }
+        let count = checker.pwned_count(&entry.password)?;
+        if count > 0 {
+            results.push(BreachResult { index, count });
+        }
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn hash_file(lines: &[String]) -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        for line in lines {
+            writeln!(file, "{}", line).unwrap();
+        }
+        file
+    }
+
+    // Отсортированный файл: соседние префиксы, несколько хешей на префикс
+    fn sample_lines() -> Vec<String> {
+        let mut lines: Vec<String> = ["password", "123456", "qwerty", "letmein", "hunter2"]
+            .iter()
+            .enumerate()
+            .map(|(i, password)| format!("{}:{}", sha1_hex(password), i + 1))
+            .collect();
+        lines.push(format!("00000{}:7", "0".repeat(35)));
+        lines.push(format!("5BAA6{}:8", "0".repeat(35)));
+        lines.push(format!("5BAA6{}:9", "F".repeat(35)));
+        lines.push(format!("FFFFF{}:10", "F".repeat(35)));
+        lines.sort();
+        lines
+    }
+
+    #[test]
+    fn finds_every_password_in_sorted_file() {
+        let file = hash_file(&sample_lines());
+        let mut source = SortedHashFile::open(file.path()).unwrap();
+        let mut checker = BreachChecker::new(&mut source);
+        assert_eq!(checker.pwned_count("password").unwrap(), 1);
+        assert_eq!(checker.pwned_count("123456").unwrap(), 2);
+        assert_eq!(checker.pwned_count("qwerty").unwrap(), 3);
+        assert_eq!(checker.pwned_count("letmein").unwrap(), 4);
+        assert_eq!(checker.pwned_count("hunter2").unwrap(), 5);
+        assert_eq!(checker.pwned_count("not in the list").unwrap(), 0);
+    }
+
+    #[test]
+    fn range_returns_all_suffixes_of_prefix() {
+        let file = hash_file(&sample_lines());
+        let mut source = SortedHashFile::open(file.path()).unwrap();
+        // SHA-1 «password» начинается с 5BAA6
+        let range = source.range("5baa6").unwrap();
+        assert_eq!(range.len(), 3);
+        assert!(range.iter().all(|entry| entry.suffix.len() == 35));
+        assert_eq!(range.iter().map(|entry| entry.count).sum::<u64>(), 1 + 8 + 9);
+    }
+
+    #[test]
+    fn first_and_last_lines_are_reachable() {
+        let file = hash_file(&sample_lines());
+        let mut source = SortedHashFile::open(file.path()).unwrap();
+        assert_eq!(source.range("00000").unwrap()[0].count, 7);
+        assert_eq!(source.range("FFFFF").unwrap()[0].count, 10);
+        assert!(source.range("ABCDE").unwrap().is_empty());
+    }
+
+    #[test]
+    fn handles_crlf_and_missing_trailing_newline() {
+        let lines = sample_lines();
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        write!(file, "{}", lines.join("\r\n")).unwrap();
+        let mut source = SortedHashFile::open(file.path()).unwrap();
+        let mut checker = BreachChecker::new(&mut source);
+        assert_eq!(checker.pwned_count("hunter2").unwrap(), 5);
+        assert_eq!(checker.pwned_count("password").unwrap(), 1);
+    }
+
+    #[test]
+    fn rejects_file_ordered_by_count() {
+        let mut lines = sample_lines();
+        lines.reverse();
+        let file = hash_file(&lines);
+        assert!(SortedHashFile::open(file.path()).is_err());
+    }
+
+    #[test]
+    fn range_directory_matches_sorted_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let hash = sha1_hex("password");
+        fs::write(dir.path().join(format!("{}.txt", &hash[..PREFIX_LEN])), format!("{}:42\r\n", &hash[PREFIX_LEN..])).unwrap();
+        let mut source = open_source(dir.path()).unwrap();
+        let mut checker = BreachChecker::new(source.as_mut());
+        assert_eq!(checker.pwned_count("password").unwrap(), 42);
+        assert_eq!(checker.pwned_count("123456").unwrap(), 0);
+    }
+
+    #[test]
+    fn check_entries_skips_empty_passwords() {
+        let file = hash_file(&sample_lines());
+        let mut source = SortedHashFile::open(file.path()).unwrap();
+        let entries = [
+            Entry::new("a".into(), "u".into(), String::new()),
+            Entry::new("b".into(), "u".into(), "qwerty".into()),
+            Entry::new("c".into(), "u".into(), "Unique-Strong-Passphrase-88".into()),
+        ];
+        let results = check_entries(&entries, &mut source).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!((results[0].index, results[0].count), (1, 3));
+    }
+}
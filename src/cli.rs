@@ -1,9 +1,27 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
+use hiho::filter::{parse_since, EntryFilter};
+use hiho::EntryKind;
+use crate::output::OutputFormat;
+use std::error::Error;
+use std::path::PathBuf;
+
+pub const DEFAULT_VAULT_FILE: &str = "data\\vault.enc";
 
 #[derive(Parser)]
 #[command(name = "hiho")]
 #[command(about = "Менеджер паролей уровня NSA", version = "0.1.1")]
 pub struct Cli {
+    /// Файл хранилища (vault.enc или база KeePass .kdbx)
+    #[arg(long, global = true, default_value = DEFAULT_VAULT_FILE)]
+    pub vault: String,
+    
+    /// Формат вывода: plain (для человека), json или tsv (для скриптов)
+    #[arg(long, global = true, value_enum, default_value = "plain")]
+    pub output: OutputFormat,
+    
+    #[command(flatten)]
+    pub password: PasswordArgs,
+    
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -25,9 +43,37 @@ pub enum Commands {
         /// Длина пароля (если генерируем)
         #[arg(long, default_value = "16")]
         length: usize,
+        
+        /// Адрес сайта (можно указать несколько раз)
+        #[arg(long)]
+        url: Vec<String>,
+        
+        /// Заметки
+        #[arg(long)]
+        notes: Option<String>,
+        
+        /// Папка
+        #[arg(long)]
+        folder: Option<String>,
+        
+        /// Метка (можно указать несколько раз)
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+        
+        /// Секрет TOTP (base32) или ссылка otpauth://
+        #[arg(long)]
+        totp: Option<String>,
+        
+        /// Последовательность автонабора, например "{USERNAME}{TAB}{PASSWORD}{ENTER}"
+        #[arg(long)]
+        auto_type: Option<String>,
     },
     /// Показать все записи
-    List,
+    List {
+        /// Показать пароли в открытом виде
+        #[arg(long)]
+        show_passwords: bool,
+    },
     /// Инициализировать хранилище
     Init,
     /// Сгенерировать пароль
@@ -40,10 +86,101 @@ pub enum Commands {
         #[arg(short, long)]
         secure: bool,
     },
-    /// Копировать пароль в буфер обмена
+    /// Помощник учётных данных git (credential.helper): get, store или erase.
+    /// Работает через запущенный и открытый агент
+    GitCredential {
+        /// Операция, которую передаёт git
+        operation: String,
+    },
+    /// Сервис freedesktop Secret Service (org.freedesktop.secrets) для
+    /// libsecret и Python keyring. Работает через агент
+    SecretService {
+        /// Папка хранилища, которая видна через D-Bus
+        #[arg(long, default_value = "Secret Service")]
+        folder: String,
+    },
+    /// SSH-ключи в хранилище; агент отдаёт их через SSH_AUTH_SOCK
+    Ssh {
+        #[command(subcommand)]
+        action: SshAction,
+    },
+    /// Фоновый агент, который держит хранилище открытым между вызовами
+    Agent {
+        #[command(subcommand)]
+        action: AgentAction,
+    },
+    /// Оценить стойкость пароля. Пароль запрашивается без отображения
+    /// или читается из первой строки stdin, если он перенаправлен
+    Strength {
+        /// Слова, которые атакующий может знать (имя сервиса, логин)
+        #[arg(short, long = "user-input")]
+        user_inputs: Vec<String>,
+    },
+    /// Проверить все записи на слабые, повторяющиеся и устаревшие пароли
+    Audit {
+        /// Считать устаревшими пароли старше указанного числа дней
+        #[arg(long, default_value = "365")]
+        max_age_days: i64,
+        
+        /// Устарело: используйте --output (text соответствует plain)
+        #[arg(short, long, hide = true)]
+        format: Option<String>,
+        
+        /// Дополнительно проверить пароли по локальной базе утечек HIBP
+        #[arg(long)]
+        breach_db: Option<String>,
+    },
+    /// Проверить пароли по локальной базе утечек Have I Been Pwned
+    BreachCheck {
+        /// Файл Pwned Passwords (SHA-1, ordered by hash) или каталог с диапазонами
+        #[arg(long)]
+        db: String,
+        
+        /// Устарело: используйте --output (text соответствует plain)
+        #[arg(short, long, hide = true)]
+        format: Option<String>,
+    },
+    /// Копировать пароль или другое поле в буфер обмена
     Copy {
         /// Название сервиса или номер записи
         name_or_index: String,
+        
+        /// Поле (username, password, url, notes, totp или доп. поле); по умолчанию пароль
+        #[arg(short, long)]
+        field: Option<String>,
+        
+        /// Скопировать логин, а после его вставки — пароль
+        #[arg(long, conflicts_with = "field")]
+        sequence: bool,
+        
+        /// Скопировать в выделение PRIMARY (вставка средней кнопкой мыши, Linux)
+        #[arg(long)]
+        primary: bool,
+    },
+    /// Набрать логин и пароль в активном окне (автонабор, Linux)
+    AutoType {
+        /// Название сервиса или номер записи
+        name_or_index: String,
+        
+        /// Последовательность вместо заданной в записи
+        #[arg(long)]
+        sequence: Option<String>,
+        
+        /// Сколько секунд дать на переключение в нужное окно
+        #[arg(long, default_value = "3")]
+        delay: u64,
+        
+        /// Только проверить последовательность, ничего не набирая
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Показать запись; с --field выводит только значение поля
+    Show {
+        /// Название сервиса или номер записи
+        name_or_index: String,
+        /// Поле (username, password, url, notes, totp или доп. поле)
+        #[arg(long)]
+        field: Option<String>,
     },
     /// Удалить запись
     Remove {
@@ -52,8 +189,16 @@ pub enum Commands {
     },
     /// Поиск записей по части имени
     Search {
-        /// Часть названия для поиска
-        query: String,
+        /// Запрос: слова, поля (user:alice tag:prod url:github.com), OR, NOT, /регулярка/
+        #[arg(allow_hyphen_values = true)]
+        query: Vec<String>,
+        
+        /// Показать пароли в открытом виде
+        #[arg(long)]
+        show_passwords: bool,
+        
+        #[command(flatten)]
+        filter: FilterArgs,
     },
     /// Редактировать запись
     Edit {
@@ -68,16 +213,35 @@ pub enum Commands {
         /// Длина нового пароля (если генерируем)
         #[arg(long, default_value = "16")]
         length: usize,
+        /// Последовательность автонабора; пустая строка — по умолчанию
+        #[arg(long)]
+        auto_type: Option<String>,
     },
     /// Экспорт данных в файл
     Export {
-        /// Путь к файлу для экспорта
-        #[arg(short, long, default_value = "hiho_export.json")]
-        file: String,
+        /// Путь к файлу для экспорта (по умолчанию hiho_export.enc, .json или .csv)
+        #[arg(short, long)]
+        file: Option<String>,
         
-        /// Формат экспорта (json, csv)
-        #[arg(short, long, default_value = "json")]
+        /// Формат экспорта (encrypted, json, csv)
+        #[arg(long, default_value = "encrypted")]
         format: String,
+        
+        /// Защитить экспорт ключевым файлом вместо пароля (создаётся, если его нет)
+        #[arg(long)]
+        key_file: Option<String>,
+        
+        /// Подтвердить экспорт паролей в открытом виде (json, csv)
+        #[arg(long)]
+        plaintext: bool,
+        
+        /// Какие поля выгружать, через запятую (name, username, password, url, notes,
+        /// folder, tags, totp, fields, attachments, history, dates); по умолчанию все
+        #[arg(long, value_delimiter = ',')]
+        fields: Vec<String>,
+        
+        #[command(flatten)]
+        filter: FilterArgs,
     },
     /// Импорт данных из файла
     Import {
@@ -85,9 +249,21 @@ pub enum Commands {
         #[arg(short, long)]
         file: String,
         
-        /// Формат импорта (json, csv)
-        #[arg(short, long, default_value = "json")]
+        /// Формат импорта (auto, encrypted, json, csv, bitwarden, keepass-xml, 1pux, lastpass, chrome, firefox)
+        #[arg(long, default_value = "auto")]
         format: String,
+        
+        /// Показать, что будет импортировано, ничего не сохраняя
+        #[arg(long)]
+        dry_run: bool,
+        
+        /// Ключевой файл зашифрованного экспорта
+        #[arg(long)]
+        key_file: Option<String>,
+        
+        /// Что делать с дубликатами (skip, overwrite, keep-both, newest)
+        #[arg(long, default_value = "skip")]
+        on_duplicate: String,
     },
     /// Настройка автоблокировки
     AutoLock {
@@ -98,9 +274,48 @@ pub enum Commands {
         /// Показать текущую конфигурацию
         #[arg(short, long)]
         show: bool,
+        
+        /// Блокировать при переходе системы в сон
+        #[arg(long, value_name = "true|false")]
+        on_sleep: Option<bool>,
+        
+        /// Блокировать при блокировке экрана
+        #[arg(long, value_name = "true|false")]
+        on_screen_lock: Option<bool>,
+    },
+    /// Настройка очистки буфера обмена после копирования пароля
+    Clipboard {
+        /// Очищать буфер через столько секунд (0 для отключения)
+        #[arg(short, long)]
+        clear_after: Option<u64>,
+        
+        /// Возвращать в буфер прежнее содержимое
+        #[arg(long, value_name = "true|false")]
+        restore: Option<bool>,
+        
+        /// Буфер по умолчанию: clipboard или primary (только Linux)
+        #[arg(long)]
+        selection: Option<String>,
+        
+        /// Показать текущую конфигурацию
+        #[arg(short, long)]
+        show: bool,
     },
-    /// Разблокировать сессию
+    /// Открыть хранилище в агенте (запрашивает мастер-пароль)
     Unlock,
+    /// Закрыть хранилище в агенте: ключ и записи удаляются из памяти
+    Lock,
+    /// Вход без мастер-пароля на этом устройстве: ключ хранилища
+    /// в хранилище ключей ОС (Secret Service)
+    Remember {
+        #[command(subcommand)]
+        action: RememberAction,
+    },
+    /// Второй фактор разблокировки: YubiKey (HMAC-SHA1 challenge-response)
+    SecondFactor {
+        #[command(subcommand)]
+        action: SecondFactorAction,
+    },
     /// Настройка биометрической аутентификации
     Biometric {
     /// Включить биометрическую аутентификацию
@@ -119,4 +334,140 @@ pub enum Commands {
     #[arg(short, long)]
     test: bool,
 },
-}
\ No newline at end of file
+}
+#[derive(Subcommand)]
+pub enum SshAction {
+    /// Добавить закрытый ключ OpenSSH из файла
+    Add {
+        /// Название записи
+        #[arg(short, long)]
+        name: String,
+        /// Файл ключа, например ~/.ssh/id_ed25519
+        file: PathBuf,
+        /// Спрашивать подтверждение (через SSH_ASKPASS) перед каждой подписью
+        #[arg(long)]
+        confirm: bool,
+        /// Папка
+        #[arg(long)]
+        folder: Option<String>,
+    },
+    /// Показать SSH-ключи хранилища
+    List,
+    /// Вывести открытый ключ для authorized_keys
+    PublicKey {
+        /// Название записи или её номер
+        name_or_index: String,
+    },
+    /// Включить или выключить подтверждение каждой подписи
+    Confirm {
+        /// Название записи или её номер
+        name_or_index: String,
+        #[arg(value_name = "true|false", action = clap::ArgAction::Set)]
+        enabled: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum RememberAction {
+    /// Запомнить ключ хранилища (запрашивает мастер-пароль)
+    Enable,
+    /// Забыть ключ и удалить его из хранилища ключей ОС
+    Disable,
+    /// Запомнено ли хранилище
+    Status,
+}
+
+#[derive(Subcommand)]
+pub enum SecondFactorAction {
+    /// Включить: хранилище перешифровывается ключом с ответом YubiKey
+    Enable {
+        /// Слот YubiKey с HMAC-SHA1 challenge-response
+        #[arg(long, default_value_t = 2, value_parser = clap::value_parser!(u8).range(1..=2))]
+        slot: u8,
+    },
+    /// Выключить: хранилище снова открывается одним мастер-паролем
+    Disable,
+    /// Включён ли второй фактор
+    Status,
+}
+
+#[derive(Subcommand)]
+pub enum AgentAction {
+    /// Запустить агент в фоне
+    Start,
+    /// Запустить агент в текущем процессе (без ухода в фон)
+    Run,
+    /// Остановить агент
+    Stop,
+    /// Состояние агента
+    Status,
+    /// Открыть хранилище в агенте
+    Unlock,
+    /// Закрыть хранилище в агенте, не останавливая его
+    Lock,
+}
+
+/// Откуда взять мастер-пароль без запроса в терминале. Без этих флагов
+/// используется переменная HIHO_PASSWORD, а затем запрос.
+#[derive(Args)]
+pub struct PasswordArgs {
+    /// Прочитать мастер-пароль из первой строки файла
+    #[arg(long, global = true, conflicts_with_all = ["password_fd", "password_stdin"])]
+    pub password_file: Option<PathBuf>,
+    
+    /// Прочитать мастер-пароль из открытого дескриптора (например, 3 с `3<файл`)
+    #[arg(long, global = true, conflicts_with = "password_stdin")]
+    pub password_fd: Option<i32>,
+    
+    /// Прочитать мастер-пароль из первой строки stdin
+    #[arg(long, global = true)]
+    pub password_stdin: bool,
+    
+    /// Не использовать запущенный агент, открыть хранилище самостоятельно
+    #[arg(long, global = true)]
+    pub no_agent: bool,
+}
+
+/// Отбор записей, общий для поиска и экспорта
+#[derive(Args)]
+pub struct FilterArgs {
+    /// Только записи с этим тегом (можно указать несколько раз)
+    #[arg(long = "tag")]
+    pub tags: Vec<String>,
+    
+    /// Папка (вместе с вложенными)
+    #[arg(long)]
+    pub folder: Option<String>,
+    
+    /// Тип записи (login, note, ssh_key)
+    #[arg(long = "type")]
+    pub kind: Option<String>,
+    
+    /// Шаблон названия, например "staging-*"
+    #[arg(long)]
+    pub name: Option<String>,
+    
+    /// Изменённые с даты: 2024-05-01, RFC 3339 или 12h, 30d, 2w
+    #[arg(long)]
+    pub modified_since: Option<String>,
+}
+
+impl FilterArgs {
+    pub fn to_filter(&self) -> Result<EntryFilter, Box<dyn Error>> {
+        let kind = match &self.kind {
+            Some(kind) => Some(EntryKind::parse(kind).ok_or_else(|| format!("Неизвестный тип записи: {}", kind))?),
+            None => None,
+        };
+        let modified_since = match &self.modified_since {
+            Some(value) => Some(parse_since(value)?),
+            None => None,
+        };
+        Ok(EntryFilter {
+            tags: self.tags.clone(),
+            folder: self.folder.clone(),
+            kind,
+            name: self.name.clone(),
+            modified_since,
+        })
+    }
+}
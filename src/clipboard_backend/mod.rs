@@ -0,0 +1,155 @@
+//! Низкоуровневый доступ к буферу обмена.
+//!
+//! В Linux используется собственная реализация: Wayland через протокол
+//! data-control (ext или wlr), иначе X11. Она умеет работать с PRIMARY и
+//! помечает секреты для менеджеров истории буфера: предлагает форматы
+//! `x-kde-passwordManagerHint` = `secret` и `CLIPBOARD_STATE` = `sensitive`,
+//! по которым Klipper, cliphist и им подобные не сохраняют содержимое.
+//! На остальных платформах — крейт `clipboard`.
+//!
+//! В Wayland и X11 содержимое буфера отдаёт программа, которая его
+//! положила, поэтому бэкенд держит фоновый поток, пока буфер не займёт
+//! кто-то другой.
+
+#[cfg(target_os = "linux")]
+mod wayland;
+#[cfg(target_os = "linux")]
+mod x11;
+
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::time::Duration;
+use zeroize::Zeroizing;
+
+/// Формат с подсказкой для менеджеров паролей и истории буфера (KDE)
+pub const PASSWORD_MANAGER_HINT: &str = "x-kde-passwordManagerHint";
+/// Формат с состоянием буфера (`sensitive` — не сохранять в историю)
+pub const CLIPBOARD_STATE: &str = "CLIPBOARD_STATE";
+
+/// Какой буфер использовать.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Selection {
+    /// Обычный буфер обмена (Ctrl+C / Ctrl+V)
+    #[default]
+    Clipboard,
+    /// Выделение PRIMARY (вставка средней кнопкой мыши), только Linux
+    Primary,
+}
+
+impl Selection {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Selection::Clipboard => "clipboard",
+            Selection::Primary => "primary",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self, Box<dyn Error>> {
+        match value.to_lowercase().as_str() {
+            "clipboard" => Ok(Selection::Clipboard),
+            "primary" => Ok(Selection::Primary),
+            _ => Err(format!("Неизвестный буфер '{}': используйте clipboard или primary", value).into()),
+        }
+    }
+}
+
+pub trait ClipboardBackend {
+    /// Текст из буфера; `None`, если буфер пуст или в нём не текст.
+    fn get_text(&mut self) -> Result<Option<Zeroizing<String>>, Box<dyn Error>>;
+
+    /// Кладёт текст в буфер. `sensitive` добавляет подсказки, чтобы
+    /// менеджеры истории буфера не запоминали содержимое.
+    fn set_text(&mut self, text: &str, sensitive: bool) -> Result<(), Box<dyn Error>>;
+
+    fn clear(&mut self) -> Result<(), Box<dyn Error>>;
+
+    /// Лежит ли в буфере `text`, положенный этим бэкендом.
+    fn holds(&mut self, text: &str) -> bool {
+        matches!(self.get_text(), Ok(Some(current)) if *current == text)
+    }
+
+    /// Ждёт, пока положенное нами содержимое не заменит другая программа.
+    /// Нужно там, где буфер живёт только вместе с процессом-владельцем.
+    fn wait_until_replaced(&mut self) {}
+
+    /// Кладёт текст в буфер для одной вставки: после первого чтения
+    /// содержимое пропадает. Там, где так нельзя, — обычная `set_text`.
+    fn set_text_once(&mut self, text: &str, sensitive: bool) -> Result<(), Box<dyn Error>> {
+        self.set_text(text, sensitive)
+    }
+
+    /// Ждёт, пока содержимое из `set_text_once` вставят. `false`, если за
+    /// `timeout` этого не случилось или буфер занял кто-то другой.
+    fn wait_for_paste(&mut self, _timeout: Duration) -> bool {
+        false
+    }
+}
+
+/// Умеет ли бэкенд этой платформы отдавать содержимое на одну вставку.
+pub const PASTE_ONCE_SUPPORTED: bool = cfg!(target_os = "linux");
+
+/// Открывает буфер обмена текущего графического сеанса.
+#[cfg(target_os = "linux")]
+pub fn open(selection: Selection) -> Result<Box<dyn ClipboardBackend>, Box<dyn Error>> {
+    let has_x11 = std::env::var_os("DISPLAY").is_some();
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        match wayland::WaylandBackend::new(selection) {
+            Ok(backend) => return Ok(Box::new(backend)),
+            // Композитор без data-control (например, GNOME): пробуем XWayland
+            Err(e) if !has_x11 => return Err(e),
+            Err(_) => {}
+        }
+    }
+    if has_x11 {
+        return Ok(Box::new(x11::X11Backend::new(selection)?));
+    }
+    Err("Буфер обмена недоступен: не заданы ни WAYLAND_DISPLAY, ни DISPLAY".into())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn open(selection: Selection) -> Result<Box<dyn ClipboardBackend>, Box<dyn Error>> {
+    if selection == Selection::Primary {
+        return Err("Выделение PRIMARY есть только в Linux".into());
+    }
+    Ok(Box::new(SystemBackend::new()?))
+}
+
+/// Буфер обмена через крейт `clipboard` (Windows, macOS).
+#[cfg(not(target_os = "linux"))]
+struct SystemBackend {
+    ctx: clipboard::ClipboardContext,
+}
+
+#[cfg(not(target_os = "linux"))]
+impl SystemBackend {
+    fn new() -> Result<Self, Box<dyn Error>> {
+        use clipboard::ClipboardProvider;
+        Ok(Self {
+            ctx: ClipboardProvider::new()?,
+        })
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+impl ClipboardBackend for SystemBackend {
+    fn get_text(&mut self) -> Result<Option<Zeroizing<String>>, Box<dyn Error>> {
+        use clipboard::ClipboardProvider;
+        // Ошибка чтения обычно означает, что в буфере не текст
+        Ok(self
+            .ctx
+            .get_contents()
+            .ok()
+            .filter(|text| !text.is_empty())
+            .map(Zeroizing::new))
+    }
+
+    fn set_text(&mut self, text: &str, _sensitive: bool) -> Result<(), Box<dyn Error>> {
+        use clipboard::ClipboardProvider;
+        self.ctx.set_contents(text.to_owned())
+    }
+
+    fn clear(&mut self) -> Result<(), Box<dyn Error>> {
+        self.set_text("", false)
+    }
+}
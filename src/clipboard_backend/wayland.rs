@@ -0,0 +1,147 @@
+use super::{ClipboardBackend, Selection, CLIPBOARD_STATE};
+use std::error::Error;
+use std::io::Read;
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use wl_clipboard_rs::copy::{self, MimeSource, MimeType, Options, ServeRequests, Source};
+use wl_clipboard_rs::paste;
+use wl_clipboard_rs::utils::{is_primary_selection_supported, PrimarySelectionCheckError};
+use zeroize::Zeroizing;
+
+/// Буфер обмена Wayland через протокол data-control.
+pub struct WaylandBackend {
+    selection: Selection,
+    // Поток, который отдаёт наше содержимое; завершается, когда буфер
+    // занимает другая программа
+    server: Option<JoinHandle<()>>,
+}
+
+impl WaylandBackend {
+    pub fn new(selection: Selection) -> Result<Self, Box<dyn Error>> {
+        // Проверка заодно выясняет, поддерживает ли композитор data-control
+        match is_primary_selection_supported() {
+            Ok(false) if selection == Selection::Primary => {
+                return Err("Композитор Wayland не поддерживает PRIMARY через data-control".into())
+            }
+            Ok(_) | Err(PrimarySelectionCheckError::NoSeats) => {}
+            Err(e) => return Err(format!("Буфер обмена Wayland недоступен: {}", e).into()),
+        }
+        Ok(Self { selection, server: None })
+    }
+
+    fn copy_type(&self) -> copy::ClipboardType {
+        match self.selection {
+            Selection::Clipboard => copy::ClipboardType::Regular,
+            Selection::Primary => copy::ClipboardType::Primary,
+        }
+    }
+
+    fn offer(&mut self, text: &str, sensitive: bool, requests: ServeRequests) -> Result<(), Box<dyn Error>> {
+        let once = requests != ServeRequests::Unlimited;
+        let mut options = Options::new();
+        // Отдаём содержимое из своего потока, чтобы знать, когда его заменят
+        options
+            .clipboard(self.copy_type())
+            .foreground(true)
+            .sensitive(sensitive)
+            .serve_requests(requests);
+
+        // Остальные текстовые форматы (UTF8_STRING, text/plain и т. д.)
+        // wl-clipboard-rs добавит сам; x-kde-passwordManagerHint — `sensitive`
+        let mut sources = vec![MimeSource {
+            source: Source::Bytes(text.as_bytes().into()),
+            mime_type: MimeType::Specific("text/plain;charset=utf-8".to_string()),
+        }];
+        // Чтение CLIPBOARD_STATE считается вставкой, поэтому для одной
+        // вставки оставляем только подсказку KDE, которую библиотека не считает
+        if sensitive && !once {
+            sources.push(MimeSource {
+                source: Source::Bytes(b"sensitive"[..].into()),
+                mime_type: MimeType::Specific(CLIPBOARD_STATE.to_string()),
+            });
+        }
+
+        // PreparedCopy не Send, поэтому готовим его в том же потоке, где обслуживаем
+        let (ready_tx, ready_rx) = mpsc::sync_channel(1);
+        let server = thread::spawn(move || match options.prepare_copy_multi(sources) {
+            Ok(prepared) => {
+                let _ = ready_tx.send(Ok(()));
+                let _ = prepared.serve();
+            }
+            Err(e) => {
+                let _ = ready_tx.send(Err(e.to_string()));
+            }
+        });
+        ready_rx
+            .recv()
+            .map_err(|_| "Поток буфера обмена завершился")?
+            .map_err(|e| format!("Не удалось записать в буфер обмена Wayland: {}", e))?;
+        // Предыдущий поток завершится сам: его содержимое только что заменено
+        self.server = Some(server);
+        Ok(())
+    }
+
+    fn serving(&self) -> bool {
+        self.server.as_ref().is_some_and(|server| !server.is_finished())
+    }
+
+    fn paste_type(&self) -> paste::ClipboardType {
+        match self.selection {
+            Selection::Clipboard => paste::ClipboardType::Regular,
+            Selection::Primary => paste::ClipboardType::Primary,
+        }
+    }
+}
+
+impl ClipboardBackend for WaylandBackend {
+    fn get_text(&mut self) -> Result<Option<Zeroizing<String>>, Box<dyn Error>> {
+        match paste::get_contents(self.paste_type(), paste::Seat::Unspecified, paste::MimeType::Text) {
+            Ok((mut pipe, _)) => {
+                let mut bytes = Zeroizing::new(Vec::new());
+                pipe.read_to_end(&mut bytes)?;
+                Ok(String::from_utf8(bytes.to_vec()).ok().map(Zeroizing::new))
+            }
+            Err(paste::Error::NoSeats | paste::Error::ClipboardEmpty | paste::Error::NoMimeType) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn set_text(&mut self, text: &str, sensitive: bool) -> Result<(), Box<dyn Error>> {
+        self.offer(text, sensitive, ServeRequests::Unlimited)
+    }
+
+    fn set_text_once(&mut self, text: &str, sensitive: bool) -> Result<(), Box<dyn Error>> {
+        self.offer(text, sensitive, ServeRequests::Only(1))
+    }
+
+    fn wait_for_paste(&mut self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        while self.serving() {
+            if Instant::now() >= deadline {
+                return false;
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        self.wait_until_replaced();
+        // После вставки источник уничтожен и буфер пуст; если в нём что-то
+        // есть, его заменила другая программа
+        matches!(self.get_text(), Ok(None))
+    }
+
+    fn clear(&mut self) -> Result<(), Box<dyn Error>> {
+        copy::clear(self.copy_type(), copy::Seat::All)?;
+        self.wait_until_replaced();
+        Ok(())
+    }
+
+    fn holds(&mut self, _text: &str) -> bool {
+        self.serving()
+    }
+
+    fn wait_until_replaced(&mut self) {
+        if let Some(server) = self.server.take() {
+            let _ = server.join();
+        }
+    }
+}
@@ -0,0 +1,320 @@
+use super::{ClipboardBackend, Selection, CLIPBOARD_STATE, PASSWORD_MANAGER_HINT};
+use std::error::Error;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use x11rb::connection::Connection;
+use x11rb::protocol::xproto::{
+    Atom, AtomEnum, ConnectionExt, CreateWindowAux, EventMask, PropMode, SelectionNotifyEvent,
+    SelectionRequestEvent, Window, WindowClass, SELECTION_NOTIFY_EVENT,
+};
+use x11rb::protocol::Event;
+use x11rb::rust_connection::RustConnection;
+use x11rb::wrapper::ConnectionExt as _;
+use x11rb::{COPY_DEPTH_FROM_PARENT, CURRENT_TIME, NONE};
+use zeroize::Zeroizing;
+
+// Сколько ждать ответа владельца буфера при чтении
+const READ_TIMEOUT: Duration = Duration::from_secs(1);
+
+x11rb::atom_manager! {
+    Atoms: AtomsCookie {
+        CLIPBOARD,
+        TARGETS,
+        UTF8_STRING,
+        TEXT,
+        TEXT_PLAIN: b"text/plain",
+        TEXT_PLAIN_UTF8: b"text/plain;charset=utf-8",
+        INCR,
+        PASSWORD_MANAGER_HINT: PASSWORD_MANAGER_HINT.as_bytes(),
+        CLIPBOARD_STATE: CLIPBOARD_STATE.as_bytes(),
+        HIHO_SELECTION,
+    }
+}
+
+// Что мы сейчас предлагаем в буфере
+struct Offer {
+    text: Zeroizing<String>,
+    sensitive: bool,
+    /// Отдать текст один раз и освободить выделение
+    once: bool,
+}
+
+struct X11Connection {
+    conn: RustConnection,
+    window: Window,
+    atoms: Atoms,
+}
+
+impl X11Connection {
+    fn open() -> Result<Self, Box<dyn Error>> {
+        let (conn, screen_num) = x11rb::connect(None)
+            .map_err(|e| format!("Не удалось подключиться к X11: {}", e))?;
+        let screen = &conn.setup().roots[screen_num];
+        let window = conn.generate_id()?;
+        // Невидимое окно нужно только как владелец и получатель выделений
+        conn.create_window(
+            COPY_DEPTH_FROM_PARENT,
+            window,
+            screen.root,
+            0,
+            0,
+            1,
+            1,
+            0,
+            WindowClass::INPUT_OUTPUT,
+            screen.root_visual,
+            &CreateWindowAux::new().event_mask(EventMask::PROPERTY_CHANGE),
+        )?;
+        let atoms = Atoms::new(&conn)?.reply()?;
+        conn.flush()?;
+        Ok(Self { conn, window, atoms })
+    }
+
+    fn selection_atom(&self, selection: Selection) -> Atom {
+        match selection {
+            Selection::Clipboard => self.atoms.CLIPBOARD,
+            Selection::Primary => AtomEnum::PRIMARY.into(),
+        }
+    }
+}
+
+/// Буфер обмена X11. Содержимое отдаёт фоновый поток, пока выделением
+/// владеет наше окно.
+pub struct X11Backend {
+    x11: Arc<X11Connection>,
+    selection: Atom,
+    offer: Arc<Mutex<Option<Offer>>>,
+    // Текст из `set_text_once` уже вставили
+    pasted: Arc<AtomicBool>,
+    server: Option<JoinHandle<()>>,
+}
+
+impl X11Backend {
+    pub fn new(selection: Selection) -> Result<Self, Box<dyn Error>> {
+        let x11 = X11Connection::open()?;
+        let selection = x11.selection_atom(selection);
+        Ok(Self {
+            x11: Arc::new(x11),
+            selection,
+            offer: Arc::new(Mutex::new(None)),
+            pasted: Arc::new(AtomicBool::new(false)),
+            server: None,
+        })
+    }
+
+    fn serving(&self) -> bool {
+        self.server.as_ref().is_some_and(|server| !server.is_finished())
+    }
+
+    fn take_selection(&mut self, text: &str, sensitive: bool, once: bool) -> Result<(), Box<dyn Error>> {
+        if let Ok(mut offer) = self.offer.lock() {
+            *offer = Some(Offer {
+                text: Zeroizing::new(text.to_string()),
+                sensitive,
+                once,
+            });
+        }
+        self.pasted.store(false, Ordering::SeqCst);
+        let x11 = &self.x11;
+        x11.conn.set_selection_owner(x11.window, self.selection, CURRENT_TIME)?;
+        if x11.conn.get_selection_owner(self.selection)?.reply()?.owner != x11.window {
+            return Err("Не удалось занять буфер обмена X11".into());
+        }
+        if !self.serving() {
+            let x11 = Arc::clone(&self.x11);
+            let offer = Arc::clone(&self.offer);
+            let pasted = Arc::clone(&self.pasted);
+            let selection = self.selection;
+            self.server = Some(thread::spawn(move || serve(&x11, selection, &offer, &pasted)));
+        }
+        Ok(())
+    }
+}
+
+impl ClipboardBackend for X11Backend {
+    fn get_text(&mut self) -> Result<Option<Zeroizing<String>>, Box<dyn Error>> {
+        // Отдельное соединение: события нашего окна забирает поток-владелец
+        let reader = X11Connection::open()?;
+        let result = read_selection(&reader, self.selection);
+        let _ = reader.conn.destroy_window(reader.window);
+        result
+    }
+
+    fn set_text(&mut self, text: &str, sensitive: bool) -> Result<(), Box<dyn Error>> {
+        self.take_selection(text, sensitive, false)
+    }
+
+    fn set_text_once(&mut self, text: &str, sensitive: bool) -> Result<(), Box<dyn Error>> {
+        self.take_selection(text, sensitive, true)
+    }
+
+    fn wait_for_paste(&mut self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        while self.serving() {
+            if Instant::now() >= deadline {
+                return false;
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        self.wait_until_replaced();
+        self.pasted.load(Ordering::SeqCst)
+    }
+
+    fn clear(&mut self) -> Result<(), Box<dyn Error>> {
+        if let Ok(mut offer) = self.offer.lock() {
+            *offer = None;
+        }
+        if self.serving() {
+            // Отказ от выделения пришлёт нам SelectionClear и завершит поток
+            self.x11.conn.set_selection_owner(NONE, self.selection, CURRENT_TIME)?;
+            self.x11.conn.flush()?;
+            self.wait_until_replaced();
+        }
+        Ok(())
+    }
+
+    fn holds(&mut self, _text: &str) -> bool {
+        self.serving()
+    }
+
+    fn wait_until_replaced(&mut self) {
+        if let Some(server) = self.server.take() {
+            let _ = server.join();
+        }
+    }
+}
+
+// Отвечает на запросы содержимого, пока выделением не завладеет другой клиент
+fn serve(x11: &X11Connection, selection: Atom, offer: &Mutex<Option<Offer>>, pasted: &AtomicBool) {
+    while let Ok(event) = x11.conn.wait_for_event() {
+        match event {
+            Event::SelectionRequest(request) if request.selection == selection => {
+                let guard = offer.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                let sent_text = matches!(answer(x11, &request, guard.as_ref()), Ok(true));
+                if sent_text && guard.as_ref().is_some_and(|offer| offer.once) {
+                    // Текст вставили: освобождаем выделение, SelectionClear
+                    // придёт следующим событием и завершит цикл
+                    pasted.store(true, Ordering::SeqCst);
+                    let _ = x11.conn.set_selection_owner(NONE, selection, CURRENT_TIME);
+                    let _ = x11.conn.flush();
+                }
+            }
+            Event::SelectionClear(clear) if clear.selection == selection => break,
+            _ => {}
+        }
+    }
+    if let Ok(mut offer) = offer.lock() {
+        *offer = None;
+    }
+}
+
+// Отвечает на один запрос; `true`, если отдан сам текст
+fn answer(x11: &X11Connection, request: &SelectionRequestEvent, offer: Option<&Offer>) -> Result<bool, Box<dyn Error>> {
+    let atoms = &x11.atoms;
+    // Старые клиенты не указывают свойство и ждут ответ в свойстве с именем формата
+    let property = if request.property == NONE { request.target } else { request.property };
+    let text_targets = [
+        atoms.UTF8_STRING,
+        atoms.TEXT,
+        atoms.TEXT_PLAIN,
+        atoms.TEXT_PLAIN_UTF8,
+        AtomEnum::STRING.into(),
+    ];
+
+    let mut sent_text = false;
+    let stored = match offer {
+        None => false,
+        Some(offer) if request.target == atoms.TARGETS => {
+            let mut targets = vec![atoms.TARGETS];
+            targets.extend(text_targets);
+            if offer.sensitive {
+                targets.extend([atoms.PASSWORD_MANAGER_HINT, atoms.CLIPBOARD_STATE]);
+            }
+            x11.conn
+                .change_property32(PropMode::REPLACE, request.requestor, property, AtomEnum::ATOM, &targets)?;
+            true
+        }
+        Some(offer) if text_targets.contains(&request.target) => {
+            // TEXT — запрос «в любой текстовой кодировке», отвечаем UTF-8
+            let kind = if request.target == atoms.TEXT { atoms.UTF8_STRING } else { request.target };
+            x11.conn.change_property8(
+                PropMode::REPLACE,
+                request.requestor,
+                property,
+                kind,
+                offer.text.as_bytes(),
+            )?;
+            sent_text = true;
+            true
+        }
+        Some(offer) if offer.sensitive && request.target == atoms.PASSWORD_MANAGER_HINT => {
+            x11.conn
+                .change_property8(PropMode::REPLACE, request.requestor, property, request.target, b"secret")?;
+            true
+        }
+        Some(offer) if offer.sensitive && request.target == atoms.CLIPBOARD_STATE => {
+            x11.conn
+                .change_property8(PropMode::REPLACE, request.requestor, property, request.target, b"sensitive")?;
+            true
+        }
+        Some(_) => false,
+    };
+
+    let notify = SelectionNotifyEvent {
+        response_type: SELECTION_NOTIFY_EVENT,
+        sequence: 0,
+        time: request.time,
+        requestor: request.requestor,
+        selection: request.selection,
+        target: request.target,
+        // NONE сообщает, что такого формата нет
+        property: if stored { property } else { NONE },
+    };
+    x11.conn.send_event(false, request.requestor, EventMask::NO_EVENT, notify)?;
+    x11.conn.flush()?;
+    Ok(sent_text)
+}
+
+fn read_selection(reader: &X11Connection, selection: Atom) -> Result<Option<Zeroizing<String>>, Box<dyn Error>> {
+    let atoms = &reader.atoms;
+    if reader.conn.get_selection_owner(selection)?.reply()?.owner == NONE {
+        return Ok(None);
+    }
+    reader.conn.convert_selection(
+        reader.window,
+        selection,
+        atoms.UTF8_STRING,
+        atoms.HIHO_SELECTION,
+        CURRENT_TIME,
+    )?;
+    reader.conn.flush()?;
+
+    let deadline = Instant::now() + READ_TIMEOUT;
+    loop {
+        match reader.conn.poll_for_event()? {
+            Some(Event::SelectionNotify(notify)) if notify.requestor == reader.window => {
+                if notify.property == NONE {
+                    return Ok(None);
+                }
+                break;
+            }
+            Some(_) => {}
+            None if Instant::now() >= deadline => return Ok(None),
+            None => thread::sleep(Duration::from_millis(10)),
+        }
+    }
+
+    let reply = reader
+        .conn
+        .get_property(true, reader.window, atoms.HIHO_SELECTION, AtomEnum::ANY, 0, u32::MAX / 4)?
+        .reply()?;
+    // Большое содержимое передаётся по частям (INCR); для пароля это не нужно,
+    // а прежнее содержимое такого размера просто не восстанавливаем
+    if reply.type_ == atoms.INCR {
+        return Ok(None);
+    }
+    let bytes = Zeroizing::new(reply.value);
+    Ok(String::from_utf8(bytes.to_vec()).ok().map(Zeroizing::new))
+}
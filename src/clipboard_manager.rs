@@ -0,0 +1,412 @@
+//! Копирование секретов в буфер обмена с автоочисткой.
+//!
+//! Секрет в буфер кладёт отдельный фоновый процесс — тот же исполняемый
+//! файл, запущенный с аргументом `--clipboard-guard`. Секрет передаётся ему
+//! через stdin, а не в аргументах, которые видны в списке процессов.
+//! Помощник ждёт таймаут и, если в буфере всё ещё наш секрет, возвращает
+//! прежнее содержимое; если пользователь успел скопировать что-то другое,
+//! буфер не трогается. В Wayland и X11 содержимое буфера живёт, пока жив
+//! владелец, поэтому помощник отдаёт его и после выхода CLI — до тех пор,
+//! пока в буфер не скопируют что-то другое.
+//!
+//! Последовательность значений (например, логин, затем пароль) помощник
+//! отдаёт по одному: каждое, кроме последнего, — на одну вставку, после
+//! которой в буфер встаёт следующее.
+
+use crate::clipboard_backend::{self, ClipboardBackend, Selection, PASTE_ONCE_SUPPORTED};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use zeroize::{Zeroize, Zeroizing};
+
+const CONFIG_FILE: &str = "data\\clipboard_config.json";
+
+/// Аргумент, с которым бинарник работает как помощник очистки буфера.
+pub const GUARD_ARG: &str = "--clipboard-guard";
+
+// Сколько ждать вставки очередного значения последовательности,
+// если автоочистка отключена
+const SEQUENCE_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ClipboardConfig {
+    pub clear_after_secs: Option<u64>, // None или 0 = не очищать
+    /// Вернуть в буфер то, что было до копирования секрета
+    pub restore_previous: bool,
+    /// Буфер по умолчанию: clipboard или primary (только Linux)
+    #[serde(default)]
+    pub selection: Selection,
+}
+
+impl Default for ClipboardConfig {
+    fn default() -> Self {
+        Self {
+            clear_after_secs: Some(30),
+            restore_previous: true,
+            selection: Selection::Clipboard,
+        }
+    }
+}
+
+impl ClipboardConfig {
+    pub fn clear_after(&self) -> Option<Duration> {
+        self.clear_after_secs
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs)
+    }
+}
+
+// Задание для помощника; передаётся одной строкой JSON через stdin
+#[derive(Serialize, Deserialize)]
+struct GuardRequest {
+    /// Значения по порядку вставки; последнее остаётся в буфере до очистки
+    secrets: Vec<String>,
+    clear_after_secs: Option<u64>,
+    restore_previous: bool,
+    selection: Selection,
+}
+
+impl Drop for GuardRequest {
+    fn drop(&mut self) {
+        self.secrets.zeroize();
+    }
+}
+
+pub struct ClipboardManager;
+
+impl ClipboardManager {
+    pub fn get_config() -> Result<ClipboardConfig, Box<dyn Error>> {
+        let config_path = Path::new(CONFIG_FILE);
+        if config_path.exists() {
+            let data = fs::read_to_string(config_path)?;
+            let config: ClipboardConfig = serde_json::from_str(&data)?;
+            Ok(config)
+        } else {
+            let config = ClipboardConfig::default();
+            Self::save_config(&config)?;
+            Ok(config)
+        }
+    }
+
+    pub fn save_config(config: &ClipboardConfig) -> Result<(), Box<dyn Error>> {
+        let config_path = Path::new(CONFIG_FILE);
+        std::fs::create_dir_all("data")?;
+        let json_data = serde_json::to_string_pretty(config)?;
+        fs::write(config_path, json_data)?;
+        Ok(())
+    }
+
+    /// Копирует секрет в буфер из настроек. Возвращает, через сколько
+    /// буфер будет очищен; `None`, если автоочистка отключена.
+    pub fn copy_secret(text: &str) -> Result<Option<Duration>, Box<dyn Error>> {
+        let selection = Self::get_config()?.selection;
+        Self::copy_secret_to(text, selection)
+    }
+
+    /// Копирует секрет в указанный буфер.
+    pub fn copy_secret_to(text: &str, selection: Selection) -> Result<Option<Duration>, Box<dyn Error>> {
+        Self::copy_to(&[text], selection)
+    }
+
+    /// Копирует значения по очереди: следующее встаёт в буфер после
+    /// вставки предыдущего. Если очередную вставку не дождались до
+    /// таймаута автоочистки (или двух минут), буфер очищается.
+    pub fn copy_sequence(values: &[&str]) -> Result<Option<Duration>, Box<dyn Error>> {
+        let selection = Self::get_config()?.selection;
+        Self::copy_sequence_to(values, selection)
+    }
+
+    /// Копирует значения по очереди в указанный буфер.
+    pub fn copy_sequence_to(values: &[&str], selection: Selection) -> Result<Option<Duration>, Box<dyn Error>> {
+        if !Self::sequence_supported() {
+            return Err("Последовательное копирование на этой платформе не поддерживается".into());
+        }
+        Self::copy_to(values, selection)
+    }
+
+    /// Можно ли копировать последовательность одним вызовом
+    /// (`copy_sequence`); иначе значения копируют по одному.
+    pub fn sequence_supported() -> bool {
+        PASTE_ONCE_SUPPORTED
+    }
+
+    fn copy_to(values: &[&str], selection: Selection) -> Result<Option<Duration>, Box<dyn Error>> {
+        if values.is_empty() {
+            return Err("Нечего копировать".into());
+        }
+        let config = Self::get_config()?;
+        let clear_after = config.clear_after();
+        spawn_guard(GuardRequest {
+            secrets: values.iter().map(|value| value.to_string()).collect(),
+            clear_after_secs: clear_after.map(|after| after.as_secs()),
+            restore_previous: config.restore_previous,
+            selection,
+        })?;
+        Ok(clear_after)
+    }
+
+    /// Работа помощника: вызывается из `main`, если первый аргумент — `GUARD_ARG`.
+    pub fn run_guard() -> Result<(), Box<dyn Error>> {
+        let mut input = Zeroizing::new(String::new());
+        std::io::stdin().read_to_string(&mut input)?;
+        let request: GuardRequest = serde_json::from_str(&input)?;
+        drop(input);
+
+        let mut stdout = std::io::stdout();
+        let (mut backend, previous) = match take_clipboard(&request) {
+            Ok(taken) => taken,
+            Err(e) => {
+                writeln!(stdout, "error\t{}", e)?;
+                return Ok(());
+            }
+        };
+        let first_set_at = Instant::now();
+        writeln!(stdout, "ok")?;
+        stdout.flush()?;
+
+        let previous = previous.as_ref().map(|text| text.as_str());
+        let Some(last_set_at) = feed_sequence(&mut *backend, &request, previous, first_set_at)? else {
+            return Ok(());
+        };
+        if let Some(secs) = request.clear_after_secs {
+            // Срок считается от момента, когда в буфер встало последнее значение
+            thread::sleep((last_set_at + Duration::from_secs(secs)).saturating_duration_since(Instant::now()));
+            if let Some(last) = request.secrets.last() {
+                release(&mut *backend, &request, last, previous)?;
+            }
+        }
+        drop(request);
+        // Держим содержимое доступным, пока его не заменят
+        backend.wait_until_replaced();
+        Ok(())
+    }
+}
+
+// Буфер, в который помощник положил секрет, и прежнее содержимое
+type TakenClipboard = (Box<dyn ClipboardBackend>, Option<Zeroizing<String>>);
+
+// Запоминает содержимое буфера и кладёт туда первое значение
+fn take_clipboard(request: &GuardRequest) -> Result<TakenClipboard, Box<dyn Error>> {
+    let first = request.secrets.first().ok_or("Нечего копировать")?;
+    let mut backend = clipboard_backend::open(request.selection)?;
+    // Пустой буфер или не текст — восстанавливать нечего
+    let previous = backend.get_text().ok().flatten().filter(|text| !text.is_empty());
+    if request.secrets.len() > 1 {
+        backend.set_text_once(first, true)?;
+    } else {
+        backend.set_text(first, true)?;
+    }
+    Ok((backend, previous))
+}
+
+// Выдаёт значения после первого, каждое — после вставки предыдущего.
+// Возвращает момент, когда в буфер встало последнее значение, или `None`,
+// если очередной вставки не дождались и буфер уже освобождён
+fn feed_sequence(
+    backend: &mut dyn ClipboardBackend,
+    request: &GuardRequest,
+    previous: Option<&str>,
+    mut set_at: Instant,
+) -> Result<Option<Instant>, Box<dyn Error>> {
+    let paste_timeout = request
+        .clear_after_secs
+        .map(Duration::from_secs)
+        .unwrap_or(SEQUENCE_TIMEOUT);
+    let count = request.secrets.len();
+    for (index, next) in request.secrets.iter().enumerate().skip(1) {
+        if !backend.wait_for_paste(paste_timeout) {
+            // Предыдущее значение так и не вставили: дальше не продолжаем
+            release(backend, request, &request.secrets[index - 1], previous)?;
+            return Ok(None);
+        }
+        if index + 1 < count {
+            backend.set_text_once(next, true)?;
+        } else {
+            backend.set_text(next, true)?;
+        }
+        set_at = Instant::now();
+    }
+    Ok(Some(set_at))
+}
+
+// Если в буфере всё ещё `secret`, возвращает прежнее содержимое или очищает буфер
+fn release(
+    backend: &mut dyn ClipboardBackend,
+    request: &GuardRequest,
+    secret: &str,
+    previous: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+    if backend.holds(secret) {
+        match previous {
+            Some(previous) if request.restore_previous && previous != secret => backend.set_text(previous, false)?,
+            _ => backend.clear()?,
+        }
+    }
+    Ok(())
+}
+
+fn spawn_guard(request: GuardRequest) -> Result<(), Box<dyn Error>> {
+    let mut command = Command::new(std::env::current_exe()?);
+    command
+        .arg(GUARD_ARG)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+    detach(&mut command);
+    let mut child = command.spawn()?;
+
+    let mut payload = serde_json::to_vec(&request)?;
+    drop(request);
+    // stdin закрывается сразу после записи: помощник читает до конца потока
+    let written = match child.stdin.take() {
+        Some(mut stdin) => stdin.write_all(&payload),
+        None => Ok(()),
+    };
+    payload.zeroize();
+    written?;
+
+    let mut answer = String::new();
+    if let Some(stdout) = child.stdout.take() {
+        BufReader::new(stdout).read_line(&mut answer)?;
+    }
+    // Забираем код завершения помощника, чтобы в GUI не копились зомби
+    thread::spawn(move || {
+        let _ = child.wait();
+    });
+
+    match answer.trim_end() {
+        "ok" => Ok(()),
+        "" => Err("Помощник буфера обмена завершился без ответа".into()),
+        other => Err(format!(
+            "Не удалось скопировать в буфер обмена: {}",
+            other.strip_prefix("error\t").unwrap_or(other)
+        )
+        .into()),
+    }
+}
+
+// Помощник не должен завершаться вместе с терминалом или группой процессов CLI
+#[cfg(unix)]
+fn detach(command: &mut Command) {
+    use std::os::unix::process::CommandExt;
+    command.process_group(0);
+}
+
+#[cfg(windows)]
+fn detach(command: &mut Command) {
+    use std::os::windows::process::CommandExt;
+    const DETACHED_PROCESS: u32 = 0x0000_0008;
+    const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+    command.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+}
+
+#[cfg(not(any(unix, windows)))]
+fn detach(_command: &mut Command) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    // Буфер в памяти; `pastes` — чем ответят очередные ожидания вставки
+    #[derive(Default)]
+    struct FakeClipboard {
+        text: Option<String>,
+        pastes: VecDeque<bool>,
+        last_sensitive: Option<bool>,
+    }
+
+    impl ClipboardBackend for FakeClipboard {
+        fn get_text(&mut self) -> Result<Option<Zeroizing<String>>, Box<dyn Error>> {
+            Ok(self.text.clone().map(Zeroizing::new))
+        }
+
+        fn set_text(&mut self, text: &str, sensitive: bool) -> Result<(), Box<dyn Error>> {
+            self.text = Some(text.to_string());
+            self.last_sensitive = Some(sensitive);
+            Ok(())
+        }
+
+        fn clear(&mut self) -> Result<(), Box<dyn Error>> {
+            self.text = None;
+            Ok(())
+        }
+
+        fn wait_for_paste(&mut self, _timeout: Duration) -> bool {
+            self.pastes.pop_front().unwrap_or(false)
+        }
+    }
+
+    fn request(secrets: &[&str], restore_previous: bool) -> GuardRequest {
+        GuardRequest {
+            secrets: secrets.iter().map(|s| s.to_string()).collect(),
+            clear_after_secs: Some(30),
+            restore_previous,
+            selection: Selection::Clipboard,
+        }
+    }
+
+    fn holding(text: &str) -> FakeClipboard {
+        FakeClipboard {
+            text: Some(text.to_string()),
+            ..FakeClipboard::default()
+        }
+    }
+
+    #[test]
+    fn release_restores_previous_content() {
+        let mut clipboard = holding("s3cret");
+        release(&mut clipboard, &request(&["s3cret"], true), "s3cret", Some("старое")).unwrap();
+        assert_eq!(clipboard.text.as_deref(), Some("старое"));
+        // Прежнее содержимое — не секрет, подсказки для истории буфера не нужны
+        assert_eq!(clipboard.last_sensitive, Some(false));
+    }
+
+    #[test]
+    fn release_clears_when_nothing_to_restore() {
+        for (restore, previous) in [(false, Some("старое")), (true, None), (true, Some("s3cret"))] {
+            let mut clipboard = holding("s3cret");
+            release(&mut clipboard, &request(&["s3cret"], restore), "s3cret", previous).unwrap();
+            assert_eq!(clipboard.text, None, "{} {:?}", restore, previous);
+        }
+    }
+
+    #[test]
+    fn release_keeps_content_copied_by_user() {
+        let mut clipboard = holding("чужое");
+        release(&mut clipboard, &request(&["s3cret"], true), "s3cret", Some("старое")).unwrap();
+        assert_eq!(clipboard.text.as_deref(), Some("чужое"));
+    }
+
+    #[test]
+    fn sequence_reports_when_last_value_was_set() {
+        let mut clipboard = FakeClipboard {
+            text: Some("alice".to_string()),
+            pastes: VecDeque::from([true]),
+            ..FakeClipboard::default()
+        };
+        let start = Instant::now();
+        let set_at = feed_sequence(&mut clipboard, &request(&["alice", "s3cret"], true), None, start)
+            .unwrap()
+            .expect("вставку дождались");
+        assert!(set_at >= start);
+        assert_eq!(clipboard.text.as_deref(), Some("s3cret"));
+
+        // Из одного значения последовательность не ждёт вставки
+        let mut clipboard = holding("s3cret");
+        assert_eq!(feed_sequence(&mut clipboard, &request(&["s3cret"], true), None, start).unwrap(), Some(start));
+    }
+
+    #[test]
+    fn sequence_without_paste_releases_clipboard() {
+        let mut clipboard = holding("alice");
+        let result = feed_sequence(&mut clipboard, &request(&["alice", "s3cret"], true), Some("старое"), Instant::now());
+        assert_eq!(result.unwrap(), None);
+        assert_eq!(clipboard.text.as_deref(), Some("старое"));
+    }
+}
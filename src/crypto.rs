@@ -34,7 +34,11 @@ pub fn derive_key(password: &str, salt: &str) -> Result<[u8; 32], Box<dyn Error>
 pub fn encrypt(data: &[u8], key: &[u8; 32]) -> Result<EncryptedData, Box<dyn Error>> {
     let mut iv = [0u8; 16];
     rand::thread_rng().fill_bytes(&mut iv);
-    
+    encrypt_with_iv(data, key, iv)
+}
+
+/// Шифрует с заданным IV — для форматов, где IV хранится отдельно от данных.
+pub fn encrypt_with_iv(data: &[u8], key: &[u8; 32], iv: [u8; 16]) -> Result<EncryptedData, Box<dyn Error>> {
     let cipher = Aes256CbcEnc::new_from_slices(key, &iv)
         .map_err(|e| format!("Cipher creation error: {}", e))?;
     
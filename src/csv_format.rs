@@ -0,0 +1,382 @@
+use crate::entry::{CustomField, Entry, EntryKind};
+use chrono::{DateTime, Utc};
+use std::error::Error;
+use std::io::{Read, Write};
+
+// Пользовательские поля выгружаются в отдельные колонки вида «field:Имя»
+const CUSTOM_FIELD_PREFIX: &str = "field:";
+
+const STANDARD_HEADERS: [&str; 13] = [
+    "id",
+    "kind",
+    "name",
+    "username",
+    "password",
+    "url",
+    "notes",
+    "folder",
+    "tags",
+    "totp",
+    "created_at",
+    "updated_at",
+    "password_changed_at",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Column {
+    Id,
+    Kind,
+    Name,
+    Username,
+    Password,
+    Url,
+    Notes,
+    Folder,
+    Tags,
+    Totp,
+    CreatedAt,
+    UpdatedAt,
+    PasswordChangedAt,
+    Custom(String),
+    Ignored,
+}
+
+impl Column {
+    // Понимаем и собственные заголовки, и распространённые названия колонок
+    // из других менеджеров паролей и браузеров
+    fn from_header(header: &str) -> Self {
+        let header = header.trim().trim_start_matches('\u{feff}');
+        if let Some(name) = header.strip_prefix(CUSTOM_FIELD_PREFIX) {
+            return Column::Custom(name.to_string());
+        }
+        match header.to_lowercase().as_str() {
+            "id" | "uuid" => Column::Id,
+            "kind" | "type" => Column::Kind,
+            "name" | "title" => Column::Name,
+            "username" | "user" | "login" | "login_username" | "email" => Column::Username,
+            "password" | "pass" | "login_password" => Column::Password,
+            "url" | "urls" | "uri" | "login_uri" | "website" => Column::Url,
+            "notes" | "note" | "comments" | "extra" => Column::Notes,
+            "folder" | "group" | "grouping" => Column::Folder,
+            "tags" | "labels" => Column::Tags,
+            "totp" | "otp" | "login_totp" | "otpauth" => Column::Totp,
+            "created_at" | "created" | "timecreated" => Column::CreatedAt,
+            "updated_at" | "modified" | "modified_at" => Column::UpdatedAt,
+            "password_changed_at" | "timepasswordchanged" => Column::PasswordChangedAt,
+            _ => Column::Ignored,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CsvRowError {
+    /// Номер строки в файле (с 1, считая заголовок)
+    pub line: u64,
+    pub message: String,
+}
+
+#[derive(Debug, Default)]
+pub struct CsvImport {
+    pub entries: Vec<Entry>,
+    pub errors: Vec<CsvRowError>,
+}
+
+/// Записывает записи в CSV по RFC 4180 с заголовком.
+pub fn write_entries<W: Write>(writer: W, entries: &[Entry]) -> Result<(), Box<dyn Error>> {
+    write_columns(writer, entries, &STANDARD_HEADERS, true)
+}
+
+/// Записывает только указанные стандартные колонки (`name`, `username`, ...)
+/// и, если нужно, колонки пользовательских полей.
+pub fn write_columns<W: Write>(
+    writer: W,
+    entries: &[Entry],
+    headers: &[&str],
+    custom_fields: bool,
+) -> Result<(), Box<dyn Error>> {
+    let columns: Vec<Column> = headers.iter().map(|h| Column::from_header(h)).collect();
+    if let Some(i) = columns.iter().position(|c| matches!(c, Column::Custom(_) | Column::Ignored)) {
+        return Err(format!("Неизвестная колонка CSV: {}", headers[i]).into());
+    }
+
+    let mut custom_names: Vec<&str> = Vec::new();
+    if custom_fields {
+        for entry in entries {
+            for field in &entry.custom_fields {
+                if !custom_names.contains(&field.name.as_str()) {
+                    custom_names.push(&field.name);
+                }
+            }
+        }
+    }
+
+    let mut csv_writer = csv::Writer::from_writer(writer);
+    let mut header: Vec<String> = headers.iter().map(|h| h.to_string()).collect();
+    header.extend(custom_names.iter().map(|name| format!("{}{}", CUSTOM_FIELD_PREFIX, name)));
+    csv_writer.write_record(&header)?;
+
+    let format_date = |date: &Option<DateTime<Utc>>| date.map(|d| d.to_rfc3339()).unwrap_or_default();
+    for entry in entries {
+        let mut record: Vec<String> = columns
+            .iter()
+            .map(|column| match column {
+                Column::Id => entry.id.clone(),
+                Column::Kind => entry.kind.as_str().to_string(),
+                Column::Name => entry.name.clone(),
+                Column::Username => entry.username.clone(),
+                Column::Password => entry.password.clone(),
+                Column::Url => entry.urls.join("\n"),
+                Column::Notes => entry.notes.clone(),
+                Column::Folder => entry.folder.clone().unwrap_or_default(),
+                Column::Tags => entry.tags.join(","),
+                Column::Totp => entry.totp.clone().unwrap_or_default(),
+                Column::CreatedAt => format_date(&entry.created_at),
+                Column::UpdatedAt => format_date(&entry.updated_at),
+                Column::PasswordChangedAt => format_date(&entry.password_changed_at),
+                Column::Custom(_) | Column::Ignored => String::new(),
+            })
+            .collect();
+        for name in &custom_names {
+            let value = entry
+                .custom_fields
+                .iter()
+                .find(|field| field.name == *name)
+                .map(|field| field.value.clone())
+                .unwrap_or_default();
+            record.push(value);
+        }
+        csv_writer.write_record(&record)?;
+    }
+    csv_writer.flush()?;
+    Ok(())
+}
+
+/// Читает CSV с заголовком. Колонки сопоставляются по названию, поэтому их
+/// порядок не важен. Ошибочные строки не прерывают импорт, а попадают в отчёт.
+pub fn read_entries<R: Read>(reader: R) -> Result<CsvImport, Box<dyn Error>> {
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(reader);
+
+    let columns: Vec<Column> = csv_reader
+        .headers()
+        .map_err(|e| format!("Не удалось прочитать заголовок CSV: {}", e))?
+        .iter()
+        .map(Column::from_header)
+        .collect();
+
+    if !columns.iter().any(|c| matches!(c, Column::Name | Column::Url)) {
+        return Err("В заголовке CSV нет колонки name (или url)".into());
+    }
+
+    let mut import = CsvImport::default();
+    for result in csv_reader.records() {
+        let record = match result {
+            Ok(record) => record,
+            Err(e) => {
+                let line = e.position().map(|p| p.line()).unwrap_or(0);
+                import.errors.push(CsvRowError {
+                    line,
+                    message: format!("Ошибка разбора CSV: {}", e),
+                });
+                continue;
+            }
+        };
+        let line = record.position().map(|p| p.line()).unwrap_or(0);
+
+        match entry_from_record(&columns, &record) {
+            Ok(entry) => import.entries.push(entry),
+            Err(message) => import.errors.push(CsvRowError { line, message }),
+        }
+    }
+    Ok(import)
+}
+
+fn entry_from_record(columns: &[Column], record: &csv::StringRecord) -> Result<Entry, String> {
+    if record.len() > columns.len() {
+        return Err(format!(
+            "Полей в строке больше, чем в заголовке ({} > {})",
+            record.len(),
+            columns.len()
+        ));
+    }
+
+    let mut entry = Entry::new(String::new(), String::new(), String::new());
+    let parse_date = |value: &str| -> Result<Option<DateTime<Utc>>, String> {
+        if value.is_empty() {
+            return Ok(None);
+        }
+        // Firefox выгружает даты в миллисекундах от начала эпохи
+        if value.chars().all(|c| c.is_ascii_digit()) {
+            return value
+                .parse()
+                .ok()
+                .and_then(DateTime::from_timestamp_millis)
+                .map(Some)
+                .ok_or_else(|| format!("Некорректная дата: {}", value));
+        }
+        DateTime::parse_from_rfc3339(value)
+            .map(|d| Some(d.with_timezone(&Utc)))
+            .map_err(|_| format!("Некорректная дата: {}", value))
+    };
+
+    for (column, value) in columns.iter().zip(record.iter()) {
+        match column {
+            Column::Id => {
+                if !value.is_empty() {
+                    entry.id = value.to_string();
+                }
+            }
+            Column::Kind => {
+                entry.kind = EntryKind::parse(value)
+                    .ok_or_else(|| format!("Неизвестный тип записи: {}", value))?;
+            }
+            Column::Name => entry.name = value.to_string(),
+            Column::Username => entry.username = value.to_string(),
+            Column::Password => entry.password = value.to_string(),
+            Column::Url => {
+                entry.urls = value
+                    .lines()
+                    .map(str::trim)
+                    .filter(|url| !url.is_empty())
+                    .map(str::to_string)
+                    .collect();
+            }
+            Column::Notes => entry.notes = value.to_string(),
+            Column::Folder => entry.folder = Some(value.to_string()).filter(|f| !f.is_empty()),
+            Column::Tags => {
+                entry.tags = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|tag| !tag.is_empty())
+                    .map(str::to_string)
+                    .collect();
+            }
+            Column::Totp => entry.totp = Some(value.to_string()).filter(|t| !t.is_empty()),
+            Column::CreatedAt => {
+                if let Some(date) = parse_date(value)? {
+                    entry.created_at = Some(date);
+                }
+            }
+            Column::UpdatedAt => {
+                if let Some(date) = parse_date(value)? {
+                    entry.updated_at = Some(date);
+                }
+            }
+            Column::PasswordChangedAt => {
+                if let Some(date) = parse_date(value)? {
+                    entry.password_changed_at = Some(date);
+                }
+            }
+            Column::Custom(name) => {
+                if !value.is_empty() {
+                    entry.custom_fields.push(CustomField {
+                        name: name.clone(),
+                        value: value.to_string(),
+                        protected: false,
+                    });
+                }
+            }
+            Column::Ignored => {}
+        }
+    }
+
+    if entry.name.is_empty() {
+        // Браузеры иногда не выгружают название — берём адрес сайта
+        match entry.urls.first() {
+            Some(url) => entry.name = url.clone(),
+            None => return Err("Не указано название записи".to_string()),
+        }
+    }
+    Ok(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn export(entries: &[Entry]) -> String {
+        let mut out = Vec::new();
+        write_entries(&mut out, entries).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn quotes_special_characters_per_rfc4180() {
+        let mut entry = Entry::new("Почта, личная".into(), "user".into(), "pa\"ss".into());
+        entry.notes = "строка 1\nстрока 2".into();
+        let csv = export(&[entry]);
+        assert!(csv.contains("\"Почта, личная\""));
+        assert!(csv.contains("\"pa\"\"ss\""));
+        assert!(csv.contains("\"строка 1\nстрока 2\""));
+    }
+
+    #[test]
+    fn round_trips_all_fields() {
+        let mut entry = Entry::new("Банк".into(), "ivan".into(), "a,b\"c\r\nd".into());
+        entry.urls = vec!["https://bank.example".into(), "https://m.bank.example".into()];
+        entry.notes = "многострочная\nзаметка".into();
+        entry.folder = Some("Финансы".into());
+        entry.tags = vec!["деньги".into(), "важное".into()];
+        entry.totp = Some("JBSWY3DPEHPK3PXP".into());
+        entry.kind = EntryKind::Note;
+        entry.custom_fields.push(CustomField {
+            name: "ПИН".into(),
+            value: "1234".into(),
+            protected: false,
+        });
+
+        let import = read_entries(export(std::slice::from_ref(&entry)).as_bytes()).unwrap();
+        assert!(import.errors.is_empty(), "{:?}", import.errors);
+        let read = &import.entries[0];
+        assert_eq!(read.id, entry.id);
+        assert_eq!(read.kind, entry.kind);
+        assert_eq!(read.name, entry.name);
+        assert_eq!(read.password, entry.password);
+        assert_eq!(read.urls, entry.urls);
+        assert_eq!(read.notes, entry.notes);
+        assert_eq!(read.folder, entry.folder);
+        assert_eq!(read.tags, entry.tags);
+        assert_eq!(read.totp, entry.totp);
+        assert_eq!(read.custom_fields[0].name, "ПИН");
+        assert_eq!(read.custom_fields[0].value, "1234");
+        assert_eq!(read.created_at.map(|d| d.timestamp()), entry.created_at.map(|d| d.timestamp()));
+    }
+
+    #[test]
+    fn maps_foreign_headers_in_any_order() {
+        let csv = "\u{feff}url,login_password,title,login_username,extra\nhttps://a.example,secret,A,alice,note\n";
+        let import = read_entries(csv.as_bytes()).unwrap();
+        let entry = &import.entries[0];
+        assert_eq!((entry.name.as_str(), entry.username.as_str(), entry.password.as_str()), ("A", "alice", "secret"));
+        assert_eq!(entry.urls, vec!["https://a.example"]);
+        assert_eq!(entry.notes, "note");
+    }
+
+    #[test]
+    fn bad_rows_are_reported_with_line_numbers() {
+        let csv = "name,username,password,kind,created_at\n\
+                   ok,u,p,login,\n\
+                   ,u,p,,\n\
+                   bad-kind,u,p,card,\n\
+                   bad-date,u,p,,вчера\n\
+                   too,many,fields,login,,extra\n\
+                   millis,u,p,,1700000000000\n";
+        let import = read_entries(csv.as_bytes()).unwrap();
+        let names: Vec<&str> = import.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["ok", "millis"]);
+        assert_eq!(import.entries[1].created_at.unwrap().timestamp(), 1_700_000_000);
+        let lines: Vec<u64> = import.errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn missing_name_column_is_rejected() {
+        assert!(read_entries("username,password\nu,p\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn write_columns_rejects_unknown_column() {
+        assert!(write_columns(Vec::new(), &[], &["name", "nonsense"], false).is_err());
+    }
+}
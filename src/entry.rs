@@ -1,8 +1,218 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryKind {
+    #[default]
+    Login,
+    Note,
+    SshKey,
+}
+
+impl EntryKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EntryKind::Login => "login",
+            EntryKind::Note => "note",
+            EntryKind::SshKey => "ssh_key",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "" | "login" => Some(EntryKind::Login),
+            "note" | "secure_note" => Some(EntryKind::Note),
+            "ssh_key" | "ssh" => Some(EntryKind::SshKey),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CustomField {
+    pub name: String,
+    pub value: String,
+    /// Скрывать значение при отображении, как пароль
+    #[serde(default)]
+    pub protected: bool,
+}
+
+/// SSH-ключ записи
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SshKey {
+    /// Закрытый ключ OpenSSH без парольной фразы
+    pub private_key: String,
+    /// Спрашивать подтверждение перед каждой подписью
+    #[serde(default)]
+    pub confirm: bool,
+}
+
+/// Вложенный файл записи
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Attachment {
+    pub name: String,
+    #[serde(with = "base64_bytes")]
+    pub data: Vec<u8>,
+}
+
+// Бинарные данные храним в JSON строкой base64, а не массивом чисел
+mod base64_bytes {
+    use base64::engine::general_purpose::STANDARD as BASE64;
+    use base64::Engine;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&BASE64.encode(data))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        BASE64.decode(encoded).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Сколько прошлых версий записи хранить (как HistoryMaxItems в KeePass)
+pub const MAX_HISTORY: usize = 10;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Entry {  
+    #[serde(default = "generate_id")]
+    pub id: String,
+    #[serde(default)]
+    pub kind: EntryKind,
     pub name: String,     
     pub username: String, 
     pub password: String, 
-}
\ No newline at end of file
+    
+    #[serde(default)]
+    pub urls: Vec<String>,
+    #[serde(default)]
+    pub notes: String,
+    #[serde(default)]
+    pub folder: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Секрет TOTP (base32) или ссылка otpauth://
+    #[serde(default)]
+    pub totp: Option<String>,
+    #[serde(default)]
+    pub custom_fields: Vec<CustomField>,
+    /// Своя последовательность автонабора (синтаксис KeePass);
+    /// `None` — `{USERNAME}{TAB}{PASSWORD}{ENTER}`
+    #[serde(default)]
+    pub auto_type: Option<String>,
+    #[serde(default)]
+    pub ssh_key: Option<SshKey>,
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+    /// Прошлые версии записи, от старых к новым
+    #[serde(default)]
+    pub history: Vec<Entry>,
+    
+    // Старые хранилища не содержат дат, поэтому поля необязательные
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub password_changed_at: Option<DateTime<Utc>>,
+}
+
+fn generate_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+impl Entry {
+    pub fn new(name: String, username: String, password: String) -> Self {
+        let now = Utc::now();
+        Self {
+            id: generate_id(),
+            kind: EntryKind::Login,
+            name,
+            username,
+            password,
+            urls: Vec::new(),
+            notes: String::new(),
+            folder: None,
+            tags: Vec::new(),
+            totp: None,
+            custom_fields: Vec::new(),
+            auto_type: None,
+            ssh_key: None,
+            attachments: Vec::new(),
+            history: Vec::new(),
+            created_at: Some(now),
+            updated_at: Some(now),
+            password_changed_at: Some(now),
+        }
+    }
+
+    /// Значение поля по имени: `username`, `password`, `url`, `notes`, `folder`,
+    /// `tags`, `totp` или название пользовательского поля.
+    pub fn field_value(&self, field: &str) -> Option<String> {
+        let field = field.trim();
+        let standard = match field.to_lowercase().as_str() {
+            "name" | "title" => Some(self.name.clone()),
+            "username" | "user" | "login" => Some(self.username.clone()),
+            "password" | "pass" => Some(self.password.clone()),
+            "url" => self.urls.first().cloned(),
+            "urls" => Some(self.urls.join("\n")),
+            "notes" | "note" => Some(self.notes.clone()),
+            "folder" => self.folder.clone(),
+            "tags" => Some(self.tags.join(",")),
+            "totp" | "otp" => self.totp.clone(),
+            _ => None,
+        };
+        standard.or_else(|| {
+            self.custom_fields
+                .iter()
+                .find(|f| f.name.eq_ignore_ascii_case(field))
+                .map(|f| f.value.clone())
+        })
+    }
+
+    /// Значение поля для копирования в буфер. В отличие от `field_value`,
+    /// для `totp` возвращает текущий одноразовый код, а не секрет.
+    pub fn copy_value(&self, field: &str) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        match field.trim().to_lowercase().as_str() {
+            "totp" | "otp" => match &self.totp {
+                Some(secret) => Ok(Some(crate::totp::Totp::parse(secret)?.current().0)),
+                None => Ok(None),
+            },
+            _ => Ok(self.field_value(field)),
+        }
+    }
+
+    /// Затирает секреты в памяти (пароль, TOTP, заметки, значения полей,
+    /// SSH-ключ), в том числе в истории.
+    pub fn zeroize_secrets(&mut self) {
+        self.password.zeroize();
+        self.notes.zeroize();
+        self.totp.zeroize();
+        if let Some(key) = &mut self.ssh_key {
+            key.private_key.zeroize();
+        }
+        for field in &mut self.custom_fields {
+            field.value.zeroize();
+        }
+        for attachment in &mut self.attachments {
+            attachment.data.zeroize();
+        }
+        for old in &mut self.history {
+            old.zeroize_secrets();
+        }
+    }
+
+    /// Сохраняет текущее состояние записи в историю перед изменением.
+    pub fn push_history(&mut self) {
+        let mut snapshot = self.clone();
+        snapshot.history.clear();
+        self.history.push(snapshot);
+        if self.history.len() > MAX_HISTORY {
+            let excess = self.history.len() - MAX_HISTORY;
+            self.history.drain(..excess);
+        }
+    }
+}
@@ -0,0 +1,408 @@
+use crate::csv_format;
+use crate::entry::Entry;
+use crate::vault::{derive_vault_key, open_entries, seal_entries};
+use rand::RngCore;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use zeroize::Zeroize;
+
+/// Минимальный размер ключевого файла в байтах
+pub const MIN_KEY_FILE_LEN: usize = 32;
+
+/// Поле записи, которое можно включить в экспорт. Id и тип записи
+/// выгружаются всегда, чтобы экспорт можно было импортировать обратно.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportField {
+    Name,
+    Username,
+    Password,
+    Url,
+    Notes,
+    Folder,
+    Tags,
+    Totp,
+    CustomFields,
+    Attachments,
+    History,
+    Dates,
+}
+
+impl ExportField {
+    pub const ALL: [ExportField; 12] = [
+        ExportField::Name,
+        ExportField::Username,
+        ExportField::Password,
+        ExportField::Url,
+        ExportField::Notes,
+        ExportField::Folder,
+        ExportField::Tags,
+        ExportField::Totp,
+        ExportField::CustomFields,
+        ExportField::Attachments,
+        ExportField::History,
+        ExportField::Dates,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportField::Name => "name",
+            ExportField::Username => "username",
+            ExportField::Password => "password",
+            ExportField::Url => "url",
+            ExportField::Notes => "notes",
+            ExportField::Folder => "folder",
+            ExportField::Tags => "tags",
+            ExportField::Totp => "totp",
+            ExportField::CustomFields => "fields",
+            ExportField::Attachments => "attachments",
+            ExportField::History => "history",
+            ExportField::Dates => "dates",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "name" | "title" => Some(ExportField::Name),
+            "username" | "user" | "login" => Some(ExportField::Username),
+            "password" | "pass" => Some(ExportField::Password),
+            "url" | "urls" => Some(ExportField::Url),
+            "notes" | "note" => Some(ExportField::Notes),
+            "folder" => Some(ExportField::Folder),
+            "tags" | "tag" => Some(ExportField::Tags),
+            "totp" | "otp" => Some(ExportField::Totp),
+            "fields" | "custom" | "custom_fields" => Some(ExportField::CustomFields),
+            "attachments" => Some(ExportField::Attachments),
+            "history" => Some(ExportField::History),
+            "dates" => Some(ExportField::Dates),
+            _ => None,
+        }
+    }
+
+    /// Разбирает список вида `name,username,password`.
+    pub fn parse_list(values: &[String]) -> Result<Vec<Self>, Box<dyn Error>> {
+        let mut fields = Vec::new();
+        for value in values.iter().flat_map(|v| v.split(',')).filter(|v| !v.trim().is_empty()) {
+            let field = Self::parse(value).ok_or_else(|| {
+                let known: Vec<&str> = Self::ALL.iter().map(|f| f.as_str()).collect();
+                format!("Неизвестное поле: {} (доступны: {})", value, known.join(", "))
+            })?;
+            if !fields.contains(&field) {
+                fields.push(field);
+            }
+        }
+        Ok(fields)
+    }
+
+    // Ключи в JSON-представлении записи
+    fn json_keys(&self) -> &'static [&'static str] {
+        match self {
+            ExportField::Name => &["name"],
+            ExportField::Username => &["username"],
+            ExportField::Password => &["password"],
+            ExportField::Url => &["urls"],
+            ExportField::Notes => &["notes"],
+            ExportField::Folder => &["folder"],
+            ExportField::Tags => &["tags"],
+            ExportField::Totp => &["totp"],
+            ExportField::CustomFields => &["custom_fields"],
+            ExportField::Attachments => &["attachments"],
+            ExportField::History => &["history"],
+            ExportField::Dates => &["created_at", "updated_at", "password_changed_at"],
+        }
+    }
+}
+
+/// Копия записи, в которой оставлены только выбранные поля.
+pub fn select_fields(entry: &Entry, fields: &[ExportField]) -> Entry {
+    let has = |field: ExportField| fields.contains(&field);
+    let mut selected = entry.clone();
+    if !has(ExportField::Name) {
+        selected.name.clear();
+    }
+    if !has(ExportField::Username) {
+        selected.username.clear();
+    }
+    if !has(ExportField::Password) {
+        selected.password.zeroize();
+    }
+    if !has(ExportField::Url) {
+        selected.urls.clear();
+    }
+    if !has(ExportField::Notes) {
+        selected.notes.clear();
+    }
+    if !has(ExportField::Folder) {
+        selected.folder = None;
+    }
+    if !has(ExportField::Tags) {
+        selected.tags.clear();
+    }
+    if !has(ExportField::Totp) {
+        selected.totp = None;
+    }
+    if !has(ExportField::CustomFields) {
+        selected.custom_fields.clear();
+    }
+    if !has(ExportField::Attachments) {
+        selected.attachments.clear();
+    }
+    if has(ExportField::History) {
+        selected.history = entry.history.iter().map(|old| select_fields(old, fields)).collect();
+    } else {
+        selected.history.clear();
+    }
+    if !has(ExportField::Dates) {
+        selected.created_at = None;
+        selected.updated_at = None;
+        selected.password_changed_at = None;
+    }
+    selected
+}
+
+/// Чем защищён зашифрованный экспорт.
+pub enum ExportKey {
+    /// Пароль: файл открывается как обычное хранилище (`hiho --vault файл`)
+    Password(String),
+    /// Содержимое ключевого файла
+    KeyFile(Vec<u8>),
+}
+
+impl ExportKey {
+    fn derive(&self) -> Result<[u8; 32], Box<dyn Error>> {
+        match self {
+            ExportKey::Password(password) => derive_vault_key(password),
+            ExportKey::KeyFile(data) => Ok(Sha256::digest(data).into()),
+        }
+    }
+}
+
+impl Drop for ExportKey {
+    fn drop(&mut self) {
+        match self {
+            ExportKey::Password(password) => password.zeroize(),
+            ExportKey::KeyFile(data) => data.zeroize(),
+        }
+    }
+}
+
+/// Записывает записи в контейнер того же формата, что и `vault.enc`.
+pub fn write_encrypted(path: &Path, entries: &[Entry], key: &ExportKey) -> Result<(), Box<dyn Error>> {
+    let data = seal_entries(entries, &key.derive()?)?;
+    create_private_file(path)?.write_all(&data)?;
+    Ok(())
+}
+
+pub fn read_encrypted(data: &[u8], key: &ExportKey) -> Result<Vec<Entry>, Box<dyn Error>> {
+    open_entries(data, &key.derive()?).map_err(|_| "Неверный пароль или ключевой файл экспорта".into())
+}
+
+/// Экспорт в открытом виде (json или csv). Файл доступен только владельцу.
+/// В файл попадают только выбранные поля, невыбранные не выводятся вовсе.
+pub fn write_plaintext(
+    path: &Path,
+    format: &str,
+    entries: &[Entry],
+    fields: &[ExportField],
+) -> Result<(), Box<dyn Error>> {
+    let file = create_private_file(path)?;
+    match format {
+        "json" => serde_json::to_writer_pretty(file, &json_with_fields(entries, fields)?)?,
+        "csv" => {
+            let (headers, custom_fields) = csv_columns(fields);
+            csv_format::write_columns(file, entries, &headers, custom_fields)?
+        }
+        _ => return Err(format!("Неподдерживаемый формат: {}", format).into()),
+    }
+    Ok(())
+}
+
+fn json_with_fields(entries: &[Entry], fields: &[ExportField]) -> Result<Vec<Value>, Box<dyn Error>> {
+    let keep: Vec<&str> = ["id", "kind"]
+        .into_iter()
+        .chain(fields.iter().flat_map(|f| f.json_keys().iter().copied()))
+        .collect();
+    let mut values = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let mut value = serde_json::to_value(select_fields(entry, fields))?;
+        if let Value::Object(map) = &mut value {
+            map.retain(|key, _| keep.contains(&key.as_str()));
+        }
+        values.push(value);
+    }
+    Ok(values)
+}
+
+// Заголовки CSV для выбранных полей и нужны ли колонки пользовательских полей
+fn csv_columns(fields: &[ExportField]) -> (Vec<&'static str>, bool) {
+    let mut headers = vec!["id", "kind"];
+    for field in fields {
+        match field {
+            ExportField::Name => headers.push("name"),
+            ExportField::Username => headers.push("username"),
+            ExportField::Password => headers.push("password"),
+            ExportField::Url => headers.push("url"),
+            ExportField::Notes => headers.push("notes"),
+            ExportField::Folder => headers.push("folder"),
+            ExportField::Tags => headers.push("tags"),
+            ExportField::Totp => headers.push("totp"),
+            ExportField::Dates => headers.extend(["created_at", "updated_at", "password_changed_at"]),
+            // Пользовательские поля идут отдельными колонками в конце,
+            // вложения и историю в CSV не выгружаем
+            ExportField::CustomFields | ExportField::Attachments | ExportField::History => {}
+        }
+    }
+    (headers, fields.contains(&ExportField::CustomFields))
+}
+
+/// Читает ключевой файл или создаёт новый со случайным ключом.
+/// Второе значение — был ли файл создан сейчас.
+pub fn load_or_create_key_file(path: &Path) -> Result<(Vec<u8>, bool), Box<dyn Error>> {
+    if path.exists() {
+        let data = fs::read(path)?;
+        if data.len() < MIN_KEY_FILE_LEN {
+            return Err(format!("Ключевой файл короче {} байт", MIN_KEY_FILE_LEN).into());
+        }
+        return Ok((data, false));
+    }
+    let mut key = vec![0u8; MIN_KEY_FILE_LEN];
+    rand::thread_rng().fill_bytes(&mut key);
+    create_private_file(path)?.write_all(&key)?;
+    Ok((key, true))
+}
+
+/// Создаёт (или перезаписывает) файл с правами 0600.
+pub fn create_private_file(path: &Path) -> Result<File, Box<dyn Error>> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        let file = options.open(path)?;
+        // mode() действует только при создании, у существующего файла права меняем явно
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+        Ok(file)
+    }
+    #[cfg(not(unix))]
+    {
+        Ok(options.open(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Entry {
+        let mut entry = Entry::new("site".to_string(), "alice".to_string(), "s3cret".to_string());
+        entry.urls.push("https://example.com".to_string());
+        entry.notes = "заметка".to_string();
+        entry.tags.push("work".to_string());
+        entry.totp = Some("JBSWY3DPEHPK3PXP".to_string());
+        entry
+    }
+
+    #[test]
+    fn encrypted_export_round_trips_with_password_and_key_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let entries = [sample()];
+        for (name, key, wrong) in [
+            ("pw.enc", ExportKey::Password("export-pass".to_string()), ExportKey::Password("other".to_string())),
+            ("key.enc", ExportKey::KeyFile(vec![7; MIN_KEY_FILE_LEN]), ExportKey::KeyFile(vec![8; MIN_KEY_FILE_LEN])),
+        ] {
+            let path = dir.path().join(name);
+            write_encrypted(&path, &entries, &key).unwrap();
+            let data = fs::read(&path).unwrap();
+            let restored = read_encrypted(&data, &key).unwrap();
+            assert_eq!(restored.len(), 1);
+            assert_eq!(restored[0].id, entries[0].id);
+            assert_eq!(restored[0].password, "s3cret");
+            assert_eq!(restored[0].totp, entries[0].totp);
+            assert!(read_encrypted(&data, &wrong).is_err());
+        }
+    }
+
+    #[test]
+    fn key_file_key_is_sha256_of_contents() {
+        let data = b"0123456789abcdef0123456789abcdef".to_vec();
+        let expected: [u8; 32] = Sha256::digest(&data).into();
+        assert_eq!(ExportKey::KeyFile(data).derive().unwrap(), expected);
+    }
+
+    #[test]
+    fn key_file_is_created_once_and_short_files_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("export.key");
+        let (created, is_new) = load_or_create_key_file(&path).unwrap();
+        assert!(is_new);
+        assert_eq!(created.len(), MIN_KEY_FILE_LEN);
+        let (loaded, is_new) = load_or_create_key_file(&path).unwrap();
+        assert!(!is_new);
+        assert_eq!(loaded, created);
+
+        let short = dir.path().join("short.key");
+        fs::write(&short, [1u8; MIN_KEY_FILE_LEN - 1]).unwrap();
+        assert!(load_or_create_key_file(&short).is_err());
+    }
+
+    #[test]
+    fn select_fields_keeps_only_chosen_fields() {
+        let mut entry = sample();
+        entry.push_history();
+        entry.password = "new".to_string();
+
+        let selected = select_fields(&entry, &[ExportField::Name, ExportField::Url]);
+        assert_eq!(selected.id, entry.id);
+        assert_eq!(selected.name, "site");
+        assert_eq!(selected.urls, entry.urls);
+        assert!(selected.username.is_empty());
+        assert!(selected.password.is_empty());
+        assert!(selected.notes.is_empty());
+        assert!(selected.tags.is_empty());
+        assert!(selected.totp.is_none());
+        assert!(selected.history.is_empty());
+        assert!(selected.created_at.is_none());
+
+        // История выгружается с тем же набором полей
+        let selected = select_fields(&entry, &[ExportField::Username, ExportField::History]);
+        assert_eq!(selected.history.len(), 1);
+        assert_eq!(selected.history[0].username, "alice");
+        assert!(selected.history[0].password.is_empty());
+    }
+
+    #[test]
+    fn parse_list_accepts_aliases_and_rejects_unknown() {
+        let fields = ExportField::parse_list(&["name,login".to_string(), "pass".to_string(), "name".to_string()]).unwrap();
+        assert_eq!(fields, vec![ExportField::Name, ExportField::Username, ExportField::Password]);
+        assert!(ExportField::parse_list(&["colour".to_string()]).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn plaintext_export_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("export.json");
+        // Существующий файл с широкими правами тоже должен стать 0600
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        write_plaintext(&path, "json", &[sample()], &[ExportField::Name]).unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o600);
+
+        let value: Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        let object = value[0].as_object().unwrap();
+        let mut keys: Vec<&str> = object.keys().map(String::as_str).collect();
+        keys.sort();
+        assert_eq!(keys, ["id", "kind", "name"]);
+
+        let fresh = dir.path().join("fresh.csv");
+        create_private_file(&fresh).unwrap();
+        assert_eq!(fs::metadata(&fresh).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+}
@@ -0,0 +1,172 @@
+use crate::entry::{Entry, EntryKind};
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+
+/// Отбор записей по тегам, папке, типу, названию и дате изменения.
+/// Пустой фильтр пропускает все записи.
+#[derive(Debug, Clone, Default)]
+pub struct EntryFilter {
+    /// Запись должна иметь все перечисленные теги
+    pub tags: Vec<String>,
+    /// Папка вместе с вложенными: «Work» подходит и для «Work/Staging»
+    pub folder: Option<String>,
+    pub kind: Option<EntryKind>,
+    /// Шаблон названия: `*` и `?`, без них — поиск подстроки
+    pub name: Option<String>,
+    pub modified_since: Option<DateTime<Utc>>,
+}
+
+impl EntryFilter {
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+            && self.folder.is_none()
+            && self.kind.is_none()
+            && self.name.is_none()
+            && self.modified_since.is_none()
+    }
+
+    pub fn matches(&self, entry: &Entry) -> bool {
+        let has_tags = self
+            .tags
+            .iter()
+            .all(|tag| entry.tags.iter().any(|t| t.to_lowercase() == tag.to_lowercase()));
+        let in_folder = self.folder.as_ref().is_none_or(|folder| {
+            entry
+                .folder
+                .as_ref()
+                .is_some_and(|f| is_same_or_subfolder(f, folder))
+        });
+        let kind_matches = self.kind.is_none_or(|kind| entry.kind == kind);
+        let name_matches = self
+            .name
+            .as_ref()
+            .is_none_or(|pattern| name_matches(&entry.name, pattern));
+        // Записи без дат (старые форматы) считаем давно не изменявшимися
+        let modified_matches = self
+            .modified_since
+            .is_none_or(|since| entry.updated_at.or(entry.created_at).is_some_and(|d| d >= since));
+
+        has_tags && in_folder && kind_matches && name_matches && modified_matches
+    }
+
+    /// Записи, прошедшие фильтр, вместе с их номерами в хранилище.
+    pub fn apply<'a>(&self, entries: &'a [Entry]) -> Vec<(usize, &'a Entry)> {
+        entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| self.matches(entry))
+            .collect()
+    }
+}
+
+/// Разбирает дату для `modified_since`: `2024-05-01`, RFC 3339 или
+/// относительный срок `12h`, `30d`, `2w`.
+pub fn parse_since(value: &str) -> Result<DateTime<Utc>, String> {
+    let value = value.trim();
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Ok(date.with_timezone(&Utc));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc());
+    }
+
+    let invalid = || format!("Не удалось разобрать дату: {}", value);
+    let (split, unit) = value.char_indices().last().ok_or_else(invalid)?;
+    let amount: i64 = value[..split].parse().map_err(|_| invalid())?;
+    let duration = match unit {
+        'h' => TimeDelta::try_hours(amount),
+        'd' => TimeDelta::try_days(amount),
+        'w' => TimeDelta::try_weeks(amount),
+        _ => return Err(invalid()),
+    }
+    .ok_or_else(|| format!("Слишком большой срок: {}", value))?;
+    Utc::now()
+        .checked_sub_signed(duration)
+        .ok_or_else(|| format!("Слишком большой срок: {}", value))
+}
+
+fn is_same_or_subfolder(folder: &str, parent: &str) -> bool {
+    let folder = folder.trim_matches('/').to_lowercase();
+    let parent = parent.trim_matches('/').to_lowercase();
+    folder == parent || folder.starts_with(&format!("{}/", parent))
+}
+
+fn name_matches(name: &str, pattern: &str) -> bool {
+    let name = name.to_lowercase();
+    let pattern = pattern.to_lowercase();
+    if !pattern.contains(['*', '?']) {
+        return name.contains(&pattern);
+    }
+    let name: Vec<char> = name.chars().collect();
+    let pattern: Vec<char> = pattern.chars().collect();
+    glob_match(&name, &pattern)
+}
+
+// Классический перебор с возвратом к последней звёздочке
+fn glob_match(text: &[char], pattern: &[char]) -> bool {
+    let (mut t, mut p) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            t += 1;
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry_with_tags(tags: &[&str]) -> Entry {
+        let mut entry = Entry::new("site".to_string(), "alice".to_string(), "pw".to_string());
+        entry.tags = tags.iter().map(|t| t.to_string()).collect();
+        entry
+    }
+
+    #[test]
+    fn parse_since_accepts_dates_and_relative_units() {
+        let date = parse_since("2024-05-01").unwrap();
+        assert_eq!(date.to_rfc3339(), "2024-05-01T00:00:00+00:00");
+        let date = parse_since("2024-05-01T12:00:00+03:00").unwrap();
+        assert_eq!(date.to_rfc3339(), "2024-05-01T09:00:00+00:00");
+
+        for (value, expected) in [
+            ("12h", TimeDelta::hours(12)),
+            ("30d", TimeDelta::days(30)),
+            (" 2w ", TimeDelta::weeks(2)),
+        ] {
+            let ago = Utc::now() - parse_since(value).unwrap();
+            assert!((ago - expected).num_seconds().abs() < 5, "{}: {}", value, ago);
+        }
+    }
+
+    #[test]
+    fn parse_since_rejects_bad_input_without_panicking() {
+        for value in ["", "d", "5m", "5д", "пять", "x5d", "99999999999999d", "9223372036854775807w", "-99999999999999h"] {
+            assert!(parse_since(value).is_err(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn tags_match_case_insensitively_including_cyrillic() {
+        let entry = entry_with_tags(&["Работа", "VPN"]);
+        let filter = |tags: &[&str]| EntryFilter {
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            ..EntryFilter::default()
+        };
+        assert!(filter(&["работа"]).matches(&entry));
+        assert!(filter(&["vpn", "РАБОТА"]).matches(&entry));
+        assert!(!filter(&["работа", "дом"]).matches(&entry));
+        assert!(EntryFilter::default().matches(&entry));
+    }
+}
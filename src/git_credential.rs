@@ -0,0 +1,359 @@
+//! Протокол помощника учётных данных git (`git credential`).
+//!
+//! git передаёт на stdin строки `ключ=значение` (`protocol`, `host`, `path`,
+//! `username`, `password`, `url`) до пустой строки и ждёт в ответ на `get`
+//! такие же строки с `username` и `password`. Неизвестные ключи пропускаются.
+//!
+//! Запись подходит, если один из её адресов совпадает по протоколу и хосту
+//! (с портом), а путь запроса лежит внутри пути адреса. Путь git присылает
+//! только при `credential.useHttpPath`, поэтому из записей на конкретные
+//! репозитории и на весь хост выбирается самая точная.
+
+use crate::entry::Entry;
+use crate::vault::Vault;
+use std::error::Error;
+use std::io::{BufRead, Write};
+use zeroize::Zeroizing;
+
+/// Метка записей, которые сохранил сам помощник (`store`). Только такие
+/// записи помощник удаляет по `erase`.
+pub const GIT_TAG: &str = "git-credential";
+
+#[derive(Default)]
+pub struct Credential {
+    pub protocol: Option<String>,
+    pub host: Option<String>,
+    pub path: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<Zeroizing<String>>,
+}
+
+impl Credential {
+    /// Читает описание учётных данных до пустой строки или конца потока.
+    pub fn read<R: BufRead>(reader: R) -> Result<Self, Box<dyn Error>> {
+        let mut credential = Credential::default();
+        let mut url = None;
+        for line in reader.lines() {
+            let line = Zeroizing::new(line?);
+            if line.is_empty() {
+                break;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(format!("Некорректная строка протокола git: без '=' ({} симв.)", line.len()).into());
+            };
+            let value = value.to_string();
+            match key {
+                "protocol" => credential.protocol = Some(value),
+                "host" => credential.host = Some(value),
+                "path" => credential.path = Some(value),
+                "username" => credential.username = Some(value),
+                "password" => credential.password = Some(Zeroizing::new(value)),
+                "url" => url = Some(value),
+                _ => {}
+            }
+        }
+        // Старые версии git присылают только url
+        if let Some(url) = url.as_deref().and_then(GitUrl::parse) {
+            credential.protocol = credential.protocol.or(url.protocol);
+            credential.host = credential.host.or(Some(url.host));
+            credential.path = credential.path.or(Some(url.path).filter(|p| !p.is_empty()));
+        }
+        Ok(credential)
+    }
+
+    /// Пишет ответ для `get`.
+    pub fn write_answer<W: Write>(writer: &mut W, username: &str, password: &str) -> Result<(), Box<dyn Error>> {
+        if [username, password].iter().any(|v| v.contains(['\n', '\0'])) {
+            return Err("Логин или пароль содержит перевод строки — git не сможет его принять".into());
+        }
+        writeln!(writer, "username={}", username)?;
+        writeln!(writer, "password={}", password)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    fn target(&self) -> Option<GitUrl> {
+        Some(GitUrl {
+            protocol: self.protocol.clone(),
+            host: self.host.clone()?.to_lowercase(),
+            path: normalize_path(self.path.as_deref().unwrap_or("")),
+        })
+    }
+
+    /// Адрес для новой записи: `протокол://хост/путь`.
+    pub fn url(&self) -> Option<String> {
+        let target = self.target()?;
+        let mut url = format!("{}://{}", target.protocol.as_deref().unwrap_or("https"), target.host);
+        if !target.path.is_empty() {
+            url.push('/');
+            url.push_str(&target.path);
+        }
+        Some(url)
+    }
+
+    /// Индекс самой подходящей записи с паролем.
+    pub fn find_entry(&self, entries: &[Entry]) -> Option<usize> {
+        let target = self.target()?;
+        entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| !entry.password.is_empty())
+            .filter(|(_, entry)| self.username.as_ref().is_none_or(|user| *user == entry.username))
+            .filter_map(|(i, entry)| {
+                let score = entry
+                    .urls
+                    .iter()
+                    .filter_map(|url| GitUrl::parse(url))
+                    .filter_map(|url| url.score(&target))
+                    .max()?;
+                Some((score, i))
+            })
+            // При равенстве выигрывает запись, которая раньше в списке
+            .max_by(|(a, i), (b, j)| a.cmp(b).then(j.cmp(i)))
+            .map(|(_, i)| i)
+    }
+
+    /// Запись, сохранённая через `store` для этих учётных данных.
+    pub fn find_stored(&self, entries: &[Entry]) -> Option<usize> {
+        let url = self.url()?;
+        let username = self.username.as_deref().unwrap_or("");
+        entries.iter().position(|entry| {
+            entry.tags.iter().any(|tag| tag == GIT_TAG)
+                && entry.username == username
+                && entry.urls.iter().any(|u| u.eq_ignore_ascii_case(&url))
+        })
+    }
+
+    /// Новая запись для `store`.
+    pub fn to_entry(&self) -> Option<Entry> {
+        let url = self.url()?;
+        let name = url.split_once("://").map(|(_, rest)| rest).unwrap_or(&url).to_string();
+        let mut entry = Entry::new(
+            name,
+            self.username.clone().unwrap_or_default(),
+            self.password.as_deref().cloned().unwrap_or_default(),
+        );
+        entry.urls = vec![url];
+        entry.tags = vec![GIT_TAG.to_string()];
+        Some(entry)
+    }
+}
+
+/// Выполняет операцию `get`, `store` или `erase` над записями хранилища;
+/// неизвестные операции git требует молча пропускать. Возвращает `true`,
+/// если записи изменились и их нужно сохранить.
+pub fn execute<W: Write>(operation: &str, credential: &Credential, vault: &mut Vault, output: &mut W) -> Result<bool, Box<dyn Error>> {
+    match operation {
+        "get" => {
+            if let Some(index) = credential.find_entry(vault.get_entries()) {
+                let entry = &vault.get_entries()[index];
+                Credential::write_answer(output, &entry.username, &entry.password)?;
+            }
+            Ok(false)
+        }
+        "store" => {
+            let Some(password) = credential.password.as_deref() else {
+                return Ok(false);
+            };
+            // git подтверждает пароль, который сам получил от нас: записывать нечего
+            let known = credential
+                .find_entry(vault.get_entries())
+                .is_some_and(|index| vault.get_entries()[index].password == *password);
+            if known {
+                return Ok(false);
+            }
+            match credential.find_stored(vault.get_entries()) {
+                Some(index) => vault.edit_entry(index, None, Some(password.clone()))?,
+                None => vault.add_entry(credential.to_entry().ok_or("git не передал хост")?),
+            }
+            Ok(true)
+        }
+        "erase" => {
+            // Удаляем только то, что сохранил помощник: записи пользователя
+            // не должны пропадать из-за одного неудачного входа
+            let stored = credential.find_stored(vault.get_entries()).filter(|index| {
+                let entry = &vault.get_entries()[*index];
+                credential.password.as_deref().is_none_or(|password| *password == entry.password)
+            });
+            Ok(stored.and_then(|index| vault.remove_entry(index)).is_some())
+        }
+        _ => Ok(false),
+    }
+}
+
+// Адрес, разобранный для сравнения
+struct GitUrl {
+    protocol: Option<String>,
+    host: String,
+    path: String,
+}
+
+impl GitUrl {
+    fn parse(url: &str) -> Option<Self> {
+        let url = url.trim();
+        let (protocol, rest) = match url.split_once("://") {
+            Some((protocol, rest)) => (Some(protocol.to_lowercase()), rest),
+            None => (None, url),
+        };
+        let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+        // Логин в адресе (`user@host`) к хосту не относится
+        let host = authority.rsplit_once('@').map(|(_, host)| host).unwrap_or(authority);
+        if host.is_empty() {
+            return None;
+        }
+        let path = path.split(['?', '#']).next().unwrap_or("");
+        Some(Self {
+            protocol,
+            host: host.to_lowercase(),
+            path: normalize_path(path),
+        })
+    }
+
+    // Насколько адрес записи подходит к запросу; `None` — не подходит
+    fn score(&self, target: &GitUrl) -> Option<usize> {
+        if let (Some(protocol), Some(wanted)) = (&self.protocol, &target.protocol) {
+            if protocol != wanted {
+                return None;
+            }
+        }
+        if self.host != target.host {
+            return None;
+        }
+        if self.path.is_empty() {
+            return Some(1);
+        }
+        if target.path.is_empty() {
+            // git не прислал путь: запись на конкретный репозиторий подходит хуже всего
+            return Some(0);
+        }
+        let inside = target.path == self.path || target.path.starts_with(&format!("{}/", self.path));
+        inside.then_some(2 + self.path.len())
+    }
+}
+
+fn normalize_path(path: &str) -> String {
+    let path = path.trim_matches('/');
+    path.strip_suffix(".git").unwrap_or(path).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn credential(input: &str) -> Credential {
+        Credential::read(input.as_bytes()).unwrap()
+    }
+
+    fn login(name: &str, username: &str, password: &str, url: &str) -> Entry {
+        let mut entry = Entry::new(name.into(), username.into(), password.into());
+        entry.urls = vec![url.into()];
+        entry
+    }
+
+    fn vault() -> Vault {
+        Vault::detached(vec![
+            login("GitHub", "octocat", "host-token", "https://github.com"),
+            login("Репозиторий", "octocat", "repo-token", "https://github.com/acme/app.git"),
+            login("Другой", "alice", "alice-token", "https://alice@github.com/"),
+            login("GitLab", "octocat", "lab-token", "ssh://gitlab.com/acme"),
+        ])
+    }
+
+    fn run(operation: &str, input: &str, vault: &mut Vault) -> (bool, String) {
+        let mut output = Vec::new();
+        let changed = execute(operation, &credential(input), vault, &mut output).unwrap();
+        (changed, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn read_parses_fields_and_skips_unknown_keys() {
+        let credential = credential("protocol=https\nhost=Example.com:8443\nwwwauth[]=Basic\npassword=a=b\n\nhost=ignored\n");
+        assert_eq!(credential.protocol.as_deref(), Some("https"));
+        assert_eq!(credential.host.as_deref(), Some("Example.com:8443"));
+        assert_eq!(credential.password.as_deref().map(String::as_str), Some("a=b"));
+        assert_eq!(credential.url().as_deref(), Some("https://example.com:8443"));
+        assert!(Credential::read("garbage\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn read_accepts_url_only() {
+        let credential = credential("url=https://bob@github.com/acme/app.git?x=1\n");
+        assert_eq!(credential.host.as_deref(), Some("github.com"));
+        assert_eq!(credential.path.as_deref(), Some("acme/app"));
+    }
+
+    #[test]
+    fn get_prefers_most_specific_path() {
+        let mut vault = vault();
+        let (_, answer) = run("get", "protocol=https\nhost=github.com\npath=acme/app.git\n", &mut vault);
+        assert_eq!(answer, "username=octocat\npassword=repo-token\n");
+        let (_, answer) = run("get", "protocol=https\nhost=github.com\npath=acme/other.git\n", &mut vault);
+        assert_eq!(answer, "username=octocat\npassword=host-token\n");
+        // Без useHttpPath запись на весь хост лучше записи на репозиторий
+        let (_, answer) = run("get", "protocol=https\nhost=github.com\n", &mut vault);
+        assert_eq!(answer, "username=octocat\npassword=host-token\n");
+    }
+
+    #[test]
+    fn get_filters_by_username_and_protocol() {
+        let mut vault = vault();
+        let (_, answer) = run("get", "protocol=https\nhost=github.com\nusername=alice\n", &mut vault);
+        assert_eq!(answer, "username=alice\npassword=alice-token\n");
+        let (_, answer) = run("get", "protocol=https\nhost=gitlab.com\npath=acme/app\n", &mut vault);
+        assert_eq!(answer, "");
+        let (changed, answer) = run("get", "protocol=https\nhost=unknown.example\n", &mut vault);
+        assert!(!changed);
+        assert_eq!(answer, "");
+    }
+
+    #[test]
+    fn store_adds_tagged_entry_and_updates_it() {
+        let mut vault = vault();
+        let input = "protocol=https\nhost=git.example.com\npath=team/repo.git\nusername=bob\npassword=one\n";
+        assert!(run("store", input, &mut vault).0);
+        let stored = vault.get_entries().last().unwrap();
+        assert_eq!(stored.name, "git.example.com/team/repo");
+        assert_eq!(stored.urls, vec!["https://git.example.com/team/repo"]);
+        assert_eq!(stored.tags, vec![GIT_TAG]);
+        let count = vault.get_entries().len();
+
+        assert!(run("store", &input.replace("password=one", "password=two"), &mut vault).0);
+        assert_eq!(vault.get_entries().len(), count);
+        assert_eq!(vault.get_entries().last().unwrap().password, "two");
+    }
+
+    #[test]
+    fn store_of_known_password_changes_nothing() {
+        let mut vault = vault();
+        let input = "protocol=https\nhost=github.com\nusername=octocat\npassword=host-token\n";
+        assert!(!run("store", input, &mut vault).0);
+        assert!(!run("store", "protocol=https\nhost=github.com\n", &mut vault).0);
+        assert_eq!(vault.get_entries().len(), 4);
+    }
+
+    #[test]
+    fn erase_removes_only_helper_entries_with_same_password() {
+        let mut vault = vault();
+        // Запись пользователя не удаляется
+        assert!(!run("erase", "protocol=https\nhost=github.com\nusername=octocat\npassword=host-token\n", &mut vault).0);
+
+        let input = "protocol=https\nhost=git.example.com\nusername=bob\npassword=one\n";
+        run("store", input, &mut vault);
+        assert!(!run("erase", &input.replace("one", "other"), &mut vault).0);
+        assert!(run("erase", input, &mut vault).0);
+        assert_eq!(vault.get_entries().len(), 4);
+    }
+
+    #[test]
+    fn unknown_operation_is_ignored() {
+        let mut vault = vault();
+        assert_eq!(run("capability", "", &mut vault), (false, String::new()));
+    }
+
+    #[test]
+    fn answer_with_newline_is_refused() {
+        let mut output = Vec::new();
+        assert!(Credential::write_answer(&mut output, "user", "pass\nword").is_err());
+        assert!(output.is_empty());
+    }
+}
@@ -1,11 +1,26 @@
 use eframe::egui;
-use std::sync::{Arc, Mutex};
-use std::path::Path;
-use hiho::AutoLockManager;
+use hiho::audit::{audit_entries, audit_entries_with_breaches, AuditConfig, AuditReport, Severity};
+use hiho::autotype;
+use hiho::breach::open_source;
+use hiho::keystore;
+use hiho::remember::{RememberManager, RememberedKey};
+use hiho::search::{search, SearchQuery};
+use hiho::strength::{display_crack_time, estimate_strength, StrengthReport, StrengthScore};
+use hiho::system_lock::{self, LockSignal};
+use hiho::totp::Totp;
 use hiho::BiometricManager;
+use hiho::{ClipboardManager, IdleTimer};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use zeroize::Zeroize;
 
 // Импортируем настоящие структуры из нашего крейта
-use hiho::{Vault, Entry};
+use hiho::{Entry, Vault};
+
+// Сколько ждать после сворачивания окна, пока фокус перейдёт к цели автонабора
+const AUTOTYPE_DELAY: Duration = Duration::from_millis(700);
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum AppState {
@@ -16,14 +31,7 @@ pub enum AppState {
     PasswordGenerator,
     Locked,
     BiometricSetup,
-}
-
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum PasswordStrength {
-    Weak,
-    Medium,
-    Strong,
-    VeryStrong,
+    Security,
 }
 
 #[derive(Debug, Clone)]
@@ -53,25 +61,47 @@ pub struct HihoApp {
     pub state: AppState,
     pub vault: Option<Arc<Mutex<Vault>>>,
     pub master_password: String,
+    /// Файл хранилища: vault.enc или база KeePass .kdbx
+    pub vault_path: String,
     pub error_message: Option<String>,
     pub entries: Vec<Entry>,
     pub search_query: String,
     pub selected_entry: Option<usize>,
-    
+
     // Для формы добавления/редактирования
     pub form_name: String,
     pub form_username: String,
     pub form_password: String,
     pub show_password_generator: bool,
     pub generated_password: String,
-    
+
     // Новые поля для генератора паролей
     pub password_options: PasswordOptions,
-    
+
     // Поля для биометрии
     pub biometric_available: bool,
     pub biometric_enabled: bool,
-    pub show_biometric_prompt: bool,
+
+    // Вход по ключу, запомненному в хранилище ключей ОС
+    pub remembered: Option<RememberedKey>,
+    pub remember_device: bool,
+
+    // Отчёт аудита безопасности
+    pub audit_report: Option<AuditReport>,
+    pub breach_db_path: String,
+
+    // Автоблокировка: таймер бездействия и сигнал системы (сон, блокировка экрана)
+    pub idle: IdleTimer,
+    pub system_lock: Arc<Mutex<Option<LockSignal>>>,
+    pub lock_reason: Option<String>,
+
+    /// Когда помощник очистит буфер обмена (для обратного отсчёта)
+    pub clipboard_clear_at: Option<Instant>,
+    /// Запись, логин которой скопирован и ждёт копирования пароля
+    /// (там, где буфер не умеет подставлять пароль сам)
+    pub sequence_entry: Option<usize>,
+    /// Итог фонового автонабора для показа в окне
+    pub autotype_status: Arc<Mutex<Option<String>>>,
 }
 
 impl Default for HihoApp {
@@ -87,28 +117,52 @@ impl Default for HihoApp {
             state: AppState::Login,
             vault: None,
             master_password: String::new(),
+            vault_path: "data\\vault.enc".to_string(),
             error_message: None,
             entries: Vec::new(),
             search_query: String::new(),
             selected_entry: None,
-            
+
             form_name: String::new(),
             form_username: String::new(),
             form_password: String::new(),
             show_password_generator: false,
             generated_password: String::new(),
-    
+
             password_options: PasswordOptions::default(),
 
             biometric_available,
             biometric_enabled,
-            show_biometric_prompt: false,
+
+            remembered: remembered_key("data\\vault.enc"),
+            remember_device: false,
+
+            audit_report: None,
+            breach_db_path: String::new(),
+
+            idle: IdleTimer::from_config(),
+            system_lock: Arc::new(Mutex::new(None)),
+            lock_reason: None,
+
+            clipboard_clear_at: None,
+            sequence_entry: None,
+            autotype_status: Arc::new(Mutex::new(None)),
         }
     }
 }
 
 impl eframe::App for HihoApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.check_auto_lock(ctx);
+        if let Some(status) = self
+            .autotype_status
+            .lock()
+            .ok()
+            .and_then(|mut status| status.take())
+        {
+            self.error_message = Some(status);
+        }
+
         egui::CentralPanel::default().show(ctx, |ui| {
             match self.state {
                 AppState::Login => {
@@ -132,110 +186,206 @@ impl eframe::App for HihoApp {
                 AppState::BiometricSetup => {
                     self.show_biometric_setup(ui);
                 }
+                AppState::Security => {
+                    self.show_security_dashboard(ui);
+                }
             }
-            
+
             // Отображение ошибок
             if let Some(error) = &self.error_message {
                 ui.add_space(10.0);
                 ui.colored_label(egui::Color32::RED, error);
             }
+
+            self.show_clipboard_countdown(ui, ctx);
         });
     }
 }
 
 impl HihoApp {
-    fn show_login_screen(&mut self, ui: &mut egui::Ui) {
-    ui.vertical_centered(|ui| {
-        ui.add_space(50.0);
-        ui.heading("🔐 hiho - Менеджер паролей уровня NSA");
-        ui.add_space(30.0);
-        
-        // Добавим кнопку биометрического входа если доступно:
-        if self.biometric_available && self.biometric_enabled {
-            if ui.button("👆 Войти с помощью биометрии").clicked() {
-                self.attempt_biometric_login();
-            }
-            ui.add_space(20.0);
-        }
-        
-        ui.horizontal(|ui| {
-            ui.label("🔑 Мастер-пароль:");
-            let password_field = ui.add(
-                egui::TextEdit::singleline(&mut self.master_password)
-                    .password(true)
-                    .hint_text("Введите пароль")
-                    .desired_width(200.0)
-            );
-            
-            if password_field.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
-                self.attempt_login();
-            }
-        });
-        
-        ui.add_space(20.0);
-        
-        ui.horizontal(|ui| {
-            if ui.button("🔓 Войти").clicked() {
-                self.attempt_login();
-            }
-            
-            if ui.button("🆕 Создать хранилище").clicked() {
-                self.create_vault();
+    /// Меняет путь к хранилищу и заново ищет запомненный для него ключ.
+    pub fn set_vault_path(&mut self, path: String) {
+        self.remembered = remembered_key(&path);
+        self.vault_path = path;
+    }
+
+    /// Подписывается на сигналы системы о сне и блокировке экрана.
+    /// Сигнал приходит из другого потока, поэтому только запоминаем его
+    /// и будим интерфейс; хранилище закрывается в `update`.
+    pub fn watch_system_lock(&self, ctx: &egui::Context) {
+        let pending = Arc::clone(&self.system_lock);
+        let ctx = ctx.clone();
+        system_lock::watch(move |signal| {
+            if let Ok(mut pending) = pending.lock() {
+                *pending = Some(signal);
             }
+            ctx.request_repaint();
         });
-    });
-}
+    }
 
-    fn show_main_screen(&mut self, ui: &mut egui::Ui) {
-        // Проверяем автоблокировку только при необходимости
-    match AutoLockManager::should_lock() {
-        Ok(true) => {
-            // Нужно заблокировать сессию
-            let _ = AutoLockManager::lock_session();
-            self.state = AppState::Locked;
+    fn check_auto_lock(&mut self, ctx: &egui::Context) {
+        let signal = self
+            .system_lock
+            .lock()
+            .ok()
+            .and_then(|mut pending| pending.take());
+        if self.vault.is_none() {
+            return;
+        }
+        if let Some(signal) = signal {
+            self.lock_vault(Some(format!("Заблокировано: {}", signal.label())));
             return;
         }
-        Ok(false) => {
-            // Продолжаем работу, обновляем активность
-            let _ = AutoLockManager::update_activity();
+
+        // Любое событие ввода (клавиши, мышь, прокрутка) считается активностью
+        if ctx.input(|i| !i.events.is_empty()) {
+            self.idle.touch();
+        }
+        if self.idle.expired() {
+            self.lock_vault(Some("Заблокировано по таймеру бездействия".to_string()));
+            return;
         }
-        Err(_) => {
-            // Ошибка проверки, но продолжаем работу
-            let _ = AutoLockManager::update_activity();
+        // Без ввода egui не перерисовывает окно, поэтому просим кадр к моменту блокировки
+        if let Some(remaining) = self.idle.remaining() {
+            ctx.request_repaint_after(remaining);
         }
     }
-    
-    // Проверяем принудительную блокировку
-    if AutoLockManager::is_locked() {
+
+    /// Закрывает хранилище: ключ, пароль и записи затираются и удаляются
+    /// из памяти. Для продолжения работы снова нужен мастер-пароль.
+    fn lock_vault(&mut self, reason: Option<String>) {
+        // Drop у Vault затирает ключ и записи внутри хранилища
+        self.vault = None;
+        for entry in &mut self.entries {
+            entry.zeroize_secrets();
+        }
+        self.entries.clear();
+        self.master_password.zeroize();
+        self.form_password.zeroize();
+        self.generated_password.zeroize();
+        self.audit_report = None;
+        self.selected_entry = None;
+        self.sequence_entry = None;
+        self.show_password_generator = false;
+        self.lock_reason = reason;
+        self.error_message = None;
         self.state = AppState::Locked;
-        return;
     }
+
+    fn show_clipboard_countdown(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+        let Some(clear_at) = self.clipboard_clear_at else {
+            return;
+        };
+        let left = clear_at.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            self.clipboard_clear_at = None;
+            return;
+        }
+        ui.add_space(10.0);
+        ui.label(format!(
+            "🧹 Буфер обмена будет очищен через {} с",
+            left.as_secs() + 1
+        ));
+        // Обновляем отсчёт без ввода пользователя
+        ctx.request_repaint_after(Duration::from_millis(250));
+    }
+
+    fn show_login_screen(&mut self, ui: &mut egui::Ui) {
+        ui.vertical_centered(|ui| {
+            ui.add_space(50.0);
+            ui.heading("🔐 hiho - Менеджер паролей уровня NSA");
+            ui.add_space(30.0);
+
+            // Вход по ключу, запомненному на этом устройстве
+            match &self.remembered {
+                Some(key) if key.biometric => {
+                    if ui.button("👆 Войти с помощью биометрии").clicked() {
+                        self.attempt_biometric_login();
+                    }
+                    ui.add_space(20.0);
+                }
+                Some(_) => {
+                    if ui.button("🗝️ Войти без пароля").clicked() {
+                        self.attempt_remembered_login();
+                    }
+                    ui.add_space(20.0);
+                }
+                None => {}
+            }
+
+            ui.horizontal(|ui| {
+                ui.label("🔑 Мастер-пароль:");
+                let password_field = ui.add(
+                    egui::TextEdit::singleline(&mut self.master_password)
+                        .password(true)
+                        .hint_text("Введите пароль")
+                        .desired_width(200.0),
+                );
+
+                if password_field.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    self.attempt_login();
+                }
+            });
+
+            if self.remembered.is_none() {
+                // Базу KeePass открывает только мастер-пароль, ключ у неё не запомнить
+                let is_kdbx = hiho::kdbx::is_kdbx(Path::new(&self.vault_path));
+                if is_kdbx {
+                    self.remember_device = false;
+                }
+                ui.add_enabled(
+                    !is_kdbx,
+                    egui::Checkbox::new(&mut self.remember_device, "Запомнить на этом устройстве"),
+                )
+                .on_disabled_hover_text("Файл KeePass открывается только мастер-паролем");
+            }
+
+            ui.add_space(20.0);
+
+            ui.horizontal(|ui| {
+                if ui.button("🔓 Войти").clicked() {
+                    self.attempt_login();
+                }
+
+                if ui.button("🆕 Создать хранилище").clicked() {
+                    self.create_vault();
+                }
+            });
+        });
+    }
+
+    fn show_main_screen(&mut self, ui: &mut egui::Ui) {
         // Верхняя панель
         ui.horizontal(|ui| {
             if ui.button("🚪 Выйти").clicked() {
+                self.lock_vault(None);
                 self.state = AppState::Login;
-                self.master_password = String::new();
-                self.vault = None;
                 return;
             }
-            
+
             if ui.button("🔒 Заблокировать").clicked() {
-                self.state = AppState::Locked;
+                self.lock_vault(None);
                 return;
             }
-            
+
             if ui.button("🎲 Генератор").clicked() {
                 self.state = AppState::PasswordGenerator;
                 return;
             }
-            
+
+            if ui.button("🛡️ Безопасность").clicked() {
+                self.run_audit();
+                self.state = AppState::Security;
+                return;
+            }
+
             ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                 ui.add(egui::TextEdit::singleline(&mut self.search_query).hint_text("🔍 Поиск..."));
             });
         });
-        
+
         ui.separator();
-        
+
         // Основная область с записями
         ui.horizontal(|ui| {
             egui::SidePanel::left("entries_panel")
@@ -244,54 +394,53 @@ impl HihoApp {
                 .show_inside(ui, |ui| {
                     self.show_entries_list(ui);
                 });
-            
+
             egui::CentralPanel::default().show_inside(ui, |ui| {
                 self.show_entry_details(ui);
             });
         });
 
         ui.horizontal(|ui| {
-        if ui.button("🚪 Выйти").clicked() {
-            self.state = AppState::Login;
-            self.master_password = String::new();
-            self.vault = None;
-            return;
-        }
-        
-        if ui.button("🔒 Заблокировать").clicked() {
-            self.state = AppState::Locked;
-            return;
-        }
-        
-        if ui.button("🎲 Генератор").clicked() {
-            self.state = AppState::PasswordGenerator;
-            return;
-        }
-        
-        if self.biometric_available && ui.button("🔐 Биометрия").clicked() {
-            self.state = AppState::BiometricSetup;
-            return;
-        }
-        
-        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-            ui.add(egui::TextEdit::singleline(&mut self.search_query).hint_text("🔍 Поиск..."));
+            if ui.button("🚪 Выйти").clicked() {
+                self.lock_vault(None);
+                self.state = AppState::Login;
+                return;
+            }
+
+            if ui.button("🔒 Заблокировать").clicked() {
+                self.lock_vault(None);
+                return;
+            }
+
+            if ui.button("🎲 Генератор").clicked() {
+                self.state = AppState::PasswordGenerator;
+                return;
+            }
+
+            if self.biometric_available && ui.button("🔐 Биометрия").clicked() {
+                self.state = AppState::BiometricSetup;
+                return;
+            }
+
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                ui.add(egui::TextEdit::singleline(&mut self.search_query).hint_text("🔍 Поиск..."));
+            });
         });
-    });
     }
 
     fn show_entries_list(&mut self, ui: &mut egui::Ui) {
         ui.heading("📋 Записи");
         ui.separator();
-        
+
         // Кнопка добавления
         if ui.button("➕ Добавить запись").clicked() {
             self.prepare_new_entry_form();
             self.state = AppState::AddEntry;
             return;
         }
-        
+
         ui.add_space(10.0);
-        
+
         // Отображаем список записей
         self.show_filtered_entries(ui);
     }
@@ -301,20 +450,20 @@ impl HihoApp {
         let search_query = self.search_query.clone();
         let entries = self.entries.clone();
         let selected_index = self.selected_entry;
-        
+
         // Создаем список индексов кликнутых элементов
         let mut clicked_indices = Vec::new();
-        
+
         egui::ScrollArea::vertical().show(ui, |ui| {
-            // Фильтруем записи
-            let mut filtered_entries = Vec::new();
-            for (index, entry) in entries.into_iter().enumerate() {
-                if search_query.is_empty() || 
-                   entry.name.to_lowercase().contains(&search_query.to_lowercase()) {
-                    filtered_entries.push((index, entry));
+            // Фильтруем записи тем же поиском, что и в CLI
+            let filtered_entries = match SearchQuery::parse(&search_query) {
+                Ok(query) => search(&entries, &query),
+                Err(e) => {
+                    ui.colored_label(egui::Color32::RED, format!("❌ {}", e));
+                    return;
                 }
-            }
-            
+            };
+
             if filtered_entries.is_empty() {
                 if search_query.is_empty() {
                     ui.label("📭 Нет записей");
@@ -330,7 +479,7 @@ impl HihoApp {
                 }
             }
         });
-        
+
         // Обрабатываем клики после отображения
         if let Some(&clicked_index) = clicked_indices.first() {
             self.selected_entry = Some(clicked_index);
@@ -341,20 +490,23 @@ impl HihoApp {
         // Создаем копию selected_entry чтобы избежать заимствования
         let selected_index = self.selected_entry;
         let entries_len = self.entries.len();
-        
+
         if let Some(index) = selected_index {
             if index < entries_len {
                 // Создаем копию записи
                 let entry = self.entries[index].clone();
-                
+
                 ui.heading(&entry.name);
                 ui.separator();
-                
+
                 ui.horizontal(|ui| {
                     ui.label("👤 Пользователь:");
                     ui.label(&entry.username);
+                    if !entry.username.is_empty() && ui.button("📋").clicked() {
+                        self.copy_to_clipboard(&entry.username);
+                    }
                 });
-                
+
                 ui.horizontal(|ui| {
                     ui.label("🔑 Пароль:");
                     ui.label("••••••••");
@@ -362,7 +514,107 @@ impl HihoApp {
                         self.copy_to_clipboard(&entry.password);
                     }
                 });
-                
+
+                for url in &entry.urls {
+                    ui.horizontal(|ui| {
+                        ui.label("🌐 Адрес:");
+                        ui.label(url);
+                        if ui.button("📋").clicked() {
+                            self.copy_to_clipboard(url);
+                        }
+                    });
+                }
+
+                if let Some(secret) = &entry.totp {
+                    ui.horizontal(|ui| {
+                        ui.label("⏱️ Код:");
+                        match Totp::parse(secret) {
+                            Ok(totp) => {
+                                let (code, left) = totp.current();
+                                ui.monospace(&code);
+                                ui.label(format!("({} с)", left));
+                                if ui.button("📋").clicked() {
+                                    self.copy_to_clipboard(&code);
+                                }
+                                // Код меняется, обновляем отсчёт каждую секунду
+                                ui.ctx().request_repaint_after(Duration::from_secs(1));
+                            }
+                            Err(e) => {
+                                ui.colored_label(egui::Color32::RED, format!("⚠️ {}", e));
+                            }
+                        }
+                    });
+                }
+
+                for field in &entry.custom_fields {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("🏷️ {}:", field.name));
+                        if field.protected {
+                            ui.label("••••••••");
+                        } else {
+                            ui.label(&field.value);
+                        }
+                        if ui.button("📋").clicked() {
+                            self.copy_to_clipboard(&field.value);
+                        }
+                    });
+                }
+
+                if let Some(ssh_key) = &entry.ssh_key {
+                    ui.horizontal(
+                        |ui| match hiho::ssh::PrivateKey::parse(&ssh_key.private_key) {
+                            Ok(key) => {
+                                ui.label(format!("🔐 SSH ({}):", key.kind()));
+                                ui.monospace(key.fingerprint());
+                                if ui.button("📋").on_hover_text("Открытый ключ").clicked()
+                                {
+                                    self.copy_to_clipboard(&key.public_line());
+                                }
+                                if ssh_key.confirm {
+                                    ui.weak("с подтверждением");
+                                }
+                            }
+                            Err(e) => {
+                                ui.colored_label(egui::Color32::RED, format!("⚠️ {}", e));
+                            }
+                        },
+                    );
+                }
+
+                if !entry.notes.is_empty() {
+                    ui.horizontal(|ui| {
+                        ui.label("📝 Заметки:");
+                        if ui.button("📋").clicked() {
+                            self.copy_to_clipboard(&entry.notes);
+                        }
+                    });
+                    ui.label(&entry.notes);
+                }
+
+                if !entry.username.is_empty() {
+                    ui.add_space(10.0);
+                    ui.horizontal(|ui| {
+                        if ui.button("👤→🔑 Логин, затем пароль").clicked() {
+                            self.copy_login_sequence(index);
+                        }
+                        if self.sequence_entry == Some(index)
+                            && ui.button("🔑 Теперь пароль").clicked()
+                        {
+                            self.sequence_entry = None;
+                            self.copy_to_clipboard(&entry.password);
+                        }
+                    });
+                }
+
+                ui.horizontal(|ui| {
+                    if ui.button("⌨️ Автонабор").clicked() {
+                        self.start_autotype(ui.ctx(), index);
+                    }
+                    if let Some(sequence) = &entry.auto_type {
+                        ui.weak(sequence);
+                    }
+                });
+
                 ui.add_space(20.0);
                 ui.horizontal(|ui| {
                     if ui.button("✏️ Редактировать").clicked() {
@@ -391,18 +643,18 @@ impl HihoApp {
     fn show_entry_form(&mut self, ui: &mut egui::Ui, title: &str) {
         ui.heading(title);
         ui.separator();
-        
+
         egui::ScrollArea::vertical().show(ui, |ui| {
             ui.horizontal(|ui| {
                 ui.label("🌐 Название:");
                 ui.text_edit_singleline(&mut self.form_name);
             });
-            
+
             ui.horizontal(|ui| {
                 ui.label("👤 Пользователь:");
                 ui.text_edit_singleline(&mut self.form_username);
             });
-            
+
             ui.horizontal(|ui| {
                 ui.label("🔑 Пароль:");
                 ui.add(egui::TextEdit::singleline(&mut self.form_password).password(true));
@@ -410,12 +662,17 @@ impl HihoApp {
                     self.show_password_generator = true;
                 }
             });
-            
+
+            if !self.form_password.is_empty() {
+                let strength = self.calculate_password_strength(&self.form_password);
+                self.show_password_strength_indicator(ui, &strength);
+            }
+
             // Показываем генератор паролей как popup
             if self.show_password_generator {
                 self.show_password_generator_popup(ui);
             }
-            
+
             ui.add_space(20.0);
             ui.horizontal(|ui| {
                 if ui.button("💾 Сохранить").clicked() {
@@ -432,23 +689,137 @@ impl HihoApp {
     fn show_locked_screen(&mut self, ui: &mut egui::Ui) {
         ui.vertical_centered(|ui| {
             ui.add_space(100.0);
-            ui.heading("🔒 Сессия заблокирована");
+            ui.heading("🔒 Хранилище заблокировано");
+            if let Some(reason) = &self.lock_reason {
+                ui.label(reason);
+            }
+            ui.label("Ключ и записи удалены из памяти, для входа нужен мастер-пароль");
             ui.add_space(30.0);
-            
+
             if ui.button("🔓 Разблокировать").clicked() {
                 self.state = AppState::Login;
             }
-            
-            // Показываем время до автоблокировки
-            if let Ok(config) = AutoLockManager::get_config() {
-                if let Some(minutes) = config.timeout_minutes {
-                    ui.add_space(20.0);
-                    ui.label(format!("⏰ Автоблокировка через {} минут", minutes));
-                }
+
+            // Показываем таймаут автоблокировки
+            if let Some(timeout) = self.idle.timeout() {
+                ui.add_space(20.0);
+                ui.label(format!(
+                    "⏰ Автоблокировка после {} минут бездействия",
+                    timeout.as_secs() / 60
+                ));
             }
         });
     }
 
+    fn run_audit(&mut self) {
+        self.audit_report = Some(audit_entries(&self.entries, &AuditConfig::default()));
+    }
+
+    fn run_audit_with_breaches(&mut self) {
+        let result = open_source(Path::new(&self.breach_db_path)).and_then(|mut source| {
+            audit_entries_with_breaches(&self.entries, &AuditConfig::default(), source.as_mut())
+        });
+        match result {
+            Ok(report) => {
+                self.audit_report = Some(report);
+                self.error_message = None;
+            }
+            Err(e) => {
+                self.error_message = Some(format!("❌ Ошибка проверки утечек: {}", e));
+            }
+        }
+    }
+
+    fn show_security_dashboard(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.button("🔙 Назад").clicked() {
+                self.state = AppState::Main;
+            }
+            if ui.button("🔄 Проверить снова").clicked() {
+                self.run_audit();
+            }
+            ui.heading("🛡️ Безопасность");
+        });
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            ui.label("База утечек HIBP:");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.breach_db_path)
+                    .hint_text("pwned-passwords-sha1-ordered-by-hash.txt")
+                    .desired_width(300.0),
+            );
+            if ui.button("🚨 Проверить утечки").clicked() && !self.breach_db_path.is_empty()
+            {
+                self.run_audit_with_breaches();
+            }
+        });
+        ui.add_space(10.0);
+
+        let report = match &self.audit_report {
+            Some(report) => report.clone(),
+            None => {
+                ui.label("Нажмите «Проверить снова» для запуска аудита");
+                return;
+            }
+        };
+
+        let score_color = match report.score {
+            0..=49 => egui::Color32::RED,
+            50..=79 => egui::Color32::YELLOW,
+            _ => egui::Color32::GREEN,
+        };
+        ui.horizontal(|ui| {
+            ui.label("Оценка хранилища:");
+            ui.colored_label(score_color, format!("{}/100", report.score));
+        });
+        ui.label(format!(
+            "Записей: {}, с проблемами: {}",
+            report.total_entries, report.affected_entries
+        ));
+        ui.add_space(10.0);
+
+        if report.groups.is_empty() {
+            ui.colored_label(egui::Color32::GREEN, "✅ Проблем не найдено");
+            return;
+        }
+
+        let mut open_entry = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for group in &report.groups {
+                let color = match group.severity {
+                    Severity::Critical | Severity::High => egui::Color32::RED,
+                    Severity::Medium => egui::Color32::YELLOW,
+                    Severity::Low => egui::Color32::LIGHT_BLUE,
+                };
+                let title =
+                    egui::RichText::new(format!("{} ({})", group.title, group.affected_entries))
+                        .color(color);
+                egui::CollapsingHeader::new(title)
+                    .id_source(format!("audit_{:?}", group.kind))
+                    .default_open(group.severity >= Severity::High)
+                    .show(ui, |ui| {
+                        for issue in &group.issues {
+                            ui.horizontal_wrapped(|ui| {
+                                for entry in &issue.entries {
+                                    if ui.link(&entry.name).clicked() {
+                                        open_entry = Some(entry.index - 1);
+                                    }
+                                }
+                                ui.label(format!("— {}", issue.detail));
+                            });
+                        }
+                    });
+            }
+        });
+
+        // Переход к записи из отчёта
+        if let Some(index) = open_entry {
+            self.selected_entry = Some(index);
+            self.state = AppState::Main;
+        }
+    }
+
     fn show_password_generator_main(&mut self, ui: &mut egui::Ui) {
         ui.horizontal(|ui| {
             if ui.button("🔙 Назад").clicked() {
@@ -457,7 +828,7 @@ impl HihoApp {
             ui.heading("🎲 Генератор паролей");
         });
         ui.separator();
-        
+
         // Используем тот же popup, но в основном окне
         self.show_password_generator_popup(ui);
     }
@@ -465,7 +836,7 @@ impl HihoApp {
     fn show_password_generator_popup(&mut self, ui: &mut egui::Ui) {
         // Создаем копию для избежания конфликта заимствований
         let mut show_popup = self.show_password_generator;
-        
+
         egui::Window::new("🎲 Генератор паролей")
             .resizable(true)
             .default_width(400.0)
@@ -474,7 +845,7 @@ impl HihoApp {
             .show(ui.ctx(), |ui| {
                 self.show_password_generator_content(ui);
             });
-            
+
         // Обновляем значение после использования
         self.show_password_generator = show_popup;
     }
@@ -484,7 +855,7 @@ impl HihoApp {
         let generated_password = self.generated_password.clone();
         let password_options = self.password_options.clone();
         let form_password = self.form_password.clone();
-        
+
         // Переменные для обновления состояния
         let mut new_length = password_options.length;
         let mut new_use_uppercase = password_options.use_uppercase;
@@ -496,7 +867,7 @@ impl HihoApp {
         let mut new_form_password = form_password.clone();
         let mut close_generator = false;
         let mut use_password = false;
-        
+
         ui.vertical(|ui| {
             // Настройки длины
             ui.horizontal(|ui| {
@@ -504,21 +875,24 @@ impl HihoApp {
                 ui.add(egui::Slider::new(&mut new_length, 4..=128));
                 ui.label(format!("{}", new_length));
             });
-            
+
             ui.separator();
-            
+
             // Опции символов
             ui.checkbox(&mut new_use_uppercase, "Заглавные буквы (A-Z)");
             ui.checkbox(&mut new_use_lowercase, "Строчные буквы (a-z)");
             ui.checkbox(&mut new_use_numbers, "Цифры (0-9)");
             ui.checkbox(&mut new_use_symbols, "Символы (!@#$%^&*)");
-            
+
             ui.separator();
-            
-            ui.checkbox(&mut new_exclude_ambiguous, "Исключить похожие символы (0,O,l,1)");
-            
+
+            ui.checkbox(
+                &mut new_exclude_ambiguous,
+                "Исключить похожие символы (0,O,l,1)",
+            );
+
             ui.separator();
-            
+
             // Кнопка генерации
             if ui.button("🔄 Сгенерировать").clicked() {
                 // Обновляем опции перед генерацией
@@ -528,30 +902,29 @@ impl HihoApp {
                 self.password_options.use_numbers = new_use_numbers;
                 self.password_options.use_symbols = new_use_symbols;
                 self.password_options.exclude_ambiguous = new_exclude_ambiguous;
-                
+
                 new_generated_password = self.generate_advanced_password();
             }
-            
+
             ui.separator();
-            
+
             // Отображение сгенерированного пароля
             if !new_generated_password.is_empty() {
                 ui.horizontal(|ui| {
                     ui.label("Сгенерированный пароль:");
                     let mut password_copy = new_generated_password.clone();
-                    ui.add(egui::TextEdit::singleline(&mut password_copy)
-                        .desired_width(200.0));
+                    ui.add(egui::TextEdit::singleline(&mut password_copy).desired_width(200.0));
                     if ui.button("📋").clicked() {
                         self.copy_to_clipboard(&new_generated_password);
                     }
                 });
-                
+
                 // Индикатор сложности (используем копию)
                 let strength = self.calculate_password_strength(&new_generated_password);
-                self.show_password_strength_indicator(ui, strength);
-                
+                self.show_password_strength_indicator(ui, &strength);
+
                 ui.separator();
-                
+
                 // Кнопки действий
                 ui.horizontal(|ui| {
                     if ui.button("✅ Использовать").clicked() {
@@ -568,7 +941,7 @@ impl HihoApp {
                 ui.label("Нажмите 'Сгенерировать' для создания пароля");
             }
         });
-        
+
         // Обновляем состояние после закрытия окна
         if close_generator {
             self.show_password_generator = false;
@@ -609,37 +982,41 @@ impl HihoApp {
     }
 
     fn generate_advanced_password(&self) -> String {
-        use rand::{Rng, thread_rng};
-        
+        use rand::{thread_rng, Rng};
+
         let mut charset = String::new();
-        
+
         if self.password_options.use_uppercase {
             charset.push_str("ABCDEFGHIJKLMNOPQRSTUVWXYZ");
         }
-        
+
         if self.password_options.use_lowercase {
             charset.push_str("abcdefghijklmnopqrstuvwxyz");
         }
-        
+
         if self.password_options.use_numbers {
             charset.push_str("0123456789");
         }
-        
+
         if self.password_options.use_symbols {
             charset.push_str("!@#$%^&*()_+-=[]{}|;:,.<>?");
         }
-        
+
         // Исключаем похожие символы если нужно
         let charset = if self.password_options.exclude_ambiguous {
-            charset.replace("0", "").replace("O", "").replace("l", "").replace("I", "")
+            charset
+                .replace("0", "")
+                .replace("O", "")
+                .replace("l", "")
+                .replace("I", "")
         } else {
             charset
         };
-        
+
         if charset.is_empty() {
             return String::new();
         }
-        
+
         let mut rng = thread_rng();
         (0..self.password_options.length)
             .map(|_| {
@@ -649,83 +1026,117 @@ impl HihoApp {
             .collect()
     }
 
-    fn calculate_password_strength(&self, password: &str) -> PasswordStrength {
-        let mut score = 0;
-        
-        // Длина
-        if password.len() >= 8 { score += 1; }
-        if password.len() >= 12 { score += 1; }
-        if password.len() >= 16 { score += 1; }
-        
-        // Разнообразие символов
-        let has_upper = password.chars().any(|c| c.is_uppercase());
-        let has_lower = password.chars().any(|c| c.is_lowercase());
-        let has_digit = password.chars().any(|c| c.is_digit(10));
-        let has_symbol = password.chars().any(|c| "!@#$%^&*()_+-=[]{}|;:,.<>?".contains(c));
-        
-        if has_upper { score += 1; }
-        if has_lower { score += 1; }
-        if has_digit { score += 1; }
-        if has_symbol { score += 1; }
-        
-        // Повторяющиеся символы (штраф)
-        let unique_chars = password.chars().collect::<std::collections::HashSet<_>>().len();
-        let repetition_ratio = unique_chars as f32 / password.len() as f32;
-        if repetition_ratio < 0.7 { score -= 1; }
-        
-        match score {
-            0..=2 => PasswordStrength::Weak,
-            3..=5 => PasswordStrength::Medium,
-            6..=8 => PasswordStrength::Strong,
-            _ => PasswordStrength::VeryStrong,
-        }
-    }
-
-    fn show_password_strength_indicator(&self, ui: &mut egui::Ui, strength: PasswordStrength) {
+    fn calculate_password_strength(&self, password: &str) -> StrengthReport {
+        // Имя сервиса и логин атакующий попробует в первую очередь
+        estimate_strength(password, &[&self.form_name, &self.form_username])
+    }
+
+    fn show_password_strength_indicator(&self, ui: &mut egui::Ui, report: &StrengthReport) {
         ui.horizontal(|ui| {
             ui.label("Сложность:");
-            let (color, text) = match strength {
-                PasswordStrength::Weak => (egui::Color32::RED, "Слабый"),
-                PasswordStrength::Medium => (egui::Color32::YELLOW, "Средний"),
-                PasswordStrength::Strong => (egui::Color32::GREEN, "Сильный"),
-                PasswordStrength::VeryStrong => (egui::Color32::from_rgb(0, 200, 0), "Очень сильный"),
+            let color = match report.score {
+                StrengthScore::VeryWeak | StrengthScore::Weak => egui::Color32::RED,
+                StrengthScore::Medium => egui::Color32::YELLOW,
+                StrengthScore::Strong => egui::Color32::GREEN,
+                StrengthScore::VeryStrong => egui::Color32::from_rgb(0, 200, 0),
             };
-            ui.colored_label(color, text);
+            ui.colored_label(color, report.score.label());
+            ui.label(format!(
+                "({:.0} бит, подбор офлайн: {})",
+                report.entropy_bits,
+                display_crack_time(report.crack_times.offline_slow_hashing)
+            ));
         });
+
+        if let Some(warning) = &report.feedback.warning {
+            ui.colored_label(egui::Color32::YELLOW, format!("⚠️ {}", warning));
+        }
+        for suggestion in &report.feedback.suggestions {
+            ui.label(format!("💡 {}", suggestion));
+        }
     }
 
     fn copy_to_clipboard(&mut self, text: &str) {
-        use clipboard::{ClipboardContext, ClipboardProvider};
-        
-        match ClipboardContext::new() {
-            Ok(mut ctx) => {
-                match ctx.set_contents(text.to_owned()) {
-                    Ok(_) => {
-                        self.error_message = Some("✅ Скопировано в буфер обмена".to_string());
-                    }
-                    Err(_) => {
-                        self.error_message = Some("❌ Ошибка копирования".to_string());
-                    }
-                }
+        match ClipboardManager::copy_secret(text) {
+            Ok(clear_after) => {
+                self.error_message = Some("✅ Скопировано в буфер обмена".to_string());
+                self.clipboard_clear_at = clear_after.map(|after| Instant::now() + after);
             }
-            Err(_) => {
-                self.error_message = Some("❌ Ошибка доступа к буферу обмена".to_string());
+            Err(e) => {
+                self.error_message = Some(format!("❌ Ошибка копирования: {}", e));
             }
         }
     }
 
+    // Логин, затем пароль. Где буфер умеет отдавать значение на одну вставку,
+    // пароль встаёт в буфер сам; иначе его копируют второй кнопкой
+    fn copy_login_sequence(&mut self, index: usize) {
+        let Some(entry) = self.entries.get(index) else {
+            return;
+        };
+        if !ClipboardManager::sequence_supported() {
+            let username = entry.username.clone();
+            self.copy_to_clipboard(&username);
+            self.sequence_entry = Some(index);
+            return;
+        }
+        match ClipboardManager::copy_sequence(&[&entry.username, &entry.password]) {
+            Ok(clear_after) => {
+                self.error_message =
+                    Some("✅ Логин скопирован, после вставки в буфере будет пароль".to_string());
+                self.clipboard_clear_at = clear_after.map(|after| Instant::now() + after);
+            }
+            Err(e) => {
+                self.error_message = Some(format!("❌ Ошибка копирования: {}", e));
+            }
+        }
+    }
+
+    // Окно сворачивается, и набор идёт в то, что получит фокус после него
+    fn start_autotype(&mut self, ctx: &egui::Context, index: usize) {
+        let Some(entry) = self.entries.get(index) else {
+            return;
+        };
+        let actions =
+            match autotype::sequence_for(entry).and_then(|sequence| sequence.resolve(entry)) {
+                Ok(actions) => actions,
+                Err(e) => {
+                    self.error_message = Some(format!("❌ Автонабор: {}", e));
+                    return;
+                }
+            };
+        let name = entry.name.clone();
+        let status = Arc::clone(&self.autotype_status);
+        let ctx = ctx.clone();
+        ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(true));
+
+        thread::spawn(move || {
+            thread::sleep(AUTOTYPE_DELAY);
+            let result = autotype::open()
+                .and_then(|mut keyboard| autotype::run(&actions, keyboard.as_mut()));
+            let message = match result {
+                Ok(()) => format!("✅ Набрано: {}", name),
+                Err(e) => format!("❌ Автонабор: {}", e),
+            };
+            if let Ok(mut status) = status.lock() {
+                *status = Some(message);
+            }
+            ctx.request_repaint();
+        });
+    }
+
     fn save_entry(&mut self) {
         if self.form_name.is_empty() {
             self.error_message = Some("Введите название записи".to_string());
             return;
         }
-        
-        let entry = Entry {
-            name: self.form_name.clone(),
-            username: self.form_username.clone(),
-            password: self.form_password.clone(),
-        };
-        
+
+        let entry = Entry::new(
+            self.form_name.clone(),
+            self.form_username.clone(),
+            self.form_password.clone(),
+        );
+
         match &mut self.vault {
             Some(vault) => {
                 match vault.lock() {
@@ -735,10 +1146,15 @@ impl HihoApp {
                                 v.add_entry(entry);
                             }
                             AppState::EditEntry(index) => {
-                                match v.edit_entry(index, Some(entry.username), Some(entry.password)) {
-                                    Ok(_) => {},
+                                match v.edit_entry(
+                                    index,
+                                    Some(entry.username),
+                                    Some(entry.password),
+                                ) {
+                                    Ok(_) => {}
                                     Err(e) => {
-                                        self.error_message = Some(format!("❌ Ошибка редактирования: {}", e));
+                                        self.error_message =
+                                            Some(format!("❌ Ошибка редактирования: {}", e));
                                         return;
                                     }
                                 }
@@ -746,7 +1162,7 @@ impl HihoApp {
                             _ => {}
                         }
                         // Сохраняем в файл
-                        let vault_path = Path::new("data\\vault.enc");
+                        let vault_path = Path::new(&self.vault_path);
                         match v.save_to_file(vault_path) {
                             Ok(_) => {
                                 self.error_message = Some("✅ Запись сохранена".to_string());
@@ -778,7 +1194,7 @@ impl HihoApp {
                     match vault.lock() {
                         Ok(mut v) => {
                             v.remove_entry(index);
-                            let vault_path = Path::new("data\\vault.enc");
+                            let vault_path = Path::new(&self.vault_path);
                             match v.save_to_file(vault_path) {
                                 Ok(_) => {
                                     self.error_message = Some("✅ Запись удалена".to_string());
@@ -810,18 +1226,16 @@ impl HihoApp {
 
         match Vault::new(&self.master_password) {
             Ok(mut vault) => {
-                let vault_path = Path::new("data\\vault.enc");
+                let vault_path = Path::new(&self.vault_path);
                 match vault.load_from_file(vault_path) {
                     Ok(_) => {
-                        self.vault = Some(Arc::new(Mutex::new(vault)));
-                        self.state = AppState::Main;
                         self.error_message = None;
-                        // Загружаем записи
-                        if let Some(v) = &self.vault {
-                            if let Ok(v_locked) = v.lock() {
-                                self.entries = v_locked.get_entries().clone();
-                            }
+                        if self.remember_device {
+                            self.remember_key();
                         }
+                        // Пароль остаётся только внутри Vault
+                        self.master_password.zeroize();
+                        self.enter_vault(vault);
                     }
                     Err(e) => {
                         self.error_message = Some(format!("Ошибка загрузки: {}", e));
@@ -834,40 +1248,122 @@ impl HihoApp {
         }
     }
 
+    fn enter_vault(&mut self, vault: Vault) {
+        self.entries = vault.get_entries().clone();
+        self.vault = Some(Arc::new(Mutex::new(vault)));
+        self.state = AppState::Main;
+        self.lock_reason = None;
+        self.idle = IdleTimer::from_config();
+    }
+
+    /// Кладёт ключ хранилища в хранилище ключей ОС (пароль уже проверен).
+    fn remember_key(&mut self) {
+        let path = match std::path::absolute(&self.vault_path) {
+            Ok(path) => path,
+            Err(e) => {
+                self.error_message = Some(format!("⚠️ Не удалось запомнить хранилище: {}", e));
+                return;
+            }
+        };
+        if hiho::kdbx::is_kdbx(&path) {
+            self.remember_device = false;
+            self.error_message = Some(
+                "⚠️ Файл KeePass открывается только мастер-паролем и не может быть запомнен"
+                    .to_string(),
+            );
+            return;
+        }
+        let result =
+            hiho::vault::derive_vault_key_for(&path, &self.master_password).and_then(|key| {
+                let key = zeroize::Zeroizing::new(key);
+                let store = keystore::platform()?;
+                RememberManager::remember(store.as_ref(), &path, &key, false)
+            });
+        match result {
+            Ok(()) => {
+                self.remembered = remembered_key(&self.vault_path);
+                self.remember_device = false;
+            }
+            Err(e) => {
+                self.error_message = Some(format!("⚠️ Не удалось запомнить хранилище: {}", e))
+            }
+        }
+    }
+
+    fn attempt_remembered_login(&mut self) {
+        let path = Path::new(&self.vault_path);
+        let result = std::path::absolute(path)
+            .map_err(Into::into)
+            .and_then(|absolute| {
+                let store = keystore::platform()?;
+                let key = RememberManager::vault_key(store.as_ref(), &absolute)?
+                    .ok_or("Хранилище не запомнено")?;
+                Vault::open_with_key(path, *key)
+            });
+        match result {
+            Ok(vault) => {
+                self.error_message = None;
+                self.enter_vault(vault);
+            }
+            Err(e) => {
+                self.error_message = Some(format!(
+                    "❌ Вход без пароля не удался: {}. Введите мастер-пароль",
+                    e
+                ));
+            }
+        }
+    }
+
     fn show_biometric_setup(&mut self, ui: &mut egui::Ui) {
         ui.vertical_centered(|ui| {
             ui.add_space(50.0);
             ui.heading("🔐 Биометрическая аутентификация");
             ui.add_space(30.0);
-            
+
             if self.biometric_available {
                 ui.label("✅ Биометрическая аутентификация доступна");
                 ui.add_space(20.0);
-                
-                ui.checkbox(&mut self.biometric_enabled, "Использовать биометрию для входа");
-                
-                ui.add_space(20.0);
-                if ui.button("🔄 Тестировать").clicked() {
-                    self.show_biometric_prompt = true;
-                }
-                
-                if self.show_biometric_prompt {
-                    ui.add_space(20.0);
-                    ui.label("👆 Приложите палец к сенсору...");
-                    
-                    // Здесь должна быть настоящая биометрическая аутентификация
-                    // Пока используем заглушку
+
+                if self.remembered.as_ref().is_some_and(|key| key.biometric) {
+                    ui.label("👆 Хранилище открывается по отпечатку");
+                    ui.add_space(10.0);
+                    if ui.button("🚫 Отключить вход по отпечатку").clicked()
+                    {
+                        self.disable_biometric();
+                    }
+                } else {
+                    ui.label("Мастер-пароль нужен один раз, чтобы запомнить ключ хранилища:");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.master_password)
+                            .password(true)
+                            .hint_text("Мастер-пароль")
+                            .desired_width(200.0),
+                    );
                     ui.add_space(10.0);
-                    if ui.button("✅ Имитировать успех").clicked() {
-                        self.show_biometric_prompt = false;
-                        self.error_message = Some("✅ Биометрия проверена успешно!".to_string());
+                    if ui.button("👆 Включить вход по отпечатку").clicked()
+                    {
+                        self.enable_biometric();
                     }
                 }
+
+                ui.add_space(20.0);
+                if ui.button("🔄 Тестировать").clicked() {
+                    self.error_message = Some(
+                        match BiometricManager::authenticate("Подтвердите свою личность")
+                        {
+                            Ok(true) => "✅ Биометрия проверена успешно!".to_string(),
+                            Ok(false) => "❌ Отпечаток не совпал".to_string(),
+                            Err(e) => format!("❌ Ошибка биометрии: {}", e),
+                        },
+                    );
+                }
             } else {
                 ui.label("❌ Биометрическая аутентификация недоступна");
-                ui.label("Убедитесь, что у вас есть Touch ID, Windows Hello или другой биометрический сенсор");
+                ui.label(
+                    "Нужен сканер отпечатков, fprintd и записанный отпечаток (fprintd-enroll)",
+                );
             }
-            
+
             ui.add_space(30.0);
             if ui.button("🔙 Назад").clicked() {
                 self.state = AppState::Main;
@@ -875,79 +1371,97 @@ impl HihoApp {
         });
     }
 
-    fn create_vault(&mut self) {
-    if self.master_password.is_empty() {
-        self.error_message = Some("Введите мастер-пароль для создания хранилища".to_string());
-        return;
-    }
-
-    let vault_path = Path::new("data\\vault.enc");
-    
-    // Проверяем, существует ли уже хранилище
-    if vault_path.exists() {
-        self.error_message = Some("❌ Хранилище уже существует! Используйте 'Войти'".to_string());
-        return;
-    }
-
-    match Vault::new(&self.master_password) {
-        Ok(vault) => {
-            // Создаем директорию если её нет
-            std::fs::create_dir_all("data").unwrap_or_default();
-            
-            match vault.save_to_file(vault_path) {
-                Ok(_) => {
-                    self.vault = Some(Arc::new(Mutex::new(vault)));
-                    self.state = AppState::Main;
-                    self.error_message = Some("✅ Хранилище создано!".to_string());
-                    self.entries = Vec::new();
-                }
-                Err(e) => {
-                    self.error_message = Some(format!("❌ Ошибка создания хранилища: {}", e));
+    fn enable_biometric(&mut self) {
+        let path = Path::new(&self.vault_path);
+        let result = std::path::absolute(path)
+            .map_err(Into::into)
+            .and_then(|absolute| {
+                if hiho::kdbx::is_kdbx(path) {
+                    return Err("Файл KeePass открывается только мастер-паролем".into());
                 }
+                let key = zeroize::Zeroizing::new(hiho::vault::derive_vault_key_for(
+                    path,
+                    &self.master_password,
+                )?);
+                Vault::open_with_key(path, *key)?;
+                BiometricManager::enable_biometric(&absolute, &key)
+            });
+        self.master_password.zeroize();
+        self.error_message = Some(match result {
+            Ok(()) => {
+                self.biometric_enabled = true;
+                self.remembered = remembered_key(&self.vault_path);
+                "✅ Вход по отпечатку включён".to_string()
             }
+            Err(e) => format!("❌ Ошибка включения биометрии: {}", e),
+        });
+    }
+
+    fn disable_biometric(&mut self) {
+        let result = std::path::absolute(&self.vault_path)
+            .map_err(Into::into)
+            .and_then(|path| BiometricManager::disable_biometric(&path));
+        self.biometric_enabled = false;
+        self.remembered = remembered_key(&self.vault_path);
+        self.error_message = Some(match result {
+            Ok(()) => "🔓 Вход по отпечатку отключён".to_string(),
+            Err(e) => format!("❌ Ошибка отключения биометрии: {}", e),
+        });
+    }
+
+    fn create_vault(&mut self) {
+        if self.master_password.is_empty() {
+            self.error_message = Some("Введите мастер-пароль для создания хранилища".to_string());
+            return;
         }
-        Err(e) => {
-            self.error_message = Some(format!("❌ Ошибка инициализации: {}", e));
+
+        let vault_path = Path::new(&self.vault_path);
+
+        // Проверяем, существует ли уже хранилище
+        if vault_path.exists() {
+            self.error_message =
+                Some("❌ Хранилище уже существует! Используйте 'Войти'".to_string());
+            return;
         }
-    }
-}
-fn attempt_biometric_login(&mut self) {
-    match BiometricManager::get_config() {
-        Ok(config) => {
-            if !config.enabled {
-                self.error_message = Some("Биометрия не включена".to_string());
-                return;
-            }
-            
-            if !BiometricManager::is_available() {
-                self.error_message = Some("Биометрия недоступна на этой платформе".to_string());
-                return;
-            }
-            
-            // Здесь должна быть настоящая биометрическая аутентификация
-            match BiometricManager::authenticate("Подтвердите личность для входа в hiho") {
-                Ok(true) => {
-                    self.error_message = Some("✅ Биометрический вход успешен!".to_string());
-                    self.state = AppState::Main;
-                    
-                    // Загружаем записи (имитация)
-                    if let Some(vault) = &self.vault {
-                        if let Ok(v_locked) = vault.lock() {
-                            self.entries = v_locked.get_entries().clone();
-                        }
+
+        match Vault::new(&self.master_password) {
+            Ok(vault) => {
+                // Создаем директорию если её нет
+                std::fs::create_dir_all("data").unwrap_or_default();
+
+                match vault.save_to_file(vault_path) {
+                    Ok(_) => {
+                        self.vault = Some(Arc::new(Mutex::new(vault)));
+                        self.state = AppState::Main;
+                        self.error_message = Some("✅ Хранилище создано!".to_string());
+                        self.master_password.zeroize();
+                        self.idle = IdleTimer::from_config();
+                        self.entries = Vec::new();
+                    }
+                    Err(e) => {
+                        self.error_message = Some(format!("❌ Ошибка создания хранилища: {}", e));
                     }
-                }
-                Ok(false) => {
-                    self.error_message = Some("❌ Биометрическая аутентификация отклонена".to_string());
-                }
-                Err(e) => {
-                    self.error_message = Some(format!("❌ Ошибка биометрии: {}", e));
                 }
             }
+            Err(e) => {
+                self.error_message = Some(format!("❌ Ошибка инициализации: {}", e));
+            }
         }
-        Err(e) => {
-            self.error_message = Some(format!("❌ Ошибка настроек биометрии: {}", e));
+    }
+    fn attempt_biometric_login(&mut self) {
+        if !BiometricManager::is_available() {
+            self.error_message =
+                Some("Биометрия недоступна: нужен fprintd и записанный отпечаток".to_string());
+            return;
         }
+        // Ключ хранилища выдаётся только после проверки отпечатка
+        self.attempt_remembered_login();
     }
 }
-}
\ No newline at end of file
+
+/// Запомненный ключ хранилища (`hiho remember enable`, флажок при входе
+/// или биометрия).
+fn remembered_key(vault_path: &str) -> Option<RememberedKey> {
+    let path = std::path::absolute(vault_path).ok()?;
+    RememberManager::find(&path).ok().flatten()
+}
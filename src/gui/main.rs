@@ -4,6 +4,12 @@ mod app;
 use app::HihoApp;
 
 fn main() -> Result<(), eframe::Error> {
+    // Тот же бинарник служит помощником очистки буфера обмена
+    if std::env::args().nth(1).as_deref() == Some(hiho::clipboard_manager::GUARD_ARG) {
+        let _ = hiho::ClipboardManager::run_guard();
+        return Ok(());
+    }
+    
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([1000.0, 700.0])
@@ -14,8 +20,14 @@ fn main() -> Result<(), eframe::Error> {
     eframe::run_native(
         "hiho - Менеджер паролей",
         native_options,
-        Box::new(|_cc| {
-            Box::<HihoApp>::default()
+        Box::new(|cc| {
+            let mut app = HihoApp::default();
+            app.watch_system_lock(&cc.egui_ctx);
+            // Путь к хранилищу можно передать первым аргументом, например base.kdbx
+            if let Some(path) = std::env::args().nth(1) {
+                app.set_vault_path(path);
+            }
+            Box::new(app)
         }),
     )
 }
\ No newline at end of file
@@ -0,0 +1,381 @@
+use super::ImportResult;
+use crate::crypto::{self, EncryptedData};
+use crate::entry::{CustomField, Entry, EntryKind};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::error::Error;
+use zeroize::Zeroize;
+
+const KDF_PBKDF2: u8 = 0;
+const KDF_ARGON2ID: u8 = 1;
+
+// Пределы параметров KDF, которые допускают клиенты Bitwarden: файл
+// экспорта не должен заставить hiho выделить гигабайты или считать часами
+const MAX_PBKDF2_ITERATIONS: u32 = 2_000_000;
+const MAX_ARGON2_MEMORY_MIB: u32 = 1024;
+const MAX_ARGON2_ITERATIONS: u32 = 10;
+const MAX_ARGON2_PARALLELISM: u32 = 16;
+
+// Типы элементов в экспорте Bitwarden
+const TYPE_LOGIN: u8 = 1;
+const TYPE_SECURE_NOTE: u8 = 2;
+
+// Типы пользовательских полей
+const FIELD_HIDDEN: u8 = 1;
+const FIELD_LINKED: u8 = 3;
+
+// Поля карт, удостоверений и SSH-ключей, которые нужно скрывать
+const PROTECTED_KEYS: [&str; 6] = ["number", "code", "ssn", "passportNumber", "licenseNumber", "privateKey"];
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Export {
+    #[serde(default)]
+    encrypted: bool,
+    #[serde(default)]
+    password_protected: bool,
+    salt: Option<String>,
+    kdf_type: Option<u8>,
+    kdf_iterations: Option<u32>,
+    kdf_memory: Option<u32>,
+    kdf_parallelism: Option<u32>,
+    #[serde(rename = "encKeyValidation_DO_NOT_EDIT")]
+    enc_key_validation: Option<String>,
+    data: Option<String>,
+    #[serde(default)]
+    folders: Vec<Folder>,
+    #[serde(default)]
+    collections: Vec<Folder>,
+    #[serde(default)]
+    items: Vec<Item>,
+}
+
+#[derive(Deserialize)]
+struct Folder {
+    id: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Item {
+    #[serde(rename = "type")]
+    item_type: u8,
+    #[serde(default)]
+    name: String,
+    notes: Option<String>,
+    folder_id: Option<String>,
+    #[serde(default)]
+    collection_ids: Option<Vec<String>>,
+    #[serde(default)]
+    fields: Vec<Field>,
+    login: Option<Login>,
+    card: Option<Value>,
+    identity: Option<Value>,
+    ssh_key: Option<Value>,
+    creation_date: Option<DateTime<Utc>>,
+    revision_date: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Login {
+    username: Option<String>,
+    password: Option<String>,
+    totp: Option<String>,
+    #[serde(default)]
+    uris: Option<Vec<LoginUri>>,
+    password_revision_date: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize)]
+struct LoginUri {
+    uri: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Field {
+    name: Option<String>,
+    value: Option<String>,
+    #[serde(rename = "type", default)]
+    field_type: u8,
+}
+
+pub fn is_password_protected(content: &str) -> Result<bool, Box<dyn Error>> {
+    let export: Export = serde_json::from_str(content)
+        .map_err(|e| format!("Не удалось разобрать экспорт Bitwarden: {}", e))?;
+    Ok(export.encrypted && export.password_protected)
+}
+
+/// Разбирает JSON-экспорт Bitwarden: обычный или защищённый паролем.
+pub fn parse(content: &str, password: Option<&str>) -> Result<ImportResult, Box<dyn Error>> {
+    let export: Export = serde_json::from_str(content)
+        .map_err(|e| format!("Не удалось разобрать экспорт Bitwarden: {}", e))?;
+
+    if export.encrypted {
+        if !export.password_protected {
+            return Err("Экспорт зашифрован ключом аккаунта Bitwarden. \
+                Выгрузите хранилище как «.json» или «.json (Encrypted)» с типом «Password protected»"
+                .into());
+        }
+        let password = password.ok_or("Для этого экспорта нужен пароль")?;
+        let mut decrypted = decrypt_export(&export, password)?;
+        let result = parse(&decrypted, None);
+        decrypted.zeroize();
+        return result;
+    }
+
+    let mut folders: HashMap<&str, &str> = HashMap::new();
+    for folder in export.folders.iter().chain(&export.collections) {
+        folders.insert(&folder.id, &folder.name);
+    }
+
+    let mut result = ImportResult::default();
+    for item in &export.items {
+        result.entries.push(convert_item(item, &folders));
+    }
+    Ok(result)
+}
+
+fn convert_item(item: &Item, folders: &HashMap<&str, &str>) -> Entry {
+    let mut entry = Entry::new(item.name.clone(), String::new(), String::new());
+    entry.notes = item.notes.clone().unwrap_or_default();
+    entry.kind = if item.item_type == TYPE_LOGIN {
+        EntryKind::Login
+    } else {
+        EntryKind::Note
+    };
+
+    // В организациях вместо папок используются коллекции
+    let folder_id = item
+        .folder_id
+        .as_deref()
+        .or_else(|| item.collection_ids.as_ref()?.first().map(String::as_str));
+    entry.folder = folder_id.and_then(|id| folders.get(id)).map(|name| name.to_string());
+
+    if let Some(login) = &item.login {
+        entry.username = login.username.clone().unwrap_or_default();
+        entry.password = login.password.clone().unwrap_or_default();
+        entry.totp = login.totp.clone().filter(|totp| !totp.is_empty());
+        entry.urls = login
+            .uris
+            .iter()
+            .flatten()
+            .filter_map(|uri| uri.uri.clone())
+            .filter(|uri| !uri.is_empty())
+            .collect();
+        entry.password_changed_at = login.password_revision_date.or(item.creation_date);
+    }
+
+    // Карты, удостоверения и SSH-ключи переносим как поля заметки
+    if item.item_type != TYPE_LOGIN && item.item_type != TYPE_SECURE_NOTE {
+        for details in [&item.card, &item.identity, &item.ssh_key].into_iter().flatten() {
+            if let Value::Object(map) = details {
+                for (key, value) in map {
+                    if let Some(value) = value.as_str().filter(|v| !v.is_empty()) {
+                        entry.custom_fields.push(CustomField {
+                            name: key.clone(),
+                            value: value.to_string(),
+                            protected: PROTECTED_KEYS.contains(&key.as_str()),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    for field in &item.fields {
+        if field.field_type == FIELD_LINKED {
+            continue;
+        }
+        entry.custom_fields.push(CustomField {
+            name: field.name.clone().unwrap_or_default(),
+            value: field.value.clone().unwrap_or_default(),
+            protected: field.field_type == FIELD_HIDDEN,
+        });
+    }
+
+    if item.creation_date.is_some() {
+        entry.created_at = item.creation_date;
+    }
+    if item.revision_date.is_some() {
+        entry.updated_at = item.revision_date;
+    }
+    entry
+}
+
+// Ключ выводится из пароля экспорта и соли, затем растягивается HKDF
+// на ключ шифрования и ключ MAC — так же, как в клиентах Bitwarden
+fn decrypt_export(export: &Export, password: &str) -> Result<String, Box<dyn Error>> {
+    let salt = export.salt.as_deref().ok_or("В экспорте нет соли")?;
+    let iterations = export.kdf_iterations.ok_or("В экспорте нет числа итераций KDF")?;
+
+    let mut master_key = [0u8; 32];
+    match export.kdf_type.unwrap_or(KDF_PBKDF2) {
+        KDF_PBKDF2 => {
+            if iterations > MAX_PBKDF2_ITERATIONS {
+                return Err(format!("Слишком много итераций PBKDF2: {}", iterations).into());
+            }
+            pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), salt.as_bytes(), iterations, &mut master_key);
+        }
+        KDF_ARGON2ID => {
+            let memory_mib = export.kdf_memory.ok_or("В экспорте нет параметра памяти Argon2")?;
+            let parallelism = export.kdf_parallelism.ok_or("В экспорте нет параметра потоков Argon2")?;
+            if memory_mib > MAX_ARGON2_MEMORY_MIB || iterations > MAX_ARGON2_ITERATIONS || parallelism > MAX_ARGON2_PARALLELISM
+            {
+                return Err(format!(
+                    "Параметры Argon2 вне допустимых пределов: {} МиБ, {} итераций, {} потоков",
+                    memory_mib, iterations, parallelism
+                )
+                .into());
+            }
+            let memory_kib = memory_mib.checked_mul(1024).ok_or("Некорректный параметр памяти Argon2")?;
+            let params = Params::new(memory_kib, iterations, parallelism, Some(32))
+                .map_err(|e| format!("Некорректные параметры Argon2: {}", e))?;
+            let salt_hash = Sha256::digest(salt.as_bytes());
+            Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+                .hash_password_into(password.as_bytes(), &salt_hash, &mut master_key)
+                .map_err(|e| format!("Ошибка Argon2: {}", e))?;
+        }
+        other => return Err(format!("Неизвестный тип KDF: {}", other).into()),
+    }
+
+    let hkdf = Hkdf::<Sha256>::from_prk(&master_key).map_err(|_| "Некорректная длина ключа")?;
+    master_key.zeroize();
+    let mut enc_key = [0u8; 32];
+    let mut mac_key = [0u8; 32];
+    hkdf.expand(b"enc", &mut enc_key).map_err(|_| "Ошибка HKDF")?;
+    hkdf.expand(b"mac", &mut mac_key).map_err(|_| "Ошибка HKDF")?;
+
+    let result = (|| {
+        // Проверочная строка расшифруется только с верным паролем
+        let validation = export
+            .enc_key_validation
+            .as_deref()
+            .ok_or("В экспорте нет проверочной строки ключа")?;
+        decrypt_enc_string(validation, &enc_key, &mac_key)?;
+
+        let data = export.data.as_deref().ok_or("В экспорте нет данных")?;
+        let plaintext = decrypt_enc_string(data, &enc_key, &mac_key)?;
+        String::from_utf8(plaintext).map_err(|_| "Расшифрованные данные не в UTF-8".into())
+    })();
+
+    enc_key.zeroize();
+    mac_key.zeroize();
+    result
+}
+
+/// Строка вида `2.iv|ciphertext|mac` (AES-256-CBC + HMAC-SHA256, всё в base64).
+fn decrypt_enc_string(value: &str, enc_key: &[u8; 32], mac_key: &[u8; 32]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let (kind, rest) = value.split_once('.').ok_or("Некорректная зашифрованная строка")?;
+    if kind != "2" {
+        return Err(format!("Неподдерживаемый тип шифрования Bitwarden: {}", kind).into());
+    }
+    let parts: Vec<&str> = rest.split('|').collect();
+    if parts.len() != 3 {
+        return Err("Некорректная зашифрованная строка".into());
+    }
+    let iv = BASE64.decode(parts[0])?;
+    let ciphertext = BASE64.decode(parts[1])?;
+    let mac = BASE64.decode(parts[2])?;
+
+    let mut hmac = <Hmac<Sha256> as Mac>::new_from_slice(mac_key).map_err(|_| "Ошибка HMAC")?;
+    hmac.update(&iv);
+    hmac.update(&ciphertext);
+    hmac.verify_slice(&mac).map_err(|_| "Неверный пароль экспорта")?;
+
+    let iv: [u8; 16] = iv.try_into().map_err(|_| "Некорректная длина IV")?;
+    crypto::decrypt(&EncryptedData { ciphertext, iv }, enc_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Экспорты «Password protected», зашифрованные независимой реализацией
+    // (Python cryptography) с паролем `export-password`
+    const PBKDF2_EXPORT: &str = include_str!("testdata/bitwarden_pbkdf2.json");
+    const ARGON2_EXPORT: &str = include_str!("testdata/bitwarden_argon2.json");
+    const PASSWORD: &str = "export-password";
+
+    fn check_entries(result: &ImportResult) {
+        assert_eq!(result.entries.len(), 2);
+        let login = &result.entries[0];
+        assert_eq!(login.kind, EntryKind::Login);
+        assert_eq!(login.name, "GitHub");
+        assert_eq!(login.username, "octocat");
+        assert_eq!(login.password, "s3cr3t!");
+        assert_eq!(login.totp.as_deref(), Some("JBSWY3DPEHPK3PXP"));
+        assert_eq!(login.urls, vec!["https://github.com"]);
+        assert_eq!(login.folder.as_deref(), Some("Работа"));
+        assert_eq!(login.custom_fields[0].name, "PIN");
+        assert!(login.custom_fields[0].protected);
+        let note = &result.entries[1];
+        assert_eq!(note.kind, EntryKind::Note);
+        assert_eq!(note.notes, "текст");
+    }
+
+    #[test]
+    fn decrypts_pbkdf2_export() {
+        assert!(is_password_protected(PBKDF2_EXPORT).unwrap());
+        check_entries(&parse(PBKDF2_EXPORT, Some(PASSWORD)).unwrap());
+    }
+
+    #[test]
+    fn decrypts_argon2id_export() {
+        check_entries(&parse(ARGON2_EXPORT, Some(PASSWORD)).unwrap());
+    }
+
+    #[test]
+    fn rejects_wrong_password() {
+        let error = parse(PBKDF2_EXPORT, Some("wrong")).err().unwrap();
+        assert_eq!(error.to_string(), "Неверный пароль экспорта");
+        assert!(parse(PBKDF2_EXPORT, None).is_err());
+    }
+
+    #[test]
+    fn rejects_oversized_kdf_parameters() {
+        for (field, value) in [
+            ("kdfMemory", u32::MAX as u64),
+            ("kdfMemory", 4_194_304),
+            ("kdfIterations", 1_000_000),
+            ("kdfParallelism", 1024),
+        ] {
+            let mut export: Value = serde_json::from_str(ARGON2_EXPORT).unwrap();
+            export[field] = Value::from(value);
+            let error = parse(&export.to_string(), Some(PASSWORD)).err().unwrap();
+            assert!(error.to_string().contains("вне допустимых пределов"), "{}: {}", field, error);
+        }
+
+        let mut export: Value = serde_json::from_str(PBKDF2_EXPORT).unwrap();
+        export["kdfIterations"] = Value::from(u32::MAX);
+        let error = parse(&export.to_string(), Some(PASSWORD)).err().unwrap();
+        assert!(error.to_string().contains("PBKDF2"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_tampered_data() {
+        let mut export: Value = serde_json::from_str(PBKDF2_EXPORT).unwrap();
+        let data = export["data"].as_str().unwrap().to_string();
+        let (head, mac) = data.rsplit_once('|').unwrap();
+        let mut mac = BASE64.decode(mac).unwrap();
+        mac[0] ^= 1;
+        export["data"] = Value::String(format!("{}|{}", head, BASE64.encode(mac)));
+        assert!(parse(&export.to_string(), Some(PASSWORD)).is_err());
+    }
+
+    #[test]
+    fn account_encrypted_export_is_refused() {
+        let content = r#"{"encrypted": true, "passwordProtected": false, "items": []}"#;
+        assert!(!is_password_protected(content).unwrap());
+        assert!(parse(content, Some(PASSWORD)).is_err());
+    }
+}
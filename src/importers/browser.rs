@@ -0,0 +1,77 @@
+use super::{from_csv_import, guess_kind, host_from_url, ImportResult};
+use crate::csv_format;
+use crate::entry::EntryKind;
+use std::error::Error;
+use std::io::Read;
+
+// Так LastPass помечает защищённые заметки
+const LASTPASS_NOTE_URL: &str = "http://sn";
+// Служебная запись синхронизации Firefox, а не пароль сайта
+const FIREFOX_ACCOUNTS_URL: &str = "chrome://FirefoxAccounts";
+
+/// CSV из LastPass: url, username, password, totp, extra, name, grouping, fav.
+pub fn read_lastpass<R: Read>(reader: R) -> Result<ImportResult, Box<dyn Error>> {
+    let mut result = from_csv_import(csv_format::read_entries(reader)?);
+    for entry in &mut result.entries {
+        if entry.urls.iter().any(|url| url == LASTPASS_NOTE_URL) {
+            entry.urls.retain(|url| url != LASTPASS_NOTE_URL);
+            if entry.name == LASTPASS_NOTE_URL {
+                entry.name.clear();
+            }
+            entry.kind = EntryKind::Note;
+        }
+        // LastPass экранирует HTML-сущности в текстовых полях при выгрузке
+        entry.name = unescape_html(&entry.name);
+        entry.notes = unescape_html(&entry.notes);
+        entry.folder = entry
+            .folder
+            .as_deref()
+            .map(|folder| unescape_html(folder).replace('\\', "/"));
+    }
+    Ok(result)
+}
+
+/// CSV из Chrome, Edge и других браузеров на Chromium: name, url, username, password, note.
+pub fn read_chrome<R: Read>(reader: R) -> Result<ImportResult, Box<dyn Error>> {
+    let mut result = from_csv_import(csv_format::read_entries(reader)?);
+    for entry in &mut result.entries {
+        entry.kind = guess_kind(entry);
+    }
+    Ok(result)
+}
+
+/// CSV из Firefox: url, username, password, httpRealm, formActionOrigin, guid,
+/// timeCreated, timeLastUsed, timePasswordChanged. Названий у записей нет.
+pub fn read_firefox<R: Read>(reader: R) -> Result<ImportResult, Box<dyn Error>> {
+    let mut result = from_csv_import(csv_format::read_entries(reader)?);
+    let before = result.entries.len();
+    result
+        .entries
+        .retain(|entry| !entry.urls.iter().any(|url| url.starts_with(FIREFOX_ACCOUNTS_URL)));
+    if result.entries.len() < before {
+        result
+            .warnings
+            .push("Пропущена служебная запись Firefox Accounts".to_string());
+    }
+
+    for entry in &mut result.entries {
+        if let Some(url) = entry.urls.first() {
+            if entry.name == *url {
+                entry.name = host_from_url(url);
+            }
+        }
+    }
+    Ok(result)
+}
+
+fn unescape_html(value: &str) -> String {
+    if !value.contains('&') {
+        return value.to_string();
+    }
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
@@ -0,0 +1,30 @@
+use super::ImportResult;
+use crate::kdbx::xml;
+use std::error::Error;
+
+/// Разбирает XML-экспорт KeePass 2.x / KeePassXC. Записи из корзины пропускаются.
+pub fn parse(content: &str) -> Result<ImportResult, Box<dyn Error>> {
+    let database = xml::parse(content, None, &[])?;
+    let mut result = ImportResult::default();
+
+    let recycle_bin = database.layout.recycle_bin.as_deref();
+    let in_recycle_bin = |folder: Option<&str>| match (folder, recycle_bin) {
+        (Some(folder), Some(bin)) => folder == bin || folder.starts_with(&format!("{}/", bin)),
+        _ => false,
+    };
+
+    let mut skipped = 0;
+    for entry in database.entries {
+        if in_recycle_bin(entry.folder.as_deref()) {
+            skipped += 1;
+        } else {
+            result.entries.push(entry);
+        }
+    }
+    if skipped > 0 {
+        result
+            .warnings
+            .push(format!("Пропущено записей из корзины KeePass: {}", skipped));
+    }
+    Ok(result)
+}
@@ -0,0 +1,236 @@
+pub mod bitwarden;
+pub mod browser;
+pub mod keepass_xml;
+pub mod onepassword;
+
+use crate::csv_format;
+use crate::entry::{Entry, EntryKind};
+use crate::export::{self, ExportKey};
+use crate::vault;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    /// Зашифрованный экспорт hiho
+    Encrypted,
+    /// Собственный JSON hiho
+    Json,
+    /// Собственный CSV hiho
+    Csv,
+    Bitwarden,
+    KeePassXml,
+    OnePassword,
+    LastPass,
+    Chrome,
+    Firefox,
+}
+
+impl ImportFormat {
+    pub const ALL: [ImportFormat; 9] = [
+        ImportFormat::Encrypted,
+        ImportFormat::Json,
+        ImportFormat::Csv,
+        ImportFormat::Bitwarden,
+        ImportFormat::KeePassXml,
+        ImportFormat::OnePassword,
+        ImportFormat::LastPass,
+        ImportFormat::Chrome,
+        ImportFormat::Firefox,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImportFormat::Encrypted => "encrypted",
+            ImportFormat::Json => "json",
+            ImportFormat::Csv => "csv",
+            ImportFormat::Bitwarden => "bitwarden",
+            ImportFormat::KeePassXml => "keepass-xml",
+            ImportFormat::OnePassword => "1pux",
+            ImportFormat::LastPass => "lastpass",
+            ImportFormat::Chrome => "chrome",
+            ImportFormat::Firefox => "firefox",
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            ImportFormat::Encrypted => "hiho, зашифрованный",
+            ImportFormat::Json => "hiho JSON",
+            ImportFormat::Csv => "hiho CSV",
+            ImportFormat::Bitwarden => "Bitwarden JSON",
+            ImportFormat::KeePassXml => "KeePass XML",
+            ImportFormat::OnePassword => "1Password 1PUX",
+            ImportFormat::LastPass => "LastPass CSV",
+            ImportFormat::Chrome => "Chrome CSV",
+            ImportFormat::Firefox => "Firefox CSV",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "encrypted" | "enc" => Some(ImportFormat::Encrypted),
+            "json" => Some(ImportFormat::Json),
+            "csv" => Some(ImportFormat::Csv),
+            "bitwarden" => Some(ImportFormat::Bitwarden),
+            "keepass" | "keepass-xml" | "keepassxml" => Some(ImportFormat::KeePassXml),
+            "1pux" | "1password" => Some(ImportFormat::OnePassword),
+            "lastpass" => Some(ImportFormat::LastPass),
+            "chrome" | "chromium" | "edge" => Some(ImportFormat::Chrome),
+            "firefox" => Some(ImportFormat::Firefox),
+            _ => None,
+        }
+    }
+
+    /// Определяет формат по расширению и содержимому файла.
+    pub fn detect(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let file = File::open(path)?;
+        let file_len = file.metadata()?.len();
+        let mut head = Vec::new();
+        file.take(4096).read_to_end(&mut head)?;
+
+        if vault::is_container(&head, file_len) {
+            return Ok(ImportFormat::Encrypted);
+        }
+
+        // .1pux — это zip-архив
+        if head.starts_with(b"PK\x03\x04") {
+            return Ok(ImportFormat::OnePassword);
+        }
+
+        let text = String::from_utf8_lossy(&head);
+        let text = text.trim_start_matches('\u{feff}').trim_start();
+        if text.starts_with('<') {
+            return Ok(ImportFormat::KeePassXml);
+        }
+        if text.starts_with('{') {
+            return Ok(ImportFormat::Bitwarden);
+        }
+        if text.starts_with('[') {
+            return Ok(ImportFormat::Json);
+        }
+
+        let header: Vec<String> = text
+            .lines()
+            .next()
+            .unwrap_or("")
+            .split(',')
+            .map(|h| h.trim().trim_matches('"').to_lowercase())
+            .collect();
+        let has = |name: &str| header.iter().any(|h| h == name);
+        if has("grouping") && has("extra") {
+            Ok(ImportFormat::LastPass)
+        } else if has("httprealm") || has("formactionorigin") {
+            Ok(ImportFormat::Firefox)
+        } else if has("name") && has("url") && has("username") && has("password") && header.len() <= 5 {
+            Ok(ImportFormat::Chrome)
+        } else {
+            Ok(ImportFormat::Csv)
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ImportResult {
+    pub entries: Vec<Entry>,
+    /// Пропущенные записи и данные, которые не удалось перенести
+    pub warnings: Vec<String>,
+}
+
+/// Нужен ли для чтения файла отдельный пароль (или ключ) экспорта.
+pub fn needs_password(path: &Path, format: ImportFormat) -> Result<bool, Box<dyn Error>> {
+    match format {
+        ImportFormat::Encrypted => Ok(true),
+        ImportFormat::Bitwarden => bitwarden::is_password_protected(&fs::read_to_string(path)?),
+        _ => Ok(false),
+    }
+}
+
+/// Читает файл экспорта и переводит его в записи hiho. В хранилище ничего
+/// не сохраняется, поэтому результат можно показать как предпросмотр.
+pub fn import_file(
+    path: &Path,
+    format: ImportFormat,
+    key: Option<&ExportKey>,
+) -> Result<ImportResult, Box<dyn Error>> {
+    let export_password = match key {
+        Some(ExportKey::Password(password)) => Some(password.as_str()),
+        _ => None,
+    };
+    let mut result = match format {
+        ImportFormat::Encrypted => {
+            let key = key.ok_or("Для зашифрованного экспорта нужен пароль или ключевой файл")?;
+            ImportResult {
+                entries: export::read_encrypted(&fs::read(path)?, key)?,
+                warnings: Vec::new(),
+            }
+        }
+        ImportFormat::Json => {
+            let content = fs::read_to_string(path)?;
+            ImportResult {
+                entries: serde_json::from_str(&content)?,
+                warnings: Vec::new(),
+            }
+        }
+        ImportFormat::Csv => from_csv_import(csv_format::read_entries(File::open(path)?)?),
+        ImportFormat::Bitwarden => bitwarden::parse(&fs::read_to_string(path)?, export_password)?,
+        ImportFormat::KeePassXml => keepass_xml::parse(&fs::read_to_string(path)?)?,
+        ImportFormat::OnePassword => onepassword::read(File::open(path)?)?,
+        ImportFormat::LastPass => browser::read_lastpass(File::open(path)?)?,
+        ImportFormat::Chrome => browser::read_chrome(File::open(path)?)?,
+        ImportFormat::Firefox => browser::read_firefox(File::open(path)?)?,
+    };
+
+    for entry in &mut result.entries {
+        fill_missing_name(entry);
+    }
+    Ok(result)
+}
+
+pub(crate) fn from_csv_import(import: csv_format::CsvImport) -> ImportResult {
+    ImportResult {
+        entries: import.entries,
+        warnings: import
+            .errors
+            .into_iter()
+            .map(|error| format!("строка {}: {}", error.line, error.message))
+            .collect(),
+    }
+}
+
+// Записи без названия встречаются почти во всех форматах
+fn fill_missing_name(entry: &mut Entry) {
+    if !entry.name.trim().is_empty() {
+        return;
+    }
+    entry.name = match entry.urls.first() {
+        Some(url) => host_from_url(url),
+        None if !entry.username.is_empty() => entry.username.clone(),
+        None => "Без названия".to_string(),
+    };
+}
+
+/// Запись с SSH-ключом — ключ; заметка — запись без логина, пароля и адреса.
+pub(crate) fn guess_kind(entry: &Entry) -> EntryKind {
+    if entry.ssh_key.is_some() {
+        EntryKind::SshKey
+    } else if entry.username.is_empty() && entry.password.is_empty() && entry.urls.is_empty() {
+        EntryKind::Note
+    } else {
+        EntryKind::Login
+    }
+}
+
+/// Имя хоста без схемы, порта и «www.»: `https://www.example.com:8080/a` → `example.com`.
+pub fn host_from_url(url: &str) -> String {
+    let without_scheme = url.split_once("://").map(|(_, rest)| rest).unwrap_or(url);
+    let authority = without_scheme
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or(without_scheme);
+    let host = authority.rsplit('@').next().unwrap_or(authority);
+    let host = host.split(':').next().unwrap_or(host);
+    host.trim_start_matches("www.").to_string()
+}
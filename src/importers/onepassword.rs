@@ -0,0 +1,151 @@
+use super::{guess_kind, ImportResult};
+use crate::entry::{CustomField, Entry, EntryKind};
+use chrono::DateTime;
+use serde_json::Value;
+use std::error::Error;
+use std::io::{Read, Seek};
+
+const EXPORT_DATA: &str = "export.data";
+const CATEGORY_SECURE_NOTE: &str = "003";
+
+/// Читает архив 1Password `.1pux`: данные лежат в `export.data` (JSON),
+/// вложения — в каталоге `files/` и не переносятся.
+pub fn read<R: Read + Seek>(reader: R) -> Result<ImportResult, Box<dyn Error>> {
+    let mut archive = zip::ZipArchive::new(reader).map_err(|e| format!("Некорректный архив 1PUX: {}", e))?;
+    let mut content = String::new();
+    archive
+        .by_name(EXPORT_DATA)
+        .map_err(|_| format!("В архиве нет {}", EXPORT_DATA))?
+        .read_to_string(&mut content)?;
+    let attachments = archive.file_names().filter(|name| name.starts_with("files/")).count();
+
+    let mut result = parse(&content)?;
+    if attachments > 0 {
+        result
+            .warnings
+            .push(format!("Вложения не импортируются (файлов: {})", attachments));
+    }
+    Ok(result)
+}
+
+pub fn parse(content: &str) -> Result<ImportResult, Box<dyn Error>> {
+    let data: Value = serde_json::from_str(content).map_err(|e| format!("Некорректный {}: {}", EXPORT_DATA, e))?;
+    let mut result = ImportResult::default();
+    let mut archived = 0;
+
+    for account in array(&data, "accounts") {
+        for vault in array(account, "vaults") {
+            let vault_name = vault["attrs"]["name"].as_str().filter(|name| !name.is_empty());
+            for item in array(vault, "items") {
+                if item["state"].as_str() == Some("archived") {
+                    archived += 1;
+                    continue;
+                }
+                let mut entry = convert_item(item);
+                entry.folder = vault_name.map(str::to_string);
+                result.entries.push(entry);
+            }
+        }
+    }
+
+    if archived > 0 {
+        result
+            .warnings
+            .push(format!("Пропущено записей из архива 1Password: {}", archived));
+    }
+    Ok(result)
+}
+
+fn convert_item(item: &Value) -> Entry {
+    let overview = &item["overview"];
+    let details = &item["details"];
+    let mut entry = Entry::new(string(overview, "title"), String::new(), String::new());
+
+    for url in std::iter::once(string(overview, "url"))
+        .chain(array(overview, "urls").iter().map(|u| string(u, "url")))
+    {
+        if !url.is_empty() && !entry.urls.contains(&url) {
+            entry.urls.push(url);
+        }
+    }
+    entry.tags = array(overview, "tags")
+        .iter()
+        .filter_map(|tag| tag.as_str().map(str::to_string))
+        .collect();
+    entry.notes = string(details, "notesPlain");
+
+    for field in array(details, "loginFields") {
+        match field["designation"].as_str() {
+            Some("username") => entry.username = string(field, "value"),
+            Some("password") => entry.password = string(field, "value"),
+            _ => {}
+        }
+    }
+    // Категория «Пароль» хранит его отдельно от полей входа
+    if entry.password.is_empty() {
+        entry.password = string(details, "password");
+    }
+
+    for section in array(details, "sections") {
+        for field in array(section, "fields") {
+            let Some((kind, value)) = field["value"].as_object().and_then(|v| v.iter().next()) else {
+                continue;
+            };
+            let value = field_value(value);
+            if value.is_empty() {
+                continue;
+            }
+            if kind == "totp" && entry.totp.is_none() {
+                entry.totp = Some(value);
+                continue;
+            }
+            let name = Some(string(field, "title"))
+                .filter(|title| !title.is_empty())
+                .unwrap_or_else(|| string(field, "id"));
+            entry.custom_fields.push(CustomField {
+                name,
+                value,
+                protected: matches!(kind.as_str(), "concealed" | "creditCardNumber" | "sshKey"),
+            });
+        }
+    }
+
+    let timestamp = |key: &str| item[key].as_i64().and_then(|seconds| DateTime::from_timestamp(seconds, 0));
+    if let Some(created) = timestamp("createdAt") {
+        entry.created_at = Some(created);
+        entry.password_changed_at = Some(created);
+    }
+    if let Some(updated) = timestamp("updatedAt") {
+        entry.updated_at = Some(updated);
+    }
+
+    entry.kind = if item["categoryUuid"].as_str() == Some(CATEGORY_SECURE_NOTE) {
+        EntryKind::Note
+    } else {
+        guess_kind(&entry)
+    };
+    entry
+}
+
+// Значения полей бывают строками, числами и объектами (email, адрес, SSH-ключ)
+fn field_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Object(map) => ["email_address", "privateKey"]
+            .iter()
+            .find_map(|key| map.get(*key).and_then(Value::as_str))
+            .map(str::to_string)
+            .unwrap_or_else(|| value.to_string()),
+        _ => String::new(),
+    }
+}
+
+fn string(value: &Value, key: &str) -> String {
+    value[key].as_str().unwrap_or("").to_string()
+}
+
+fn array<'a>(value: &'a Value, key: &str) -> &'a [Value] {
+    value[key].as_array().map(Vec::as_slice).unwrap_or(&[])
+}
@@ -0,0 +1,684 @@
+//! Файлы KeePass KDBX 4 как альтернативное хранилище рядом с `vault.enc`.
+//!
+//! Поддерживаются шифры AES-256 и ChaCha20, KDF Argon2d/Argon2id и AES-KDF.
+//! Группы становятся папками, дополнительные строки — пользовательскими
+//! полями, вложения и история переносятся в запись как есть.
+
+pub mod xml;
+
+use crate::crypto::{self, EncryptedData};
+use crate::entry::Entry;
+use crate::vault::UnlockError;
+use aes::cipher::{generic_array::GenericArray, BlockEncrypt, KeyInit, KeyIvInit, StreamCipher};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20::ChaCha20;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::{Digest, Sha256, Sha512};
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::{Read, Write};
+use std::path::Path;
+use xml::Layout;
+use zeroize::Zeroize;
+
+const SIGNATURE_1: u32 = 0x9AA2_D903;
+const SIGNATURE_2: u32 = 0xB54B_FB67;
+const VERSION_4: u32 = 0x0004_0000;
+
+const CIPHER_AES256: [u8; 16] = uuid_bytes(0x31c1f2e6_bf71_4350_be58_05216afc5aff);
+const CIPHER_CHACHA20: [u8; 16] = uuid_bytes(0xd6038a2b_8b6f_4cb5_a524_339a31dbb59a);
+const KDF_AES: [u8; 16] = uuid_bytes(0xc9d9f39a_628a_4460_bf74_0d08c18a4fea);
+const KDF_ARGON2D: [u8; 16] = uuid_bytes(0xef636ddf_8c29_444b_91f7_a9a403e30a0c);
+const KDF_ARGON2ID: [u8; 16] = uuid_bytes(0x9e298b19_56db_4773_b23d_fc3ec6f0a1e6);
+
+// Поля внешнего заголовка
+const HEADER_END: u8 = 0;
+const HEADER_CIPHER_ID: u8 = 2;
+const HEADER_COMPRESSION: u8 = 3;
+const HEADER_MASTER_SEED: u8 = 4;
+const HEADER_ENCRYPTION_IV: u8 = 7;
+const HEADER_KDF_PARAMETERS: u8 = 11;
+
+// Поля внутреннего заголовка
+const INNER_END: u8 = 0;
+const INNER_STREAM_ID: u8 = 1;
+const INNER_STREAM_KEY: u8 = 2;
+const INNER_BINARY: u8 = 3;
+const INNER_STREAM_CHACHA20: u32 = 3;
+
+// Типы значений в VariantDictionary
+const VARIANT_UINT32: u8 = 0x04;
+const VARIANT_UINT64: u8 = 0x05;
+const VARIANT_BYTES: u8 = 0x42;
+const VARIANT_DICTIONARY_VERSION: u16 = 0x0100;
+
+const BLOCK_SIZE: usize = 1024 * 1024;
+
+const fn uuid_bytes(value: u128) -> [u8; 16] {
+    value.to_be_bytes()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cipher {
+    Aes256,
+    ChaCha20,
+}
+
+impl Cipher {
+    fn iv_len(&self) -> usize {
+        match self {
+            Cipher::Aes256 => 16,
+            Cipher::ChaCha20 => 12,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Kdf {
+    Aes {
+        rounds: u64,
+    },
+    Argon2 {
+        algorithm: Algorithm,
+        /// Память в байтах, как в заголовке KDBX
+        memory: u64,
+        iterations: u64,
+        parallelism: u32,
+        version: u32,
+    },
+}
+
+/// Параметры файла, которые сохраняются при перезаписи.
+#[derive(Debug, Clone)]
+pub struct KdbxSettings {
+    pub cipher: Cipher,
+    pub kdf: Kdf,
+    pub compress: bool,
+    pub layout: Layout,
+}
+
+impl Default for KdbxSettings {
+    fn default() -> Self {
+        Self {
+            cipher: Cipher::ChaCha20,
+            kdf: Kdf::Argon2 {
+                algorithm: Algorithm::Argon2id,
+                memory: 64 * 1024 * 1024,
+                iterations: 3,
+                parallelism: 2,
+                version: 0x13,
+            },
+            compress: true,
+            layout: Layout::default(),
+        }
+    }
+}
+
+pub struct KdbxDatabase {
+    pub entries: Vec<Entry>,
+    pub settings: KdbxSettings,
+}
+
+/// Хранилище в формате KeePass определяется по расширению `.kdbx`.
+pub fn is_kdbx(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("kdbx"))
+}
+
+pub fn read(data: &[u8], password: &str) -> Result<KdbxDatabase, Box<dyn Error>> {
+    let mut reader = ByteReader::new(data);
+    if reader.u32()? != SIGNATURE_1 || reader.u32()? != SIGNATURE_2 {
+        return Err("Это не файл KeePass".into());
+    }
+    let version = reader.u32()?;
+    if version >> 16 != 4 {
+        return Err(format!(
+            "Поддерживается только KDBX 4 (версия файла {}.{}). Пересохраните базу в KeePassXC",
+            version >> 16,
+            version & 0xFFFF
+        )
+        .into());
+    }
+
+    let mut fields: HashMap<u8, &[u8]> = HashMap::new();
+    loop {
+        let id = reader.u8()?;
+        let len = reader.u32()? as usize;
+        let value = reader.bytes(len)?;
+        if id == HEADER_END {
+            break;
+        }
+        fields.insert(id, value);
+    }
+    let header = &data[..reader.pos];
+    let header_hash = reader.bytes(32)?;
+    let header_hmac = reader.bytes(32)?;
+    if Sha256::digest(header).as_slice() != header_hash {
+        return Err("Файл KDBX повреждён: не совпадает хеш заголовка".into());
+    }
+
+    let field = |id: u8, name: &str| fields.get(&id).copied().ok_or(format!("В заголовке KDBX нет поля {}", name));
+    let cipher = match field(HEADER_CIPHER_ID, "CipherID")? {
+        id if id == CIPHER_AES256 => Cipher::Aes256,
+        id if id == CIPHER_CHACHA20 => Cipher::ChaCha20,
+        _ => return Err("Шифр базы не поддерживается (нужен AES-256 или ChaCha20)".into()),
+    };
+    let compress = le_u32(field(HEADER_COMPRESSION, "CompressionFlags")?)? == 1;
+    let master_seed = field(HEADER_MASTER_SEED, "MasterSeed")?;
+    let iv = field(HEADER_ENCRYPTION_IV, "EncryptionIV")?;
+    if iv.len() != cipher.iv_len() {
+        return Err("Некорректная длина вектора инициализации KDBX".into());
+    }
+    let kdf_params = read_variant_dictionary(field(HEADER_KDF_PARAMETERS, "KdfParameters")?)?;
+    let (kdf, kdf_salt) = parse_kdf(&kdf_params)?;
+
+    let keys = derive_keys(password, &kdf, &kdf_salt, master_seed)?;
+    let mut mac = block_hmac(&keys.hmac_base, u64::MAX);
+    mac.update(header);
+    mac.verify_slice(header_hmac)
+        .map_err(|_| UnlockError)?;
+
+    let mut payload = Vec::new();
+    for index in 0u64.. {
+        let block_mac = reader.bytes(32)?;
+        let size = reader.u32()?;
+        let block = reader.bytes(size as usize)?;
+        let mut mac = block_hmac(&keys.hmac_base, index);
+        mac.update(&index.to_le_bytes());
+        mac.update(&size.to_le_bytes());
+        mac.update(block);
+        mac.verify_slice(block_mac)
+            .map_err(|_| format!("Файл KDBX повреждён: блок {} не прошёл проверку", index))?;
+        if size == 0 {
+            break;
+        }
+        payload.extend_from_slice(block);
+    }
+
+    let mut plaintext = match cipher {
+        Cipher::Aes256 => crypto::decrypt(
+            &EncryptedData {
+                ciphertext: payload,
+                iv: iv.try_into()?,
+            },
+            &keys.encryption,
+        )?,
+        Cipher::ChaCha20 => {
+            ChaCha20::new(keys.encryption.as_slice().into(), iv.into()).apply_keystream(&mut payload);
+            payload
+        }
+    };
+    if compress {
+        let mut decompressed = Vec::new();
+        GzDecoder::new(plaintext.as_slice())
+            .read_to_end(&mut decompressed)
+            .map_err(|e| format!("Не удалось распаковать базу KDBX: {}", e))?;
+        plaintext.zeroize();
+        plaintext = decompressed;
+    }
+
+    // Внутренний заголовок: ключ потокового шифра и вложения
+    let mut inner = ByteReader::new(&plaintext);
+    let mut stream_id = 0;
+    let mut stream_key = Vec::new();
+    let mut binaries = Vec::new();
+    loop {
+        let id = inner.u8()?;
+        let len = inner.u32()? as usize;
+        let value = inner.bytes(len)?;
+        match id {
+            INNER_END => break,
+            INNER_STREAM_ID => stream_id = le_u32(value)?,
+            INNER_STREAM_KEY => stream_key = value.to_vec(),
+            // Первый байт — флаги вложения, дальше содержимое
+            INNER_BINARY if !value.is_empty() => binaries.push(value[1..].to_vec()),
+            _ => {}
+        }
+    }
+    if stream_id != INNER_STREAM_CHACHA20 {
+        return Err("Потоковый шифр защищённых полей не поддерживается (нужен ChaCha20)".into());
+    }
+
+    let xml_text = std::str::from_utf8(&plaintext[inner.pos..]).map_err(|_| "XML базы KDBX не в UTF-8")?;
+    let mut stream = inner_stream(&stream_key);
+    let database = xml::parse(xml_text, Some(&mut |data: &mut [u8]| stream.apply_keystream(data)), &binaries)?;
+    plaintext.zeroize();
+
+    Ok(KdbxDatabase {
+        entries: database.entries,
+        settings: KdbxSettings {
+            cipher,
+            kdf,
+            compress,
+            layout: database.layout,
+        },
+    })
+}
+
+pub fn write(entries: &[Entry], password: &str, settings: &KdbxSettings) -> Result<Vec<u8>, Box<dyn Error>> {
+    // Соли и векторы инициализации меняются при каждом сохранении
+    let mut rng = rand::thread_rng();
+    let mut master_seed = [0u8; 32];
+    let mut kdf_salt = [0u8; 32];
+    let mut iv = vec![0u8; settings.cipher.iv_len()];
+    let mut stream_key = [0u8; 64];
+    rng.fill_bytes(&mut master_seed);
+    rng.fill_bytes(&mut kdf_salt);
+    rng.fill_bytes(&mut iv);
+    rng.fill_bytes(&mut stream_key);
+
+    let mut header = Vec::new();
+    header.extend_from_slice(&SIGNATURE_1.to_le_bytes());
+    header.extend_from_slice(&SIGNATURE_2.to_le_bytes());
+    header.extend_from_slice(&VERSION_4.to_le_bytes());
+    let cipher_id = match settings.cipher {
+        Cipher::Aes256 => CIPHER_AES256,
+        Cipher::ChaCha20 => CIPHER_CHACHA20,
+    };
+    write_field(&mut header, HEADER_CIPHER_ID, &cipher_id);
+    write_field(&mut header, HEADER_COMPRESSION, &u32::from(settings.compress).to_le_bytes());
+    write_field(&mut header, HEADER_MASTER_SEED, &master_seed);
+    write_field(&mut header, HEADER_ENCRYPTION_IV, &iv);
+    write_field(&mut header, HEADER_KDF_PARAMETERS, &write_kdf(&settings.kdf, &kdf_salt));
+    write_field(&mut header, HEADER_END, b"\r\n\r\n");
+
+    let keys = derive_keys(password, &settings.kdf, &kdf_salt, &master_seed)?;
+
+    let mut stream = inner_stream(&stream_key);
+    let mut binaries = Vec::new();
+    let xml_text = xml::write(
+        entries,
+        &settings.layout,
+        &mut |data: &mut [u8]| stream.apply_keystream(data),
+        &mut binaries,
+    );
+
+    let mut plaintext = Vec::new();
+    write_field(&mut plaintext, INNER_STREAM_ID, &INNER_STREAM_CHACHA20.to_le_bytes());
+    write_field(&mut plaintext, INNER_STREAM_KEY, &stream_key);
+    for binary in &binaries {
+        let mut value = Vec::with_capacity(binary.len() + 1);
+        value.push(0);
+        value.extend_from_slice(binary);
+        write_field(&mut plaintext, INNER_BINARY, &value);
+    }
+    write_field(&mut plaintext, INNER_END, &[]);
+    plaintext.extend_from_slice(xml_text.as_bytes());
+    stream_key.zeroize();
+
+    if settings.compress {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&plaintext)?;
+        plaintext.zeroize();
+        plaintext = encoder.finish()?;
+    }
+
+    let payload = match settings.cipher {
+        Cipher::Aes256 => {
+            let encrypted = crypto::encrypt_with_iv(&plaintext, &keys.encryption, iv.as_slice().try_into()?)?;
+            encrypted.ciphertext
+        }
+        Cipher::ChaCha20 => {
+            let mut payload = plaintext.clone();
+            ChaCha20::new(keys.encryption.as_slice().into(), iv.as_slice().into()).apply_keystream(&mut payload);
+            payload
+        }
+    };
+    plaintext.zeroize();
+
+    let mut output = header.clone();
+    output.extend_from_slice(&Sha256::digest(&header));
+    let mut mac = block_hmac(&keys.hmac_base, u64::MAX);
+    mac.update(&header);
+    output.extend_from_slice(&mac.finalize().into_bytes());
+
+    // Последний блок всегда пустой — это признак конца данных
+    let blocks = payload.chunks(BLOCK_SIZE).chain(std::iter::once(&[][..]));
+    for (index, block) in (0u64..).zip(blocks) {
+        let size = block.len() as u32;
+        let mut mac = block_hmac(&keys.hmac_base, index);
+        mac.update(&index.to_le_bytes());
+        mac.update(&size.to_le_bytes());
+        mac.update(block);
+        output.extend_from_slice(&mac.finalize().into_bytes());
+        output.extend_from_slice(&size.to_le_bytes());
+        output.extend_from_slice(block);
+    }
+    Ok(output)
+}
+
+struct Keys {
+    encryption: [u8; 32],
+    hmac_base: [u8; 64],
+}
+
+impl Drop for Keys {
+    fn drop(&mut self) {
+        self.encryption.zeroize();
+        self.hmac_base.zeroize();
+    }
+}
+
+fn derive_keys(password: &str, kdf: &Kdf, salt: &[u8], master_seed: &[u8]) -> Result<Keys, Box<dyn Error>> {
+    // Составной ключ из одного пароля: SHA-256(SHA-256(пароль))
+    let mut composite: [u8; 32] = Sha256::digest(Sha256::digest(password.as_bytes())).into();
+    let mut transformed = [0u8; 32];
+
+    match kdf {
+        Kdf::Aes { rounds } => {
+            let cipher = aes::Aes256::new_from_slice(salt).map_err(|_| "Некорректная соль AES-KDF")?;
+            let mut block = composite;
+            for _ in 0..*rounds {
+                for half in block.chunks_exact_mut(16) {
+                    cipher.encrypt_block(GenericArray::from_mut_slice(half));
+                }
+            }
+            transformed = Sha256::digest(block).into();
+            block.zeroize();
+        }
+        Kdf::Argon2 {
+            algorithm,
+            memory,
+            iterations,
+            parallelism,
+            version,
+        } => {
+            let version = if *version == 0x10 { Version::V0x10 } else { Version::V0x13 };
+            let params = Params::new((memory / 1024) as u32, *iterations as u32, *parallelism, Some(32))
+                .map_err(|e| format!("Некорректные параметры Argon2: {}", e))?;
+            Argon2::new(*algorithm, version, params)
+                .hash_password_into(&composite, salt, &mut transformed)
+                .map_err(|e| format!("Ошибка Argon2: {}", e))?;
+        }
+    }
+    composite.zeroize();
+
+    let mut encryption_input = master_seed.to_vec();
+    encryption_input.extend_from_slice(&transformed);
+    let encryption: [u8; 32] = Sha256::digest(&encryption_input).into();
+    encryption_input.push(1);
+    let hmac_base: [u8; 64] = Sha512::digest(&encryption_input).into();
+    encryption_input.zeroize();
+    transformed.zeroize();
+
+    Ok(Keys { encryption, hmac_base })
+}
+
+// Каждый блок подписывается своим ключом, зависящим от номера блока
+fn block_hmac(hmac_base: &[u8; 64], index: u64) -> Hmac<Sha256> {
+    let mut hasher = Sha512::new();
+    hasher.update(index.to_le_bytes());
+    hasher.update(hmac_base);
+    <Hmac<Sha256> as Mac>::new_from_slice(&hasher.finalize()).expect("HMAC принимает ключ любой длины")
+}
+
+fn inner_stream(key: &[u8]) -> ChaCha20 {
+    let hash = Sha512::digest(key);
+    ChaCha20::new(hash[..32].into(), hash[32..44].into())
+}
+
+fn parse_kdf(params: &HashMap<String, Vec<u8>>) -> Result<(Kdf, Vec<u8>), Box<dyn Error>> {
+    let get = |key: &str| params.get(key).ok_or(format!("В параметрах KDF нет «{}»", key));
+    let uuid = get("$UUID")?.as_slice();
+    let salt = get("S")?.clone();
+
+    let kdf = if uuid == KDF_AES {
+        Kdf::Aes {
+            rounds: le_u64(get("R")?)?,
+        }
+    } else if uuid == KDF_ARGON2D || uuid == KDF_ARGON2ID {
+        Kdf::Argon2 {
+            algorithm: if uuid == KDF_ARGON2D { Algorithm::Argon2d } else { Algorithm::Argon2id },
+            memory: le_u64(get("M")?)?,
+            iterations: le_u64(get("I")?)?,
+            parallelism: le_u32(get("P")?)?,
+            version: params.get("V").map(|v| le_u32(v)).transpose()?.unwrap_or(0x13),
+        }
+    } else {
+        return Err("Неизвестная функция формирования ключа KDBX".into());
+    };
+    Ok((kdf, salt))
+}
+
+fn write_kdf(kdf: &Kdf, salt: &[u8]) -> Vec<u8> {
+    let mut out = VARIANT_DICTIONARY_VERSION.to_le_bytes().to_vec();
+    match kdf {
+        Kdf::Aes { rounds } => {
+            write_variant(&mut out, VARIANT_BYTES, "$UUID", &KDF_AES);
+            write_variant(&mut out, VARIANT_UINT64, "R", &rounds.to_le_bytes());
+        }
+        Kdf::Argon2 {
+            algorithm,
+            memory,
+            iterations,
+            parallelism,
+            version,
+        } => {
+            let uuid = if *algorithm == Algorithm::Argon2d { KDF_ARGON2D } else { KDF_ARGON2ID };
+            write_variant(&mut out, VARIANT_BYTES, "$UUID", &uuid);
+            write_variant(&mut out, VARIANT_UINT64, "M", &memory.to_le_bytes());
+            write_variant(&mut out, VARIANT_UINT64, "I", &iterations.to_le_bytes());
+            write_variant(&mut out, VARIANT_UINT32, "P", &parallelism.to_le_bytes());
+            write_variant(&mut out, VARIANT_UINT32, "V", &version.to_le_bytes());
+        }
+    }
+    write_variant(&mut out, VARIANT_BYTES, "S", salt);
+    out.push(0);
+    out
+}
+
+// VariantDictionary: версия, затем записи «тип, ключ, значение» до нулевого типа
+fn read_variant_dictionary(data: &[u8]) -> Result<HashMap<String, Vec<u8>>, Box<dyn Error>> {
+    let mut reader = ByteReader::new(data);
+    let version = u16::from_le_bytes(reader.bytes(2)?.try_into()?);
+    if version >> 8 != VARIANT_DICTIONARY_VERSION >> 8 {
+        return Err("Неподдерживаемая версия параметров KDF".into());
+    }
+    let mut values = HashMap::new();
+    loop {
+        let kind = reader.u8()?;
+        if kind == 0 {
+            break;
+        }
+        let key_len = reader.u32()? as usize;
+        let key = String::from_utf8(reader.bytes(key_len)?.to_vec())?;
+        let value_len = reader.u32()? as usize;
+        values.insert(key, reader.bytes(value_len)?.to_vec());
+    }
+    Ok(values)
+}
+
+fn write_variant(out: &mut Vec<u8>, kind: u8, key: &str, value: &[u8]) {
+    out.push(kind);
+    out.extend_from_slice(&(key.len() as u32).to_le_bytes());
+    out.extend_from_slice(key.as_bytes());
+    out.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    out.extend_from_slice(value);
+}
+
+fn write_field(out: &mut Vec<u8>, id: u8, value: &[u8]) {
+    out.push(id);
+    out.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    out.extend_from_slice(value);
+}
+
+fn le_u32(value: &[u8]) -> Result<u32, Box<dyn Error>> {
+    Ok(u32::from_le_bytes(value.try_into().map_err(|_| "Ожидалось 4-байтовое число")?))
+}
+
+fn le_u64(value: &[u8]) -> Result<u64, Box<dyn Error>> {
+    Ok(u64::from_le_bytes(value.try_into().map_err(|_| "Ожидалось 8-байтовое число")?))
+}
+
+struct ByteReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], Box<dyn Error>> {
+        let end = self.pos.checked_add(len).filter(|end| *end <= self.data.len());
+        let end = end.ok_or("Файл KDBX обрезан или повреждён")?;
+        let slice = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, Box<dyn Error>> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, Box<dyn Error>> {
+        le_u32(self.bytes(4)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entry::{Attachment, CustomField};
+
+    // Минимальные параметры KDF, чтобы тесты шли быстро
+    fn settings(cipher: Cipher, kdf: Kdf, compress: bool) -> KdbxSettings {
+        KdbxSettings {
+            cipher,
+            kdf,
+            compress,
+            layout: Layout::default(),
+        }
+    }
+
+    fn argon2() -> Kdf {
+        Kdf::Argon2 {
+            algorithm: Algorithm::Argon2id,
+            memory: 1024 * 1024,
+            iterations: 1,
+            parallelism: 1,
+            version: 0x13,
+        }
+    }
+
+    fn sample_entries() -> Vec<Entry> {
+        let mut login = Entry::new("Почта".into(), "ivan@example.com".into(), "p@ss<w>&\"rd\"".into());
+        login.urls = vec!["https://mail.example.com".into()];
+        login.notes = "первая строка\nвторая".into();
+        login.folder = Some("Работа/Серверы".into());
+        login.tags = vec!["почта".into(), "важное".into()];
+        login.totp = Some("otpauth://totp/x?secret=JBSWY3DPEHPK3PXP".into());
+        login.custom_fields = vec![
+            CustomField {
+                name: "ПИН".into(),
+                value: "0000".into(),
+                protected: true,
+            },
+            CustomField {
+                name: "Вопрос".into(),
+                value: "ответ".into(),
+                protected: false,
+            },
+        ];
+        login.attachments = vec![Attachment {
+            name: "key.bin".into(),
+            data: (0..=255).collect(),
+        }];
+        let mut old = login.clone();
+        old.password = "старый пароль".into();
+        old.history.clear();
+        login.history = vec![old];
+
+        let note = Entry::new("Заметка".into(), String::new(), String::new());
+        vec![login, note]
+    }
+
+    fn assert_round_trip(settings: &KdbxSettings) {
+        let entries = sample_entries();
+        let data = write(&entries, "пароль", settings).unwrap();
+        let database = read(&data, "пароль").unwrap();
+        assert_eq!(database.settings.cipher, settings.cipher);
+        assert_eq!(database.settings.kdf, settings.kdf);
+        assert_eq!(database.settings.compress, settings.compress);
+
+        // Записи читаются в порядке групп: сначала корень, потом вложенные
+        assert_eq!(database.entries.len(), entries.len());
+        let login = &entries[0];
+        let read_login = database.entries.iter().find(|entry| entry.id == login.id).unwrap();
+        assert_eq!(read_login.name, login.name);
+        assert_eq!(read_login.username, login.username);
+        assert_eq!(read_login.password, login.password);
+        assert_eq!(read_login.urls, login.urls);
+        assert_eq!(read_login.notes, login.notes);
+        assert_eq!(read_login.folder, login.folder);
+        assert_eq!(read_login.tags, login.tags);
+        assert_eq!(read_login.totp, login.totp);
+        assert_eq!(read_login.custom_fields, login.custom_fields);
+        assert_eq!(read_login.attachments, login.attachments);
+        assert_eq!(read_login.history.len(), 1);
+        assert_eq!(read_login.history[0].password, "старый пароль");
+        assert_eq!(
+            read_login.password_changed_at.map(|d| d.timestamp()),
+            login.password_changed_at.map(|d| d.timestamp())
+        );
+        assert!(database.entries.iter().any(|entry| entry.id == entries[1].id && entry.name == "Заметка"));
+    }
+
+    #[test]
+    fn round_trip_chacha20_argon2id() {
+        assert_round_trip(&settings(Cipher::ChaCha20, argon2(), true));
+    }
+
+    #[test]
+    fn round_trip_aes256_aes_kdf_uncompressed() {
+        assert_round_trip(&settings(Cipher::Aes256, Kdf::Aes { rounds: 100 }, false));
+    }
+
+    #[test]
+    fn round_trip_argon2d() {
+        let kdf = Kdf::Argon2 {
+            algorithm: Algorithm::Argon2d,
+            memory: 1024 * 1024,
+            iterations: 2,
+            parallelism: 2,
+            version: 0x13,
+        };
+        assert_round_trip(&settings(Cipher::Aes256, kdf, true));
+    }
+
+    #[test]
+    fn layout_keeps_empty_groups() {
+        let mut settings = settings(Cipher::ChaCha20, argon2(), true);
+        settings.layout.database_name = "Семейная".into();
+        settings.layout.groups.push(xml::GroupInfo {
+            path: "Пустая".into(),
+            uuid: *uuid::Uuid::new_v4().as_bytes(),
+        });
+        let data = write(&sample_entries(), "пароль", &settings).unwrap();
+        let layout = read(&data, "пароль").unwrap().settings.layout;
+        assert_eq!(layout.database_name, "Семейная");
+        assert_eq!(layout.root_uuid, settings.layout.root_uuid);
+        assert!(layout.groups.iter().any(|group| group.path == "Пустая"));
+        assert!(layout.groups.iter().any(|group| group.path == "Работа/Серверы"));
+    }
+
+    #[test]
+    fn wrong_password_is_unlock_error() {
+        let data = write(&sample_entries(), "пароль", &settings(Cipher::ChaCha20, argon2(), true)).unwrap();
+        let error = read(&data, "не тот").err().unwrap();
+        assert!(error.downcast_ref::<UnlockError>().is_some(), "{}", error);
+    }
+
+    #[test]
+    fn tampered_block_is_rejected() {
+        let mut data = write(&sample_entries(), "пароль", &settings(Cipher::Aes256, argon2(), true)).unwrap();
+        let last = data.len() - 40;
+        data[last] ^= 1;
+        assert!(read(&data, "пароль").is_err());
+        assert!(read(b"not a kdbx file", "пароль").is_err());
+    }
+}
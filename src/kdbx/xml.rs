@@ -0,0 +1,589 @@
+//! XML-часть базы KeePass: чтение и запись дерева групп и записей.
+//!
+//! Один и тот же разбор используется для XML-экспорта KeePass и для
+//! внутреннего XML файла KDBX 4, где защищённые значения дополнительно
+//! зашифрованы потоковым шифром.
+
+use crate::entry::{Attachment, CustomField, Entry, SshKey};
+use crate::ssh::key::PEM_BEGIN;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+use flate2::read::GzDecoder;
+use roxmltree::{Document, Node, NodeId};
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::Read;
+
+// Стандартные строковые поля KeePass
+const TITLE: &str = "Title";
+const USER_NAME: &str = "UserName";
+const PASSWORD: &str = "Password";
+const URL: &str = "URL";
+const NOTES: &str = "Notes";
+// Дополнительные адреса в формате KeePass2Android / KeePassXC
+const EXTRA_URL_PREFIX: &str = "KP2A_URL";
+// TOTP: KeePassXC хранит ссылку otpauth, KeePass 2.47+ — секрет в base32
+const TOTP_URI: &str = "otp";
+const TOTP_SECRET: &str = "TimeOtp-Secret-Base32";
+// Дата смены пароля — в CustomData записи, у KeePass такого поля нет
+const PASSWORD_CHANGED_KEY: &str = "hiho.password_changed_at";
+// SSH-ключ — вложение с закрытым ключом OpenSSH, как у KeePassXC;
+// подтверждение подписей — в CustomData
+const SSH_KEY_ATTACHMENT: &str = "id_ssh_key";
+const SSH_CONFIRM_KEY: &str = "hiho.ssh_confirm";
+
+const NIL_UUID: [u8; 16] = [0; 16];
+
+/// Группа KeePass; путь совпадает с `Entry::folder` («Работа/Серверы»).
+#[derive(Debug, Clone)]
+pub struct GroupInfo {
+    pub path: String,
+    pub uuid: [u8; 16],
+}
+
+/// Всё, что нужно помнить о дереве групп, чтобы записать файл обратно
+/// без потери пустых групп, идентификаторов и корзины.
+#[derive(Debug, Clone)]
+pub struct Layout {
+    pub database_name: String,
+    pub root_name: String,
+    pub root_uuid: [u8; 16],
+    /// Вложенные группы в порядке документа
+    pub groups: Vec<GroupInfo>,
+    /// Путь группы-корзины
+    pub recycle_bin: Option<String>,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            database_name: "hiho".to_string(),
+            root_name: "hiho".to_string(),
+            root_uuid: *uuid::Uuid::new_v4().as_bytes(),
+            groups: Vec::new(),
+            recycle_bin: None,
+        }
+    }
+}
+
+pub struct KeePassDatabase {
+    pub entries: Vec<Entry>,
+    pub layout: Layout,
+}
+
+/// Применяет ключевой поток к защищённому значению (шифрует и расшифровывает).
+pub type ProtectedStream<'a> = &'a mut dyn FnMut(&mut [u8]);
+
+struct ReadContext<'a> {
+    protected: HashMap<NodeId, String>,
+    binaries: &'a [Vec<u8>],
+    layout: Layout,
+}
+
+/// Разбирает XML KeePass. Без `stream` защищённые значения не допускаются
+/// (так выглядит обычный XML-экспорт); `binaries` — вложения из внутреннего
+/// заголовка KDBX 4, для XML-экспорта они берутся из `Meta/Binaries`.
+pub fn parse(
+    xml: &str,
+    stream: Option<ProtectedStream>,
+    binaries: &[Vec<u8>],
+) -> Result<KeePassDatabase, Box<dyn Error>> {
+    let document = Document::parse(xml).map_err(|e| format!("Некорректный XML KeePass: {}", e))?;
+    let root = document.root_element();
+    if !root.has_tag_name("KeePassFile") {
+        return Err("Это не база KeePass".into());
+    }
+
+    // Ключевой поток расходуется по порядку документа, включая историю и корзину
+    let mut protected = HashMap::new();
+    let mut stream = stream;
+    for node in root.descendants().filter(|n| n.has_tag_name("Value")) {
+        if node.attribute("Protected") != Some("True") {
+            continue;
+        }
+        let Some(apply) = stream.as_mut() else {
+            return Err("Значения в XML зашифрованы. Экспортируйте базу KeePass в «KeePass XML (2.x)»".into());
+        };
+        let mut data = BASE64.decode(node.text().unwrap_or("").trim())?;
+        apply(&mut data);
+        protected.insert(node.id(), String::from_utf8_lossy(&data).into_owned());
+    }
+
+    let meta = child(root, "Meta");
+    let meta_binaries;
+    let binaries = if binaries.is_empty() {
+        meta_binaries = meta.map(read_meta_binaries).transpose()?.unwrap_or_default();
+        &meta_binaries
+    } else {
+        binaries
+    };
+
+    let recycle_bin_uuid = meta
+        .filter(|meta| child_text(*meta, "RecycleBinEnabled").unwrap_or("True") == "True")
+        .and_then(|meta| child_text(meta, "RecycleBinUUID"))
+        .map(decode_uuid)
+        .filter(|uuid| *uuid != NIL_UUID);
+
+    let top_group = child(root, "Root")
+        .and_then(|r| child(r, "Group"))
+        .ok_or("В базе KeePass нет корневой группы")?;
+
+    let mut context = ReadContext {
+        protected,
+        binaries,
+        layout: Layout {
+            database_name: meta.and_then(|m| child_text(m, "DatabaseName")).unwrap_or("").to_string(),
+            root_name: child_text(top_group, "Name").unwrap_or("").to_string(),
+            root_uuid: child_text(top_group, "UUID").map(decode_uuid).unwrap_or(NIL_UUID),
+            groups: Vec::new(),
+            recycle_bin: None,
+        },
+    };
+
+    let mut entries = Vec::new();
+    // Корневая группа — это само хранилище, в путь папки её не включаем
+    collect_group(top_group, None, recycle_bin_uuid, &mut context, &mut entries);
+    Ok(KeePassDatabase {
+        entries,
+        layout: context.layout,
+    })
+}
+
+fn read_meta_binaries(meta: Node) -> Result<Vec<Vec<u8>>, Box<dyn Error>> {
+    let mut binaries = Vec::new();
+    for node in child(meta, "Binaries").iter().flat_map(|b| b.children()) {
+        if !node.has_tag_name("Binary") {
+            continue;
+        }
+        let mut data = BASE64.decode(node.text().unwrap_or("").trim())?;
+        if node.attribute("Compressed") == Some("True") {
+            let mut decompressed = Vec::new();
+            GzDecoder::new(data.as_slice()).read_to_end(&mut decompressed)?;
+            data = decompressed;
+        }
+        let index: usize = node.attribute("ID").and_then(|id| id.parse().ok()).unwrap_or(binaries.len());
+        if binaries.len() <= index {
+            binaries.resize(index + 1, Vec::new());
+        }
+        binaries[index] = data;
+    }
+    Ok(binaries)
+}
+
+fn collect_group(
+    group: Node,
+    path: Option<&str>,
+    recycle_bin: Option<[u8; 16]>,
+    context: &mut ReadContext,
+    entries: &mut Vec<Entry>,
+) {
+    for node in group.children().filter(Node::is_element) {
+        match node.tag_name().name() {
+            "Entry" => entries.push(convert_entry(node, path, context)),
+            "Group" => {
+                let name = child_text(node, "Name").unwrap_or("");
+                let sub_path = match path {
+                    Some(path) => format!("{}/{}", path, name),
+                    None => name.to_string(),
+                };
+                let uuid = child_text(node, "UUID").map(decode_uuid).unwrap_or(NIL_UUID);
+                if recycle_bin == Some(uuid) {
+                    context.layout.recycle_bin = Some(sub_path.clone());
+                }
+                context.layout.groups.push(GroupInfo {
+                    path: sub_path.clone(),
+                    uuid,
+                });
+                collect_group(node, Some(&sub_path), recycle_bin, context, entries);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn convert_entry(node: Node, folder: Option<&str>, context: &ReadContext) -> Entry {
+    let mut entry = Entry::new(String::new(), String::new(), String::new());
+    entry.folder = folder.map(str::to_string);
+
+    if let Some(uuid) = child_text(node, "UUID") {
+        entry.id = uuid::Uuid::from_bytes(decode_uuid(uuid)).to_string();
+    }
+
+    let mut extra_urls = Vec::new();
+    for field in node.children().filter(|n| n.has_tag_name("String")) {
+        let key = child_text(field, "Key").unwrap_or("");
+        let value_node = child(field, "Value");
+        let value = value_node
+            .map(|v| match context.protected.get(&v.id()) {
+                Some(value) => value.clone(),
+                None => v.text().unwrap_or("").to_string(),
+            })
+            .unwrap_or_default();
+        let protected = value_node.is_some_and(|v| {
+            v.attribute("Protected") == Some("True") || v.attribute("ProtectInMemory") == Some("True")
+        });
+
+        match key {
+            TITLE => entry.name = value,
+            USER_NAME => entry.username = value,
+            PASSWORD => entry.password = value,
+            URL => {
+                if !value.is_empty() {
+                    entry.urls.insert(0, value);
+                }
+            }
+            NOTES => entry.notes = value,
+            TOTP_URI | TOTP_SECRET if entry.totp.is_none() => {
+                entry.totp = Some(value).filter(|v| !v.is_empty());
+            }
+            _ if key.starts_with(EXTRA_URL_PREFIX) => {
+                if !value.is_empty() {
+                    extra_urls.push(value);
+                }
+            }
+            _ => {
+                if !value.is_empty() {
+                    entry.custom_fields.push(CustomField {
+                        name: key.to_string(),
+                        value,
+                        protected,
+                    });
+                }
+            }
+        }
+    }
+    entry.urls.extend(extra_urls);
+
+    for binary in node.children().filter(|n| n.has_tag_name("Binary")) {
+        let name = child_text(binary, "Key").unwrap_or("").to_string();
+        let reference = child(binary, "Value")
+            .and_then(|v| v.attribute("Ref"))
+            .and_then(|r| r.parse::<usize>().ok());
+        if let Some(data) = reference.and_then(|r| context.binaries.get(r)) {
+            // Первый закрытый ключ OpenSSH среди вложений становится SSH-ключом записи
+            let key_text = std::str::from_utf8(data).ok().filter(|text| text.trim_start().starts_with(PEM_BEGIN));
+            if let (None, Some(text)) = (&entry.ssh_key, key_text) {
+                entry.ssh_key = Some(SshKey {
+                    private_key: text.trim().to_string(),
+                    confirm: false,
+                });
+                continue;
+            }
+            entry.attachments.push(Attachment {
+                name,
+                data: data.clone(),
+            });
+        }
+    }
+
+    if let Some(tags) = child_text(node, "Tags") {
+        entry.tags = tags
+            .split([';', ','])
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(str::to_string)
+            .collect();
+    }
+
+    // Своя последовательность автонабора; пустая — по умолчанию группы
+    if let Some(sequence) = child(node, "AutoType").and_then(|auto_type| child_text(auto_type, "DefaultSequence")) {
+        entry.auto_type = Some(sequence.to_string()).filter(|s| !s.is_empty());
+    }
+
+    if let Some(times) = child(node, "Times") {
+        if let Some(created) = child_text(times, "CreationTime").and_then(parse_time) {
+            entry.created_at = Some(created);
+            entry.password_changed_at = Some(created);
+        }
+        if let Some(modified) = child_text(times, "LastModificationTime").and_then(parse_time) {
+            entry.updated_at = Some(modified);
+        }
+    }
+
+    let custom_data = child(node, "CustomData").into_iter().flat_map(|c| c.children());
+    for item in custom_data.filter(|n| n.has_tag_name("Item")) {
+        match child_text(item, "Key") {
+            Some(PASSWORD_CHANGED_KEY) => {
+                if let Some(changed) = child_text(item, "Value").and_then(parse_time) {
+                    entry.password_changed_at = Some(changed);
+                }
+            }
+            Some(SSH_CONFIRM_KEY) => {
+                if let Some(key) = &mut entry.ssh_key {
+                    key.confirm = child_text(item, "Value") == Some("True");
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Прошлые версии лежат в History внутри Entry
+    if let Some(history) = child(node, "History") {
+        entry.history = history
+            .children()
+            .filter(|n| n.has_tag_name("Entry"))
+            .map(|n| convert_entry(n, folder, context))
+            .collect();
+    }
+
+    entry.kind = crate::importers::guess_kind(&entry);
+    entry
+}
+
+/// Записывает базу в XML KDBX 4. Защищённые значения проходят через `stream`
+/// в порядке документа, вложения складываются в `binaries` без повторов.
+pub fn write(entries: &[Entry], layout: &Layout, stream: ProtectedStream, binaries: &mut Vec<Vec<u8>>) -> String {
+    let now = format_time(Utc::now());
+
+    // Папки записей, которых ещё нет в дереве, добавляем вместе с родителями
+    let mut groups: Vec<GroupInfo> = layout.groups.clone();
+    for folder in entries.iter().filter_map(|e| e.folder.as_deref()) {
+        let mut path = String::new();
+        for part in folder.split('/') {
+            if !path.is_empty() {
+                path.push('/');
+            }
+            path.push_str(part);
+            if !groups.iter().any(|g| g.path == path) {
+                groups.push(GroupInfo {
+                    path: path.clone(),
+                    uuid: *uuid::Uuid::new_v4().as_bytes(),
+                });
+            }
+        }
+    }
+
+    let recycle_bin = layout
+        .recycle_bin
+        .as_ref()
+        .and_then(|path| groups.iter().find(|g| &g.path == path))
+        .map(|g| g.uuid)
+        .unwrap_or(NIL_UUID);
+
+    let mut writer = XmlWriter {
+        out: String::new(),
+        stream,
+        binaries,
+        now,
+    };
+    writer.out.push_str("<?xml version=\"1.0\" encoding=\"utf-8\" standalone=\"yes\"?>\n<KeePassFile>\n<Meta>\n");
+    writer.element("Generator", "hiho");
+    writer.element("DatabaseName", &layout.database_name);
+    writer.out.push_str("<MemoryProtection>");
+    for (name, value) in [
+        ("ProtectTitle", "False"),
+        ("ProtectUserName", "False"),
+        ("ProtectPassword", "True"),
+        ("ProtectURL", "False"),
+        ("ProtectNotes", "False"),
+    ] {
+        writer.element(name, value);
+    }
+    writer.out.push_str("</MemoryProtection>\n");
+    writer.element("RecycleBinEnabled", "True");
+    writer.element("RecycleBinUUID", &BASE64.encode(recycle_bin));
+    writer.element("HistoryMaxItems", &crate::entry::MAX_HISTORY.to_string());
+    writer.out.push_str("</Meta>\n<Root>\n");
+
+    writer.group(&layout.root_name, layout.root_uuid, "", &groups, entries);
+
+    writer.out.push_str("<DeletedObjects/>\n</Root>\n</KeePassFile>\n");
+    writer.out
+}
+
+struct XmlWriter<'a, 'b> {
+    out: String,
+    stream: ProtectedStream<'b>,
+    binaries: &'a mut Vec<Vec<u8>>,
+    now: String,
+}
+
+impl XmlWriter<'_, '_> {
+    fn element(&mut self, name: &str, value: &str) {
+        self.out.push_str(&format!("<{0}>{1}</{0}>", name, escape(value)));
+    }
+
+    fn group(&mut self, name: &str, uuid: [u8; 16], path: &str, groups: &[GroupInfo], entries: &[Entry]) {
+        self.out.push_str("<Group>");
+        self.element("UUID", &BASE64.encode(uuid));
+        self.element("Name", name);
+        self.element("IconID", if path.is_empty() { "49" } else { "48" });
+        let now = self.now.clone();
+        self.times(&now, &now);
+        self.element("IsExpanded", "True");
+        self.out.push('\n');
+
+        for entry in entries.iter().filter(|e| e.folder.as_deref().unwrap_or("") == path) {
+            self.entry(entry, true);
+        }
+
+        for group in groups.iter().filter(|g| parent_path(&g.path) == path) {
+            let name = group.path.rsplit('/').next().unwrap_or(&group.path);
+            self.group(name, group.uuid, &group.path, groups, entries);
+        }
+        self.out.push_str("</Group>\n");
+    }
+
+    fn entry(&mut self, entry: &Entry, with_history: bool) {
+        self.out.push_str("<Entry>");
+        let uuid = uuid::Uuid::parse_str(&entry.id).unwrap_or_else(|_| uuid::Uuid::new_v4());
+        self.element("UUID", &BASE64.encode(uuid.as_bytes()));
+        self.element("IconID", "0");
+        self.element("Tags", &entry.tags.join(";"));
+
+        let created = format_time(entry.created_at.unwrap_or_else(Utc::now));
+        let modified = entry.updated_at.map(format_time).unwrap_or_else(|| created.clone());
+        self.times(&created, &modified);
+
+        self.string(TITLE, &entry.name, false);
+        self.string(USER_NAME, &entry.username, false);
+        self.string(PASSWORD, &entry.password, true);
+        self.string(URL, entry.urls.first().map(String::as_str).unwrap_or(""), false);
+        self.string(NOTES, &entry.notes, false);
+        for (i, url) in entry.urls.iter().enumerate().skip(1) {
+            self.string(&format!("{}_{}", EXTRA_URL_PREFIX, i), url, false);
+        }
+        if let Some(totp) = &entry.totp {
+            let key = if totp.starts_with("otpauth://") { TOTP_URI } else { TOTP_SECRET };
+            self.string(key, totp, true);
+        }
+        for field in &entry.custom_fields {
+            self.string(&field.name, &field.value, field.protected);
+        }
+
+        let ssh_key = entry.ssh_key.as_ref().map(|key| (SSH_KEY_ATTACHMENT, key.private_key.as_bytes()));
+        let attachments = entry.attachments.iter().map(|a| (a.name.as_str(), a.data.as_slice()));
+        for (name, data) in ssh_key.into_iter().chain(attachments) {
+            let index = match self.binaries.iter().position(|b| b == data) {
+                Some(index) => index,
+                None => {
+                    self.binaries.push(data.to_vec());
+                    self.binaries.len() - 1
+                }
+            };
+            self.out.push_str("<Binary>");
+            self.element("Key", name);
+            self.out.push_str(&format!("<Value Ref=\"{}\"/></Binary>", index));
+        }
+
+        if let Some(sequence) = &entry.auto_type {
+            self.out.push_str("<AutoType><Enabled>True</Enabled>");
+            self.element("DefaultSequence", sequence);
+            self.out.push_str("</AutoType>");
+        }
+
+        let confirm = entry.ssh_key.as_ref().is_some_and(|key| key.confirm);
+        if entry.password_changed_at.is_some() || confirm {
+            self.out.push_str("<CustomData>");
+            if let Some(changed) = entry.password_changed_at {
+                self.out.push_str("<Item>");
+                self.element("Key", PASSWORD_CHANGED_KEY);
+                self.element("Value", &changed.to_rfc3339());
+                self.out.push_str("</Item>");
+            }
+            if confirm {
+                self.out.push_str("<Item>");
+                self.element("Key", SSH_CONFIRM_KEY);
+                self.element("Value", "True");
+                self.out.push_str("</Item>");
+            }
+            self.out.push_str("</CustomData>");
+        }
+
+        if with_history && !entry.history.is_empty() {
+            self.out.push_str("<History>");
+            for old in &entry.history {
+                let mut old = old.clone();
+                old.id = uuid.to_string();
+                self.entry(&old, false);
+            }
+            self.out.push_str("</History>");
+        }
+        self.out.push_str("</Entry>\n");
+    }
+
+    fn string(&mut self, key: &str, value: &str, protected: bool) {
+        self.out.push_str("<String>");
+        self.element("Key", key);
+        if protected {
+            let mut data = value.as_bytes().to_vec();
+            (self.stream)(&mut data);
+            self.out
+                .push_str(&format!("<Value Protected=\"True\">{}</Value>", BASE64.encode(&data)));
+        } else {
+            self.element("Value", value);
+        }
+        self.out.push_str("</String>");
+    }
+
+    fn times(&mut self, created: &str, modified: &str) {
+        self.out.push_str("<Times>");
+        self.element("CreationTime", created);
+        self.element("LastModificationTime", modified);
+        self.element("LastAccessTime", modified);
+        self.element("ExpiryTime", created);
+        self.element("Expires", "False");
+        self.element("UsageCount", "0");
+        self.element("LocationChanged", modified);
+        self.out.push_str("</Times>");
+    }
+}
+
+fn parent_path(path: &str) -> &str {
+    path.rsplit_once('/').map(|(parent, _)| parent).unwrap_or("")
+}
+
+// Управляющие символы недопустимы в XML 1.0 даже в экранированном виде
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if (c as u32) < 0x20 => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn decode_uuid(value: &str) -> [u8; 16] {
+    BASE64
+        .decode(value.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .unwrap_or(NIL_UUID)
+}
+
+fn epoch() -> DateTime<Utc> {
+    NaiveDate::from_ymd_opt(1, 1, 1)
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|d| d.and_utc())
+        .unwrap_or(DateTime::<Utc>::MIN_UTC)
+}
+
+/// Дата в XML-экспорте записана в ISO 8601, а во внутреннем XML KDBX 4 —
+/// как base64 от числа секунд с 0001-01-01 (little-endian i64).
+pub fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(date.with_timezone(&Utc));
+    }
+    let bytes: [u8; 8] = BASE64.decode(value).ok()?.try_into().ok()?;
+    epoch().checked_add_signed(TimeDelta::try_seconds(i64::from_le_bytes(bytes))?)
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    BASE64.encode((time - epoch()).num_seconds().to_le_bytes())
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|n| n.has_tag_name(name))
+}
+
+fn child_text<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    child(node, name).and_then(|n| n.text())
+}
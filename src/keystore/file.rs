@@ -0,0 +1,58 @@
+//! Хранилище ключей в каталоге для тестов: по файлу на секрет. Секреты
+//! лежат незашифрованными, поэтому в сборку оно не попадает.
+
+use super::KeyStore;
+use std::error::Error;
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::PathBuf;
+use zeroize::Zeroizing;
+
+pub(crate) struct FileKeyStore {
+    dir: PathBuf,
+}
+
+impl FileKeyStore {
+    pub(crate) fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path(&self, id: &str) -> Result<PathBuf, Box<dyn Error>> {
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Err(format!("Недопустимое имя ключа: {}", id).into());
+        }
+        Ok(self.dir.join(id))
+    }
+}
+
+impl KeyStore for FileKeyStore {
+    fn describe(&self) -> String {
+        format!("каталог {}", self.dir.display())
+    }
+
+    fn store(&self, id: &str, secret: &[u8]) -> Result<(), Box<dyn Error>> {
+        let path = self.path(id)?;
+        fs::create_dir_all(&self.dir)?;
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options.open(path)?.write_all(secret)?;
+        Ok(())
+    }
+
+    fn load(&self, id: &str) -> Result<Option<Zeroizing<Vec<u8>>>, Box<dyn Error>> {
+        match fs::read(self.path(id)?) {
+            Ok(secret) => Ok(Some(Zeroizing::new(secret))),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn delete(&self, id: &str) -> Result<(), Box<dyn Error>> {
+        match fs::remove_file(self.path(id)?) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,50 @@
+//! Хранилища ключей ОС для входа без мастер-пароля.
+//!
+//! Ключ хранилища не покидает hiho в открытом виде: он шифруется случайным
+//! ключом обёртки, и только ключ обёртки кладётся в хранилище ключей ОС
+//! (Secret Service в Linux — gnome-keyring или KWallet). Обёрнутый ключ
+//! хранилища лежит в `data\remembered_keys.json` (см. `remember`), так что
+//! для входа нужны оба: файл настроек и открытая связка ключей.
+//!
+//! В тестах вместо хранилища ключей ОС — `FileKeyStore` с файлами
+//! в каталоге.
+
+#[cfg(test)]
+mod file;
+#[cfg(target_os = "linux")]
+mod secret_service;
+
+#[cfg(test)]
+pub(crate) use file::FileKeyStore;
+#[cfg(target_os = "linux")]
+pub use secret_service::SecretServiceKeyStore;
+
+use std::error::Error;
+use zeroize::Zeroizing;
+
+/// Место, где ОС хранит секреты пользователя.
+pub trait KeyStore {
+    /// Название для сообщений, например «Secret Service»
+    fn describe(&self) -> String;
+
+    /// Сохраняет секрет под именем `id`, заменяя прежний.
+    fn store(&self, id: &str, secret: &[u8]) -> Result<(), Box<dyn Error>>;
+
+    /// Секрет по имени; `None`, если его нет.
+    fn load(&self, id: &str) -> Result<Option<Zeroizing<Vec<u8>>>, Box<dyn Error>>;
+
+    /// Удаляет секрет; отсутствующий — не ошибка.
+    fn delete(&self, id: &str) -> Result<(), Box<dyn Error>>;
+}
+
+/// Хранилище ключей этой системы.
+pub fn platform() -> Result<Box<dyn KeyStore>, Box<dyn Error>> {
+    #[cfg(target_os = "linux")]
+    {
+        Ok(Box::new(SecretServiceKeyStore::connect()?))
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        Err("Хранилище ключей ОС пока поддерживается только в Linux".into())
+    }
+}
@@ -0,0 +1,171 @@
+//! Клиент freedesktop Secret Service: ключи обёртки в коллекции
+//! по умолчанию (gnome-keyring, KWallet, KeePassXC).
+//!
+//! Секреты передаются через сессию `dh-ietf1024-sha256-aes128-cbc-pkcs7`,
+//! если сервис её поддерживает. Заблокированную коллекцию открывает
+//! запрос самого сервиса (окно ввода пароля связки ключей).
+//!
+//! Ключ разблокировки hiho нельзя хранить в `hiho secret-service`: эта
+//! коллекция сама открывается только вместе с хранилищем.
+
+use super::KeyStore;
+use crate::secret_service::session::{Cipher, DhKeyPair, DH_AES, PLAIN};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use std::collections::HashMap;
+use std::error::Error;
+use zbus::blocking::{Connection, MessageIterator};
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, Value};
+use zbus::{MatchRule, MessageType};
+use zeroize::Zeroizing;
+
+const BUS_NAME: &str = "org.freedesktop.secrets";
+const SERVICE_PATH: &str = "/org/freedesktop/secrets";
+const SERVICE: &str = "org.freedesktop.Secret.Service";
+const COLLECTION: &str = "org.freedesktop.Secret.Collection";
+const ITEM: &str = "org.freedesktop.Secret.Item";
+const PROMPT: &str = "org.freedesktop.Secret.Prompt";
+const SESSION: &str = "org.freedesktop.Secret.Session";
+const SCHEMA: &str = "org.hiho.UnlockKey";
+const LABEL: &str = "hiho: ключ разблокировки хранилища";
+
+/// Секрет в виде структуры `(oayays)` из спецификации
+type Secret = (OwnedObjectPath, Vec<u8>, Vec<u8>, String);
+
+pub struct SecretServiceKeyStore {
+    connection: Connection,
+}
+
+impl SecretServiceKeyStore {
+    pub fn connect() -> Result<Self, Box<dyn Error>> {
+        let connection = Connection::session()
+            .map_err(|e| format!("Не удалось подключиться к сессионной шине D-Bus: {}", e))?;
+        Ok(Self { connection })
+    }
+
+    fn call<B, R>(&self, path: &str, interface: &str, method: &str, body: &B) -> Result<R, Box<dyn Error>>
+    where
+        B: serde::Serialize + zbus::zvariant::DynamicType,
+        R: serde::de::DeserializeOwned + zbus::zvariant::Type,
+    {
+        let reply = self
+            .connection
+            .call_method(Some(BUS_NAME), path, Some(interface), method, body)
+            .map_err(|e| match e {
+                zbus::Error::MethodError(name, _, _) if name.as_str() == "org.freedesktop.DBus.Error.ServiceUnknown" => {
+                    "Secret Service не запущен (gnome-keyring, KWallet или KeePassXC)".to_string()
+                }
+                e => format!("Secret Service: {}", e),
+            })?;
+        Ok(reply.body::<R>()?)
+    }
+
+    /// Сессия для передачи секретов: DH, а если сервис его не умеет — plain.
+    fn open_session(&self) -> Result<(OwnedObjectPath, Cipher), Box<dyn Error>> {
+        let pair = DhKeyPair::generate();
+        let opened: Result<(OwnedValue, OwnedObjectPath), _> =
+            self.call(SERVICE_PATH, SERVICE, "OpenSession", &(DH_AES, Value::from(pair.public.clone())));
+        if let Ok((output, session)) = opened {
+            let peer: Vec<u8> = Value::from(output).try_into()?;
+            return Ok((session, pair.agree(&peer)?));
+        }
+        let (_, session): (OwnedValue, OwnedObjectPath) =
+            self.call(SERVICE_PATH, SERVICE, "OpenSession", &(PLAIN, Value::from("")))?;
+        Ok((session, Cipher::Plain))
+    }
+
+    fn close_session(&self, session: &OwnedObjectPath) {
+        let _: Result<(), _> = self.call(session.as_str(), SESSION, "Close", &());
+    }
+
+    /// Показывает запрос сервиса и ждёт ответа пользователя.
+    fn prompt(&self, prompt: &OwnedObjectPath) -> Result<(), Box<dyn Error>> {
+        if prompt.as_str() == "/" {
+            return Ok(());
+        }
+        let rule = MatchRule::builder()
+            .msg_type(MessageType::Signal)
+            .interface(PROMPT)?
+            .member("Completed")?
+            .path(prompt.as_str())?
+            .build();
+        let mut completed = MessageIterator::for_match_rule(rule, &self.connection, Some(1))?;
+        self.call::<_, ()>(prompt.as_str(), PROMPT, "Prompt", &("",))?;
+        let message = completed.next().ok_or("Secret Service закрыл соединение")??;
+        let (dismissed, _): (bool, OwnedValue) = message.body()?;
+        if dismissed {
+            return Err("Запрос Secret Service отклонён".into());
+        }
+        Ok(())
+    }
+
+    fn unlock(&self, objects: &[OwnedObjectPath]) -> Result<(), Box<dyn Error>> {
+        let (_, prompt): (Vec<OwnedObjectPath>, OwnedObjectPath) =
+            self.call(SERVICE_PATH, SERVICE, "Unlock", &(objects,))?;
+        self.prompt(&prompt)
+    }
+
+    /// Элементы с ключом `id`; заблокированные сначала открываются.
+    fn search(&self, id: &str) -> Result<Vec<OwnedObjectPath>, Box<dyn Error>> {
+        let (unlocked, locked): (Vec<OwnedObjectPath>, Vec<OwnedObjectPath>) =
+            self.call(SERVICE_PATH, SERVICE, "SearchItems", &(attributes(id),))?;
+        if !locked.is_empty() {
+            self.unlock(&locked)?;
+        }
+        Ok(unlocked.into_iter().chain(locked).collect())
+    }
+}
+
+impl KeyStore for SecretServiceKeyStore {
+    fn describe(&self) -> String {
+        "Secret Service".to_string()
+    }
+
+    fn store(&self, id: &str, secret: &[u8]) -> Result<(), Box<dyn Error>> {
+        let collection: OwnedObjectPath = self.call(SERVICE_PATH, SERVICE, "ReadAlias", &("default",))?;
+        if collection.as_str() == "/" {
+            return Err("В Secret Service нет коллекции по умолчанию".into());
+        }
+        self.unlock(std::slice::from_ref(&collection))?;
+
+        let (session, cipher) = self.open_session()?;
+        let encoded = Zeroizing::new(BASE64.encode(secret));
+        let (parameters, value) = cipher.encrypt(encoded.as_bytes());
+        let mut properties: HashMap<&str, Value> = HashMap::new();
+        properties.insert("org.freedesktop.Secret.Item.Label", Value::from(LABEL));
+        properties.insert("org.freedesktop.Secret.Item.Attributes", Value::from(attributes(id)));
+        let secret: Secret = (session.clone(), parameters, value, "text/plain".to_string());
+        let created: Result<(OwnedObjectPath, OwnedObjectPath), _> =
+            self.call(collection.as_str(), COLLECTION, "CreateItem", &(properties, secret, true));
+        self.close_session(&session);
+        let (_, prompt) = created?;
+        self.prompt(&prompt)
+    }
+
+    fn load(&self, id: &str) -> Result<Option<Zeroizing<Vec<u8>>>, Box<dyn Error>> {
+        let items = self.search(id)?;
+        let Some(item) = items.first() else {
+            return Ok(None);
+        };
+        let (session, cipher) = self.open_session()?;
+        let secret: Result<(Secret,), _> = self.call(item.as_str(), ITEM, "GetSecret", &(&session,));
+        self.close_session(&session);
+        let ((_, parameters, value, _),) = secret?;
+        let encoded = cipher.decrypt(&parameters, &value)?;
+        let secret = BASE64
+            .decode(encoded.as_slice())
+            .map_err(|_| "Ключ в Secret Service повреждён")?;
+        Ok(Some(Zeroizing::new(secret)))
+    }
+
+    fn delete(&self, id: &str) -> Result<(), Box<dyn Error>> {
+        for item in self.search(id)? {
+            let prompt: OwnedObjectPath = self.call(item.as_str(), ITEM, "Delete", &())?;
+            self.prompt(&prompt)?;
+        }
+        Ok(())
+    }
+}
+
+fn attributes(id: &str) -> HashMap<&str, &str> {
+    HashMap::from([("xdg:schema", SCHEMA), ("application", "hiho"), ("hiho:key-id", id)])
+}
@@ -2,12 +2,36 @@ pub mod crypto;
 pub mod vault;
 pub mod entry;
 pub mod password_generator;
-pub mod session;
 pub mod auto_lock;
+pub mod system_lock;
+pub mod clipboard_backend;
+pub mod clipboard_manager;
+pub mod autotype;
 pub mod biometric;
+pub mod keystore;
+pub mod remember;
+pub mod second_factor;
+pub mod strength;
+pub mod audit;
+pub mod breach;
+pub mod csv_format;
+pub mod export;
+pub mod importers;
+pub mod kdbx;
+pub mod merge;
+pub mod filter;
+pub mod search;
+pub mod git_credential;
+pub mod totp;
+pub mod ssh;
+#[cfg(unix)]
+pub mod agent;
+#[cfg(target_os = "linux")]
+pub mod secret_service;
 
 
 pub use vault::Vault;
-pub use entry::Entry; 
-pub use auto_lock::AutoLockManager;
-pub use biometric::BiometricManager;
\ No newline at end of file
+pub use entry::{Attachment, CustomField, Entry, EntryKind, SshKey};
+pub use auto_lock::{AutoLockManager, IdleTimer};
+pub use biometric::BiometricManager;
+pub use clipboard_manager::ClipboardManager;
\ No newline at end of file
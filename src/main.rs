@@ -1,114 +1,137 @@
 #![cfg_attr(not(feature = "cli"), allow(dead_code, unused_imports))]
 
-mod crypto;
-mod vault;
-mod entry;
 #[cfg(feature = "cli")]
 mod cli;
-mod password_generator;
-mod session;
-mod auto_lock;
-mod biometric;
-
-use cli::{Cli, Commands};
-use entry::Entry;
-use vault::Vault;
+mod output;
+mod unlock;
+
+use cli::{AgentAction, Cli, Commands, RememberAction, SecondFactorAction, SshAction};
+use hiho::{Entry, EntryKind, SshKey, Vault};
 use std::path::Path;
 use std::error::Error;
 use clap::Parser;
-use password_generator::{generate_password, generate_secure_password};
-use clipboard::{ClipboardContext, ClipboardProvider};
-use std::fs::File;
-use std::io::{BufReader, BufRead};
-use auto_lock::AutoLockManager;
-use biometric::BiometricManager;
+use hiho::password_generator::{generate_password, generate_secure_password};
+use hiho::strength::{display_crack_time, estimate_strength};
+use hiho::audit::{audit_entries, audit_entries_with_breaches, AuditConfig, AuditReport, Severity};
+use hiho::breach::{check_entries, open_source};
+use hiho::export::{self, ExportField, ExportKey};
+use hiho::importers::{self, ImportFormat, ImportResult};
+use hiho::search::SearchQuery;
+use hiho::merge::{MergeAction, MergeReport, MergeStrategy};
+use hiho::AutoLockManager;
+use hiho::autotype::{self, RecordingSink, Sequence};
+use hiho::clipboard_backend::Selection;
+use hiho::clipboard_manager::{self, ClipboardManager};
+use hiho::BiometricManager;
+use output::{CliError, OutputFormat};
+use serde_json::json;
+use std::process::ExitCode;
+use zeroize::Zeroizing;
 
-const VAULT_FILE: &str = "data\\vault.enc";
+/// Имя, под которым git ищет помощника для `credential.helper = hiho`
+const GIT_HELPER_NAME: &str = "git-credential-hiho";
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let cli = Cli::parse();
-    
-    if !matches!(cli.command, Commands::Unlock | Commands::Init) {
-        // Проверяем автоматическую блокировку
-        match AutoLockManager::should_lock() {
-            Ok(true) => {
-                AutoLockManager::lock_session()?;
-                println!("🔒 Сессия автоматически заблокирована по таймеру");
-                println!("Используйте 'hiho unlock' для разблокировки");
-                return Ok(());
-            }
-            Ok(false) => {
-                // Обновляем активность если не нужно блокировать
-                let _ = AutoLockManager::update_activity();
-            }
-            Err(_) => {
-                // Ошибка проверки, но продолжаем
-                let _ = AutoLockManager::update_activity();
-            }
-        }
-        
-        // Проверяем принудительную блокировку
-        if AutoLockManager::is_locked() {
-            println!("🔒 Сессия заблокирована. Используйте 'hiho unlock' для разблокировки.");
-            return Ok(());
+/// Текст для человека. При `--output json|tsv` уходит в stderr, чтобы в stdout
+/// был только машиночитаемый результат.
+macro_rules! say {
+    ($output:expr $(, $($arg:tt)+)?) => {
+        if $output == OutputFormat::Plain {
+            println!($($($arg)+)?)
+        } else {
+            eprintln!($($($arg)+)?)
         }
+    };
+}
+
+fn main() -> ExitCode {
+    if std::env::args().nth(1).as_deref() == Some(clipboard_manager::GUARD_ARG) {
+        return match ClipboardManager::run_guard() {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(_) => ExitCode::FAILURE,
+        };
     }
     
+    let mut args: Vec<std::ffi::OsString> = std::env::args_os().collect();
+    // Ссылка git-credential-hiho позволяет указать просто `credential.helper = hiho`
+    if args.first().and_then(|arg0| Path::new(arg0).file_stem()) == Some(GIT_HELPER_NAME.as_ref()) {
+        args.insert(1, "git-credential".into());
+    }
+    let cli = Cli::parse_from(args);
+    match run(&cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => output::report_error(error.as_ref(), cli.output),
+    }
+}
+
+fn run(cli: &Cli) -> Result<(), Box<dyn Error>> {
+    let vault_file = cli.vault.as_str();
+    let output = cli.output;
+    
     match &cli.command {
         Commands::Init => {
-            let vault_path = Path::new(VAULT_FILE);
+            let vault_path = Path::new(vault_file);
             
             // Проверяем, существует ли уже хранилище
             if vault_path.exists() {
-                println!("❌ Хранилище уже существует!");
-                println!("Используйте существующее хранилище или удалите файл {} для создания нового", VAULT_FILE);
-                return Ok(());
+                return Err(format!(
+                    "Хранилище уже существует! Используйте существующее хранилище или удалите файл {} для создания нового",
+                    vault_file
+                ).into());
             }
             
-            println!("🔐 Инициализация хранилища hiho...");
-            let password = rpassword::prompt_password("Введите мастер-пароль: ")?;
+            say!(output, "🔐 Инициализация хранилища hiho...");
+            let password = unlock::master_password(&cli.password, "Введите мастер-пароль: ")?;
             let vault = Vault::new(&password)?;
             
             std::fs::create_dir_all("data")?;
+            if let Some(parent) = vault_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                std::fs::create_dir_all(parent)?;
+            }
             vault.save_to_file(vault_path)?;
-            println!("✅ Хранилище создано!");
+            say!(output, "✅ Хранилище создано!");
+            output::print_status(output, "ok", json!({ "vault": vault_file }))?;
         }
-        Commands::Add { name, username, password, length } => {
-            let master_password = rpassword::prompt_password("Введите мастер-пароль: ")?;
-            let mut vault = Vault::new(&master_password)?;
-            
-            if Path::new(VAULT_FILE).exists() {
-                vault.load_from_file(Path::new(VAULT_FILE))?;
+        Commands::Add { name, username, password, length, url, notes, folder, tags, totp, auto_type } => {
+            if let Some(sequence) = auto_type {
+                Sequence::parse(sequence).map_err(|e| CliError::usage(e.to_string()))?;
             }
+            let mut vault = unlock::unlock_vault(&cli.password, vault_file)?;
             
             let final_password = match password {
                 Some(p) => p.clone(),
                 None => {
-                    println!("Генерируем пароль длиной {} символов...", length);
+                    eprintln!("Генерируем пароль длиной {} символов...", length);
                     generate_secure_password(*length)
                 }
             };
             
-            let entry = Entry {
-                name: name.clone(),
-                username: username.clone(),
-                password: final_password,
-            };
+            let mut entry = Entry::new(
+                name.clone(),
+                username.clone(),
+                final_password,
+            );
+            entry.urls = url.clone();
+            entry.notes = notes.clone().unwrap_or_default();
+            entry.folder = folder.clone();
+            entry.tags = tags.clone();
+            entry.totp = totp.clone();
+            entry.auto_type = auto_type.clone();
+            let id = entry.id.clone();
             
             vault.add_entry(entry);
-            vault.save_to_file(Path::new(VAULT_FILE))?;
-            println!("✅ Запись добавлена!");
+            vault.save()?;
+            say!(output, "✅ Запись добавлена!");
+            output::print_status(output, "ok", json!({ "id": id, "index": vault.get_entries().len() }))?;
         }
         
-        Commands::List => {
-            let master_password = rpassword::prompt_password("Введите мастер-пароль: ")?;
-            let mut vault = Vault::new(&master_password)?;
-            
-            if Path::new(VAULT_FILE).exists() {
-                vault.load_from_file(Path::new(VAULT_FILE))?;
-            }
+        Commands::List { show_passwords } => {
+            let vault = unlock::unlock_vault(&cli.password, vault_file)?;
             
             let entries = vault.get_entries();
+            if output != OutputFormat::Plain {
+                let all: Vec<(usize, &Entry)> = entries.iter().enumerate().collect();
+                return output::print_entries(output, &all, *show_passwords);
+            }
             if entries.is_empty() {
                 println!("📭 Хранилище пусто!");
                 return Ok(());
@@ -116,7 +139,7 @@ fn main() -> Result<(), Box<dyn Error>> {
             
             println!("📋 Ваши записи:");
             for (i, entry) in entries.iter().enumerate() {
-                println!("{}. {}: {} - {}", i+1, entry.name, entry.username, entry.password);
+                println!("{}. {}: {} - {}", i+1, entry.name, entry.username, display_password(&entry.password, *show_passwords));
             }
         }
         
@@ -126,89 +149,229 @@ fn main() -> Result<(), Box<dyn Error>> {
             } else {
                 generate_password(*length)
             };
+            if output != OutputFormat::Plain {
+                return output::print_generated(output, &password);
+            }
             println!("🔐 Сгенерированный пароль: {}", password);
         }
         
-        Commands::Copy { name_or_index } => {
-            let master_password = rpassword::prompt_password("Введите мастер-пароль: ")?;
-            let mut vault = Vault::new(&master_password)?;
+        Commands::Strength { user_inputs } => {
+            let password = unlock::secret_input("Введите пароль для проверки: ")?;
+            let user_inputs: Vec<&str> = user_inputs.iter().map(String::as_str).collect();
+            let report = estimate_strength(&password, &user_inputs);
+            if output != OutputFormat::Plain {
+                return output::print_strength(output, &report);
+            }
+            
+            println!("🛡️  Оценка: {} ({}/4)", report.score.label(), report.score as u8);
+            println!("   Энтропия: {:.1} бит (≈10^{:.1} попыток)", report.entropy_bits, report.guesses_log10);
+            println!("   Онлайн-атака с ограничениями: {}", display_crack_time(report.crack_times.online_throttled));
+            println!("   Офлайн-атака, медленный хеш: {}", display_crack_time(report.crack_times.offline_slow_hashing));
+            println!("   Офлайн-атака, быстрый хеш: {}", display_crack_time(report.crack_times.offline_fast_hashing));
+            
+            if let Some(warning) = &report.feedback.warning {
+                println!("⚠️  {}", warning);
+            }
+            for suggestion in &report.feedback.suggestions {
+                println!("💡 {}", suggestion);
+            }
+        }
+        
+        Commands::Audit { max_age_days, format, breach_db } => {
+            let output = output::with_legacy_format(output, format.as_deref())?;
+            let vault = unlock::unlock_vault(&cli.password, vault_file)?;
             
-            if Path::new(VAULT_FILE).exists() {
-                vault.load_from_file(Path::new(VAULT_FILE))?;
+            let config = AuditConfig {
+                max_age_days: *max_age_days,
+                ..AuditConfig::default()
+            };
+            let report = match breach_db {
+                Some(db) => {
+                    let mut source = open_source(Path::new(db))?;
+                    audit_entries_with_breaches(vault.get_entries(), &config, source.as_mut())?
+                }
+                None => audit_entries(vault.get_entries(), &config),
+            };
+            
+            match output {
+                OutputFormat::Plain => print_audit_report(&report),
+                _ => output::print_audit(output, &report)?,
             }
+        }
+        
+        Commands::BreachCheck { db, format } => {
+            let output = output::with_legacy_format(output, format.as_deref())?;
+            // Открываем базу до запроса пароля, чтобы сразу сообщить об ошибке пути
+            let mut source = open_source(Path::new(db))?;
+            
+            let vault = unlock::unlock_vault(&cli.password, vault_file)?;
             
             let entries = vault.get_entries();
-            if entries.is_empty() {
-                println!("📭 Хранилище пусто!");
-                return Ok(());
+            let results = check_entries(entries, source.as_mut())?;
+            
+            match output {
+                OutputFormat::Plain => {
+                    if results.is_empty() {
+                        println!("✅ Ни один из {} паролей не найден в базе утечек", entries.len());
+                    } else {
+                        println!("🚨 Найдено в утечках: {} из {}", results.len(), entries.len());
+                        for r in &results {
+                            let entry = &entries[r.index];
+                            println!("{}. {}: {} — встречался {} раз", r.index + 1, entry.name, entry.username, r.count);
+                        }
+                        println!("💡 Смените эти пароли как можно скорее");
+                    }
+                }
+                _ => output::print_breaches(output, entries, &results)?,
             }
+        }
+        
+        Commands::Copy { name_or_index, field, sequence, primary } => {
+            let vault = unlock::unlock_vault(&cli.password, vault_file)?;
             
-            if let Some(entry) = find_entry(&vault, name_or_index)? {
-                copy_to_clipboard(&entry.password)?;
-                println!("✅ Пароль для '{}' скопирован в буфер обмена!", entry.name);
+            let entry = find_entry(&vault, name_or_index)?.ok_or_else(|| entry_not_found(name_or_index))?;
+            let selection = primary.then_some(Selection::Primary);
+            let field_name = field.as_deref().unwrap_or("password");
+            let clear_after = if *sequence {
+                copy_login_sequence(output, entry, selection)?
             } else {
-                println!("❌ Запись '{}' не найдена!", name_or_index);
-            }
+                let value = Zeroizing::new(entry.copy_value(field_name)?.ok_or_else(|| {
+                    CliError::not_found(format!("У записи '{}' нет поля '{}'", entry.name, field_name))
+                })?);
+                let clear_after = copy_to_clipboard(&value, selection)?;
+                match field {
+                    None => say!(output, "✅ Пароль для '{}' скопирован в буфер обмена!", entry.name),
+                    Some(field) => say!(output, "✅ Поле '{}' записи '{}' скопировано в буфер обмена!", field, entry.name),
+                }
+                print_clipboard_clear(output, clear_after);
+                clear_after
+            };
+            output::print_status(output, "ok", json!({
+                "id": entry.id,
+                "field": if *sequence { "sequence" } else { field_name },
+                "clear_after_secs": clear_after.map(|after| after.as_secs()),
+            }))?;
         }
         
-        Commands::Remove { name_or_index } => {
-            let master_password = rpassword::prompt_password("Введите мастер-пароль: ")?;
-            let mut vault = Vault::new(&master_password)?;
+        Commands::AutoType { name_or_index, sequence, delay, dry_run } => {
+            let vault = unlock::unlock_vault(&cli.password, vault_file)?;
             
-            if Path::new(VAULT_FILE).exists() {
-                vault.load_from_file(Path::new(VAULT_FILE))?;
+            let entry = find_entry(&vault, name_or_index)?.ok_or_else(|| entry_not_found(name_or_index))?;
+            let parsed = match sequence {
+                Some(sequence) => Sequence::parse(sequence),
+                None => autotype::sequence_for(entry),
             }
+            .map_err(|e| CliError::usage(e.to_string()))?;
+            let actions = parsed.resolve(entry)?;
             
-            let entries = vault.get_entries();
-            if entries.is_empty() {
-                println!("📭 Хранилище пусто!");
+            if *dry_run {
+                let mut recorder = RecordingSink::new();
+                autotype::run(&actions, &mut recorder)?;
+                println!("⌨️  Последовательность: {}", parsed);
+                println!(
+                    "   Нажатий: {}, длительность: ~{} мс",
+                    recorder.key_count(),
+                    recorder.total_pause().as_millis()
+                );
                 return Ok(());
             }
             
-            if let Some((index, entry)) = find_entry_with_index(&vault, name_or_index)? {
-                println!("🗑️  Удалить запись: {} - {}?", entry.name, entry.username);
-                println!("Введите 'y' для подтверждения:");
-                
-                let mut input = String::new();
-                std::io::stdin().read_line(&mut input)?;
-                
-                if input.trim().to_lowercase() == "y" {
-                    vault.remove_entry(index);
-                    vault.save_to_file(Path::new(VAULT_FILE))?;
-                    println!("✅ Запись удалена!");
-                } else {
-                    println!("❌ Удаление отменено");
+            let mut keyboard = autotype::open()?;
+            println!("⌨️  Переключитесь в нужное окно: набор начнётся через {} с...", delay);
+            std::thread::sleep(std::time::Duration::from_secs(*delay));
+            autotype::run(&actions, keyboard.as_mut())?;
+            println!("✅ Набрано: {}", entry.name);
+        }
+        
+        Commands::Show { name_or_index, field } => {
+            let vault = unlock::unlock_vault(&cli.password, vault_file)?;
+            
+            let (index, entry) = find_entry_with_index(&vault, name_or_index)?
+                .ok_or_else(|| entry_not_found(name_or_index))?;
+            
+            match field {
+                Some(field) => {
+                    let value = entry.field_value(field).ok_or_else(|| {
+                        CliError::not_found(format!("У записи '{}' нет поля '{}'", entry.name, field))
+                    })?;
+                    match output {
+                        // Только значение, без оформления, чтобы его можно было передать дальше
+                        OutputFormat::Plain => println!("{}", value),
+                        _ => output::print_field(output, index, field, &value)?,
+                    }
                 }
-            } else {
-                println!("❌ Запись '{}' не найдена!", name_or_index);
+                None if output != OutputFormat::Plain => output::print_entry(output, index, entry)?,
+                None => print_entry_details(index, entry),
             }
         }
         
+        Commands::Remove { name_or_index } => {
+            let mut vault = unlock::unlock_vault(&cli.password, vault_file)?;
+            
+            let (index, entry) = find_entry_with_index(&vault, name_or_index)?
+                .ok_or_else(|| entry_not_found(name_or_index))?;
+            let id = entry.id.clone();
+            say!(output, "🗑️  Удалить запись: {} - {}?", entry.name, entry.username);
+            say!(output, "Введите 'y' для подтверждения:");
+            
+            let mut input = String::new();
+            std::io::stdin().read_line(&mut input)?;
+            
+            let status = if input.trim().to_lowercase() == "y" {
+                vault.remove_entry(index);
+                vault.save()?;
+                say!(output, "✅ Запись удалена!");
+                "ok"
+            } else {
+                say!(output, "❌ Удаление отменено");
+                "cancelled"
+            };
+            output::print_status(output, status, json!({ "id": id, "index": index + 1 }))?;
+        }
+        
+        Commands::Remember { action } => {
+            run_remember(action, cli)?;
+        }
+
+        Commands::SecondFactor { action } => {
+            run_second_factor(action, cli)?;
+        }
+
         Commands::Biometric { enable, disable, status, test } => {
+                let path = std::path::absolute(vault_file)?;
                 if *status {
                     let config = BiometricManager::get_config()?;
+                    let remembered = hiho::remember::RememberManager::find(&path)?.is_some_and(|key| key.biometric);
                     println!("📊 Статус биометрической аутентификации:");
                     println!("   Включена: {}", if config.enabled { "✅ Да" } else { "❌ Нет" });
-                    println!("   Платформа: {}", config.platform);
-                    println!("   Доступна: {}", if BiometricManager::is_available() { "✅ Да" } else { "❌ Нет" });
+                    match BiometricManager::authenticator() {
+                        Ok(authenticator) => println!(
+                            "   Сканер ({}): {}",
+                            authenticator.describe(),
+                            if authenticator.is_available() { "✅ готов" } else { "❌ нет записанных отпечатков" }
+                        ),
+                        Err(e) => println!("   Сканер: ❌ {}", e),
+                    }
+                    println!("   Ключ {}: {}", path.display(), if remembered { "✅ выдаётся по отпечатку" } else { "❌ не запомнен" });
                 } else if *enable {
                     if BiometricManager::is_available() {
-                        match BiometricManager::enable_biometric() {
+                        let key = checked_vault_key(cli, &path)?;
+                        match BiometricManager::enable_biometric(&path, &key) {
                             Ok(_) => {
                                 println!("✅ Биометрическая аутентификация включена");
-                                println!("💡 Теперь вы можете использовать биометрию для входа");
+                                println!("💡 {} открывается по отпечатку без мастер-пароля", path.display());
                             }
                             Err(e) => {
                                 println!("❌ Ошибка включения биометрии: {}", e);
                             }
                         }
                     } else {
-                        println!("❌ Биометрическая аутентификация недоступна на этой платформе");
+                        println!("❌ Биометрическая аутентификация недоступна: нужен fprintd и записанный отпечаток");
                     }
                 } else if *disable {
-                    match BiometricManager::disable_biometric() {
+                    match BiometricManager::disable_biometric(&path) {
                         Ok(_) => {
-                            println!("🔓 Биометрическая аутентификация отключена");
+                            println!("🔓 Биометрическая аутентификация отключена, ключ хранилища забыт");
                         }
                         Err(e) => {
                             println!("❌ Ошибка отключения биометрии: {}", e);
@@ -232,49 +395,45 @@ fn main() -> Result<(), Box<dyn Error>> {
                         println!("❌ Биометрическая аутентификация недоступна");
                     }
                 } else {
-                    println!("❌ Укажите один из параметров: --enable, --disable, --status, --test");
+                    return Err(CliError::usage("Укажите один из параметров: --enable, --disable, --status, --test").into());
                 }
             }
 
-        Commands::Search { query } => {
-            let master_password = rpassword::prompt_password("Введите мастер-пароль: ")?;
-            let mut vault = Vault::new(&master_password)?;
-            
-            if Path::new(VAULT_FILE).exists() {
-                vault.load_from_file(Path::new(VAULT_FILE))?;
+        Commands::Search { query, show_passwords, filter } => {
+            let query_text = query.join(" ");
+            let search_query = SearchQuery::parse(&query_text)?;
+            let filter = filter.to_filter()?;
+            if search_query.is_empty() && filter.is_empty() {
+                return Err(CliError::usage("Укажите запрос или хотя бы один фильтр").into());
             }
             
-            let entries = vault.get_entries();
-            let results: Vec<(usize, &Entry)> = entries
-                .iter()
-                .enumerate()
-                .filter(|(_, entry)| {
-                    entry.name.to_lowercase().contains(&query.to_lowercase())
-                })
-                .collect();
+            let vault = unlock::unlock_vault(&cli.password, vault_file)?;
+            
+            let results = search_query.rank(filter.apply(vault.get_entries()));
+            if output != OutputFormat::Plain {
+                return output::print_entries(output, &results, *show_passwords);
+            }
                 
             if results.is_empty() {
-                println!("🔍 Ничего не найдено по запросу '{}'", query);
+                println!("🔍 Ничего не найдено по запросу '{}'", query_text);
             } else {
                 println!("🔍 Найдено {} записей:", results.len());
-                for (i, (_, entry)) in results.iter().enumerate() {
-                    println!("{}. {}: {} - {}", i+1, entry.name, entry.username, entry.password);
+                for (i, (index, entry)) in results.iter().enumerate() {
+                    println!("{}. {}: {} - {} (№{})", i+1, entry.name, entry.username, display_password(&entry.password, *show_passwords), index + 1);
                 }
             }
         }
         
-        Commands::Edit { name_or_index, username, password, length } => {
-            let master_password = rpassword::prompt_password("Введите мастер-пароль: ")?;
-            let mut vault = Vault::new(&master_password)?;
-            
-            if Path::new(VAULT_FILE).exists() {
-                vault.load_from_file(Path::new(VAULT_FILE))?;
+        Commands::Edit { name_or_index, username, password, length, auto_type } => {
+            if let Some(sequence) = auto_type.as_deref().filter(|s| !s.is_empty()) {
+                Sequence::parse(sequence).map_err(|e| CliError::usage(e.to_string()))?;
             }
+            let mut vault = unlock::unlock_vault(&cli.password, vault_file)?;
             
             let entries = vault.get_entries();
             if entries.is_empty() {
-                println!("📭 Хранилище пусто!");
-                return Ok(());
+                say!(output, "📭 Хранилище пусто!");
+                return output::print_status(output, "empty", json!({}));
             }
             
             let mut found_index = None;
@@ -296,7 +455,8 @@ fn main() -> Result<(), Box<dyn Error>> {
             
             if let Some(index) = found_index {
                 let entry = &entries[index];
-                println!("✏️  Редактирование записи: {} - {}", entry.name, entry.username);
+                let id = entry.id.clone();
+                say!(output, "✏️  Редактирование записи: {} - {}", entry.name, entry.username);
                 
                 let new_username = username.clone().unwrap_or_else(|| entry.username.clone());
                 let new_password = match password {
@@ -304,7 +464,7 @@ fn main() -> Result<(), Box<dyn Error>> {
                     None => {
                         if username.is_some() || password.is_some() {
                             // Если указаны параметры, но не пароль, генерируем
-                            println!("Генерируем новый пароль длиной {} символов...", length);
+                            eprintln!("Генерируем новый пароль длиной {} символов...", length);
                             generate_secure_password(*length)
                         } else {
                             entry.password.clone()
@@ -312,172 +472,641 @@ fn main() -> Result<(), Box<dyn Error>> {
                     }
                 };
                 
-                match vault.edit_entry(index, Some(new_username), Some(new_password)) {
+                let edited = vault.edit_entry(index, Some(new_username), Some(new_password)).and_then(|_| match auto_type {
+                    Some(sequence) => vault.set_auto_type(index, Some(sequence.clone()).filter(|s| !s.is_empty())),
+                    None => Ok(()),
+                });
+                match edited {
                     Ok(_) => {
-                        vault.save_to_file(Path::new(VAULT_FILE))?;
-                        println!("✅ Запись обновлена!");
+                        vault.save()?;
+                        say!(output, "✅ Запись обновлена!");
+                        output::print_status(output, "ok", json!({ "id": id, "index": index + 1 }))?;
                     }
                     Err(e) => {
-                        println!("❌ Ошибка редактирования: {}", e);
+                        return Err(format!("Ошибка редактирования: {}", e).into());
                     }
                 }
             } else {
-                println!("❌ Запись '{}' не найдена!", name_or_index);
+                return Err(entry_not_found(name_or_index).into());
             }
         }
         
-        Commands::Export { file, format } => {
-            let master_password = rpassword::prompt_password("Введите мастер-пароль: ")?;
-            let mut vault = Vault::new(&master_password)?;
+        Commands::Export { file, format, key_file, plaintext, fields, filter } => {
+            let is_plaintext = match format.as_str() {
+                "encrypted" => false,
+                "json" | "csv" => true,
+                _ => {
+                    return Err(CliError::usage(format!("Неподдерживаемый формат: {}", format)).into());
+                }
+            };
+            let filter = filter.to_filter()?;
+            let fields = if fields.is_empty() {
+                ExportField::ALL.to_vec()
+            } else {
+                ExportField::parse_list(fields)?
+            };
+            if is_plaintext && !*plaintext {
+                return Err(CliError::usage(format!(
+                    "В формате {} пароли сохраняются без шифрования. Добавьте --plaintext, если это действительно нужно, или используйте --format encrypted",
+                    format
+                )).into());
+            }
             
-            if Path::new(VAULT_FILE).exists() {
-                vault.load_from_file(Path::new(VAULT_FILE))?;
+            let vault = unlock::unlock_vault(&cli.password, vault_file)?;
+            
+            if vault.get_entries().is_empty() {
+                say!(output, "📭 Хранилище пусто!");
+                return output::print_status(output, "empty", json!({ "entries": 0 }));
             }
             
-            let entries = vault.get_entries();
+            let entries: Vec<Entry> = filter
+                .apply(vault.get_entries())
+                .into_iter()
+                .map(|(_, entry)| export::select_fields(entry, &fields))
+                .collect();
             if entries.is_empty() {
-                println!("📭 Хранилище пусто!");
-                return Ok(());
+                say!(output, "🔍 Ни одна запись не подходит под фильтр — экспорт не создан");
+                return output::print_status(output, "empty", json!({ "entries": 0 }));
             }
             
-            match format.as_str() {
-                "json" => {
-                    let json_data = serde_json::to_string_pretty(entries)?;
-                    std::fs::write(file, json_data)?;
-                    println!("✅ Данные экспортированы в {} ({} записей)", file, entries.len());
-                }
-                "csv" => {
-                    let mut csv_data = String::new();
-                    csv_data.push_str("name,username,password\n");
-                    for entry in entries {
-                        csv_data.push_str(&format!("{},{},{}\n", 
-                            escape_csv(&entry.name), 
-                            escape_csv(&entry.username), 
-                            escape_csv(&entry.password)
-                        ));
+            let file = match file {
+                Some(file) => file.clone(),
+                None if is_plaintext => format!("hiho_export.{}", format),
+                None => "hiho_export.enc".to_string(),
+            };
+            let path = Path::new(&file);
+            
+            if is_plaintext {
+                export::write_plaintext(path, format, &entries, &fields)?;
+                say!(output, "✅ Данные экспортированы в {} ({} записей)", file, entries.len());
+                say!(output, "⚠️  Файл содержит пароли в открытом виде — удалите его после использования");
+                return output::print_status(
+                    output,
+                    "ok",
+                    json!({ "file": file, "format": format, "entries": entries.len(), "encrypted": false }),
+                );
+            }
+            
+            let key = match key_file {
+                Some(key_file) => {
+                    let (data, created) = export::load_or_create_key_file(Path::new(key_file))?;
+                    if created {
+                        say!(output, "🔑 Создан ключевой файл {} — храните его отдельно от экспорта", key_file);
                     }
-                    std::fs::write(file, csv_data)?;
-                    println!("✅ Данные экспортированы в {} ({} записей)", file, entries.len());
+                    ExportKey::KeyFile(data)
                 }
-                _ => {
-                    println!("❌ Неподдерживаемый формат: {}", format);
+                None => {
+                    let password = rpassword::prompt_password("Пароль экспорта (Enter — мастер-пароль): ")?;
+                    if password.is_empty() {
+                        // Хранилище могло открыться через агент или запомненный ключ,
+                        // поэтому мастер-пароль спрашиваем и проверяем отдельно
+                        let master_password = unlock::master_password(&cli.password, "Введите мастер-пароль: ")?;
+                        Vault::open(Path::new(vault_file), &master_password)?;
+                        ExportKey::Password(master_password.to_string())
+                    } else {
+                        let confirm = rpassword::prompt_password("Повторите пароль экспорта: ")?;
+                        if confirm != password {
+                            return Err(CliError::usage("Пароли не совпадают").into());
+                        }
+                        ExportKey::Password(password)
+                    }
                 }
+            };
+            
+            export::write_encrypted(path, &entries, &key)?;
+            say!(output, "🔐 Данные зашифрованы и экспортированы в {} ({} записей)", file, entries.len());
+            if matches!(key, ExportKey::Password(_)) {
+                say!(output, "Открыть как хранилище: hiho --vault {} list", file);
             }
+            output::print_status(
+                output,
+                "ok",
+                json!({ "file": file, "format": format, "entries": entries.len(), "encrypted": true }),
+            )?;
         }
         
-        Commands::Import { file, format } => {
-            if !Path::new(file).exists() {
-                println!("❌ Файл {} не найден!", file);
-                return Ok(());
+        Commands::Import { file, format, dry_run, key_file, on_duplicate } => {
+            let path = Path::new(file);
+            if !path.exists() {
+                return Err(CliError::not_found(format!("Файл {} не найден!", file)).into());
             }
             
-            let master_password = rpassword::prompt_password("Введите мастер-пароль: ")?;
-            let mut vault = Vault::new(&master_password)?;
-            
-            // Загружаем существующие данные если есть
-            if Path::new(VAULT_FILE).exists() {
-                vault.load_from_file(Path::new(VAULT_FILE))?;
-            }
-            
-            match format.as_str() {
-                "json" => {
-                    let file_content = std::fs::read_to_string(file)?;
-                    let imported_entries: Vec<Entry> = serde_json::from_str(&file_content)?;
-                    let count = imported_entries.len();
-                    
-                    for entry in imported_entries {
-                        vault.add_entry(entry);
+            let format = if format == "auto" {
+                ImportFormat::detect(path)?
+            } else {
+                match ImportFormat::parse(format) {
+                    Some(format) => format,
+                    None => {
+                        let known: Vec<&str> = ImportFormat::ALL.iter().map(|f| f.as_str()).collect();
+                        return Err(CliError::usage(format!(
+                            "Неподдерживаемый формат: {} (доступны: auto, {})",
+                            format,
+                            known.join(", ")
+                        )).into());
                     }
-                    
-                    vault.save_to_file(Path::new(VAULT_FILE))?;
-                    println!("✅ Импортировано {} записей из {}", count, file);
                 }
-                "csv" => {
-                    let file_handle = File::open(file)?;
-                    let reader = BufReader::new(file_handle);
-                    let mut entries_count = 0;
-                    
-                    for (line_num, line) in reader.lines().enumerate() {
-                        let line = line?;
-                        if line_num == 0 {
-                            // Пропускаем заголовок
-                            continue;
-                        }
-                        
-                        let fields: Vec<&str> = line.split(',').collect();
-                        if fields.len() >= 3 {
-                            let entry = Entry {
-                                name: unescape_csv(fields[0]),
-                                username: unescape_csv(fields[1]),
-                                password: unescape_csv(fields[2]),
-                            };
-                            vault.add_entry(entry);
-                            entries_count += 1;
-                        }
-                    }
-                    
-                    vault.save_to_file(Path::new(VAULT_FILE))?;
-                    println!("✅ Импортировано {} записей из {}", entries_count, file);
+            };
+            
+            let Some(strategy) = MergeStrategy::parse(on_duplicate) else {
+                return Err(CliError::usage(format!(
+                    "Неизвестная стратегия: {} (доступны: skip, overwrite, keep-both, newest)",
+                    on_duplicate
+                )).into());
+            };
+            
+            let key = match key_file {
+                Some(key_file) => Some(ExportKey::KeyFile(std::fs::read(key_file)?)),
+                None if importers::needs_password(path, format)? => {
+                    Some(ExportKey::Password(rpassword::prompt_password("Введите пароль экспорта: ")?))
                 }
-                _ => {
-                    println!("❌ Неподдерживаемый формат: {}", format);
+                None => None,
+            };
+            let import = importers::import_file(path, format, key.as_ref())?;
+            
+            if *dry_run {
+                print_import_preview(output, &import, format);
+                return output::print_status(
+                    output,
+                    "dry_run",
+                    json!({ "format": format.as_str(), "entries": import.entries.len(), "warnings": import.warnings }),
+                );
+            }
+            
+            let mut vault = unlock::unlock_vault(&cli.password, vault_file)?;
+            
+            let report = vault.merge_entries(import.entries, strategy);
+            if report.has_changes() {
+                vault.save()?;
+            }
+            say!(output, "✅ Импорт из {} ({}), дубликаты: {}", file, format.title(), strategy.as_str());
+            print_merge_report(output, &report);
+            
+            if !import.warnings.is_empty() {
+                say!(output, "⚠️  Предупреждения: {}", import.warnings.len());
+                for warning in &import.warnings {
+                    say!(output, "   {}", warning);
                 }
             }
+            output::print_status(
+                output,
+                "ok",
+                json!({
+                    "format": format.as_str(),
+                    "added": report.added(),
+                    "updated": report.updated(),
+                    "skipped": report.skipped(),
+                    "warnings": import.warnings,
+                }),
+            )?;
         }
         
-        Commands::AutoLock { timeout, show } => {
+        Commands::AutoLock { timeout, show, on_sleep, on_screen_lock } => {
             if *show {
                 let config = AutoLockManager::get_config()?;
                 match config.timeout_minutes {
-                    Some(minutes) => {
+                    Some(minutes) if minutes > 0 => {
                         println!("⏰ Автоблокировка включена: {} минут", minutes);
                     }
-                    None => {
-                        println!("🔓 Автоблокировка отключена");
+                    _ => {
+                        println!("🔓 Автоблокировка по времени отключена");
                     }
                 }
-            } else if let Some(minutes) = timeout {
-                AutoLockManager::set_timeout(Some(*minutes))?;
-                if *minutes == 0 {
-                    println!("🔓 Автоблокировка отключена");
-                } else {
-                    println!("✅ Автоблокировка установлена на {} минут", minutes);
+                println!("   😴 При переходе в сон: {}", if config.lock_on_sleep { "да" } else { "нет" });
+                println!("   🖥️  При блокировке экрана: {}", if config.lock_on_screen_lock { "да" } else { "нет" });
+            } else if timeout.is_some() || on_sleep.is_some() || on_screen_lock.is_some() {
+                let mut config = AutoLockManager::get_config()?;
+                if let Some(minutes) = timeout {
+                    config.timeout_minutes = Some(*minutes);
+                    if *minutes == 0 {
+                        println!("🔓 Автоблокировка по времени отключена");
+                    } else {
+                        println!("✅ Автоблокировка установлена на {} минут", minutes);
+                    }
                 }
+                if let Some(enabled) = on_sleep {
+                    config.lock_on_sleep = *enabled;
+                }
+                if let Some(enabled) = on_screen_lock {
+                    config.lock_on_screen_lock = *enabled;
+                }
+                AutoLockManager::save_config(&config)?;
             } else {
-                println!("❌ Укажите --timeout или --show");
+                return Err(CliError::usage("Укажите --timeout, --on-sleep, --on-screen-lock или --show").into());
             }
         }
         
-        Commands::Unlock => {
-            if AutoLockManager::is_locked() {
-                let password = rpassword::prompt_password("Введите мастер-пароль для разблокировки: ")?;
-                // Простая проверка правильности пароля
-                let _vault = Vault::new(&password)?;
-                
-                if Path::new(VAULT_FILE).exists() {
-                    let mut test_vault = Vault::new(&password)?;
-                    match test_vault.load_from_file(Path::new(VAULT_FILE)) {
-                        Ok(_) => {
-                            AutoLockManager::unlock()?;
-                            println!("✅ Сессия разблокирована!");
-                        }
-                        Err(_) => {
-                            println!("❌ Неверный пароль");
-                        }
-                    }
-                } else {
-                    AutoLockManager::unlock()?;
-                    println!("✅ Сессия разблокирована!");
+        Commands::Clipboard { clear_after, restore, selection, show } => {
+            let mut config = ClipboardManager::get_config()?;
+            if *show {
+                match config.clear_after() {
+                    Some(after) => println!("🧹 Очистка буфера обмена через {} с", after.as_secs()),
+                    None => println!("📋 Очистка буфера обмена отключена"),
                 }
+                println!("   Восстанавливать прежнее содержимое: {}", if config.restore_previous { "да" } else { "нет" });
+                println!("   Буфер: {}", config.selection.as_str());
+            } else if clear_after.is_some() || restore.is_some() || selection.is_some() {
+                if let Some(secs) = clear_after {
+                    config.clear_after_secs = Some(*secs);
+                }
+                if let Some(restore) = restore {
+                    config.restore_previous = *restore;
+                }
+                if let Some(selection) = selection {
+                    config.selection = Selection::parse(selection).map_err(|e| CliError::usage(e.to_string()))?;
+                }
+                ClipboardManager::save_config(&config)?;
+                println!("✅ Настройки буфера обмена обновлены");
             } else {
-                println!("🔓 Сессия не заблокирована");
+                return Err(CliError::usage("Укажите --clear-after, --restore, --selection или --show").into());
             }
         }
+        
+        Commands::GitCredential { operation } => {
+            run_git_credential(operation, vault_file)?;
+        }
+        
+        Commands::SecretService { folder } => {
+            run_secret_service(folder, vault_file)?;
+        }
+        
+        Commands::Ssh { action } => {
+            run_ssh(action, cli)?;
+        }
+        
+        Commands::Agent { action } => {
+            run_agent(action, cli)?;
+        }
+        
+        Commands::Unlock => {
+            run_agent(&AgentAction::Unlock, cli)?;
+        }
+        
+        Commands::Lock => {
+            run_agent(&AgentAction::Lock, cli)?;
+        }
     }
     
     Ok(())
 }
 
+#[cfg(unix)]
+fn run_agent(action: &AgentAction, cli: &Cli) -> Result<(), Box<dyn Error>> {
+    use hiho::agent::{self, AgentClient};
+
+    let socket = agent::socket_path();
+    match action {
+        AgentAction::Run => agent::server::run(&socket)?,
+        AgentAction::Start => {
+            if AgentClient::connect().is_some() {
+                return Err(format!("Агент уже запущен: {}", socket.display()).into());
+            }
+            spawn_agent()?;
+            // Ждём, пока агент создаст сокет
+            let mut client = None;
+            for _ in 0..50 {
+                std::thread::sleep(std::time::Duration::from_millis(100));
+                client = AgentClient::connect();
+                if client.is_some() {
+                    break;
+                }
+            }
+            let status = client.ok_or("Агент не запустился")?.status()?;
+            println!("✅ Агент запущен (PID {})", status.pid);
+            println!("   Сокет: {}", socket.display());
+            if let Some(ssh_socket) = &status.ssh_socket {
+                println!("🔑 Для SSH: export SSH_AUTH_SOCK={}", ssh_socket.display());
+            }
+        }
+        AgentAction::Stop => {
+            agent_client()?.stop()?;
+            println!("✅ Агент остановлен, хранилище закрыто");
+        }
+        AgentAction::Unlock => {
+            let mut client = agent_client()?;
+            let path = std::path::absolute(&cli.vault)?;
+            unlock::unlock_agent(&cli.password, &mut client, &path, "Введите мастер-пароль: ")?;
+            println!("🔓 Хранилище открыто в агенте: {}", path.display());
+        }
+        AgentAction::Lock => match AgentClient::connect() {
+            Some(mut client) => {
+                client.lock()?;
+                println!("🔒 Хранилище в агенте закрыто");
+            }
+            None => println!("🔒 Агент не запущен: хранилище не хранится в памяти"),
+        },
+        AgentAction::Status => {
+            let status = agent_client()?.status()?;
+            println!("🤖 Агент запущен (PID {})", status.pid);
+            println!("   Сокет: {}", socket.display());
+            if let Some(ssh_socket) = &status.ssh_socket {
+                println!("   Сокет ssh-agent: {}", ssh_socket.display());
+            }
+            match &status.vault {
+                Some(vault) => println!("   🔓 Открыто хранилище: {}", vault.display()),
+                None => println!("   🔒 Хранилище не открыто"),
+            }
+            match (status.idle_timeout_secs, status.locks_in_secs) {
+                (Some(timeout), Some(left)) => {
+                    println!("   ⏰ Автоблокировка через {} с (таймаут {} мин)", left, timeout / 60)
+                }
+                (Some(timeout), None) => println!("   ⏰ Таймаут автоблокировки: {} мин", timeout / 60),
+                _ => println!("   Автоблокировка отключена"),
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn run_agent(_action: &AgentAction, _cli: &Cli) -> Result<(), Box<dyn Error>> {
+    Err("Агент поддерживается только в Unix".into())
+}
+
+// Хранилище открывает агент: сервис на шине живёт долго, а мастер-пароль
+// спрашивается только по запросу Unlock от клиента
+#[cfg(target_os = "linux")]
+fn run_secret_service(folder: &str, vault_file: &str) -> Result<(), Box<dyn Error>> {
+    use hiho::secret_service;
+
+    let path = std::path::absolute(vault_file)?;
+    if hiho::agent::AgentClient::connect().is_none() {
+        eprintln!("⚠️  Агент не запущен (hiho agent start): коллекция будет заблокирована");
+    }
+    eprintln!("🗝️  Secret Service: {} → папка «{}» хранилища {}", secret_service::BUS_NAME, folder, path.display());
+    secret_service::serve(path, folder.to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn run_secret_service(_folder: &str, _vault_file: &str) -> Result<(), Box<dyn Error>> {
+    Err("Secret Service работает через D-Bus и поддерживается только в Linux".into())
+}
+
+/// Ключ хранилища из мастер-пароля, проверенный открытием хранилища, —
+/// чтобы запомнить его (`remember`, `biometric`).
+fn checked_vault_key(cli: &Cli, path: &Path) -> Result<Zeroizing<[u8; 32]>, Box<dyn Error>> {
+    if hiho::kdbx::is_kdbx(path) {
+        return Err("Файл KeePass открывается только мастер-паролем и не может быть запомнен".into());
+    }
+    if !path.exists() {
+        return Err(format!("Хранилище {} ещё не создано", path.display()).into());
+    }
+    let password = unlock::master_password(&cli.password, "Введите мастер-пароль: ")?;
+    let key = Zeroizing::new(hiho::vault::derive_vault_key_for(path, &password)?);
+    Vault::open_with_key(path, *key)?;
+    Ok(key)
+}
+
+fn run_remember(action: &RememberAction, cli: &Cli) -> Result<(), Box<dyn Error>> {
+    use hiho::keystore;
+    use hiho::remember::RememberManager;
+
+    let path = std::path::absolute(&cli.vault)?;
+    match action {
+        RememberAction::Enable => {
+            let store = keystore::platform()?;
+            let key = checked_vault_key(cli, &path)?;
+            RememberManager::remember(store.as_ref(), &path, &key, false)?;
+            println!("✅ Ключ хранилища запомнен ({})", store.describe());
+            println!("💡 {} открывается без мастер-пароля на этом устройстве", path.display());
+        }
+        RememberAction::Disable => {
+            let store = keystore::platform()?;
+            if RememberManager::forget(store.as_ref(), &path)? {
+                println!("🔒 Ключ хранилища забыт: {} снова открывается только мастер-паролем", path.display());
+            } else {
+                println!("ℹ️  Хранилище {} не запомнено", path.display());
+            }
+        }
+        RememberAction::Status => match RememberManager::find(&path)? {
+            Some(remembered) => {
+                let store = keystore::platform()?;
+                let present = store.load(&remembered.key_id)?.is_some();
+                println!("📊 Хранилище {} запомнено", path.display());
+                println!("   С: {}", remembered.created_at.format("%Y-%m-%d %H:%M"));
+                println!(
+                    "   Ключ обёртки ({}): {}",
+                    store.describe(),
+                    if present { "✅ на месте" } else { "❌ не найден" }
+                );
+            }
+            None => println!("📊 Хранилище {} не запомнено (hiho remember enable)", path.display()),
+        },
+    }
+    Ok(())
+}
+
+fn run_second_factor(action: &SecondFactorAction, cli: &Cli) -> Result<(), Box<dyn Error>> {
+    use hiho::second_factor::{Device, SecondFactorManager};
+
+    let path = std::path::absolute(&cli.vault)?;
+    match action {
+        SecondFactorAction::Enable { slot } => {
+            let device = Device::YubiKey { slot: *slot };
+            let password = unlock::master_password(&cli.password, "Введите мастер-пароль: ")?;
+            lock_agent_vault(&path)?;
+            SecondFactorManager::enable(&path, &password, device)?;
+            println!("✅ Второй фактор включён: {}", device.describe());
+            println!("💡 Без него хранилище не открыть. Храните data\\second_factor.json вместе с хранилищем");
+            forget_remembered_key(&path);
+        }
+        SecondFactorAction::Disable => {
+            let password = unlock::master_password(&cli.password, "Введите мастер-пароль: ")?;
+            lock_agent_vault(&path)?;
+            SecondFactorManager::disable(&path, &password)?;
+            println!("🔓 Второй фактор выключен: {} открывается одним мастер-паролем", path.display());
+            forget_remembered_key(&path);
+        }
+        SecondFactorAction::Status => match SecondFactorManager::find(&path)? {
+            Some(factor) => {
+                println!("📊 Второй фактор для {}: {}", path.display(), factor.device.describe());
+                println!("   С: {}", factor.created_at.format("%Y-%m-%d %H:%M"));
+            }
+            None => println!("📊 Второй фактор для {} не включён", path.display()),
+        },
+    }
+    Ok(())
+}
+
+/// Закрывает хранилище в агенте перед сменой ключа: иначе агент сохранит
+/// его прежним ключом.
+#[cfg(unix)]
+fn lock_agent_vault(path: &Path) -> Result<(), Box<dyn Error>> {
+    if let Some(mut client) = hiho::agent::AgentClient::connect() {
+        if client.status()?.vault.as_deref() == Some(path) {
+            client.lock()?;
+            println!("🔒 Хранилище закрыто в агенте: его ключ меняется");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn lock_agent_vault(_path: &Path) -> Result<(), Box<dyn Error>> {
+    Ok(())
+}
+
+/// Запомненный ключ после смены ключа хранилища уже не подходит.
+fn forget_remembered_key(path: &Path) {
+    use hiho::remember::RememberManager;
+
+    if !matches!(RememberManager::find(path), Ok(Some(_))) {
+        return;
+    }
+    match hiho::keystore::platform().and_then(|store| RememberManager::forget(store.as_ref(), path)) {
+        Ok(_) => println!("🗝️  Запомненный ключ хранилища забыт: запомните его заново (hiho remember enable)"),
+        Err(e) => eprintln!("⚠️  Запомненный ключ больше не подходит, но не забыт: {}", e),
+    }
+}
+
+fn run_ssh(action: &SshAction, cli: &Cli) -> Result<(), Box<dyn Error>> {
+    use hiho::ssh::PrivateKey;
+
+    match action {
+        SshAction::Add { name, file, confirm, folder } => {
+            let text = Zeroizing::new(
+                std::fs::read_to_string(file).map_err(|e| format!("Не удалось прочитать {}: {}", file.display(), e))?,
+            );
+            let key = PrivateKey::parse(&text)?;
+            let mut vault = unlock::unlock_vault(&cli.password, &cli.vault)?;
+            
+            let mut entry = Entry::new(name.clone(), String::new(), String::new());
+            entry.kind = EntryKind::SshKey;
+            entry.folder = folder.clone();
+            entry.ssh_key = Some(SshKey {
+                private_key: text.trim().to_string(),
+                confirm: *confirm,
+            });
+            vault.add_entry(entry);
+            vault.save()?;
+            println!("✅ SSH-ключ добавлен: {} {} ({})", key.bits(), key.fingerprint(), key.kind());
+            println!("💡 Файл {} больше не нужен: агент hiho отдаёт ключ, пока хранилище открыто", file.display());
+        }
+        SshAction::List => {
+            let vault = unlock::unlock_vault(&cli.password, &cli.vault)?;
+            
+            let keys: Vec<(usize, &Entry, &SshKey)> = vault
+                .get_entries()
+                .iter()
+                .enumerate()
+                .filter_map(|(i, entry)| Some((i, entry, entry.ssh_key.as_ref()?)))
+                .collect();
+            if keys.is_empty() {
+                println!("🔑 В хранилище нет SSH-ключей (hiho ssh add)");
+                return Ok(());
+            }
+            println!("🔑 SSH-ключи:");
+            for (i, entry, ssh_key) in keys {
+                let confirm = if ssh_key.confirm { " — с подтверждением" } else { "" };
+                match PrivateKey::parse(&ssh_key.private_key) {
+                    Ok(key) => println!(
+                        "{}. {}: {} {} ({}){}",
+                        i + 1,
+                        entry.name,
+                        key.bits(),
+                        key.fingerprint(),
+                        key.kind(),
+                        confirm
+                    ),
+                    Err(e) => println!("{}. {}: ⚠️  {}", i + 1, entry.name, e),
+                }
+            }
+        }
+        SshAction::PublicKey { name_or_index } => {
+            let vault = unlock::unlock_vault(&cli.password, &cli.vault)?;
+            
+            let entry = find_entry(&vault, name_or_index)?.ok_or_else(|| entry_not_found(name_or_index))?;
+            let ssh_key = entry
+                .ssh_key
+                .as_ref()
+                .ok_or_else(|| CliError::not_found(format!("В записи '{}' нет SSH-ключа", entry.name)))?;
+            println!("{}", PrivateKey::parse(&ssh_key.private_key)?.public_line());
+        }
+        SshAction::Confirm { name_or_index, enabled } => {
+            let mut vault = unlock::unlock_vault(&cli.password, &cli.vault)?;
+            
+            let (index, entry) = find_entry_with_index(&vault, name_or_index)?
+                .ok_or_else(|| entry_not_found(name_or_index))?;
+            if entry.ssh_key.is_none() {
+                return Err(CliError::not_found(format!("В записи '{}' нет SSH-ключа", entry.name)).into());
+            }
+            let name = entry.name.clone();
+            vault.set_ssh_confirm(index, *enabled)?;
+            vault.save()?;
+            if *enabled {
+                println!("✅ Подписи ключом '{}' нужно подтверждать", name);
+            } else {
+                println!("✅ Ключ '{}' подписывает без подтверждения", name);
+            }
+        }
+    }
+    Ok(())
+}
+
+// Помощник git работает только через открытый агент: stdin занят протоколом,
+// и спрашивать мастер-пароль не у кого. Без агента ничего не отвечаем, и git
+// переходит к следующему помощнику или спрашивает пароль сам
+#[cfg(unix)]
+fn run_git_credential(operation: &str, vault_file: &str) -> Result<(), Box<dyn Error>> {
+    use hiho::agent::AgentClient;
+    use hiho::git_credential::{self, Credential};
+
+    // Неизвестные операции git требует молча пропускать
+    if !matches!(operation, "get" | "store" | "erase") {
+        return Ok(());
+    }
+    let credential = Credential::read(std::io::stdin().lock())?;
+    let Some(mut client) = AgentClient::connect() else {
+        eprintln!("hiho: агент не запущен (hiho agent start), учётные данные git не переданы");
+        return Ok(());
+    };
+    let path = std::path::absolute(vault_file)?;
+    let Some(entries) = client.entries(&path)? else {
+        eprintln!("hiho: хранилище в агенте заблокировано (hiho unlock), учётные данные git не переданы");
+        return Ok(());
+    };
+    let mut vault = Vault::detached(entries);
+    if git_credential::execute(operation, &credential, &mut vault, &mut std::io::stdout().lock())? {
+        client.store(&path, vault.get_entries().clone())?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn run_git_credential(_operation: &str, _vault_file: &str) -> Result<(), Box<dyn Error>> {
+    Err("Помощник git работает через агент, а он поддерживается только в Unix".into())
+}
+
+#[cfg(unix)]
+fn agent_client() -> Result<hiho::agent::AgentClient, Box<dyn Error>> {
+    hiho::agent::AgentClient::connect().ok_or_else(|| {
+        CliError::not_found(format!(
+            "Агент не запущен ({}). Запустите 'hiho agent start'",
+            hiho::agent::socket_path().display()
+        ))
+        .into()
+    })
+}
+
+// Запускает `hiho agent run` отдельным процессом, отвязанным от терминала
+#[cfg(unix)]
+fn spawn_agent() -> Result<(), Box<dyn Error>> {
+    use std::os::unix::process::CommandExt;
+    use std::process::{Command, Stdio};
+
+    Command::new(std::env::current_exe()?)
+        .args(["agent", "run"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(0)
+        .spawn()?;
+    Ok(())
+}
+
+fn entry_not_found(name_or_index: &str) -> CliError {
+    CliError::not_found(format!("Запись '{}' не найдена!", name_or_index))
+}
+
 // Вспомогательная функция для поиска записи
 fn find_entry<'a>(vault: &'a Vault, name_or_index: &str) -> Result<Option<&'a Entry>, Box<dyn Error>> {
     let entries = vault.get_entries();
@@ -520,25 +1149,201 @@ fn find_entry_with_index<'a>(vault: &'a Vault, name_or_index: &str) -> Result<Op
     Ok(None)
 }
 
-fn copy_to_clipboard(text: &str) -> Result<(), Box<dyn Error>> {
-    let mut ctx: ClipboardContext = ClipboardProvider::new()?;
-    ctx.set_contents(text.to_owned())?;
-    Ok(())
+fn display_password(password: &str, show: bool) -> &str {
+    if show || password.is_empty() {
+        password
+    } else {
+        "********"
+    }
 }
 
-// Вспомогательные функции для CSV
-fn escape_csv(text: &str) -> String {
-    if text.contains(',') || text.contains('"') || text.contains('\n') {
-        format!("\"{}\"", text.replace("\"", "\"\""))
-    } else {
-        text.to_string()
+fn print_entry_details(index: usize, entry: &Entry) {
+    println!("🔐 {}. {} [{}]", index + 1, entry.name, entry.kind.as_str());
+    if !entry.username.is_empty() {
+        println!("   Пользователь: {}", entry.username);
+    }
+    if !entry.password.is_empty() {
+        println!("   Пароль: {} (hiho show {} --field password)", display_password(&entry.password, false), index + 1);
+    }
+    for url in &entry.urls {
+        println!("   URL: {}", url);
+    }
+    if let Some(folder) = &entry.folder {
+        println!("   Папка: {}", folder);
+    }
+    if !entry.tags.is_empty() {
+        println!("   Теги: {}", entry.tags.join(", "));
+    }
+    if entry.totp.is_some() {
+        println!("   TOTP: есть");
+    }
+    if let Some(sequence) = &entry.auto_type {
+        println!("   Автонабор: {}", sequence);
+    }
+    if let Some(ssh_key) = &entry.ssh_key {
+        let confirm = if ssh_key.confirm { ", с подтверждением" } else { "" };
+        match hiho::ssh::PrivateKey::parse(&ssh_key.private_key) {
+            Ok(key) => println!("   SSH-ключ: {} {}{}", key.kind(), key.fingerprint(), confirm),
+            Err(e) => println!("   SSH-ключ: ⚠️  {}", e),
+        }
+    }
+    for field in &entry.custom_fields {
+        println!("   {}: {}", field.name, display_password(&field.value, !field.protected));
+    }
+    for attachment in &entry.attachments {
+        println!("   Вложение: {} ({} байт)", attachment.name, attachment.data.len());
+    }
+    if !entry.notes.is_empty() {
+        println!("   Заметка: {}", entry.notes);
+    }
+    if let Some(updated_at) = entry.updated_at {
+        println!("   Изменена: {}", updated_at.format("%Y-%m-%d %H:%M"));
+    }
+    if !entry.history.is_empty() {
+        println!("   Версий в истории: {}", entry.history.len());
+    }
+}
+
+fn print_audit_report(report: &AuditReport) {
+    println!("🛡️  Оценка безопасности хранилища: {}/100", report.score);
+    println!("   Записей: {}, с проблемами: {}", report.total_entries, report.affected_entries);
+    
+    if report.groups.is_empty() {
+        println!("✅ Проблем не найдено!");
+        return;
+    }
+    
+    for group in &report.groups {
+        let icon = match group.severity {
+            Severity::Critical => "🔴",
+            Severity::High => "🟠",
+            Severity::Medium => "🟡",
+            Severity::Low => "🔵",
+        };
+        println!();
+        println!("{} {} ({})", icon, group.title, group.affected_entries);
+        for issue in &group.issues {
+            let names: Vec<String> = issue.entries
+                .iter()
+                .map(|e| format!("{}. {} ({})", e.index, e.name, e.username))
+                .collect();
+            println!("   • {} — {}", names.join(", "), issue.detail);
+        }
     }
 }
 
-fn unescape_csv(text: &str) -> String {
-    if text.starts_with('"') && text.ends_with('"') {
-        text[1..text.len()-1].replace("\"\"", "\"")
+fn print_merge_report(output: OutputFormat, report: &MergeReport) {
+    for outcome in &report.outcomes {
+        let (icon, detail) = match outcome.action {
+            MergeAction::Added => ("➕", String::new()),
+            MergeAction::KeptBoth(reason) => ("➕", format!(" — добавлена копией ({})", reason.label())),
+            MergeAction::Updated(reason) => ("🔄", format!(" — обновлена ({})", reason.label())),
+            MergeAction::Unchanged(reason) => ("⏭️ ", format!(" — уже есть ({})", reason.label())),
+            MergeAction::Skipped(reason) => ("⏭️ ", format!(" — пропущена ({})", reason.label())),
+        };
+        if outcome.username.is_empty() {
+            say!(output, "{} {}{}", icon, outcome.name, detail);
+        } else {
+            say!(output, "{} {} ({}){}", icon, outcome.name, outcome.username, detail);
+        }
+    }
+    say!(output, 
+        "📊 Добавлено: {}, обновлено: {}, пропущено: {}",
+        report.added(),
+        report.updated(),
+        report.skipped()
+    );
+}
+
+fn print_import_preview(output: OutputFormat, import: &ImportResult, format: ImportFormat) {
+    say!(output, "🔍 Предпросмотр импорта ({}): {} записей, хранилище не изменено", format.title(), import.entries.len());
+
+    for (i, entry) in import.entries.iter().enumerate() {
+        say!(output, "{}. {} [{}]", i + 1, entry.name, entry.kind.as_str());
+        if !entry.username.is_empty() {
+            say!(output, "   Пользователь: {}", entry.username);
+        }
+        if !entry.password.is_empty() {
+            say!(output, "   Пароль: {}", display_password(&entry.password, false));
+        }
+        for url in &entry.urls {
+            say!(output, "   URL: {}", url);
+        }
+        if let Some(folder) = &entry.folder {
+            say!(output, "   Папка: {}", folder);
+        }
+        if !entry.tags.is_empty() {
+            say!(output, "   Теги: {}", entry.tags.join(", "));
+        }
+        if entry.totp.is_some() {
+            say!(output, "   TOTP: есть");
+        }
+        if !entry.custom_fields.is_empty() {
+            let names: Vec<&str> = entry.custom_fields.iter().map(|f| f.name.as_str()).collect();
+            say!(output, "   Доп. поля: {}", names.join(", "));
+        }
+        if !entry.notes.is_empty() {
+            say!(output, "   Заметка: {} симв.", entry.notes.chars().count());
+        }
+    }
+
+    if !import.warnings.is_empty() {
+        say!(output);
+        say!(output, "⚠️  Предупреждения: {}", import.warnings.len());
+        for warning in &import.warnings {
+            say!(output, "   {}", warning);
+        }
+    }
+}
+
+fn copy_to_clipboard(value: &str, selection: Option<Selection>) -> Result<Option<std::time::Duration>, Box<dyn Error>> {
+    match selection {
+        Some(selection) => ClipboardManager::copy_secret_to(value, selection),
+        None => ClipboardManager::copy_secret(value),
+    }
+}
+
+// Логин, затем пароль: где буфер умеет отдавать значение на одну вставку,
+// пароль подставляется сам, иначе — по нажатию Enter
+fn copy_login_sequence(
+    output: OutputFormat,
+    entry: &Entry,
+    selection: Option<Selection>,
+) -> Result<Option<std::time::Duration>, Box<dyn Error>> {
+    if entry.username.is_empty() {
+        return Err(CliError::not_found(format!("У записи '{}' нет логина", entry.name)).into());
+    }
+    if ClipboardManager::sequence_supported() {
+        let values = [entry.username.as_str(), entry.password.as_str()];
+        let clear_after = match selection {
+            Some(selection) => ClipboardManager::copy_sequence_to(&values, selection)?,
+            None => ClipboardManager::copy_sequence(&values)?,
+        };
+        say!(output, "✅ Логин для '{}' скопирован в буфер обмена!", entry.name);
+        say!(output, "🔑 После его вставки в буфере окажется пароль");
+        print_clipboard_clear(output, clear_after);
+        return Ok(clear_after);
+    }
+    
+    copy_to_clipboard(&entry.username, selection)?;
+    say!(output, "✅ Логин для '{}' скопирован в буфер обмена!", entry.name);
+    let prompt = "🔑 Вставьте его и нажмите Enter, чтобы скопировать пароль...";
+    if output == OutputFormat::Plain {
+        print!("{}", prompt);
+        std::io::Write::flush(&mut std::io::stdout())?;
     } else {
-        text.to_string()
+        eprint!("{}", prompt);
     }
-}
\ No newline at end of file
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    let clear_after = copy_to_clipboard(&entry.password, selection)?;
+    say!(output, "✅ Пароль для '{}' скопирован в буфер обмена!", entry.name);
+    print_clipboard_clear(output, clear_after);
+    Ok(clear_after)
+}
+
+fn print_clipboard_clear(output: OutputFormat, clear_after: Option<std::time::Duration>) {
+    if let Some(clear_after) = clear_after {
+        say!(output, "🧹 Буфер обмена будет очищен через {} с", clear_after.as_secs());
+    }
+}
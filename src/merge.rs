@@ -0,0 +1,334 @@
+use crate::entry::Entry;
+use std::mem;
+
+/// Что делать, если импортируемая запись уже есть в хранилище.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// Оставить существующую запись
+    Skip,
+    /// Заменить существующую запись импортируемой
+    Overwrite,
+    /// Добавить импортируемую запись рядом с существующей
+    KeepBoth,
+    /// Оставить ту, что изменялась позже
+    Newest,
+}
+
+impl MergeStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MergeStrategy::Skip => "skip",
+            MergeStrategy::Overwrite => "overwrite",
+            MergeStrategy::KeepBoth => "keep-both",
+            MergeStrategy::Newest => "newest",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "skip" => Some(MergeStrategy::Skip),
+            "overwrite" => Some(MergeStrategy::Overwrite),
+            "keep-both" | "keep_both" | "both" => Some(MergeStrategy::KeepBoth),
+            "newest" | "newest-wins" => Some(MergeStrategy::Newest),
+            _ => None,
+        }
+    }
+}
+
+/// По какому признаку запись признана дубликатом.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchReason {
+    Id,
+    NameAndUsername,
+    UrlAndUsername,
+}
+
+impl MatchReason {
+    pub fn label(&self) -> &'static str {
+        match self {
+            MatchReason::Id => "совпадает id",
+            MatchReason::NameAndUsername => "совпадают название и логин",
+            MatchReason::UrlAndUsername => "совпадают адрес и логин",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeAction {
+    Added,
+    Updated(MatchReason),
+    /// Пропущена: в хранилище та же запись без изменений
+    Unchanged(MatchReason),
+    /// Пропущена по стратегии (skip или более старая при newest)
+    Skipped(MatchReason),
+    KeptBoth(MatchReason),
+}
+
+#[derive(Debug, Clone)]
+pub struct MergeOutcome {
+    pub name: String,
+    pub username: String,
+    pub action: MergeAction,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MergeReport {
+    pub outcomes: Vec<MergeOutcome>,
+}
+
+impl MergeReport {
+    pub fn added(&self) -> usize {
+        self.count(|a| matches!(a, MergeAction::Added | MergeAction::KeptBoth(_)))
+    }
+
+    pub fn updated(&self) -> usize {
+        self.count(|a| matches!(a, MergeAction::Updated(_)))
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.count(|a| matches!(a, MergeAction::Skipped(_) | MergeAction::Unchanged(_)))
+    }
+
+    /// Изменилось ли хранилище
+    pub fn has_changes(&self) -> bool {
+        self.added() + self.updated() > 0
+    }
+
+    fn count(&self, predicate: impl Fn(&MergeAction) -> bool) -> usize {
+        self.outcomes.iter().filter(|o| predicate(&o.action)).count()
+    }
+}
+
+/// Ищет в `existing` запись, которую `entry` дублирует.
+pub fn find_duplicate(existing: &[Entry], entry: &Entry) -> Option<(usize, MatchReason)> {
+    if let Some(index) = existing.iter().position(|e| e.id == entry.id) {
+        return Some((index, MatchReason::Id));
+    }
+
+    let same_username = |e: &Entry| e.username.trim().eq_ignore_ascii_case(entry.username.trim());
+    let name = entry.name.trim().to_lowercase();
+    if let Some(index) = existing
+        .iter()
+        .position(|e| same_username(e) && e.name.trim().to_lowercase() == name)
+    {
+        return Some((index, MatchReason::NameAndUsername));
+    }
+
+    let urls: Vec<String> = entry.urls.iter().map(|u| normalize_url(u)).collect();
+    if urls.is_empty() {
+        return None;
+    }
+    existing
+        .iter()
+        .position(|e| same_username(e) && e.urls.iter().any(|u| urls.contains(&normalize_url(u))))
+        .map(|index| (index, MatchReason::UrlAndUsername))
+}
+
+/// Добавляет импортированные записи в `existing`, разрешая дубликаты по стратегии.
+/// Записи внутри одного импорта тоже сверяются между собой.
+pub fn merge_entries(existing: &mut Vec<Entry>, incoming: Vec<Entry>, strategy: MergeStrategy) -> MergeReport {
+    let mut report = MergeReport::default();
+
+    for mut entry in incoming {
+        let name = entry.name.clone();
+        let username = entry.username.clone();
+
+        let action = match find_duplicate(existing, &entry) {
+            None => {
+                existing.push(entry);
+                MergeAction::Added
+            }
+            Some((index, reason)) if same_content(&existing[index], &entry) => MergeAction::Unchanged(reason),
+            Some((index, reason)) => {
+                let incoming_is_newer = entry.updated_at > existing[index].updated_at;
+                match strategy {
+                    MergeStrategy::Skip => MergeAction::Skipped(reason),
+                    MergeStrategy::Newest if !incoming_is_newer => MergeAction::Skipped(reason),
+                    MergeStrategy::Overwrite | MergeStrategy::Newest => {
+                        overwrite(&mut existing[index], entry);
+                        MergeAction::Updated(reason)
+                    }
+                    MergeStrategy::KeepBoth => {
+                        // Вторая копия не должна совпадать с первой по id
+                        if reason == MatchReason::Id {
+                            entry.id = uuid::Uuid::new_v4().to_string();
+                        }
+                        existing.push(entry);
+                        MergeAction::KeptBoth(reason)
+                    }
+                }
+            }
+        };
+
+        report.outcomes.push(MergeOutcome { name, username, action });
+    }
+    report
+}
+
+// Существующая версия уходит в историю, id и дата создания сохраняются
+fn overwrite(existing: &mut Entry, incoming: Entry) {
+    existing.push_history();
+    let id = mem::take(&mut existing.id);
+    let history = mem::take(&mut existing.history);
+    let created_at = match (existing.created_at, incoming.created_at) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    };
+    let password_changed = existing.password != incoming.password;
+    let previous_password_change = existing.password_changed_at;
+
+    *existing = incoming;
+    existing.id = id;
+    existing.history = history;
+    existing.created_at = created_at;
+    if !password_changed {
+        existing.password_changed_at = previous_password_change;
+    }
+}
+
+fn same_content(a: &Entry, b: &Entry) -> bool {
+    a.kind == b.kind
+        && a.name == b.name
+        && a.username == b.username
+        && a.password == b.password
+        && a.urls == b.urls
+        && a.notes == b.notes
+        && a.folder == b.folder
+        && a.tags == b.tags
+        && a.totp == b.totp
+        && a.custom_fields == b.custom_fields
+        && a.auto_type == b.auto_type
+        && a.ssh_key == b.ssh_key
+        && a.attachments == b.attachments
+}
+
+fn normalize_url(url: &str) -> String {
+    url.trim().trim_end_matches('/').to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+
+    fn entry(name: &str, username: &str, password: &str) -> Entry {
+        Entry::new(name.to_string(), username.to_string(), password.to_string())
+    }
+
+    // Существующая запись и её изменённая копия из импорта
+    fn conflict(incoming_is_newer: bool) -> (Vec<Entry>, Entry) {
+        let mut existing = entry("GitHub", "octocat", "old");
+        existing.created_at = Some(Utc::now() - Duration::days(30));
+        let mut incoming = entry("github ", "OctoCat", "new");
+        let shift = if incoming_is_newer { 1 } else { -1 };
+        incoming.updated_at = existing.updated_at.map(|date| date + Duration::days(shift));
+        (vec![existing], incoming)
+    }
+
+    #[test]
+    fn strategies_parse_from_cli_names() {
+        for strategy in [MergeStrategy::Skip, MergeStrategy::Overwrite, MergeStrategy::KeepBoth, MergeStrategy::Newest] {
+            assert_eq!(MergeStrategy::parse(strategy.as_str()), Some(strategy));
+        }
+        assert_eq!(MergeStrategy::parse("both"), Some(MergeStrategy::KeepBoth));
+        assert_eq!(MergeStrategy::parse("merge"), None);
+    }
+
+    #[test]
+    fn finds_duplicates_by_id_name_and_url() {
+        let mut site = entry("Банк", "ivan", "p");
+        site.urls = vec!["https://Bank.example/".into()];
+        let existing = vec![entry("Почта", "ivan", "p"), site];
+
+        let same_id = existing[0].clone();
+        assert_eq!(find_duplicate(&existing, &same_id), Some((0, MatchReason::Id)));
+        assert_eq!(
+            find_duplicate(&existing, &entry(" почта", "IVAN", "x")),
+            Some((0, MatchReason::NameAndUsername))
+        );
+        let mut by_url = entry("Другое название", "ivan", "x");
+        by_url.urls = vec!["https://bank.example".into()];
+        assert_eq!(find_duplicate(&existing, &by_url), Some((1, MatchReason::UrlAndUsername)));
+        by_url.username = "petr".into();
+        assert_eq!(find_duplicate(&existing, &by_url), None);
+    }
+
+    #[test]
+    fn skip_keeps_existing() {
+        let (mut existing, incoming) = conflict(true);
+        let report = merge_entries(&mut existing, vec![incoming], MergeStrategy::Skip);
+        assert_eq!(report.outcomes[0].action, MergeAction::Skipped(MatchReason::NameAndUsername));
+        assert_eq!(existing.len(), 1);
+        assert_eq!(existing[0].password, "old");
+        assert!(!report.has_changes());
+    }
+
+    #[test]
+    fn overwrite_keeps_id_history_and_creation_date() {
+        let (mut existing, incoming) = conflict(false);
+        let (id, created_at) = (existing[0].id.clone(), existing[0].created_at);
+        let report = merge_entries(&mut existing, vec![incoming], MergeStrategy::Overwrite);
+        assert_eq!(report.updated(), 1);
+        assert_eq!(existing.len(), 1);
+        assert_eq!(existing[0].id, id);
+        assert_eq!(existing[0].created_at, created_at);
+        assert_eq!(existing[0].password, "new");
+        assert_eq!(existing[0].history.len(), 1);
+        assert_eq!(existing[0].history[0].password, "old");
+    }
+
+    #[test]
+    fn overwrite_keeps_password_date_when_password_is_same() {
+        let (mut existing, mut incoming) = conflict(true);
+        incoming.password = "old".into();
+        incoming.notes = "новая заметка".into();
+        let changed_at = Some(Utc::now() - Duration::days(100));
+        existing[0].password_changed_at = changed_at;
+        merge_entries(&mut existing, vec![incoming], MergeStrategy::Overwrite);
+        assert_eq!(existing[0].notes, "новая заметка");
+        assert_eq!(existing[0].password_changed_at, changed_at);
+    }
+
+    #[test]
+    fn newest_takes_only_newer_entries() {
+        let (mut existing, incoming) = conflict(false);
+        merge_entries(&mut existing, vec![incoming], MergeStrategy::Newest);
+        assert_eq!(existing[0].password, "old");
+
+        let (mut existing, incoming) = conflict(true);
+        let report = merge_entries(&mut existing, vec![incoming], MergeStrategy::Newest);
+        assert_eq!(report.updated(), 1);
+        assert_eq!(existing[0].password, "new");
+    }
+
+    #[test]
+    fn keep_both_gives_copy_a_new_id() {
+        let (mut existing, _) = conflict(true);
+        let mut same_id = existing[0].clone();
+        same_id.password = "new".into();
+        let report = merge_entries(&mut existing, vec![same_id], MergeStrategy::KeepBoth);
+        assert_eq!(report.outcomes[0].action, MergeAction::KeptBoth(MatchReason::Id));
+        assert_eq!(report.added(), 1);
+        assert_eq!(existing.len(), 2);
+        assert_ne!(existing[0].id, existing[1].id);
+    }
+
+    #[test]
+    fn identical_entries_are_unchanged_and_import_is_deduplicated() {
+        let (mut existing, _) = conflict(true);
+        let copy = existing[0].clone();
+        let fresh = entry("Новая", "anna", "p");
+        let report = merge_entries(&mut existing, vec![copy, fresh.clone(), fresh], MergeStrategy::KeepBoth);
+        let actions: Vec<MergeAction> = report.outcomes.iter().map(|o| o.action).collect();
+        assert_eq!(
+            actions,
+            vec![
+                MergeAction::Unchanged(MatchReason::Id),
+                MergeAction::Added,
+                MergeAction::Unchanged(MatchReason::Id),
+            ]
+        );
+        assert_eq!(existing.len(), 2);
+        assert_eq!((report.added(), report.skipped()), (1, 2));
+    }
+}
@@ -0,0 +1,537 @@
+//! Машиночитаемый вывод CLI (`--output json|tsv`) и единая обработка ошибок.
+//!
+//! Схемы стабильны: поля только добавляются. В TSV первая строка — заголовок,
+//! а табуляция, перевод строки и обратный слэш в значениях экранируются
+//! как `\t`, `\n` и `\\`.
+
+use clap::ValueEnum;
+use hiho::audit::AuditReport;
+use hiho::breach::BreachResult;
+use hiho::strength::StrengthReport;
+use hiho::vault::UnlockError;
+use hiho::Entry;
+use serde_json::{json, Value};
+use std::error::Error;
+use std::fmt;
+use std::process::ExitCode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Текст для человека
+    Plain,
+    Json,
+    Tsv,
+}
+
+/// Класс ошибки; у каждого свой код завершения.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// Прочие ошибки — 1
+    General,
+    /// Неверные аргументы — 2, как у ошибок разбора clap
+    Usage,
+    /// Запись, поле или файл не найдены — 3
+    NotFound,
+    /// Неверный мастер-пароль или отказ в доступе — 4
+    Auth,
+    /// Сессия заблокирована — 5
+    Locked,
+    /// Ошибка ввода-вывода — 6
+    Io,
+}
+
+impl ErrorKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorKind::General => "general",
+            ErrorKind::Usage => "usage",
+            ErrorKind::NotFound => "not_found",
+            ErrorKind::Auth => "auth",
+            ErrorKind::Locked => "locked",
+            ErrorKind::Io => "io",
+        }
+    }
+
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            ErrorKind::General => 1,
+            ErrorKind::Usage => 2,
+            ErrorKind::NotFound => 3,
+            ErrorKind::Auth => 4,
+            ErrorKind::Locked => 5,
+            ErrorKind::Io => 6,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct CliError {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+impl CliError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        CliError {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    pub fn usage(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Usage, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::NotFound, message)
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for CliError {}
+
+fn classify(error: &(dyn Error + 'static)) -> ErrorKind {
+    if let Some(error) = error.downcast_ref::<CliError>() {
+        error.kind
+    } else if error.is::<UnlockError>() {
+        ErrorKind::Auth
+    } else if let Some(kind) = agent_error_kind(error) {
+        kind
+    } else if error.is::<std::io::Error>() {
+        ErrorKind::Io
+    } else {
+        ErrorKind::General
+    }
+}
+
+#[cfg(unix)]
+fn agent_error_kind(error: &(dyn Error + 'static)) -> Option<ErrorKind> {
+    use hiho::agent::{AgentError, AgentErrorCode};
+
+    error.downcast_ref::<AgentError>().map(|error| match error.code {
+        AgentErrorCode::Locked => ErrorKind::Locked,
+        AgentErrorCode::Auth => ErrorKind::Auth,
+        AgentErrorCode::Io => ErrorKind::Io,
+        AgentErrorCode::WrongVault | AgentErrorCode::BadRequest => ErrorKind::General,
+    })
+}
+
+#[cfg(not(unix))]
+fn agent_error_kind(_error: &(dyn Error + 'static)) -> Option<ErrorKind> {
+    None
+}
+
+/// Печатает ошибку в stderr в выбранном формате и возвращает код завершения.
+pub fn report_error(error: &(dyn Error + 'static), format: OutputFormat) -> ExitCode {
+    let kind = classify(error);
+    let message = error.to_string();
+    match format {
+        OutputFormat::Plain => eprintln!("❌ {}", message),
+        OutputFormat::Json => eprintln!(
+            "{}",
+            json!({ "error": { "code": kind.as_str(), "exit_code": kind.exit_code(), "message": message } })
+        ),
+        OutputFormat::Tsv => eprintln!("error\t{}\t{}", kind.as_str(), escape_tsv(&message)),
+    }
+    ExitCode::from(kind.exit_code())
+}
+
+/// Печатает JSON с отступами.
+pub fn print_json(value: &impl serde::Serialize) -> Result<(), Box<dyn Error>> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+pub fn print_tsv(header: &[&str], rows: &[Vec<String>]) {
+    println!("{}", header.join("\t"));
+    for row in rows {
+        let cells: Vec<String> = row.iter().map(|cell| escape_tsv(cell)).collect();
+        println!("{}", cells.join("\t"));
+    }
+}
+
+fn escape_tsv(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn format_date(date: Option<chrono::DateTime<chrono::Utc>>) -> String {
+    date.map(|d| d.to_rfc3339()).unwrap_or_default()
+}
+
+/// Список записей для `list` и `search`. Индекс — номер записи в `hiho list`.
+pub fn print_entries(
+    format: OutputFormat,
+    entries: &[(usize, &Entry)],
+    show_passwords: bool,
+) -> Result<(), Box<dyn Error>> {
+    match format {
+        OutputFormat::Json => {
+            let items: Vec<Value> = entries
+                .iter()
+                .map(|(index, entry)| {
+                    json!({
+                        "index": index + 1,
+                        "id": entry.id,
+                        "kind": entry.kind.as_str(),
+                        "name": entry.name,
+                        "username": entry.username,
+                        "password": show_passwords.then_some(&entry.password),
+                        "urls": entry.urls,
+                        "folder": entry.folder,
+                        "tags": entry.tags,
+                        "updated_at": entry.updated_at,
+                    })
+                })
+                .collect();
+            print_json(&items)
+        }
+        _ => {
+            let rows: Vec<Vec<String>> = entries
+                .iter()
+                .map(|(index, entry)| {
+                    vec![
+                        (index + 1).to_string(),
+                        entry.id.clone(),
+                        entry.kind.as_str().to_string(),
+                        entry.name.clone(),
+                        entry.username.clone(),
+                        if show_passwords { entry.password.clone() } else { String::new() },
+                        entry.urls.first().cloned().unwrap_or_default(),
+                        entry.folder.clone().unwrap_or_default(),
+                        entry.tags.join(","),
+                        format_date(entry.updated_at),
+                    ]
+                })
+                .collect();
+            print_tsv(
+                &["index", "id", "kind", "name", "username", "password", "url", "folder", "tags", "updated_at"],
+                &rows,
+            );
+            Ok(())
+        }
+    }
+}
+
+/// Запись целиком для `show`. Пароль и защищённые поля не выводятся.
+pub fn print_entry(format: OutputFormat, index: usize, entry: &Entry) -> Result<(), Box<dyn Error>> {
+    match format {
+        OutputFormat::Json => print_json(&json!({
+            "index": index + 1,
+            "id": entry.id,
+            "kind": entry.kind.as_str(),
+            "name": entry.name,
+            "username": entry.username,
+            "has_password": !entry.password.is_empty(),
+            "urls": entry.urls,
+            "notes": entry.notes,
+            "folder": entry.folder,
+            "tags": entry.tags,
+            "has_totp": entry.totp.is_some(),
+            "auto_type": entry.auto_type,
+            "has_ssh_key": entry.ssh_key.is_some(),
+            "custom_fields": entry.custom_fields.iter().map(|f| json!({
+                "name": f.name,
+                "value": (!f.protected).then_some(&f.value),
+                "protected": f.protected,
+            })).collect::<Vec<_>>(),
+            "attachments": entry.attachments.iter().map(|a| json!({
+                "name": a.name,
+                "size": a.data.len(),
+            })).collect::<Vec<_>>(),
+            "history_count": entry.history.len(),
+            "created_at": entry.created_at,
+            "updated_at": entry.updated_at,
+            "password_changed_at": entry.password_changed_at,
+        })),
+        _ => {
+            let mut rows = vec![
+                vec!["index".to_string(), (index + 1).to_string()],
+                vec!["id".to_string(), entry.id.clone()],
+                vec!["kind".to_string(), entry.kind.as_str().to_string()],
+                vec!["name".to_string(), entry.name.clone()],
+                vec!["username".to_string(), entry.username.clone()],
+            ];
+            rows.extend(entry.urls.iter().map(|url| vec!["url".to_string(), url.clone()]));
+            rows.push(vec!["notes".to_string(), entry.notes.clone()]);
+            rows.push(vec!["folder".to_string(), entry.folder.clone().unwrap_or_default()]);
+            rows.push(vec!["tags".to_string(), entry.tags.join(",")]);
+            if let Some(sequence) = &entry.auto_type {
+                rows.push(vec!["auto_type".to_string(), sequence.clone()]);
+            }
+            rows.extend(
+                entry
+                    .custom_fields
+                    .iter()
+                    .filter(|f| !f.protected)
+                    .map(|f| vec![format!("field:{}", f.name), f.value.clone()]),
+            );
+            rows.push(vec!["created_at".to_string(), format_date(entry.created_at)]);
+            rows.push(vec!["updated_at".to_string(), format_date(entry.updated_at)]);
+            rows.push(vec!["password_changed_at".to_string(), format_date(entry.password_changed_at)]);
+            print_tsv(&["field", "value"], &rows);
+            Ok(())
+        }
+    }
+}
+
+/// Одно поле записи для `show --field`.
+pub fn print_field(format: OutputFormat, index: usize, field: &str, value: &str) -> Result<(), Box<dyn Error>> {
+    match format {
+        OutputFormat::Json => print_json(&json!({ "index": index + 1, "field": field, "value": value })),
+        _ => {
+            print_tsv(&["field", "value"], &[vec![field.to_string(), value.to_string()]]);
+            Ok(())
+        }
+    }
+}
+
+/// Отчёт аудита: в JSON — целиком, в TSV — строка на каждую запись с проблемой.
+pub fn print_audit(format: OutputFormat, report: &AuditReport) -> Result<(), Box<dyn Error>> {
+    match format {
+        OutputFormat::Json => print_json(report),
+        _ => {
+            let mut rows = Vec::new();
+            for group in &report.groups {
+                for issue in &group.issues {
+                    for entry in &issue.entries {
+                        rows.push(vec![
+                            json_name(&issue.severity),
+                            json_name(&issue.kind),
+                            entry.index.to_string(),
+                            entry.name.clone(),
+                            entry.username.clone(),
+                            issue.detail.clone(),
+                        ]);
+                    }
+                }
+            }
+            print_tsv(&["severity", "kind", "index", "name", "username", "detail"], &rows);
+            Ok(())
+        }
+    }
+}
+
+/// Оценка стойкости для `strength`; время взлома — в секундах.
+pub fn print_strength(format: OutputFormat, report: &StrengthReport) -> Result<(), Box<dyn Error>> {
+    let times = &report.crack_times;
+    match format {
+        OutputFormat::Json => print_json(&json!({
+            "score": report.score as u8,
+            "label": report.score.label(),
+            "guesses_log10": report.guesses_log10,
+            "entropy_bits": report.entropy_bits,
+            "crack_times": {
+                "online_throttled": times.online_throttled,
+                "online_unthrottled": times.online_unthrottled,
+                "offline_slow_hashing": times.offline_slow_hashing,
+                "offline_fast_hashing": times.offline_fast_hashing,
+            },
+            "warning": report.feedback.warning,
+            "suggestions": report.feedback.suggestions,
+        })),
+        _ => {
+            let row = vec![
+                (report.score as u8).to_string(),
+                report.score.label().to_string(),
+                format!("{:.2}", report.guesses_log10),
+                format!("{:.1}", report.entropy_bits),
+                times.online_throttled.to_string(),
+                times.online_unthrottled.to_string(),
+                times.offline_slow_hashing.to_string(),
+                times.offline_fast_hashing.to_string(),
+                report.feedback.warning.clone().unwrap_or_default(),
+                report.feedback.suggestions.join("\n"),
+            ];
+            print_tsv(
+                &[
+                    "score",
+                    "label",
+                    "guesses_log10",
+                    "entropy_bits",
+                    "online_throttled",
+                    "online_unthrottled",
+                    "offline_slow_hashing",
+                    "offline_fast_hashing",
+                    "warning",
+                    "suggestions",
+                ],
+                &[row],
+            );
+            Ok(())
+        }
+    }
+}
+
+/// Записи из базы утечек для `breach-check`.
+pub fn print_breaches(format: OutputFormat, entries: &[Entry], results: &[BreachResult]) -> Result<(), Box<dyn Error>> {
+    match format {
+        OutputFormat::Json => {
+            let items: Vec<Value> = results
+                .iter()
+                .map(|r| {
+                    json!({
+                        "index": r.index + 1,
+                        "name": entries[r.index].name,
+                        "username": entries[r.index].username,
+                        "count": r.count,
+                    })
+                })
+                .collect();
+            print_json(&items)
+        }
+        _ => {
+            let rows: Vec<Vec<String>> = results
+                .iter()
+                .map(|r| {
+                    vec![
+                        (r.index + 1).to_string(),
+                        entries[r.index].name.clone(),
+                        entries[r.index].username.clone(),
+                        r.count.to_string(),
+                    ]
+                })
+                .collect();
+            print_tsv(&["index", "name", "username", "count"], &rows);
+            Ok(())
+        }
+    }
+}
+
+pub fn print_generated(format: OutputFormat, password: &str) -> Result<(), Box<dyn Error>> {
+    match format {
+        OutputFormat::Json => print_json(&json!({ "password": password, "length": password.chars().count() })),
+        _ => {
+            print_tsv(&["password"], &[vec![password.to_string()]]);
+            Ok(())
+        }
+    }
+}
+
+/// Итог команды, которая меняет хранилище или настройки (`add`, `remove`,
+/// `import`…). В plain ничего не печатает: там итог уже описан текстом.
+pub fn print_status(format: OutputFormat, status: &str, details: Value) -> Result<(), Box<dyn Error>> {
+    let value = status_value(status, details);
+    match format {
+        OutputFormat::Plain => Ok(()),
+        OutputFormat::Json => print_json(&value),
+        OutputFormat::Tsv => {
+            let (header, row) = status_row(&value);
+            print_tsv(&header, &[row]);
+            Ok(())
+        }
+    }
+}
+
+fn status_value(status: &str, details: Value) -> Value {
+    let mut value = json!({ "status": status });
+    if let (Value::Object(map), Value::Object(details)) = (&mut value, details) {
+        map.extend(details);
+    }
+    value
+}
+
+// Строки выводятся как есть, остальные значения — в JSON-записи
+fn status_row(value: &Value) -> (Vec<&str>, Vec<String>) {
+    let Value::Object(map) = value else {
+        return (Vec::new(), Vec::new());
+    };
+    map.iter()
+        .map(|(key, value)| {
+            let cell = match value {
+                Value::String(text) => text.clone(),
+                Value::Null => String::new(),
+                other => other.to_string(),
+            };
+            (key.as_str(), cell)
+        })
+        .unzip()
+}
+
+/// Формат отчёта с учётом устаревшего `--format text|json` у `audit` и
+/// `breach-check`. Явно указанный `--output` важнее.
+pub fn with_legacy_format(output: OutputFormat, format: Option<&str>) -> Result<OutputFormat, CliError> {
+    let Some(format) = format else {
+        return Ok(output);
+    };
+    eprintln!("⚠️  --format устарел, используйте --output");
+    match (output, format) {
+        (OutputFormat::Plain, "text") => Ok(OutputFormat::Plain),
+        (OutputFormat::Plain, "json") => Ok(OutputFormat::Json),
+        (OutputFormat::Plain, _) => Err(CliError::usage(format!("Неподдерживаемый формат: {}", format))),
+        _ => Ok(output),
+    }
+}
+
+// Имя варианта в том же виде, что и в JSON-отчёте (snake_case)
+fn json_name(value: &impl serde::Serialize) -> String {
+    match serde_json::to_value(value) {
+        Ok(Value::String(name)) => name,
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_are_classified_by_type() {
+        let usage = CliError::usage("bad flag");
+        assert_eq!(classify(&usage), ErrorKind::Usage);
+        assert_eq!(classify(&CliError::not_found("нет записи")), ErrorKind::NotFound);
+        let io = std::io::Error::new(std::io::ErrorKind::NotFound, "missing");
+        assert_eq!(classify(&io), ErrorKind::Io);
+        let boxed: Box<dyn Error> = "что-то пошло не так".into();
+        assert_eq!(classify(boxed.as_ref()), ErrorKind::General);
+        let exit_codes: Vec<u8> = [
+            ErrorKind::General,
+            ErrorKind::Usage,
+            ErrorKind::NotFound,
+            ErrorKind::Auth,
+            ErrorKind::Locked,
+            ErrorKind::Io,
+        ]
+        .iter()
+        .map(ErrorKind::exit_code)
+        .collect();
+        assert_eq!(exit_codes, [1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn tsv_escapes_separators_and_backslashes() {
+        assert_eq!(escape_tsv("plain"), "plain");
+        assert_eq!(escape_tsv("a\\tb"), "a\\\\tb");
+        assert_eq!(escape_tsv("a\tb\nc\r"), "a\\tb\\nc\\r");
+        assert_eq!(escape_tsv("путь\\к"), "путь\\\\к");
+    }
+
+    #[test]
+    fn status_merges_details() {
+        let value = status_value("ok", json!({ "id": "abc", "index": 3, "folder": null }));
+        assert_eq!(value, json!({ "status": "ok", "id": "abc", "index": 3, "folder": null }));
+        let (header, row) = status_row(&value);
+        let cells: Vec<(&str, &str)> = header.into_iter().zip(row.iter().map(String::as_str)).collect();
+        assert!(cells.contains(&("status", "ok")));
+        assert!(cells.contains(&("index", "3")));
+        assert!(cells.contains(&("folder", "")));
+    }
+
+    #[test]
+    fn legacy_format_maps_to_output() {
+        assert_eq!(with_legacy_format(OutputFormat::Tsv, None).unwrap(), OutputFormat::Tsv);
+        assert_eq!(with_legacy_format(OutputFormat::Plain, Some("json")).unwrap(), OutputFormat::Json);
+        assert_eq!(with_legacy_format(OutputFormat::Plain, Some("text")).unwrap(), OutputFormat::Plain);
+        assert_eq!(with_legacy_format(OutputFormat::Tsv, Some("json")).unwrap(), OutputFormat::Tsv);
+        let error = with_legacy_format(OutputFormat::Plain, Some("xml")).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Usage);
+    }
+}
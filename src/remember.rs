@@ -0,0 +1,380 @@
+//! «Запомнить на этом устройстве»: вход без мастер-пароля.
+//!
+//! Для каждого запомненного хранилища создаётся случайный ключ обёртки,
+//! он уходит в хранилище ключей ОС (`keystore`). Ключом обёртки шифруется
+//! ключ хранилища (`derive_vault_key_for`), и результат лежит в
+//! `data\remembered_keys.json`. Мастер-пароль нигде не сохраняется.
+//! Смена мастер-пароля или второго фактора делает запомненный ключ
+//! бесполезным — его нужно запомнить заново. Ключ уже учитывает второй
+//! фактор, поэтому вход по нему YubiKey не спрашивает.
+//!
+//! Ключ, запомненный под биометрию (`hiho biometric --enable`), выдаётся
+//! только после проверки отпечатка. Это правило лежит в хранилище ключей
+//! ОС первым байтом секрета, а ключ обёртки выводится из всего секрета
+//! вместе с ним: правку правила или флажка в настройках вход не переживёт.
+
+use crate::biometric::{Authenticator, BiometricManager};
+use crate::crypto::{self, EncryptedData};
+use crate::keystore::KeyStore;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::{DateTime, Utc};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use zeroize::Zeroizing;
+
+const REMEMBER_CONFIG: &str = "data\\remembered_keys.json";
+
+// Первый байт секрета в хранилище ключей ОС: когда выдавать ключ
+const POLICY_ALWAYS: u8 = 0;
+const POLICY_BIOMETRIC: u8 = 1;
+const SECRET_LEN: usize = 33;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct RememberConfig {
+    pub vaults: Vec<RememberedKey>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RememberedKey {
+    /// Абсолютный путь к хранилищу
+    pub vault: PathBuf,
+    /// Имя ключа обёртки в хранилище ключей ОС
+    pub key_id: String,
+    /// Ключ хранилища, зашифрованный ключом обёртки (AES-256-CBC), base64
+    pub wrapped_key: String,
+    pub iv: String,
+    /// Запомнен под биометрию. Для показа: правило выдачи хранится
+    /// в хранилище ключей ОС, флажок может его только ужесточить
+    #[serde(default)]
+    pub biometric: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+pub struct RememberManager;
+
+impl RememberManager {
+    pub fn get_config() -> Result<RememberConfig, Box<dyn Error>> {
+        Self::config_at(Path::new(REMEMBER_CONFIG))
+    }
+
+    pub fn save_config(config: &RememberConfig) -> Result<(), Box<dyn Error>> {
+        Self::save_config_at(Path::new(REMEMBER_CONFIG), config)
+    }
+
+    /// Запомненный ключ хранилища `vault` (абсолютный путь).
+    pub fn find(vault: &Path) -> Result<Option<RememberedKey>, Box<dyn Error>> {
+        Self::find_in(Path::new(REMEMBER_CONFIG), vault)
+    }
+
+    /// Запоминает ключ хранилища: новый ключ обёртки — в `store`,
+    /// обёрнутый ключ хранилища — в настройки. Прежний ключ забывается.
+    pub fn remember(
+        store: &dyn KeyStore,
+        vault: &Path,
+        vault_key: &[u8; 32],
+        biometric: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        Self::remember_in(Path::new(REMEMBER_CONFIG), store, vault, vault_key, biometric)
+    }
+
+    /// Забывает ключ хранилища; `false`, если он не был запомнен.
+    pub fn forget(store: &dyn KeyStore, vault: &Path) -> Result<bool, Box<dyn Error>> {
+        Self::forget_in(Path::new(REMEMBER_CONFIG), store, vault)
+    }
+
+    /// Ключ хранилища из `store`; `None`, если хранилище не запомнено.
+    /// Ключ под биометрию выдаётся после проверки через fprintd.
+    pub fn vault_key(store: &dyn KeyStore, vault: &Path) -> Result<Option<Zeroizing<[u8; 32]>>, Box<dyn Error>> {
+        Self::vault_key_in(Path::new(REMEMBER_CONFIG), store, &BiometricManager::authenticator, vault)
+    }
+
+    // Дальше — то же с явным файлом настроек, чтобы тесты не трогали data\
+
+    fn config_at(path: &Path) -> Result<RememberConfig, Box<dyn Error>> {
+        if path.exists() {
+            let data = fs::read_to_string(path)?;
+            Ok(serde_json::from_str(&data)?)
+        } else {
+            Ok(RememberConfig::default())
+        }
+    }
+
+    fn save_config_at(path: &Path, config: &RememberConfig) -> Result<(), Box<dyn Error>> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let json_data = serde_json::to_string_pretty(config)?;
+        fs::write(path, json_data)?;
+        Ok(())
+    }
+
+    fn find_in(config: &Path, vault: &Path) -> Result<Option<RememberedKey>, Box<dyn Error>> {
+        Ok(Self::config_at(config)?.vaults.into_iter().find(|key| key.vault == vault))
+    }
+
+    fn remember_in(
+        config_path: &Path,
+        store: &dyn KeyStore,
+        vault: &Path,
+        vault_key: &[u8; 32],
+        biometric: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        Self::forget_in(config_path, store, vault)?;
+
+        let mut secret = Zeroizing::new([0u8; SECRET_LEN]);
+        rand::thread_rng().fill_bytes(&mut secret[1..]);
+        secret[0] = if biometric { POLICY_BIOMETRIC } else { POLICY_ALWAYS };
+        let key_id = uuid::Uuid::new_v4().to_string();
+        store.store(&key_id, secret.as_slice())?;
+
+        let wrapped = crypto::encrypt(vault_key, &wrapping_key(secret.as_slice()))?;
+        let mut config = Self::config_at(config_path)?;
+        config.vaults.push(RememberedKey {
+            vault: vault.to_path_buf(),
+            key_id,
+            wrapped_key: BASE64.encode(&wrapped.ciphertext),
+            iv: BASE64.encode(wrapped.iv),
+            biometric,
+            created_at: Utc::now(),
+        });
+        Self::save_config_at(config_path, &config)
+    }
+
+    fn forget_in(config_path: &Path, store: &dyn KeyStore, vault: &Path) -> Result<bool, Box<dyn Error>> {
+        let mut config = Self::config_at(config_path)?;
+        let Some(index) = config.vaults.iter().position(|key| key.vault == vault) else {
+            return Ok(false);
+        };
+        // Без обёрнутого ключа ключ обёртки бесполезен, поэтому запись
+        // удаляется, даже если хранилище ключей сейчас недоступно
+        let remembered = config.vaults.remove(index);
+        Self::save_config_at(config_path, &config)?;
+        store.delete(&remembered.key_id).map_err(|e| {
+            format!("Ключ забыт, но ключ обёртки остался в хранилище ключей ({}): {}", store.describe(), e)
+        })?;
+        Ok(true)
+    }
+
+    fn vault_key_in(
+        config: &Path,
+        store: &dyn KeyStore,
+        authenticator: &dyn Fn() -> Result<Box<dyn Authenticator>, Box<dyn Error>>,
+        vault: &Path,
+    ) -> Result<Option<Zeroizing<[u8; 32]>>, Box<dyn Error>> {
+        let Some(remembered) = Self::find_in(config, vault)? else {
+            return Ok(None);
+        };
+        let secret = store.load(&remembered.key_id)?.ok_or_else(|| {
+            format!("Ключа обёртки нет в хранилище ключей ({}): запомните хранилище заново", store.describe())
+        })?;
+        if secret.len() != SECRET_LEN {
+            return Err("Ключ обёртки повреждён или запомнен старой версией: запомните хранилище заново".into());
+        }
+        let biometric = match secret[0] {
+            POLICY_ALWAYS => remembered.biometric,
+            POLICY_BIOMETRIC => true,
+            _ => return Err("Неизвестное правило выдачи запомненного ключа".into()),
+        };
+        if biometric && !authenticator()?.verify("Подтвердите личность, чтобы открыть хранилище hiho")? {
+            return Err("Биометрическая проверка не пройдена".into());
+        }
+
+        let encrypted = EncryptedData {
+            ciphertext: BASE64.decode(&remembered.wrapped_key)?,
+            iv: BASE64
+                .decode(&remembered.iv)?
+                .try_into()
+                .map_err(|_| "Некорректный IV запомненного ключа")?,
+        };
+        let plaintext = Zeroizing::new(
+            crypto::decrypt(&encrypted, &wrapping_key(&secret))
+                .map_err(|_| "Запомненный ключ не расшифровался ключом обёртки")?,
+        );
+        let key: [u8; 32] = plaintext
+            .as_slice()
+            .try_into()
+            .map_err(|_| "Запомненный ключ повреждён")?;
+        Ok(Some(Zeroizing::new(key)))
+    }
+}
+
+/// Ключ обёртки из секрета в хранилище ключей ОС вместе с байтом правила.
+fn wrapping_key(secret: &[u8]) -> Zeroizing<[u8; 32]> {
+    Zeroizing::new(Sha256::digest(secret).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::biometric::MockAuthenticator;
+    use crate::keystore::FileKeyStore;
+
+    struct Fixture {
+        _dir: tempfile::TempDir,
+        config: PathBuf,
+        keystore: PathBuf,
+        store: FileKeyStore,
+        vault: PathBuf,
+    }
+
+    fn fixture() -> Fixture {
+        let dir = tempfile::tempdir().unwrap();
+        let keystore = dir.path().join("keystore");
+        Fixture {
+            config: dir.path().join("data").join("remembered_keys.json"),
+            store: FileKeyStore::new(&keystore),
+            keystore,
+            vault: dir.path().join("vault.enc"),
+            _dir: dir,
+        }
+    }
+
+    // Ключ без биометрии сканер не спрашивает
+    fn no_scanner() -> Result<Box<dyn Authenticator>, Box<dyn Error>> {
+        Err("сканер не должен понадобиться".into())
+    }
+
+    fn scanner(mock: &MockAuthenticator) -> impl Fn() -> Result<Box<dyn Authenticator>, Box<dyn Error>> + '_ {
+        move || Ok(Box::new(mock.clone()))
+    }
+
+    fn stored_keys(fixture: &Fixture) -> usize {
+        fs::read_dir(&fixture.keystore).map_or(0, |dir| dir.count())
+    }
+
+    #[test]
+    fn remembered_key_unlocks_vault() {
+        let f = fixture();
+        let key = [7u8; 32];
+        RememberManager::remember_in(&f.config, &f.store, &f.vault, &key, false).unwrap();
+
+        let remembered = RememberManager::find_in(&f.config, &f.vault).unwrap().unwrap();
+        assert!(!remembered.biometric);
+        // В настройках только обёрнутый ключ
+        assert!(!fs::read_to_string(&f.config).unwrap().contains(&BASE64.encode(key)));
+        assert_eq!(stored_keys(&f), 1);
+
+        let unlocked = RememberManager::vault_key_in(&f.config, &f.store, &no_scanner, &f.vault).unwrap().unwrap();
+        assert_eq!(*unlocked, key);
+    }
+
+    #[test]
+    fn unknown_vault_has_no_key() {
+        let f = fixture();
+        RememberManager::remember_in(&f.config, &f.store, &f.vault, &[1; 32], false).unwrap();
+        let other = f.vault.with_file_name("other.enc");
+        assert!(RememberManager::vault_key_in(&f.config, &f.store, &no_scanner, &other).unwrap().is_none());
+        assert!(!RememberManager::forget_in(&f.config, &f.store, &other).unwrap());
+    }
+
+    #[test]
+    fn remembering_again_replaces_old_key() {
+        let f = fixture();
+        RememberManager::remember_in(&f.config, &f.store, &f.vault, &[1; 32], false).unwrap();
+        let old_id = RememberManager::find_in(&f.config, &f.vault).unwrap().unwrap().key_id;
+        RememberManager::remember_in(&f.config, &f.store, &f.vault, &[2; 32], false).unwrap();
+
+        let config = RememberManager::config_at(&f.config).unwrap();
+        assert_eq!(config.vaults.len(), 1);
+        assert_ne!(config.vaults[0].key_id, old_id);
+        assert!(f.store.load(&old_id).unwrap().is_none());
+        assert_eq!(stored_keys(&f), 1);
+        assert_eq!(*RememberManager::vault_key_in(&f.config, &f.store, &no_scanner, &f.vault).unwrap().unwrap(), [2; 32]);
+    }
+
+    #[test]
+    fn forget_removes_config_entry_and_wrapping_key() {
+        let f = fixture();
+        RememberManager::remember_in(&f.config, &f.store, &f.vault, &[3; 32], false).unwrap();
+        assert!(RememberManager::forget_in(&f.config, &f.store, &f.vault).unwrap());
+
+        assert!(RememberManager::find_in(&f.config, &f.vault).unwrap().is_none());
+        assert_eq!(stored_keys(&f), 0);
+        assert!(RememberManager::vault_key_in(&f.config, &f.store, &no_scanner, &f.vault).unwrap().is_none());
+        assert!(!RememberManager::forget_in(&f.config, &f.store, &f.vault).unwrap());
+    }
+
+    #[test]
+    fn missing_or_wrong_wrapping_key_is_an_error() {
+        let f = fixture();
+        RememberManager::remember_in(&f.config, &f.store, &f.vault, &[4; 32], false).unwrap();
+        let key_id = RememberManager::find_in(&f.config, &f.vault).unwrap().unwrap().key_id;
+
+        f.store.store(&key_id, &[0u8; SECRET_LEN]).unwrap();
+        assert!(RememberManager::vault_key_in(&f.config, &f.store, &no_scanner, &f.vault).is_err());
+
+        f.store.delete(&key_id).unwrap();
+        let error = RememberManager::vault_key_in(&f.config, &f.store, &no_scanner, &f.vault).err().unwrap();
+        assert!(error.to_string().contains("запомните хранилище заново"), "{}", error);
+    }
+
+    #[test]
+    fn biometric_key_needs_matching_finger() {
+        let f = fixture();
+        RememberManager::remember_in(&f.config, &f.store, &f.vault, &[5; 32], true).unwrap();
+
+        let matching = MockAuthenticator::new(true);
+        let key = RememberManager::vault_key_in(&f.config, &f.store, &scanner(&matching), &f.vault).unwrap();
+        assert_eq!(*key.unwrap(), [5; 32]);
+        assert_eq!(matching.calls.get(), 1);
+
+        let stranger = MockAuthenticator::new(false);
+        let error = RememberManager::vault_key_in(&f.config, &f.store, &scanner(&stranger), &f.vault)
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("не пройдена"), "{}", error);
+        assert_eq!(stranger.calls.get(), 1);
+
+        assert!(RememberManager::vault_key_in(&f.config, &f.store, &no_scanner, &f.vault).is_err());
+    }
+
+    #[test]
+    fn clearing_biometric_flag_in_config_does_not_skip_scanner() {
+        let f = fixture();
+        RememberManager::remember_in(&f.config, &f.store, &f.vault, &[6; 32], true).unwrap();
+        let mut config = RememberManager::config_at(&f.config).unwrap();
+        config.vaults[0].biometric = false;
+        RememberManager::save_config_at(&f.config, &config).unwrap();
+
+        let stranger = MockAuthenticator::new(false);
+        assert!(RememberManager::vault_key_in(&f.config, &f.store, &scanner(&stranger), &f.vault).is_err());
+        assert_eq!(stranger.calls.get(), 1);
+    }
+
+    #[test]
+    fn tampered_policy_in_keystore_breaks_the_key() {
+        let f = fixture();
+        RememberManager::remember_in(&f.config, &f.store, &f.vault, &[7; 32], true).unwrap();
+        let remembered = RememberManager::find_in(&f.config, &f.vault).unwrap().unwrap();
+        let mut secret = f.store.load(&remembered.key_id).unwrap().unwrap();
+        assert_eq!(secret[0], POLICY_BIOMETRIC);
+        secret[0] = POLICY_ALWAYS;
+        f.store.store(&remembered.key_id, &secret).unwrap();
+        let mut config = RememberManager::config_at(&f.config).unwrap();
+        config.vaults[0].biometric = false;
+        RememberManager::save_config_at(&f.config, &config).unwrap();
+
+        // Сканер больше не спрашивают, но ключ обёртки уже другой
+        let result = RememberManager::vault_key_in(&f.config, &f.store, &no_scanner, &f.vault);
+        assert!(result.map_or(true, |key| key.is_none_or(|key| *key != [7; 32])));
+    }
+
+    #[test]
+    fn flag_in_config_can_only_tighten_policy() {
+        let f = fixture();
+        RememberManager::remember_in(&f.config, &f.store, &f.vault, &[8; 32], false).unwrap();
+        let mut config = RememberManager::config_at(&f.config).unwrap();
+        config.vaults[0].biometric = true;
+        RememberManager::save_config_at(&f.config, &config).unwrap();
+
+        let stranger = MockAuthenticator::new(false);
+        assert!(RememberManager::vault_key_in(&f.config, &f.store, &scanner(&stranger), &f.vault).is_err());
+        let matching = MockAuthenticator::new(true);
+        let key = RememberManager::vault_key_in(&f.config, &f.store, &scanner(&matching), &f.vault).unwrap();
+        assert_eq!(*key.unwrap(), [8; 32]);
+    }
+}
@@ -0,0 +1,486 @@
+use crate::entry::Entry;
+use regex::{Regex, RegexBuilder};
+use std::error::Error;
+
+/// Поле записи, к которому можно обратиться в запросе (`user:alice`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchField {
+    Name,
+    Username,
+    Url,
+    Tag,
+    Notes,
+    Folder,
+}
+
+impl SearchField {
+    const ALL: [SearchField; 6] = [
+        SearchField::Name,
+        SearchField::Username,
+        SearchField::Url,
+        SearchField::Tag,
+        SearchField::Notes,
+        SearchField::Folder,
+    ];
+
+    fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "name" | "title" => Some(SearchField::Name),
+            "user" | "username" | "login" => Some(SearchField::Username),
+            "url" | "site" => Some(SearchField::Url),
+            "tag" | "tags" => Some(SearchField::Tag),
+            "note" | "notes" => Some(SearchField::Notes),
+            "folder" | "group" => Some(SearchField::Folder),
+            _ => None,
+        }
+    }
+
+    // Вес совпадения в процентах: совпадение в названии важнее, чем в заметке
+    fn weight(&self) -> u32 {
+        match self {
+            SearchField::Name => 100,
+            SearchField::Username => 80,
+            SearchField::Url => 70,
+            SearchField::Tag => 70,
+            SearchField::Folder => 50,
+            SearchField::Notes => 30,
+        }
+    }
+
+    // Нечёткое совпадение имеет смысл только для коротких полей
+    fn allows_fuzzy(&self) -> bool {
+        matches!(self, SearchField::Name | SearchField::Username | SearchField::Url)
+    }
+
+    fn values<'a>(&self, entry: &'a Entry) -> Vec<&'a str> {
+        match self {
+            SearchField::Name => vec![entry.name.as_str()],
+            SearchField::Username => vec![entry.username.as_str()],
+            SearchField::Url => entry.urls.iter().map(String::as_str).collect(),
+            SearchField::Tag => entry.tags.iter().map(String::as_str).collect(),
+            SearchField::Notes => vec![entry.notes.as_str()],
+            SearchField::Folder => entry.folder.as_deref().into_iter().collect(),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Pattern {
+    /// Текст в нижнем регистре
+    Text(String),
+    Regex(Regex),
+}
+
+#[derive(Debug)]
+enum Expr {
+    Term(Option<SearchField>, Pattern),
+    Not(Box<Expr>),
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+}
+
+#[derive(Debug)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Term(Option<SearchField>, Pattern),
+}
+
+/// Разобранный поисковый запрос.
+///
+/// Синтаксис: слова через пробел должны совпасть все (`AND` можно не писать),
+/// `OR` или `|` — любое из, `NOT` или `-` перед словом — исключение, скобки
+/// группируют. Поле задаётся префиксом: `name:`, `user:`, `url:`, `tag:`,
+/// `notes:`, `folder:`. Фраза с пробелами берётся в кавычки, регулярное
+/// выражение — в слэши: `url:/git(hub|lab)\.com/`.
+///
+/// Без префикса слово ищется во всех полях. В названии, логине и адресе
+/// совпадение может быть нечётким (`gthb` находит `github`), результаты
+/// упорядочиваются по релевантности.
+#[derive(Debug, Default)]
+pub struct SearchQuery {
+    expr: Option<Expr>,
+}
+
+impl SearchQuery {
+    pub fn parse(query: &str) -> Result<Self, Box<dyn Error>> {
+        let tokens = tokenize(query)?;
+        if tokens.is_empty() {
+            return Ok(SearchQuery::default());
+        }
+        let mut parser = Parser { tokens, position: 0 };
+        let expr = parser.parse_or()?;
+        if parser.position < parser.tokens.len() {
+            return Err("Лишняя закрывающая скобка в запросе".into());
+        }
+        Ok(SearchQuery { expr: Some(expr) })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.expr.is_none()
+    }
+
+    /// Релевантность записи; `None` — запись не подходит.
+    pub fn score(&self, entry: &Entry) -> Option<u32> {
+        match &self.expr {
+            Some(expr) => evaluate(expr, entry),
+            None => Some(0),
+        }
+    }
+
+    /// Подходящие записи от самой релевантной; при равенстве сохраняется
+    /// исходный порядок.
+    pub fn rank<'a>(&self, candidates: Vec<(usize, &'a Entry)>) -> Vec<(usize, &'a Entry)> {
+        let mut scored: Vec<(u32, usize, &Entry)> = candidates
+            .into_iter()
+            .filter_map(|(index, entry)| self.score(entry).map(|score| (score, index, entry)))
+            .collect();
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        scored.into_iter().map(|(_, index, entry)| (index, entry)).collect()
+    }
+}
+
+/// Ищет записи по запросу (см. [`SearchQuery`]).
+pub fn search<'a>(entries: &'a [Entry], query: &SearchQuery) -> Vec<(usize, &'a Entry)> {
+    query.rank(entries.iter().enumerate().collect())
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>, Box<dyn Error>> {
+    let chars: Vec<char> = query.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        match c {
+            '(' => {
+                tokens.push(Token::Open);
+                i += 1;
+                continue;
+            }
+            ')' => {
+                tokens.push(Token::Close);
+                i += 1;
+                continue;
+            }
+            '|' => {
+                tokens.push(Token::Or);
+                i += 1;
+                continue;
+            }
+            '-' if chars.get(i + 1).is_some_and(|next| !next.is_whitespace()) => {
+                tokens.push(Token::Not);
+                i += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        // Префикс поля: буквы до двоеточия
+        let mut field = None;
+        let word_end = (i..chars.len())
+            .find(|&j| !chars[j].is_alphabetic())
+            .unwrap_or(chars.len());
+        if chars.get(word_end) == Some(&':') {
+            let prefix: String = chars[i..word_end].iter().collect();
+            if let Some(parsed) = SearchField::parse(&prefix) {
+                field = Some(parsed);
+                i = word_end + 1;
+            }
+        }
+
+        let (pattern, next) = match chars.get(i) {
+            Some('"') => {
+                let end = (i + 1..chars.len())
+                    .find(|&j| chars[j] == '"')
+                    .ok_or("Незакрытая кавычка в запросе")?;
+                let text: String = chars[i + 1..end].iter().collect();
+                (Pattern::Text(text.to_lowercase()), end + 1)
+            }
+            Some('/') => {
+                let end = (i + 1..chars.len())
+                    .find(|&j| chars[j] == '/' && chars[j - 1] != '\\')
+                    .ok_or("Незакрытое регулярное выражение в запросе")?;
+                let source: String = chars[i + 1..end].iter().collect();
+                let regex = RegexBuilder::new(&source)
+                    .case_insensitive(true)
+                    .build()
+                    .map_err(|e| format!("Ошибка в регулярном выражении /{}/: {}", source, e))?;
+                (Pattern::Regex(regex), end + 1)
+            }
+            _ => {
+                let end = (i..chars.len())
+                    .find(|&j| chars[j].is_whitespace() || chars[j] == '(' || chars[j] == ')')
+                    .unwrap_or(chars.len());
+                let text: String = chars[i..end].iter().collect();
+                if field.is_none() {
+                    let operator = match text.as_str() {
+                        "AND" => Some(Token::And),
+                        "OR" => Some(Token::Or),
+                        "NOT" => Some(Token::Not),
+                        _ => None,
+                    };
+                    if let Some(operator) = operator {
+                        tokens.push(operator);
+                        i = end;
+                        continue;
+                    }
+                }
+                (Pattern::Text(text.to_lowercase()), end)
+            }
+        };
+
+        // Пустое значение (`user:`) ни с чем не сравниваем
+        if !matches!(&pattern, Pattern::Text(text) if text.is_empty()) {
+            tokens.push(Token::Term(field, pattern));
+        }
+        i = next;
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, Box<dyn Error>> {
+        let mut items = vec![self.parse_and()?];
+        while matches!(self.peek(), Some(Token::Or)) {
+            self.position += 1;
+            items.push(self.parse_and()?);
+        }
+        Ok(if items.len() == 1 { items.remove(0) } else { Expr::Or(items) })
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, Box<dyn Error>> {
+        let mut items = vec![self.parse_unary()?];
+        loop {
+            match self.peek() {
+                Some(Token::And) => self.position += 1,
+                Some(Token::Or) | Some(Token::Close) | None => break,
+                _ => {}
+            }
+            items.push(self.parse_unary()?);
+        }
+        Ok(if items.len() == 1 { items.remove(0) } else { Expr::And(items) })
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, Box<dyn Error>> {
+        let token = self.tokens.get_mut(self.position).ok_or("Запрос обрывается на операторе")?;
+        // Термы забираем из списка, чтобы не клонировать регулярные выражения
+        let token = std::mem::replace(token, Token::Close);
+        self.position += 1;
+        match token {
+            Token::Not => Ok(Expr::Not(Box::new(self.parse_unary()?))),
+            Token::Open => {
+                let expr = self.parse_or()?;
+                if !matches!(self.peek(), Some(Token::Close)) {
+                    return Err("Незакрытая скобка в запросе".into());
+                }
+                self.position += 1;
+                Ok(expr)
+            }
+            Token::Term(field, pattern) => Ok(Expr::Term(field, pattern)),
+            Token::Close => Err("Пустые скобки или лишняя закрывающая скобка в запросе".into()),
+            Token::And | Token::Or => Err("Оператор без левого операнда в запросе".into()),
+        }
+    }
+}
+
+fn evaluate(expr: &Expr, entry: &Entry) -> Option<u32> {
+    match expr {
+        Expr::Term(field, pattern) => match field {
+            Some(field) => field_score(*field, pattern, entry),
+            None => SearchField::ALL
+                .iter()
+                .filter_map(|field| field_score(*field, pattern, entry))
+                .max(),
+        },
+        Expr::Not(inner) => match evaluate(inner, entry) {
+            Some(_) => None,
+            None => Some(0),
+        },
+        Expr::And(items) => items
+            .iter()
+            .try_fold(0, |total, item| evaluate(item, entry).map(|score| total + score)),
+        Expr::Or(items) => items.iter().filter_map(|item| evaluate(item, entry)).max(),
+    }
+}
+
+fn field_score(field: SearchField, pattern: &Pattern, entry: &Entry) -> Option<u32> {
+    field
+        .values(entry)
+        .into_iter()
+        .filter_map(|value| match pattern {
+            Pattern::Regex(regex) => regex.is_match(value).then_some(60),
+            // Тег указывают целиком, поэтому для tag: только точное совпадение
+            Pattern::Text(text) if field == SearchField::Tag => {
+                (value.to_lowercase() == *text).then_some(100)
+            }
+            Pattern::Text(text) => text_score(&value.to_lowercase(), text, field.allows_fuzzy()),
+        })
+        .max()
+        .map(|score| score * field.weight() / 100)
+}
+
+// 100 — полное совпадение, 80 — начало, 70 — начало слова, 60 — подстрока,
+// до 40 — нечёткое совпадение по подпоследовательности
+fn text_score(value: &str, pattern: &str, fuzzy: bool) -> Option<u32> {
+    if value == pattern {
+        return Some(100);
+    }
+    if value.starts_with(pattern) {
+        return Some(80);
+    }
+    if let Some(position) = value.find(pattern) {
+        let at_word_start = value[..position]
+            .chars()
+            .next_back()
+            .is_some_and(|c| !c.is_alphanumeric());
+        return Some(if at_word_start { 70 } else { 60 });
+    }
+    if fuzzy {
+        fuzzy_score(value, pattern)
+    } else {
+        None
+    }
+}
+
+// Все символы запроса должны встретиться в значении по порядку. Подряд идущие
+// символы и символы в начале слов ценятся выше, пропуски снижают оценку.
+fn fuzzy_score(value: &str, pattern: &str) -> Option<u32> {
+    let value: Vec<char> = value.chars().collect();
+    let pattern: Vec<char> = pattern.chars().collect();
+    if pattern.len() < 2 {
+        return None;
+    }
+
+    let mut points: i64 = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+    for c in &pattern {
+        let found = (position..value.len()).find(|&j| value[j] == *c)?;
+        points += 10;
+        if previous.is_some_and(|p| p + 1 == found) {
+            points += 8;
+        }
+        if found == 0 || !value[found - 1].is_alphanumeric() {
+            points += 6;
+        }
+        if let Some(p) = previous {
+            points -= (found - p - 1).min(5) as i64;
+        }
+        previous = Some(found);
+        position = found + 1;
+    }
+
+    let best = pattern.len() as i64 * 24;
+    Some((points.max(1) * 40 / best).clamp(1, 40) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries() -> Vec<Entry> {
+        let mut github = Entry::new("GitHub".into(), "octocat".into(), "p".into());
+        github.urls = vec!["https://github.com/login".into()];
+        github.tags = vec!["work".into(), "dev".into()];
+        github.folder = Some("Работа".into());
+        let mut gitlab = Entry::new("GitLab".into(), "alice".into(), "p".into());
+        gitlab.urls = vec!["https://gitlab.com".into()];
+        gitlab.tags = vec!["dev".into()];
+        let mut bank = Entry::new("Банк".into(), "alice".into(), "p".into());
+        bank.notes = "карта заканчивается на 1234, github не нужен".into();
+        bank.tags = vec!["finance".into()];
+        vec![github, gitlab, bank]
+    }
+
+    fn names(query: &str) -> Vec<String> {
+        let entries = entries();
+        let query = SearchQuery::parse(query).unwrap();
+        search(&entries, &query).into_iter().map(|(_, entry)| entry.name.clone()).collect()
+    }
+
+    #[test]
+    fn empty_query_matches_everything_in_order() {
+        assert!(SearchQuery::parse("   ").unwrap().is_empty());
+        assert_eq!(names(""), vec!["GitHub", "GitLab", "Банк"]);
+    }
+
+    #[test]
+    fn words_are_and_by_default() {
+        assert_eq!(names("lab alice"), vec!["GitLab"]);
+        assert_eq!(names("lab AND alice"), vec!["GitLab"]);
+    }
+
+    #[test]
+    fn or_not_and_parentheses() {
+        assert_eq!(names("user:octocat OR tag:finance"), vec!["GitHub", "Банк"]);
+        assert_eq!(names("user:octocat | tag:finance"), vec!["GitHub", "Банк"]);
+        assert_eq!(names("tag:dev -user:octocat"), vec!["GitLab"]);
+        assert_eq!(names("NOT tag:dev"), vec!["Банк"]);
+        assert_eq!(names("(name:github | name:банк) user:alice"), vec!["Банк"]);
+    }
+
+    #[test]
+    fn field_prefixes_restrict_search() {
+        assert_eq!(names("url:gitlab.com"), vec!["GitLab"]);
+        assert_eq!(names("folder:работа"), vec!["GitHub"]);
+        assert_eq!(names("notes:1234"), vec!["Банк"]);
+        // Тег сравнивается целиком
+        assert!(names("tag:fin").is_empty());
+        // Неизвестный префикс — часть слова
+        assert!(names("foo:bar").is_empty());
+    }
+
+    #[test]
+    fn tag_match_ignores_unicode_case() {
+        let mut entry = Entry::new("VPN".into(), "alice".into(), "p".into());
+        entry.tags = vec!["Работа".into()];
+        let entries = [entry];
+        for query in ["tag:работа", "tag:РАБОТА", "tag:Работа"] {
+            let query = SearchQuery::parse(query).unwrap();
+            assert_eq!(search(&entries, &query).len(), 1);
+        }
+        assert!(search(&entries, &SearchQuery::parse("tag:работ").unwrap()).is_empty());
+    }
+
+    #[test]
+    fn quoted_phrases_and_regex() {
+        assert_eq!(names("\"на 1234\""), vec!["Банк"]);
+        assert_eq!(names(r"url:/git(hub|lab)\.com/"), vec!["GitHub", "GitLab"]);
+        assert_eq!(names("name:/^BANK$|^банк$/"), vec!["Банк"]);
+    }
+
+    #[test]
+    fn fuzzy_matches_short_fields_only() {
+        assert_eq!(names("gthb"), vec!["GitHub"]);
+        assert!(names("notes:крт").is_empty());
+    }
+
+    #[test]
+    fn name_match_ranks_above_notes_match() {
+        assert_eq!(names("github"), vec!["GitHub", "Банк"]);
+    }
+
+    #[test]
+    fn syntax_errors_are_reported() {
+        for query in ["\"open", "/open", "(git", "git)", "()", "OR git", "git OR", "url:/(/"] {
+            assert!(SearchQuery::parse(query).is_err(), "{}", query);
+        }
+    }
+}
@@ -0,0 +1,428 @@
+//! Второй фактор разблокировки: HMAC-SHA1 challenge-response YubiKey.
+//!
+//! При включении создаётся случайный запрос (challenge) и сохраняется
+//! в `data\second_factor.json`. Ответ ключа на этот запрос смешивается
+//! с ключом из мастер-пароля через HKDF-SHA256, и получившимся ключом
+//! шифруется vault.enc. Украденных файла хранилища и пароля без YubiKey
+//! недостаточно. Запрос не секретен, но без него хранилище не открыть:
+//! копируйте настройки вместе с хранилищем.
+//!
+//! YubiKey опрашивается через `ykchalresp` из yubikey-personalization;
+//! слот настраивается, например, `ykman otp chalresp --generate 2`.
+//! В тестах YubiKey заменяет `Emulator`, который считает тот же HMAC-SHA1
+//! с известным секретом.
+
+use crate::vault::{self, derive_vault_key, open_entries, seal_entries, UnlockError, Vault};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::{DateTime, Utc};
+use hkdf::Hkdf;
+#[cfg(test)]
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+#[cfg(test)]
+use sha1::Sha1;
+use sha2::Sha256;
+use std::error::Error;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use zeroize::Zeroizing;
+
+const SECOND_FACTOR_CONFIG: &str = "data\\second_factor.json";
+
+const CHALLENGE_LEN: usize = 32;
+
+/// Устройство, которое отвечает на запрос.
+pub trait ChallengeResponse {
+    /// Название для сообщений, например «YubiKey (слот 2)»
+    fn describe(&self) -> String;
+
+    /// HMAC-SHA1 запроса секретом устройства.
+    fn respond(&self, challenge: &[u8]) -> Result<Zeroizing<Vec<u8>>, Box<dyn Error>>;
+}
+
+pub struct YubiKey {
+    pub slot: u8,
+}
+
+impl ChallengeResponse for YubiKey {
+    fn describe(&self) -> String {
+        format!("YubiKey (слот {})", self.slot)
+    }
+
+    fn respond(&self, challenge: &[u8]) -> Result<Zeroizing<Vec<u8>>, Box<dyn Error>> {
+        let challenge: String = challenge.iter().map(|byte| format!("{:02x}", byte)).collect();
+        eprintln!("🔑 Коснитесь YubiKey, если он мигает");
+        let output = Command::new("ykchalresp")
+            .arg(format!("-{}", self.slot))
+            .arg("-x")
+            .arg(challenge)
+            .output()
+            .map_err(|e| match e.kind() {
+                ErrorKind::NotFound => "ykchalresp не найден: установите yubikey-personalization".to_string(),
+                _ => format!("Не удалось запустить ykchalresp: {}", e),
+            })?;
+        if !output.status.success() {
+            let message = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("{} не ответил: {}", self.describe(), message.trim()).into());
+        }
+        let stdout = Zeroizing::new(output.stdout);
+        let hex = std::str::from_utf8(&stdout)
+            .map_err(|_| "Некорректный ответ ykchalresp")?
+            .trim();
+        decode_hex(hex).ok_or_else(|| "Некорректный ответ ykchalresp".into())
+    }
+}
+
+/// Программный YubiKey для тестов: HMAC-SHA1 с известным секретом.
+#[cfg(test)]
+pub(crate) struct Emulator {
+    secret: Zeroizing<Vec<u8>>,
+}
+
+#[cfg(test)]
+impl Emulator {
+    pub(crate) fn new(secret: &[u8]) -> Self {
+        Self {
+            secret: Zeroizing::new(secret.to_vec()),
+        }
+    }
+}
+
+#[cfg(test)]
+impl ChallengeResponse for Emulator {
+    fn describe(&self) -> String {
+        "программный эмулятор".to_string()
+    }
+
+    fn respond(&self, challenge: &[u8]) -> Result<Zeroizing<Vec<u8>>, Box<dyn Error>> {
+        let mut mac = <Hmac<Sha1> as Mac>::new_from_slice(&self.secret).map_err(|_| "Некорректный секрет эмулятора")?;
+        mac.update(challenge);
+        Ok(Zeroizing::new(mac.finalize().into_bytes().to_vec()))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Device {
+    YubiKey { slot: u8 },
+}
+
+impl Device {
+    pub fn describe(&self) -> String {
+        self.open().describe()
+    }
+
+    pub fn open(&self) -> Box<dyn ChallengeResponse> {
+        match self {
+            Device::YubiKey { slot } => Box::new(YubiKey { slot: *slot }),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SecondFactorConfig {
+    pub vaults: Vec<SecondFactor>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SecondFactor {
+    /// Абсолютный путь к хранилищу
+    pub vault: PathBuf,
+    pub device: Device,
+    /// Запрос к устройству, base64
+    pub challenge: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl SecondFactor {
+    /// Ключ хранилища из ключа мастер-пароля и ответа устройства.
+    pub fn mix(&self, password_key: &[u8; 32]) -> Result<[u8; 32], Box<dyn Error>> {
+        self.mix_with(self.device.open().as_ref(), password_key)
+    }
+
+    fn mix_with(&self, responder: &dyn ChallengeResponse, password_key: &[u8; 32]) -> Result<[u8; 32], Box<dyn Error>> {
+        let challenge = BASE64.decode(&self.challenge)?;
+        let response = responder.respond(&challenge)?;
+        let mut key = [0u8; 32];
+        Hkdf::<Sha256>::new(Some(&response), password_key)
+            .expand(b"hiho second factor", &mut key)
+            .map_err(|_| "Ошибка HKDF")?;
+        Ok(key)
+    }
+}
+
+pub struct SecondFactorManager;
+
+impl SecondFactorManager {
+    pub fn get_config() -> Result<SecondFactorConfig, Box<dyn Error>> {
+        Self::config_at(Path::new(SECOND_FACTOR_CONFIG))
+    }
+
+    pub fn save_config(config: &SecondFactorConfig) -> Result<(), Box<dyn Error>> {
+        Self::save_config_at(Path::new(SECOND_FACTOR_CONFIG), config)
+    }
+
+    /// Второй фактор хранилища `vault` (абсолютный путь).
+    pub fn find(vault: &Path) -> Result<Option<SecondFactor>, Box<dyn Error>> {
+        Ok(Self::get_config()?.vaults.into_iter().find(|factor| factor.vault == vault))
+    }
+
+    /// Перешифровывает хранилище ключом с ответом устройства. Устройство
+    /// опрашивается до того, как что-либо записано.
+    pub fn enable(vault: &Path, password: &str, device: Device) -> Result<(), Box<dyn Error>> {
+        Self::enable_with(Path::new(SECOND_FACTOR_CONFIG), vault, password, device, device.open().as_ref())
+    }
+
+    /// Возвращает хранилищу ключ только из мастер-пароля.
+    pub fn disable(vault: &Path, password: &str) -> Result<(), Box<dyn Error>> {
+        let config_path = Path::new(SECOND_FACTOR_CONFIG);
+        let device = Self::find(vault)?
+            .ok_or("Второй фактор для этого хранилища не включён")?
+            .device;
+        Self::disable_with(config_path, vault, password, device.open().as_ref())
+    }
+
+    // Дальше — то же с явным файлом настроек и устройством, чтобы тесты
+    // обходились без data\ и YubiKey
+
+    fn config_at(path: &Path) -> Result<SecondFactorConfig, Box<dyn Error>> {
+        if path.exists() {
+            let data = fs::read_to_string(path)?;
+            Ok(serde_json::from_str(&data)?)
+        } else {
+            Ok(SecondFactorConfig::default())
+        }
+    }
+
+    fn save_config_at(path: &Path, config: &SecondFactorConfig) -> Result<(), Box<dyn Error>> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let json_data = serde_json::to_string_pretty(config)?;
+        fs::write(path, json_data)?;
+        Ok(())
+    }
+
+    fn enable_with(
+        config_path: &Path,
+        vault: &Path,
+        password: &str,
+        device: Device,
+        responder: &dyn ChallengeResponse,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut config = Self::config_at(config_path)?;
+        if config.vaults.iter().any(|factor| factor.vault == vault) {
+            return Err("Второй фактор для этого хранилища уже включён".into());
+        }
+        let data = read_container(vault)?;
+        let password_key = Zeroizing::new(derive_vault_key(password)?);
+        let entries = Vault::detached(open_entries(&data, &password_key).map_err(|_| UnlockError)?);
+
+        let mut challenge = [0u8; CHALLENGE_LEN];
+        rand::thread_rng().fill_bytes(&mut challenge);
+        let factor = SecondFactor {
+            vault: vault.to_path_buf(),
+            device,
+            challenge: BASE64.encode(challenge),
+            created_at: Utc::now(),
+        };
+        let key = Zeroizing::new(factor.mix_with(responder, &password_key)?);
+        let sealed = seal_entries(entries.get_entries(), &key)?;
+
+        config.vaults.push(factor);
+        Self::save_config_at(config_path, &config)?;
+        if let Err(e) = replace_file(vault, &sealed) {
+            config.vaults.pop();
+            Self::save_config_at(config_path, &config)?;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn disable_with(
+        config_path: &Path,
+        vault: &Path,
+        password: &str,
+        responder: &dyn ChallengeResponse,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut config = Self::config_at(config_path)?;
+        let index = config
+            .vaults
+            .iter()
+            .position(|factor| factor.vault == vault)
+            .ok_or("Второй фактор для этого хранилища не включён")?;
+        let data = read_container(vault)?;
+        let password_key = Zeroizing::new(derive_vault_key(password)?);
+        let key = Zeroizing::new(config.vaults[index].mix_with(responder, &password_key)?);
+        let entries = Vault::detached(open_entries(&data, &key).map_err(|_| UnlockError)?);
+        let sealed = seal_entries(entries.get_entries(), &password_key)?;
+
+        let factor = config.vaults.remove(index);
+        Self::save_config_at(config_path, &config)?;
+        if let Err(e) = replace_file(vault, &sealed) {
+            config.vaults.insert(index, factor);
+            Self::save_config_at(config_path, &config)?;
+            return Err(e);
+        }
+        Ok(())
+    }
+}
+
+/// Записывает хранилище через временный файл рядом: при сбое прежнее
+/// содержимое остаётся целым.
+fn replace_file(path: &Path, data: &[u8]) -> Result<(), Box<dyn Error>> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    let temporary = PathBuf::from(temporary);
+    let result = fs::write(&temporary, data).and_then(|()| fs::rename(&temporary, path));
+    if let Err(e) = result {
+        let _ = fs::remove_file(&temporary);
+        return Err(format!("Не удалось перезаписать {}: {}", path.display(), e).into());
+    }
+    Ok(())
+}
+
+fn read_container(vault: &Path) -> Result<Vec<u8>, Box<dyn Error>> {
+    if crate::kdbx::is_kdbx(vault) {
+        return Err("Второй фактор поддерживается только для vault.enc".into());
+    }
+    if !vault.exists() {
+        return Err(format!("Хранилище {} ещё не создано", vault.display()).into());
+    }
+    let data = fs::read(vault)?;
+    if !vault::is_container(&data, data.len() as u64) {
+        return Err(format!("{} не похож на хранилище hiho", vault.display()).into());
+    }
+    Ok(data)
+}
+
+fn decode_hex(hex: &str) -> Option<Zeroizing<Vec<u8>>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| hex.get(i..i + 2).and_then(|pair| u8::from_str_radix(pair, 16).ok()))
+        .collect::<Option<Vec<u8>>>()
+        .map(Zeroizing::new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entry::Entry;
+
+    const PASSWORD: &str = "correct horse";
+    const DEVICE: Device = Device::YubiKey { slot: 2 };
+
+    struct Fixture {
+        _dir: tempfile::TempDir,
+        config: PathBuf,
+        vault: PathBuf,
+    }
+
+    // Хранилище с одной записью, зашифрованное одним мастер-паролем
+    fn fixture() -> Fixture {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path().join("vault.enc");
+        let entries = [Entry::new("GitHub".into(), "octocat".into(), "hunter2".into())];
+        let key = derive_vault_key(PASSWORD).unwrap();
+        fs::write(&vault, seal_entries(&entries, &key).unwrap()).unwrap();
+        Fixture {
+            config: dir.path().join("data").join("second_factor.json"),
+            vault,
+            _dir: dir,
+        }
+    }
+
+    fn open_with(vault: &Path, key: &[u8; 32]) -> Option<Vec<Entry>> {
+        open_entries(&fs::read(vault).unwrap(), key).ok()
+    }
+
+    fn factor(f: &Fixture) -> Option<SecondFactor> {
+        SecondFactorManager::config_at(&f.config)
+            .unwrap()
+            .vaults
+            .into_iter()
+            .find(|factor| factor.vault == f.vault)
+    }
+
+    #[test]
+    fn enable_mix_disable_round_trip() {
+        let f = fixture();
+        let yubikey = Emulator::new(b"yubikey secret");
+        let password_key = derive_vault_key(PASSWORD).unwrap();
+
+        SecondFactorManager::enable_with(&f.config, &f.vault, PASSWORD, DEVICE, &yubikey).unwrap();
+        let enabled = factor(&f).unwrap();
+        assert_eq!(enabled.device, DEVICE);
+        assert_eq!(BASE64.decode(&enabled.challenge).unwrap().len(), CHALLENGE_LEN);
+        assert!(open_with(&f.vault, &password_key).is_none());
+        let mixed = enabled.mix_with(&yubikey, &password_key).unwrap();
+        let entries = open_with(&f.vault, &mixed).unwrap();
+        assert_eq!(entries[0].password, "hunter2");
+
+        let again = SecondFactorManager::enable_with(&f.config, &f.vault, PASSWORD, DEVICE, &yubikey);
+        assert!(again.is_err());
+
+        SecondFactorManager::disable_with(&f.config, &f.vault, PASSWORD, &yubikey).unwrap();
+        assert!(factor(&f).is_none());
+        assert_eq!(open_with(&f.vault, &password_key).unwrap()[0].password, "hunter2");
+        assert!(SecondFactorManager::disable_with(&f.config, &f.vault, PASSWORD, &yubikey).is_err());
+    }
+
+    #[test]
+    fn wrong_response_is_rejected() {
+        let f = fixture();
+        let yubikey = Emulator::new(b"yubikey secret");
+        let other = Emulator::new(b"another secret");
+        SecondFactorManager::enable_with(&f.config, &f.vault, PASSWORD, DEVICE, &yubikey).unwrap();
+        let before = fs::read(&f.vault).unwrap();
+
+        let password_key = derive_vault_key(PASSWORD).unwrap();
+        let wrong = factor(&f).unwrap().mix_with(&other, &password_key).unwrap();
+        assert!(open_with(&f.vault, &wrong).is_none());
+
+        let error = SecondFactorManager::disable_with(&f.config, &f.vault, PASSWORD, &other).err().unwrap();
+        assert!(error.is::<UnlockError>());
+        assert!(SecondFactorManager::disable_with(&f.config, &f.vault, "wrong password", &yubikey).is_err());
+        // Ничего не изменилось
+        assert!(factor(&f).is_some());
+        assert_eq!(fs::read(&f.vault).unwrap(), before);
+    }
+
+    #[test]
+    fn failed_reseal_rolls_back_config() {
+        let f = fixture();
+        let yubikey = Emulator::new(b"yubikey secret");
+        let password_key = derive_vault_key(PASSWORD).unwrap();
+        // Каталог на месте временного файла: записать хранилище не получится
+        let temporary = f.vault.with_file_name("vault.enc.tmp");
+        fs::create_dir(&temporary).unwrap();
+
+        let error = SecondFactorManager::enable_with(&f.config, &f.vault, PASSWORD, DEVICE, &yubikey).err().unwrap();
+        assert!(error.to_string().contains("Не удалось перезаписать"), "{}", error);
+        assert!(factor(&f).is_none());
+        assert!(open_with(&f.vault, &password_key).is_some());
+
+        fs::remove_dir(&temporary).unwrap();
+        SecondFactorManager::enable_with(&f.config, &f.vault, PASSWORD, DEVICE, &yubikey).unwrap();
+        let sealed = fs::read(&f.vault).unwrap();
+        fs::create_dir(&temporary).unwrap();
+
+        assert!(SecondFactorManager::disable_with(&f.config, &f.vault, PASSWORD, &yubikey).is_err());
+        assert!(factor(&f).is_some());
+        assert_eq!(fs::read(&f.vault).unwrap(), sealed);
+    }
+
+    #[test]
+    fn emulator_matches_rfc2202_hmac_sha1() {
+        // RFC 2202, тест 2
+        let response = Emulator::new(b"Jefe").respond(b"what do ya want for nothing?").unwrap();
+        let expected = decode_hex("effcdf6ae5eb2fa2d27416d5f184df9c259a7c79").unwrap();
+        assert_eq!(response.as_slice(), expected.as_slice());
+    }
+}
@@ -0,0 +1,313 @@
+//! Провайдер freedesktop Secret Service (`org.freedesktop.secrets`).
+//!
+//! `hiho secret-service` занимает имя `org.freedesktop.secrets` на сессионной
+//! шине D-Bus и показывает одну папку хранилища как коллекцию `hiho`
+//! с псевдонимом `default`. Так клиенты libsecret (приложения GNOME,
+//! `secret-tool`), Python keyring и SecretStorage хранят и читают свои
+//! секреты в hiho, а не в gnome-keyring.
+//!
+//! Каждый элемент коллекции — запись из этой папки: метка — название записи,
+//! секрет — пароль, атрибуты — незащищённые дополнительные поля. Записи
+//! в других папках через шину не видны.
+//!
+//! Хранилище открывает агент, как для помощника git: коллекция открыта,
+//! пока открыт агент. `Unlock` спрашивает мастер-пароль через `$SSH_ASKPASS`
+//! (по умолчанию `ssh-askpass`) и открывает хранилище в агенте, `Lock`
+//! блокирует агент. Пока хранилище заблокировано, поиск работает по
+//! атрибутам, запомненным с последнего открытия, а секреты недоступны.
+//!
+//! Шина берётся из `DBUS_SESSION_BUS_ADDRESS`, поэтому сервис можно
+//! проверить на отдельной шине: `dbus-run-session -- sh -c 'hiho
+//! secret-service & secret-tool lookup …'`.
+
+mod objects;
+pub(crate) mod session;
+
+use crate::agent::{self, AgentClient};
+use crate::entry::{CustomField, Entry};
+use crate::Vault;
+use objects::SecretError;
+use session::Cipher;
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Имя сервиса на шине
+pub const BUS_NAME: &str = "org.freedesktop.secrets";
+/// Папка хранилища, которая видна через Secret Service, если не задана другая
+pub const DEFAULT_FOLDER: &str = "Secret Service";
+
+const SERVICE_PATH: &str = "/org/freedesktop/secrets";
+const COLLECTION_PATH: &str = "/org/freedesktop/secrets/collection/hiho";
+const DEFAULT_ALIAS: &str = "default";
+const ALIAS_PATH: &str = "/org/freedesktop/secrets/aliases/default";
+
+/// Занимает имя сервиса на сессионной шине и обслуживает запросы, пока
+/// процесс не завершат.
+pub fn serve(vault: PathBuf, folder: String) -> Result<(), Box<dyn Error>> {
+    let state = Arc::new(State::new(vault, folder, agent::socket_path()));
+    let _connection = publish(zbus::blocking::ConnectionBuilder::session()?, &state)?;
+    loop {
+        std::thread::park();
+    }
+}
+
+// Выставляет объекты сервиса на шину и занимает имя BUS_NAME
+fn publish(
+    builder: zbus::blocking::ConnectionBuilder<'_>,
+    state: &Arc<State>,
+) -> Result<zbus::blocking::Connection, Box<dyn Error>> {
+    let connection = builder
+        .serve_at(SERVICE_PATH, objects::Service::new(Arc::clone(state)))?
+        .serve_at(COLLECTION_PATH, objects::Collection::new(Arc::clone(state)))?
+        // SecretStorage обращается к коллекции по умолчанию по этому пути
+        .serve_at(ALIAS_PATH, objects::Collection::new(Arc::clone(state)))?
+        .name(BUS_NAME)?
+        .build()
+        .map_err(|e| match e {
+            zbus::Error::NameTaken => format!(
+                "Имя {} уже занято: остановите gnome-keyring, KeePassXC или другой запущенный сервис",
+                BUS_NAME
+            ),
+            e => format!("Не удалось подключиться к сессионной шине D-Bus: {}", e),
+        })?;
+    zbus::block_on(objects::sync(state, connection.object_server().inner())).map_err(|e| e.to_string())?;
+    Ok(connection)
+}
+
+/// Общее состояние объектов на шине.
+pub(crate) struct State {
+    vault: PathBuf,
+    folder: String,
+    /// Сокет агента, который держит хранилище открытым
+    agent: PathBuf,
+    inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    /// Элементы по последнему сегменту пути; переживают блокировку
+    items: BTreeMap<String, CachedItem>,
+    /// Открытые сессии по пути объекта
+    sessions: HashMap<String, Session>,
+    next_id: u64,
+}
+
+/// То, что известно об элементе без расшифровки хранилища.
+#[derive(Clone, PartialEq)]
+pub(crate) struct CachedItem {
+    pub label: String,
+    pub attributes: HashMap<String, String>,
+    pub created: u64,
+    pub modified: u64,
+}
+
+struct Session {
+    /// Уникальное имя клиента на шине: чужой сессией пользоваться нельзя
+    owner: String,
+    cipher: Cipher,
+}
+
+impl State {
+    fn new(vault: PathBuf, folder: String, agent: PathBuf) -> Self {
+        Self {
+            vault,
+            folder,
+            agent,
+            inner: Mutex::new(Inner::default()),
+        }
+    }
+
+    /// Клиент агента; `None`, если агент не запущен.
+    fn agent(&self) -> Option<AgentClient> {
+        AgentClient::connect_to(&self.agent).ok()
+    }
+
+    fn inner(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn next_id(&self) -> u64 {
+        let mut inner = self.inner();
+        inner.next_id += 1;
+        inner.next_id
+    }
+
+    /// Записи хранилища; `None` — агент не запущен или заблокирован.
+    fn load(&self) -> Result<Option<Vec<Entry>>, SecretError> {
+        let Some(mut client) = self.agent() else {
+            return Ok(None);
+        };
+        client.entries(&self.vault).map_err(objects::failed)
+    }
+
+    /// Копия хранилища для изменения; заблокированное — ошибка `IsLocked`.
+    fn open_vault(&self) -> Result<Vault, SecretError> {
+        let entries = self.load()?.ok_or_else(objects::locked)?;
+        Ok(Vault::detached(entries))
+    }
+
+    fn store(&self, vault: &Vault) -> Result<(), SecretError> {
+        let mut client = self.agent().ok_or_else(objects::locked)?;
+        client
+            .store(&self.vault, vault.get_entries().clone())
+            .map_err(objects::failed)
+    }
+
+    fn is_open(&self) -> bool {
+        self.agent()
+            .and_then(|mut client| client.status().ok())
+            .is_some_and(|status| status.vault.as_ref() == Some(&self.vault))
+    }
+
+    fn unlock(&self, password: &str) -> Result<(), Box<dyn Error>> {
+        let mut client = self.agent().ok_or("агент не запущен (hiho agent start)")?;
+        client.unlock(&self.vault, password)
+    }
+
+    fn lock(&self) -> Result<(), SecretError> {
+        match self.agent() {
+            Some(mut client) => client.lock().map_err(objects::failed),
+            None => Ok(()),
+        }
+    }
+
+    fn exposes(&self, entry: &Entry) -> bool {
+        entry.folder.as_deref() == Some(self.folder.as_str())
+    }
+
+    /// Индекс записи элемента среди записей хранилища.
+    fn position(&self, entries: &[Entry], segment: &str) -> Option<usize> {
+        entries
+            .iter()
+            .position(|entry| self.exposes(entry) && path_segment(&entry.id) == segment)
+    }
+
+    /// Элементы открытого хранилища по сегменту пути.
+    fn items(&self, entries: &[Entry]) -> BTreeMap<String, CachedItem> {
+        entries
+            .iter()
+            .filter(|entry| self.exposes(entry) && !entry.id.is_empty())
+            .map(|entry| (path_segment(&entry.id), CachedItem::from(entry)))
+            .collect()
+    }
+
+    fn cached(&self, segment: &str) -> Option<CachedItem> {
+        self.inner().items.get(segment).cloned()
+    }
+}
+
+impl From<&Entry> for CachedItem {
+    fn from(entry: &Entry) -> Self {
+        let timestamp = |time: Option<chrono::DateTime<chrono::Utc>>| time.map_or(0, |t| t.timestamp().max(0) as u64);
+        Self {
+            label: entry.name.clone(),
+            attributes: attributes(entry),
+            created: timestamp(entry.created_at),
+            modified: timestamp(entry.updated_at),
+        }
+    }
+}
+
+/// Атрибуты элемента: незащищённые дополнительные поля записи.
+fn attributes(entry: &Entry) -> HashMap<String, String> {
+    entry
+        .custom_fields
+        .iter()
+        .filter(|field| !field.protected)
+        .map(|field| (field.name.clone(), field.value.clone()))
+        .collect()
+}
+
+/// Дополнительные поля с новыми атрибутами; защищённые поля остаются.
+fn with_attributes(fields: &[CustomField], attributes: &HashMap<String, String>) -> Vec<CustomField> {
+    let sorted: BTreeMap<_, _> = attributes.iter().collect();
+    fields
+        .iter()
+        .filter(|field| field.protected)
+        .cloned()
+        .chain(sorted.into_iter().map(|(name, value)| CustomField {
+            name: name.clone(),
+            value: value.clone(),
+            protected: false,
+        }))
+        .collect()
+}
+
+// В пути объекта D-Bus допустимы только [A-Za-z0-9_]; остальное — `_xx`
+fn path_segment(id: &str) -> String {
+    id.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' => (byte as char).to_string(),
+            _ => format!("_{:02x}", byte),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str, value: &str, protected: bool) -> CustomField {
+        CustomField {
+            name: name.to_string(),
+            value: value.to_string(),
+            protected,
+        }
+    }
+
+    fn entry(id: &str, folder: Option<&str>, fields: Vec<CustomField>) -> Entry {
+        let mut entry = Entry::new(format!("Запись {}", id), "user".into(), "secret".into());
+        entry.id = id.to_string();
+        entry.folder = folder.map(str::to_string);
+        entry.custom_fields = fields;
+        entry
+    }
+
+    #[test]
+    fn path_segment_escapes_everything_but_alphanumerics() {
+        assert_eq!(path_segment("abcXYZ019"), "abcXYZ019");
+        assert_eq!(path_segment("a-b_c"), "a_2db_5fc");
+        assert_eq!(path_segment("ключ"), "_d0_ba_d0_bb_d1_8e_d1_87");
+        assert_ne!(path_segment("a_2d"), path_segment("a-"));
+    }
+
+    #[test]
+    fn items_come_only_from_the_exposed_folder() {
+        let state = State::new(PathBuf::from("vault.json"), DEFAULT_FOLDER.to_string(), PathBuf::from("agent.sock"));
+        let entries = vec![
+            entry("a-1", Some(DEFAULT_FOLDER), vec![field("service", "mail", false), field("pin", "1234", true)]),
+            entry("b", Some("Работа"), Vec::new()),
+            entry("c", None, Vec::new()),
+            entry("", Some(DEFAULT_FOLDER), Vec::new()),
+        ];
+        let items = state.items(&entries);
+        assert_eq!(items.keys().collect::<Vec<_>>(), ["a_2d1"]);
+        let item = &items["a_2d1"];
+        assert_eq!(item.label, "Запись a-1");
+        // Защищённые поля не становятся атрибутами
+        assert_eq!(item.attributes, HashMap::from([("service".to_string(), "mail".to_string())]));
+
+        assert_eq!(state.position(&entries, "a_2d1"), Some(0));
+        assert_eq!(state.position(&entries, "b"), None);
+    }
+
+    #[test]
+    fn with_attributes_replaces_plain_fields_and_keeps_protected() {
+        let fields = vec![field("old", "1", false), field("pin", "1234", true)];
+        let attributes = HashMap::from([
+            ("service".to_string(), "mail".to_string()),
+            ("account".to_string(), "alice".to_string()),
+        ]);
+        let updated = with_attributes(&fields, &attributes);
+        let summary: Vec<_> = updated
+            .iter()
+            .map(|field| (field.name.as_str(), field.value.as_str(), field.protected))
+            .collect();
+        assert_eq!(
+            summary,
+            [("pin", "1234", true), ("account", "alice", false), ("service", "mail", false)]
+        );
+    }
+}
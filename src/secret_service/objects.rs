@@ -0,0 +1,906 @@
+//! Объекты Secret Service на шине: сервис, коллекция, элементы, сессии
+//! и запросы (prompt) на открытие хранилища.
+
+use super::session::{self, Cipher};
+use super::{
+    with_attributes, CachedItem, Session, State, ALIAS_PATH, COLLECTION_PATH, DEFAULT_ALIAS, SERVICE_PATH,
+};
+use crate::entry::Entry;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Type, Value};
+use zbus::names::ErrorName;
+use zbus::{dbus_interface, fdo, Connection, DBusError, Message, MessageBuilder, MessageHeader, ObjectServer, SignalContext};
+use zeroize::Zeroizing;
+
+const LABEL_PROPERTY: &str = "org.freedesktop.Secret.Item.Label";
+const ATTRIBUTES_PROPERTY: &str = "org.freedesktop.Secret.Item.Attributes";
+const CONTENT_TYPE: &str = "text/plain; charset=utf8";
+
+/// Ошибки Secret Service (`org.freedesktop.Secret.Error.*`) и стандартные
+/// ошибки D-Bus под своими именами.
+#[derive(Debug)]
+pub enum SecretError {
+    Fdo(fdo::Error),
+    IsLocked(String),
+    NoSession(String),
+    NoSuchObject(String),
+}
+
+impl DBusError for SecretError {
+    fn create_reply(&self, call: &MessageHeader<'_>) -> zbus::Result<Message> {
+        match self {
+            SecretError::Fdo(error) => error.create_reply(call),
+            error => MessageBuilder::error(call, error.name())?.build(&(DBusError::description(error).unwrap_or(""),)),
+        }
+    }
+
+    fn name(&self) -> ErrorName<'_> {
+        ErrorName::from_str_unchecked(match self {
+            SecretError::Fdo(error) => return error.name(),
+            SecretError::IsLocked(_) => "org.freedesktop.Secret.Error.IsLocked",
+            SecretError::NoSession(_) => "org.freedesktop.Secret.Error.NoSession",
+            SecretError::NoSuchObject(_) => "org.freedesktop.Secret.Error.NoSuchObject",
+        })
+    }
+
+    fn description(&self) -> Option<&str> {
+        match self {
+            SecretError::Fdo(error) => DBusError::description(error),
+            SecretError::IsLocked(text) | SecretError::NoSession(text) | SecretError::NoSuchObject(text) => Some(text),
+        }
+    }
+}
+
+impl fmt::Display for SecretError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.name(), DBusError::description(self).unwrap_or(""))
+    }
+}
+
+impl Error for SecretError {}
+
+impl From<fdo::Error> for SecretError {
+    fn from(error: fdo::Error) -> Self {
+        SecretError::Fdo(error)
+    }
+}
+
+impl From<zbus::Error> for SecretError {
+    fn from(error: zbus::Error) -> Self {
+        match error {
+            zbus::Error::FDO(error) => SecretError::Fdo(*error),
+            error => SecretError::Fdo(fdo::Error::ZBus(error)),
+        }
+    }
+}
+
+pub(super) fn failed(error: impl Display) -> SecretError {
+    fdo::Error::Failed(error.to_string()).into()
+}
+
+pub(super) fn locked() -> SecretError {
+    SecretError::IsLocked("Хранилище hiho заблокировано".to_string())
+}
+
+// Сеттеры свойств zbus возвращают только zbus::Error
+fn property_error(error: SecretError) -> zbus::Error {
+    match error {
+        SecretError::Fdo(error) => zbus::Error::FDO(Box::new(error)),
+        error => zbus::Error::FDO(Box::new(fdo::Error::Failed(DBusError::description(&error).unwrap_or("").to_string()))),
+    }
+}
+
+fn no_such_object(path: &str) -> SecretError {
+    SecretError::NoSuchObject(format!("Нет такого объекта: {}", path))
+}
+
+/// Секрет в том виде, в котором он ходит по шине: `(oayays)`.
+#[derive(Serialize, Deserialize, Type)]
+pub struct Secret {
+    session: OwnedObjectPath,
+    parameters: Vec<u8>,
+    value: Vec<u8>,
+    content_type: String,
+}
+
+fn path(value: &str) -> OwnedObjectPath {
+    ObjectPath::try_from(value.to_string())
+        .expect("путь объекта собирается из допустимых символов")
+        .into()
+}
+
+fn no_prompt() -> OwnedObjectPath {
+    path("/")
+}
+
+fn item_path(segment: &str) -> OwnedObjectPath {
+    path(&format!("{}/{}", COLLECTION_PATH, segment))
+}
+
+// Сегмент элемента из пути; элементы доступны и через псевдоним коллекции
+fn item_segment(item: &str) -> Option<&str> {
+    [COLLECTION_PATH, ALIAS_PATH]
+        .iter()
+        .find_map(|collection| item.strip_prefix(collection)?.strip_prefix('/'))
+        .filter(|segment| !segment.is_empty() && !segment.contains('/'))
+}
+
+fn sender(header: &MessageHeader<'_>) -> Result<String, SecretError> {
+    Ok(header.sender()?.ok_or_else(|| failed("Сообщение без отправителя"))?.to_string())
+}
+
+fn matches(item: &CachedItem, wanted: &HashMap<String, String>) -> bool {
+    wanted.iter().all(|(name, value)| item.attributes.get(name) == Some(value))
+}
+
+/// Приводит объекты на шине в соответствие с открытым хранилищем: новые
+/// записи папки получают объекты, удалённые — теряют. Возвращает, открыто ли
+/// хранилище; заблокированное оставляет всё как было.
+pub(super) async fn sync(state: &Arc<State>, server: &ObjectServer) -> Result<bool, SecretError> {
+    let Some(entries) = state.load()? else {
+        return Ok(false);
+    };
+    let items = state.items(&entries);
+    let (added, removed) = {
+        let mut inner = state.inner();
+        let known: BTreeSet<String> = inner.items.keys().cloned().collect();
+        let added: Vec<String> = items.keys().filter(|segment| !known.contains(*segment)).cloned().collect();
+        let removed: Vec<String> = known.into_iter().filter(|segment| !items.contains_key(segment)).collect();
+        inner.items = items;
+        (added, removed)
+    };
+    for segment in added {
+        let item = Item {
+            state: Arc::clone(state),
+            segment: segment.clone(),
+        };
+        server.at(item_path(&segment), item).await?;
+    }
+    for segment in removed {
+        server.remove::<Item, _>(item_path(&segment)).await?;
+    }
+    Ok(true)
+}
+
+impl State {
+    fn open_session(&self, owner: String, cipher: Cipher) -> OwnedObjectPath {
+        let session = path(&format!("{}/session/s{}", SERVICE_PATH, self.next_id()));
+        self.inner().sessions.insert(session.to_string(), Session { owner, cipher });
+        session
+    }
+
+    fn encrypt(&self, session: &str, sender: &str, secret: &[u8]) -> Result<Secret, SecretError> {
+        let inner = self.inner();
+        let cipher = &inner
+            .sessions
+            .get(session)
+            .filter(|open| open.owner == sender)
+            .ok_or_else(|| SecretError::NoSession(format!("Нет такой сессии: {}", session)))?
+            .cipher;
+        let (parameters, value) = cipher.encrypt(secret);
+        Ok(Secret {
+            session: path(session),
+            parameters,
+            value,
+            content_type: CONTENT_TYPE.to_string(),
+        })
+    }
+
+    /// Текст секрета от клиента.
+    fn decrypt(&self, secret: &Secret, sender: &str) -> Result<Zeroizing<String>, SecretError> {
+        let inner = self.inner();
+        let session = inner
+            .sessions
+            .get(secret.session.as_str())
+            .filter(|open| open.owner == sender)
+            .ok_or_else(|| SecretError::NoSession(format!("Нет такой сессии: {}", secret.session.as_str())))?;
+        let mut value = session.cipher.decrypt(&secret.parameters, &secret.value).map_err(failed)?;
+        let text = String::from_utf8(std::mem::take(&mut *value))
+            .map_err(|_| fdo::Error::InvalidArgs("hiho хранит только текстовые секреты (UTF-8)".to_string()))?;
+        Ok(Zeroizing::new(text))
+    }
+
+    /// Пути найденных элементов.
+    fn search(&self, wanted: &HashMap<String, String>) -> Vec<OwnedObjectPath> {
+        self.inner()
+            .items
+            .iter()
+            .filter(|(_, item)| matches(item, wanted))
+            .map(|(segment, _)| item_path(segment))
+            .collect()
+    }
+
+    /// Изменяет запись элемента в хранилище агента.
+    fn modify<F>(&self, segment: &str, change: F) -> Result<(), SecretError>
+    where
+        F: FnOnce(&mut crate::Vault, usize) -> Result<(), SecretError>,
+    {
+        let mut vault = self.open_vault()?;
+        let index = self
+            .position(vault.get_entries(), segment)
+            .ok_or_else(|| no_such_object(item_path(segment).as_str()))?;
+        change(&mut vault, index)?;
+        self.store(&vault)?;
+        // Удалённую запись уберёт с шины sync
+        if let Some(index) = self.position(vault.get_entries(), segment) {
+            let item = CachedItem::from(&vault.get_entries()[index]);
+            self.inner().items.insert(segment.to_string(), item);
+        }
+        Ok(())
+    }
+}
+
+fn collection_signals(connection: &Connection) -> zbus::Result<SignalContext<'_>> {
+    SignalContext::new(connection, COLLECTION_PATH)
+}
+
+pub struct Service {
+    state: Arc<State>,
+}
+
+impl Service {
+    pub(super) fn new(state: Arc<State>) -> Self {
+        Self { state }
+    }
+}
+
+#[dbus_interface(name = "org.freedesktop.Secret.Service")]
+impl Service {
+    async fn open_session(
+        &self,
+        algorithm: &str,
+        input: Value<'_>,
+        #[zbus(header)] header: MessageHeader<'_>,
+        #[zbus(object_server)] server: &ObjectServer,
+    ) -> Result<(OwnedValue, OwnedObjectPath), SecretError> {
+        let input: Vec<u8> = match algorithm {
+            session::PLAIN => Vec::new(),
+            session::DH_AES => input
+                .try_into()
+                .map_err(|_| fdo::Error::InvalidArgs("Ожидался открытый ключ (ay)".to_string()))?,
+            _ => {
+                let message = format!("Алгоритм сессии {} не поддерживается", algorithm);
+                return Err(fdo::Error::NotSupported(message).into());
+            }
+        };
+        let (cipher, output) = Cipher::negotiate(algorithm, &input).map_err(|e| fdo::Error::InvalidArgs(e.to_string()))?;
+        let output = match cipher {
+            Cipher::Plain => Value::from(""),
+            Cipher::Aes(_) => Value::from(output),
+        };
+        let session = self.state.open_session(sender(&header)?, cipher);
+        server
+            .at(
+                session.clone(),
+                SessionObject {
+                    state: Arc::clone(&self.state),
+                },
+            )
+            .await?;
+        // Клиенты начинают с сессии: удобный момент показать новые записи
+        sync(&self.state, server).await?;
+        Ok((output.into(), session))
+    }
+
+    /// Коллекция одна — папка хранилища; её и отдаём вместо новой.
+    async fn create_collection(
+        &self,
+        _properties: HashMap<String, OwnedValue>,
+        _alias: &str,
+    ) -> (OwnedObjectPath, OwnedObjectPath) {
+        (path(COLLECTION_PATH), no_prompt())
+    }
+
+    async fn search_items(
+        &self,
+        attributes: HashMap<String, String>,
+        #[zbus(object_server)] server: &ObjectServer,
+    ) -> Result<(Vec<OwnedObjectPath>, Vec<OwnedObjectPath>), SecretError> {
+        let open = sync(&self.state, server).await?;
+        let found = self.state.search(&attributes);
+        Ok(if open { (found, Vec::new()) } else { (Vec::new(), found) })
+    }
+
+    async fn unlock(
+        &self,
+        objects: Vec<OwnedObjectPath>,
+        #[zbus(object_server)] server: &ObjectServer,
+    ) -> Result<(Vec<OwnedObjectPath>, OwnedObjectPath), SecretError> {
+        if sync(&self.state, server).await? {
+            return Ok((objects, no_prompt()));
+        }
+        let prompt = path(&format!("{}/prompt/p{}", SERVICE_PATH, self.state.next_id()));
+        let object = Prompt {
+            state: Arc::clone(&self.state),
+            path: prompt.clone(),
+            objects,
+        };
+        server.at(prompt.clone(), object).await?;
+        Ok((Vec::new(), prompt))
+    }
+
+    /// Блокирует хранилище в агенте целиком.
+    async fn lock(&self, objects: Vec<OwnedObjectPath>) -> Result<(Vec<OwnedObjectPath>, OwnedObjectPath), SecretError> {
+        self.state.lock()?;
+        Ok((objects, no_prompt()))
+    }
+
+    async fn get_secrets(
+        &self,
+        items: Vec<OwnedObjectPath>,
+        session: ObjectPath<'_>,
+        #[zbus(header)] header: MessageHeader<'_>,
+    ) -> Result<HashMap<OwnedObjectPath, Secret>, SecretError> {
+        let sender = sender(&header)?;
+        let entries = self.state.load()?.ok_or_else(locked)?;
+        let mut secrets = HashMap::new();
+        for item in items {
+            let Some(index) = item_segment(item.as_str()).and_then(|segment| self.state.position(&entries, segment))
+            else {
+                continue;
+            };
+            let secret = self.state.encrypt(session.as_str(), &sender, entries[index].password.as_bytes())?;
+            secrets.insert(item, secret);
+        }
+        Ok(secrets)
+    }
+
+    async fn read_alias(&self, name: &str) -> OwnedObjectPath {
+        if name == DEFAULT_ALIAS {
+            path(COLLECTION_PATH)
+        } else {
+            no_prompt()
+        }
+    }
+
+    async fn set_alias(&self, name: &str, collection: ObjectPath<'_>) -> fdo::Result<()> {
+        if name == DEFAULT_ALIAS && [COLLECTION_PATH, ALIAS_PATH].contains(&collection.as_str()) {
+            return Ok(());
+        }
+        Err(fdo::Error::NotSupported(
+            "hiho показывает одну коллекцию с псевдонимом default".to_string(),
+        ))
+    }
+
+    #[dbus_interface(property)]
+    async fn collections(&self) -> Vec<OwnedObjectPath> {
+        vec![path(COLLECTION_PATH)]
+    }
+}
+
+pub struct Collection {
+    state: Arc<State>,
+}
+
+impl Collection {
+    pub(super) fn new(state: Arc<State>) -> Self {
+        Self { state }
+    }
+}
+
+#[dbus_interface(name = "org.freedesktop.Secret.Collection")]
+impl Collection {
+    async fn delete(&self) -> fdo::Result<OwnedObjectPath> {
+        Err(fdo::Error::NotSupported(
+            "Коллекцию hiho нельзя удалить: это папка хранилища".to_string(),
+        ))
+    }
+
+    async fn search_items(
+        &self,
+        attributes: HashMap<String, String>,
+        #[zbus(object_server)] server: &ObjectServer,
+    ) -> Result<Vec<OwnedObjectPath>, SecretError> {
+        sync(&self.state, server).await?;
+        Ok(self.state.search(&attributes))
+    }
+
+    async fn create_item(
+        &self,
+        properties: HashMap<String, OwnedValue>,
+        secret: Secret,
+        replace: bool,
+        #[zbus(header)] header: MessageHeader<'_>,
+        #[zbus(connection)] connection: &Connection,
+        #[zbus(object_server)] server: &ObjectServer,
+    ) -> Result<(OwnedObjectPath, OwnedObjectPath), SecretError> {
+        let label = match properties.get(LABEL_PROPERTY) {
+            Some(value) => String::try_from(value.clone()).map_err(|e| fdo::Error::InvalidArgs(e.to_string()))?,
+            None => String::new(),
+        };
+        let attributes: HashMap<String, String> = match properties.get(ATTRIBUTES_PROPERTY) {
+            Some(value) => Value::from(value.clone())
+                .try_into()
+                .map_err(|e: zbus::zvariant::Error| fdo::Error::InvalidArgs(e.to_string()))?,
+            None => HashMap::new(),
+        };
+        let password = self.state.decrypt(&secret, &sender(&header)?)?;
+
+        let mut vault = self.state.open_vault()?;
+        // replace: тот же набор атрибутов — та же запись
+        let existing = vault
+            .get_entries()
+            .iter()
+            .position(|entry| self.state.exposes(entry) && super::attributes(entry) == attributes)
+            .filter(|_| replace);
+        let index = match existing {
+            Some(index) => {
+                vault.edit_entry(index, None, Some(password.to_string())).map_err(failed)?;
+                vault.rename_entry(index, label).map_err(failed)?;
+                index
+            }
+            None => {
+                let mut entry = Entry::new(label, String::new(), password.to_string());
+                entry.folder = Some(self.state.folder.clone());
+                entry.custom_fields = with_attributes(&[], &attributes);
+                vault.add_entry(entry);
+                vault.get_entries().len() - 1
+            }
+        };
+        let segment = super::path_segment(&vault.get_entries()[index].id);
+        self.state.store(&vault)?;
+        sync(&self.state, server).await?;
+
+        let item = item_path(&segment);
+        let context = collection_signals(connection)?;
+        match existing {
+            Some(_) => Collection::item_changed(&context, item.as_ref()).await?,
+            None => Collection::item_created(&context, item.as_ref()).await?,
+        }
+        Ok((item, no_prompt()))
+    }
+
+    #[dbus_interface(signal)]
+    async fn item_created(context: &SignalContext<'_>, item: ObjectPath<'_>) -> zbus::Result<()>;
+
+    #[dbus_interface(signal)]
+    async fn item_deleted(context: &SignalContext<'_>, item: ObjectPath<'_>) -> zbus::Result<()>;
+
+    #[dbus_interface(signal)]
+    async fn item_changed(context: &SignalContext<'_>, item: ObjectPath<'_>) -> zbus::Result<()>;
+
+    #[dbus_interface(property)]
+    async fn items(&self) -> Vec<OwnedObjectPath> {
+        self.state.inner().items.keys().map(|segment| item_path(segment)).collect()
+    }
+
+    #[dbus_interface(property)]
+    async fn label(&self) -> String {
+        self.state.folder.clone()
+    }
+
+    #[dbus_interface(property)]
+    async fn locked(&self) -> bool {
+        !self.state.is_open()
+    }
+
+    #[dbus_interface(property)]
+    async fn created(&self) -> u64 {
+        self.state.inner().items.values().map(|item| item.created).min().unwrap_or(0)
+    }
+
+    #[dbus_interface(property)]
+    async fn modified(&self) -> u64 {
+        self.state.inner().items.values().map(|item| item.modified).max().unwrap_or(0)
+    }
+}
+
+/// Элемент коллекции — запись в папке хранилища.
+pub struct Item {
+    state: Arc<State>,
+    segment: String,
+}
+
+impl Item {
+    fn cached(&self) -> CachedItem {
+        self.state.cached(&self.segment).unwrap_or_else(|| CachedItem {
+            label: String::new(),
+            attributes: HashMap::new(),
+            created: 0,
+            modified: 0,
+        })
+    }
+}
+
+#[dbus_interface(name = "org.freedesktop.Secret.Item")]
+impl Item {
+    async fn delete(
+        &self,
+        #[zbus(connection)] connection: &Connection,
+        #[zbus(object_server)] server: &ObjectServer,
+    ) -> Result<OwnedObjectPath, SecretError> {
+        self.state.modify(&self.segment, |vault, index| {
+            vault.remove_entry(index);
+            Ok(())
+        })?;
+        sync(&self.state, server).await?;
+        Collection::item_deleted(&collection_signals(connection)?, item_path(&self.segment).as_ref()).await?;
+        Ok(no_prompt())
+    }
+
+    async fn get_secret(
+        &self,
+        session: ObjectPath<'_>,
+        #[zbus(header)] header: MessageHeader<'_>,
+    ) -> Result<(Secret,), SecretError> {
+        let entries = self.state.load()?.ok_or_else(locked)?;
+        let index = self
+            .state
+            .position(&entries, &self.segment)
+            .ok_or_else(|| no_such_object(item_path(&self.segment).as_str()))?;
+        let secret = self.state.encrypt(session.as_str(), &sender(&header)?, entries[index].password.as_bytes())?;
+        Ok((secret,))
+    }
+
+    async fn set_secret(
+        &self,
+        secret: Secret,
+        #[zbus(header)] header: MessageHeader<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> Result<(), SecretError> {
+        let password = self.state.decrypt(&secret, &sender(&header)?)?;
+        self.state.modify(&self.segment, |vault, index| {
+            vault.edit_entry(index, None, Some(password.to_string())).map_err(failed)
+        })?;
+        Collection::item_changed(&collection_signals(connection)?, item_path(&self.segment).as_ref()).await?;
+        Ok(())
+    }
+
+    #[dbus_interface(property)]
+    async fn locked(&self) -> bool {
+        !self.state.is_open()
+    }
+
+    #[dbus_interface(property)]
+    async fn attributes(&self) -> HashMap<String, String> {
+        self.cached().attributes
+    }
+
+    #[dbus_interface(property)]
+    async fn set_attributes(&self, value: HashMap<String, String>) -> zbus::Result<()> {
+        self.state
+            .modify(&self.segment, |vault, index| {
+                let fields = with_attributes(&vault.get_entries()[index].custom_fields, &value);
+                vault.set_custom_fields(index, fields).map_err(failed)
+            })
+            .map_err(property_error)
+    }
+
+    #[dbus_interface(property)]
+    async fn label(&self) -> String {
+        self.cached().label
+    }
+
+    #[dbus_interface(property)]
+    async fn set_label(&self, value: String) -> zbus::Result<()> {
+        self.state
+            .modify(&self.segment, |vault, index| vault.rename_entry(index, value).map_err(failed))
+            .map_err(property_error)
+    }
+
+    #[dbus_interface(property)]
+    async fn created(&self) -> u64 {
+        self.cached().created
+    }
+
+    #[dbus_interface(property)]
+    async fn modified(&self) -> u64 {
+        self.cached().modified
+    }
+}
+
+/// Сессия для передачи секретов.
+pub struct SessionObject {
+    state: Arc<State>,
+}
+
+#[dbus_interface(name = "org.freedesktop.Secret.Session")]
+impl SessionObject {
+    async fn close(
+        &self,
+        #[zbus(header)] header: MessageHeader<'_>,
+        #[zbus(object_server)] server: &ObjectServer,
+    ) -> Result<(), SecretError> {
+        let session = header.path()?.ok_or_else(|| failed("Сообщение без пути"))?.to_owned();
+        self.state.inner().sessions.remove(session.as_str());
+        server.remove::<SessionObject, _>(session).await?;
+        Ok(())
+    }
+}
+
+/// Запрос мастер-пароля для `Unlock`.
+pub struct Prompt {
+    state: Arc<State>,
+    path: OwnedObjectPath,
+    /// Что разблокировать: возвращается клиенту в `Completed`
+    objects: Vec<OwnedObjectPath>,
+}
+
+#[dbus_interface(name = "org.freedesktop.Secret.Prompt")]
+impl Prompt {
+    /// Спрашивает пароль в отдельном потоке: ответ придёт сигналом
+    /// `Completed`, а шина тем временем обслуживает других клиентов.
+    async fn prompt(&self, _window_id: &str, #[zbus(connection)] connection: &Connection) {
+        let state = Arc::clone(&self.state);
+        let connection = connection.clone();
+        let prompt = self.path.clone();
+        let objects = self.objects.clone();
+        std::thread::spawn(move || {
+            let unlocked = ask_password().and_then(|password| state.unlock(&password));
+            if let Err(e) = &unlocked {
+                eprintln!("hiho secret-service: хранилище не открыто: {}", e);
+            }
+            zbus::block_on(async {
+                let server = connection.object_server();
+                let open = unlocked.is_ok() && sync(&state, &server).await.unwrap_or(false);
+                let result = if open { objects } else { Vec::new() };
+                finish(&connection, &prompt, !open, result).await;
+            });
+        });
+    }
+
+    async fn dismiss(&self, #[zbus(connection)] connection: &Connection) {
+        finish(connection, &self.path, true, Vec::new()).await;
+    }
+
+    #[dbus_interface(signal)]
+    async fn completed(context: &SignalContext<'_>, dismissed: bool, result: Value<'_>) -> zbus::Result<()>;
+}
+
+// Сообщает клиенту итог запроса и убирает объект с шины
+async fn finish(connection: &Connection, prompt: &OwnedObjectPath, dismissed: bool, objects: Vec<OwnedObjectPath>) {
+    let sent = match SignalContext::new(connection, prompt.as_ref()) {
+        Ok(context) => Prompt::completed(&context, dismissed, Value::from(objects)).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = sent {
+        eprintln!("hiho secret-service: не удалось отправить Completed: {}", e);
+    }
+    let _ = connection.object_server().remove::<Prompt, _>(prompt.as_ref()).await;
+}
+
+// Мастер-пароль через $SSH_ASKPASS, как подтверждение в ssh-agent hiho
+fn ask_password() -> Result<Zeroizing<String>, Box<dyn Error>> {
+    let program = std::env::var_os("SSH_ASKPASS").unwrap_or_else(|| "ssh-askpass".into());
+    let output = Command::new(&program)
+        .arg("Приложение запрашивает секреты из hiho. Мастер-пароль:")
+        .stdin(Stdio::null())
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|e| format!("не удалось запустить {}: {}", program.to_string_lossy(), e))?;
+    let mut stdout = Zeroizing::new(output.stdout);
+    if !output.status.success() {
+        return Err("ввод пароля отменён".into());
+    }
+    let mut password = String::from_utf8(std::mem::take(&mut *stdout)).map_err(|_| "пароль не в UTF-8")?;
+    while password.ends_with(['\n', '\r']) {
+        password.pop();
+    }
+    Ok(Zeroizing::new(password))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secret_service::session::DhKeyPair;
+    use std::io::{BufRead, BufReader};
+    use std::path::{Path, PathBuf};
+    use std::process::Child;
+
+    fn state() -> State {
+        State::new(PathBuf::from("vault.json"), "Secret Service".to_string(), PathBuf::from("agent.sock"))
+    }
+
+    #[test]
+    fn item_segment_accepts_collection_and_alias_paths() {
+        assert_eq!(item_segment(&format!("{}/abc", COLLECTION_PATH)), Some("abc"));
+        assert_eq!(item_segment(&format!("{}/abc", ALIAS_PATH)), Some("abc"));
+        assert_eq!(item_segment(COLLECTION_PATH), None);
+        assert_eq!(item_segment(&format!("{}/", COLLECTION_PATH)), None);
+        assert_eq!(item_segment(&format!("{}/a/b", COLLECTION_PATH)), None);
+        assert_eq!(item_segment("/org/freedesktop/secrets/collection/other/abc"), None);
+    }
+
+    #[test]
+    fn search_matches_all_wanted_attributes() {
+        let state = state();
+        let item = |service: &str| CachedItem {
+            label: service.to_string(),
+            attributes: HashMap::from([
+                ("service".to_string(), service.to_string()),
+                ("user".to_string(), "alice".to_string()),
+            ]),
+            created: 0,
+            modified: 0,
+        };
+        state.inner().items.insert("one".to_string(), item("mail"));
+        state.inner().items.insert("two".to_string(), item("chat"));
+
+        let wanted = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+        };
+        assert_eq!(state.search(&wanted(&[("service", "mail")])), [item_path("one")]);
+        assert_eq!(state.search(&wanted(&[("user", "alice")])).len(), 2);
+        assert!(state.search(&wanted(&[("service", "mail"), ("user", "bob")])).is_empty());
+        assert_eq!(state.search(&HashMap::new()).len(), 2);
+    }
+
+    #[test]
+    fn secrets_travel_encrypted_in_owned_session() {
+        let state = state();
+        let client = DhKeyPair::generate();
+        let (cipher, reply) = Cipher::negotiate(session::DH_AES, &client.public).unwrap();
+        let session = state.open_session(":1.42".to_string(), cipher);
+        let client = client.agree(&reply).unwrap();
+
+        let secret = state.encrypt(session.as_str(), ":1.42", b"hunter2").unwrap();
+        assert_eq!(secret.content_type, CONTENT_TYPE);
+        assert_ne!(secret.value, b"hunter2");
+        assert_eq!(client.decrypt(&secret.parameters, &secret.value).unwrap().as_slice(), b"hunter2");
+
+        let (parameters, value) = client.encrypt("новый пароль".as_bytes());
+        let incoming = Secret {
+            session: session.clone(),
+            parameters,
+            value,
+            content_type: CONTENT_TYPE.to_string(),
+        };
+        assert_eq!(state.decrypt(&incoming, ":1.42").unwrap().as_str(), "новый пароль");
+    }
+
+    #[test]
+    fn foreign_or_unknown_session_is_rejected() {
+        let state = state();
+        let session = state.open_session(":1.42".to_string(), Cipher::Plain);
+        assert!(matches!(
+            state.encrypt(session.as_str(), ":1.43", b"x"),
+            Err(SecretError::NoSession(_))
+        ));
+        assert!(matches!(
+            state.encrypt("/org/freedesktop/secrets/session/s99", ":1.42", b"x"),
+            Err(SecretError::NoSession(_))
+        ));
+
+        let binary = Secret {
+            session,
+            parameters: Vec::new(),
+            value: vec![0xFF, 0xFE],
+            content_type: CONTENT_TYPE.to_string(),
+        };
+        assert!(matches!(state.decrypt(&binary, ":1.43"), Err(SecretError::NoSession(_))));
+        assert!(matches!(state.decrypt(&binary, ":1.42"), Err(SecretError::Fdo(fdo::Error::InvalidArgs(_)))));
+    }
+
+    // Отдельный dbus-daemon, чтобы не трогать сессионную шину пользователя
+    struct PrivateBus {
+        daemon: Child,
+        address: String,
+    }
+
+    impl PrivateBus {
+        fn start(directory: &Path) -> Option<Self> {
+            let config = directory.join("bus.conf");
+            std::fs::write(
+                &config,
+                format!(
+                    "<busconfig><type>session</type><listen>unix:dir={}</listen><auth>EXTERNAL</auth>\
+                     <policy context=\"default\"><allow send_destination=\"*\" eavesdrop=\"true\"/>\
+                     <allow eavesdrop=\"true\"/><allow own=\"*\"/></policy></busconfig>",
+                    directory.display()
+                ),
+            )
+            .ok()?;
+            let mut daemon = Command::new("dbus-daemon")
+                .arg(format!("--config-file={}", config.display()))
+                .args(["--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+                .ok()?;
+            let mut address = String::new();
+            BufReader::new(daemon.stdout.take()?).read_line(&mut address).ok()?;
+            Some(Self {
+                daemon,
+                address: address.trim().to_string(),
+            })
+        }
+    }
+
+    impl Drop for PrivateBus {
+        fn drop(&mut self) {
+            let _ = self.daemon.kill();
+            let _ = self.daemon.wait();
+        }
+    }
+
+    #[test]
+    fn item_created_over_dh_session_is_read_back() {
+        use crate::agent::{server, AgentClient};
+        use crate::secret_service::{publish, BUS_NAME};
+        use crate::vault::seal_entries;
+        use zbus::blocking::{Connection, ConnectionBuilder};
+
+        const KEY: [u8; 32] = [7; 32];
+        let dir = tempfile::tempdir().unwrap();
+        let Some(bus) = PrivateBus::start(dir.path()) else {
+            eprintln!("dbus-daemon не найден, проверка Secret Service на шине пропущена");
+            return;
+        };
+
+        // Хранилище открыто в агенте, как при `hiho secret-service`
+        let vault = dir.path().join("vault.enc");
+        std::fs::write(&vault, seal_entries(&[], &KEY).unwrap()).unwrap();
+        let socket = dir.path().join("agent.sock");
+        server::spawn_for_tests(&socket).unwrap();
+        AgentClient::connect_to(&socket).unwrap().unlock_with_key(&vault, &KEY).unwrap();
+
+        let state = Arc::new(State::new(vault.clone(), "Secret Service".to_string(), socket));
+        let _service = publish(ConnectionBuilder::address(bus.address.as_str()).unwrap(), &state).unwrap();
+        let client: Connection = ConnectionBuilder::address(bus.address.as_str()).unwrap().build().unwrap();
+
+        let keys = DhKeyPair::generate();
+        let reply = client
+            .call_method(
+                Some(BUS_NAME),
+                SERVICE_PATH,
+                Some("org.freedesktop.Secret.Service"),
+                "OpenSession",
+                &(session::DH_AES, Value::from(keys.public.clone())),
+            )
+            .unwrap();
+        let (output, session): (OwnedValue, OwnedObjectPath) = reply.body().unwrap();
+        let output: Vec<u8> = output.try_into().unwrap();
+        let cipher = keys.agree(&output).unwrap();
+
+        let (parameters, value) = cipher.encrypt("hunter2".as_bytes());
+        let secret = Secret {
+            session: session.clone(),
+            parameters,
+            value,
+            content_type: CONTENT_TYPE.to_string(),
+        };
+        let wanted = HashMap::from([("service".to_string(), "mail".to_string())]);
+        let properties = HashMap::from([
+            (LABEL_PROPERTY, Value::from("Почта")),
+            (ATTRIBUTES_PROPERTY, Value::from(wanted.clone())),
+        ]);
+        let reply = client
+            .call_method(
+                Some(BUS_NAME),
+                COLLECTION_PATH,
+                Some("org.freedesktop.Secret.Collection"),
+                "CreateItem",
+                &(properties, secret, false),
+            )
+            .unwrap();
+        let (item, prompt): (OwnedObjectPath, OwnedObjectPath) = reply.body().unwrap();
+        assert_eq!(prompt, no_prompt());
+
+        let reply = client
+            .call_method(
+                Some(BUS_NAME),
+                SERVICE_PATH,
+                Some("org.freedesktop.Secret.Service"),
+                "GetSecrets",
+                &(vec![item.clone()], session),
+            )
+            .unwrap();
+        let secrets: HashMap<OwnedObjectPath, Secret> = reply.body().unwrap();
+        let secret = &secrets[&item];
+        assert_ne!(secret.value, b"hunter2");
+        assert_eq!(cipher.decrypt(&secret.parameters, &secret.value).unwrap().as_slice(), b"hunter2");
+
+        // Элемент сохранён агентом как запись в папке коллекции
+        let saved = crate::Vault::open_with_key(&vault, KEY).unwrap();
+        let entry = &saved.get_entries()[0];
+        assert_eq!(entry.name, "Почта");
+        assert_eq!(entry.password, "hunter2");
+        assert_eq!(entry.folder.as_deref(), Some("Secret Service"));
+        assert_eq!(super::super::attributes(entry), wanted);
+    }
+}
@@ -0,0 +1,204 @@
+//! Сессии Secret Service: как секреты шифруются по дороге через шину.
+//!
+//! `plain` передаёт секрет как есть. `dh-ietf1024-sha256-aes128-cbc-pkcs7` —
+//! обмен Диффи — Хеллмана в группе 2 из RFC 2409, ключ AES-128 из общего
+//! секрета через HKDF-SHA256 без соли, шифрование AES-128-CBC с PKCS#7
+//! и случайным IV в параметрах секрета. Этот алгоритм по умолчанию
+//! выбирают libsecret и SecretStorage.
+
+use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use crypto_bigint::modular::runtime_mod::{DynResidue, DynResidueParams};
+use crypto_bigint::{Encoding, U1024};
+use hkdf::Hkdf;
+use rand::RngCore;
+use sha2::Sha256;
+use std::error::Error;
+use zeroize::Zeroizing;
+
+pub const PLAIN: &str = "plain";
+pub const DH_AES: &str = "dh-ietf1024-sha256-aes128-cbc-pkcs7";
+
+// Простое число Oakley Group 2 (RFC 2409, раздел 6.2), генератор 2
+const PRIME: U1024 = U1024::from_be_hex(concat!(
+    "FFFFFFFFFFFFFFFFC90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74",
+    "020BBEA63B139B22514A08798E3404DDEF9519B3CD3A431B302B0A6DF25F1437",
+    "4FE1356D6D51C245E485B576625E7EC6F44C42E9A637ED6B0BFF5CB6F406B7ED",
+    "EE386BFB5A899FA5AE9F24117C4B1FE649286651ECE65381FFFFFFFFFFFFFFFF",
+));
+const PRIME_LEN: usize = 128;
+
+type Aes128CbcEnc = cbc::Encryptor<aes::Aes128>;
+type Aes128CbcDec = cbc::Decryptor<aes::Aes128>;
+
+/// Способ защиты секретов в сессии.
+pub enum Cipher {
+    Plain,
+    Aes(Zeroizing<[u8; 16]>),
+}
+
+/// Ключи одной стороны обмена Диффи — Хеллмана. Нужны и сервису, и клиенту
+/// (хранилищу ключей разблокировки в `keystore`).
+pub(crate) struct DhKeyPair {
+    private: Zeroizing<U1024>,
+    /// Открытый ключ для другой стороны, дополненный до длины p
+    pub public: Vec<u8>,
+}
+
+impl DhKeyPair {
+    pub fn generate() -> Self {
+        let mut bytes = Zeroizing::new([0u8; PRIME_LEN]);
+        rand::thread_rng().fill_bytes(bytes.as_mut_slice());
+        let private = Zeroizing::new(U1024::from_be_slice(bytes.as_slice()));
+        let generator = DynResidue::new(&U1024::from_u8(2), DynResidueParams::new(&PRIME));
+        let public = generator.pow(&private).retrieve();
+        Self {
+            private,
+            public: public.to_be_bytes().to_vec(),
+        }
+    }
+
+    /// Ключ сессии из открытого ключа другой стороны.
+    pub fn agree(&self, peer: &[u8]) -> Result<Cipher, Box<dyn Error>> {
+        let start = peer.iter().position(|&byte| byte != 0).unwrap_or(peer.len());
+        let peer = &peer[start..];
+        if peer.len() > PRIME_LEN {
+            return Err("Некорректный открытый ключ DH".into());
+        }
+        let mut padded = [0u8; PRIME_LEN];
+        padded[PRIME_LEN - peer.len()..].copy_from_slice(peer);
+        let peer = U1024::from_be_slice(&padded);
+        // Открытый ключ другой стороны должен лежать строго между 1 и p-1
+        if peer <= U1024::ONE || peer >= PRIME.wrapping_sub(&U1024::ONE) {
+            return Err("Некорректный открытый ключ DH".into());
+        }
+        let shared = DynResidue::new(&peer, DynResidueParams::new(&PRIME)).pow(&self.private).retrieve();
+        // Как libsecret: общий секрет дополняется нулями до длины p
+        let shared = Zeroizing::new(shared.to_be_bytes());
+
+        let mut key = Zeroizing::new([0u8; 16]);
+        Hkdf::<Sha256>::new(None, shared.as_slice())
+            .expand(&[], key.as_mut_slice())
+            .map_err(|_| "Ошибка HKDF")?;
+        Ok(Cipher::Aes(key))
+    }
+}
+
+impl Cipher {
+    /// Согласует сессию по алгоритму и данным клиента. Возвращает шифр
+    /// и ответ для клиента: пустой для `plain`, открытый ключ для DH.
+    pub fn negotiate(algorithm: &str, input: &[u8]) -> Result<(Self, Vec<u8>), Box<dyn Error>> {
+        match algorithm {
+            PLAIN => Ok((Cipher::Plain, Vec::new())),
+            DH_AES => {
+                let pair = DhKeyPair::generate();
+                Ok((pair.agree(input)?, pair.public))
+            }
+            _ => Err(format!("Алгоритм сессии {} не поддерживается", algorithm).into()),
+        }
+    }
+
+    /// Шифрует секрет: возвращает параметры (IV) и значение.
+    pub fn encrypt(&self, secret: &[u8]) -> (Vec<u8>, Vec<u8>) {
+        match self {
+            Cipher::Plain => (Vec::new(), secret.to_vec()),
+            Cipher::Aes(key) => {
+                let mut iv = [0u8; 16];
+                rand::thread_rng().fill_bytes(&mut iv);
+                let mut buffer = Zeroizing::new(vec![0u8; secret.len() + 16]);
+                buffer[..secret.len()].copy_from_slice(secret);
+                let value = Aes128CbcEnc::new(key.as_slice().into(), &iv.into())
+                    .encrypt_padded_mut::<Pkcs7>(&mut buffer, secret.len())
+                    .expect("в буфере есть место под дополнение")
+                    .to_vec();
+                (iv.to_vec(), value)
+            }
+        }
+    }
+
+    pub fn decrypt(&self, parameters: &[u8], value: &[u8]) -> Result<Zeroizing<Vec<u8>>, Box<dyn Error>> {
+        match self {
+            Cipher::Plain => Ok(Zeroizing::new(value.to_vec())),
+            Cipher::Aes(key) => {
+                let iv: [u8; 16] = parameters.try_into().map_err(|_| "Некорректный IV секрета")?;
+                let mut buffer = Zeroizing::new(value.to_vec());
+                let plaintext = Aes128CbcDec::new(key.as_slice().into(), &iv.into())
+                    .decrypt_padded_mut::<Pkcs7>(&mut buffer)
+                    .map_err(|_| "Секрет не расшифровался ключом сессии")?;
+                Ok(Zeroizing::new(plaintext.to_vec()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(cipher: &Cipher) -> [u8; 16] {
+        match cipher {
+            Cipher::Aes(key) => **key,
+            Cipher::Plain => panic!("ожидался шифр AES"),
+        }
+    }
+
+    #[test]
+    fn both_sides_agree_on_session_key() {
+        let client = DhKeyPair::generate();
+        let (service, reply) = Cipher::negotiate(DH_AES, &client.public).unwrap();
+        assert_eq!(reply.len(), PRIME_LEN);
+        let client = client.agree(&reply).unwrap();
+        assert_eq!(key(&client), key(&service));
+
+        let (parameters, value) = service.encrypt(b"hunter2");
+        assert_eq!(parameters.len(), 16);
+        assert_eq!(value.len(), 16);
+        assert_eq!(client.decrypt(&parameters, &value).unwrap().as_slice(), b"hunter2");
+    }
+
+    #[test]
+    fn peer_key_with_leading_zeros_is_accepted() {
+        let first = DhKeyPair::generate();
+        let second = DhKeyPair::generate();
+        let mut padded = vec![0u8; 3];
+        padded.extend_from_slice(&second.public);
+        assert_eq!(key(&first.agree(&padded).unwrap()), key(&first.agree(&second.public).unwrap()));
+    }
+
+    #[test]
+    fn rejects_degenerate_peer_keys() {
+        let pair = DhKeyPair::generate();
+        let prime = PRIME.to_be_bytes();
+        let prime_minus_one = PRIME.wrapping_sub(&U1024::ONE).to_be_bytes();
+        for peer in [&[][..], &[0], &[1], &prime_minus_one[..], &prime[..], &[0xFF; PRIME_LEN + 1][..]] {
+            assert!(pair.agree(peer).is_err(), "{:?}", peer);
+        }
+        assert!(pair.agree(&[2]).is_ok());
+    }
+
+    #[test]
+    fn plain_session_passes_secret_through() {
+        let (cipher, reply) = Cipher::negotiate(PLAIN, &[]).unwrap();
+        assert!(reply.is_empty());
+        let (parameters, value) = cipher.encrypt(b"secret");
+        assert!(parameters.is_empty());
+        assert_eq!(value, b"secret");
+        assert_eq!(cipher.decrypt(&parameters, &value).unwrap().as_slice(), b"secret");
+    }
+
+    #[test]
+    fn wrong_key_or_iv_does_not_decrypt() {
+        let (service, _) = Cipher::negotiate(DH_AES, &DhKeyPair::generate().public).unwrap();
+        let (stranger, _) = Cipher::negotiate(DH_AES, &DhKeyPair::generate().public).unwrap();
+        let (parameters, value) = service.encrypt(b"correct horse battery staple");
+        assert!(service.decrypt(&parameters[..8], &value).is_err());
+        if let Ok(plaintext) = stranger.decrypt(&parameters, &value) {
+            assert_ne!(plaintext.as_slice(), b"correct horse battery staple");
+        }
+    }
+
+    #[test]
+    fn unknown_algorithm_is_rejected() {
+        let error = Cipher::negotiate("dh-ietf2048-sha256-aes256-cbc-pkcs7", &[]).err().unwrap();
+        assert!(error.to_string().contains("не поддерживается"));
+    }
+}
@@ -0,0 +1,108 @@
+//! Сервер протокола ssh-agent (draft-miller-ssh-agent): список ключей
+//! и подпись. Добавлять и удалять ключи через протокол нельзя — ими
+//! управляет хранилище.
+
+use super::key::PrivateKey;
+use super::wire::{Reader, Writer};
+use std::error::Error;
+use std::io::{ErrorKind, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::process::{Command, Stdio};
+
+const SSH_AGENT_FAILURE: u8 = 5;
+const SSH_AGENTC_REQUEST_IDENTITIES: u8 = 11;
+const SSH_AGENT_IDENTITIES_ANSWER: u8 = 12;
+const SSH_AGENTC_SIGN_REQUEST: u8 = 13;
+const SSH_AGENT_SIGN_RESPONSE: u8 = 14;
+
+// Запросы длиннее не бывают; защищает от чтения мусора в память
+const MAX_MESSAGE: usize = 256 * 1024;
+
+/// Ключ, доступный агенту.
+pub struct Identity {
+    pub key: PrivateKey,
+    /// Название для списка ключей и запроса подтверждения
+    pub comment: String,
+    /// Спрашивать подтверждение перед каждой подписью
+    pub confirm: bool,
+}
+
+/// Обслуживает одно подключение. Ключи запрашиваются у `identities` на
+/// каждое сообщение, поэтому закрытое хранилище сразу перестаёт их отдавать.
+pub fn serve<F>(mut stream: UnixStream, identities: F) -> Result<(), Box<dyn Error>>
+where
+    F: Fn() -> Vec<Identity>,
+{
+    loop {
+        let mut length = [0u8; 4];
+        match stream.read_exact(&mut length) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(()),
+            Err(e) => return Err(e.into()),
+        }
+        let length = u32::from_be_bytes(length) as usize;
+        if length == 0 || length > MAX_MESSAGE {
+            return Err(format!("Некорректная длина сообщения ssh-agent: {}", length).into());
+        }
+        let mut message = vec![0u8; length];
+        stream.read_exact(&mut message)?;
+
+        let answer = handle(&message, &identities).unwrap_or_else(|e| {
+            eprintln!("hiho ssh-agent: {}", e);
+            vec![SSH_AGENT_FAILURE]
+        });
+        let mut packet = Writer::new();
+        packet.string(&answer);
+        stream.write_all(&packet.finish())?;
+    }
+}
+
+fn handle<F>(message: &[u8], identities: &F) -> Result<Vec<u8>, Box<dyn Error>>
+where
+    F: Fn() -> Vec<Identity>,
+{
+    let mut reader = Reader::new(message);
+    let mut answer = Writer::new();
+    match reader.u8()? {
+        SSH_AGENTC_REQUEST_IDENTITIES => {
+            let identities = identities();
+            answer.u8(SSH_AGENT_IDENTITIES_ANSWER).u32(identities.len() as u32);
+            for identity in &identities {
+                answer.string(&identity.key.public_blob()).string(identity.comment.as_bytes());
+            }
+        }
+        SSH_AGENTC_SIGN_REQUEST => {
+            let blob = reader.string()?;
+            let data = reader.string()?;
+            let flags = reader.u32()?;
+            let Some(identity) = identities().into_iter().find(|identity| identity.key.public_blob() == blob) else {
+                return Ok(vec![SSH_AGENT_FAILURE]);
+            };
+            if identity.confirm && !confirm(&identity) {
+                return Ok(vec![SSH_AGENT_FAILURE]);
+            }
+            answer.u8(SSH_AGENT_SIGN_RESPONSE).string(&identity.key.sign(data, flags)?);
+        }
+        // Добавление, удаление ключей, блокировка агента и расширения
+        _ => return Ok(vec![SSH_AGENT_FAILURE]),
+    }
+    Ok(answer.finish())
+}
+
+// Как `ssh-add -c`: спрашиваем через $SSH_ASKPASS (по умолчанию ssh-askpass)
+// в режиме подтверждения. Нет программы или нет ответа — отказ
+fn confirm(identity: &Identity) -> bool {
+    let program = std::env::var_os("SSH_ASKPASS").unwrap_or_else(|| "ssh-askpass".into());
+    let prompt = format!(
+        "Разрешить использование ключа {}?\nОтпечаток ключа {}.",
+        identity.comment,
+        identity.key.fingerprint()
+    );
+    Command::new(program)
+        .arg(prompt)
+        .env("SSH_ASKPASS_PROMPT", "confirm")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
@@ -0,0 +1,172 @@
+//! Подпись ECDSA на кривых NIST P-256, P-384 и P-521 (RFC 5656) через
+//! крейты p256, p384 и p521 с арифметикой постоянного времени. Одноразовое
+//! число у P-256 и P-384 детерминированное по RFC 6979, у P-521 крейт пока
+//! берёт его из генератора случайных чисел ОС.
+
+use p256::ecdsa::signature::{Signer, Verifier};
+use std::error::Error;
+use zeroize::Zeroizing;
+
+/// Имена кривых в SSH
+pub(crate) const CURVES: [&str; 3] = ["nistp256", "nistp384", "nistp521"];
+
+pub(crate) enum EcdsaKey {
+    P256(p256::ecdsa::SigningKey),
+    P384(p384::ecdsa::SigningKey),
+    P521(p521::ecdsa::SigningKey),
+}
+
+// Подпись сразу проверяется открытым ключом, чтобы не отдать испорченную
+macro_rules! sign_checked {
+    ($curve:ident, $key:expr, $message:expr) => {{
+        let signature: $curve::ecdsa::Signature = $key.try_sign($message)?;
+        $curve::ecdsa::VerifyingKey::from($key)
+            .verify($message, &signature)
+            .map_err(|_| "Подпись ECDSA не прошла проверку: ключ повреждён")?;
+        let (r, s) = signature.split_bytes();
+        (r.to_vec(), s.to_vec())
+    }};
+}
+
+macro_rules! public_point {
+    ($curve:ident, $key:expr) => {
+        $curve::ecdsa::VerifyingKey::from($key).to_encoded_point(false).as_bytes().to_vec()
+    };
+}
+
+impl EcdsaKey {
+    /// Закрытый ключ кривой `curve` из числа SSH (big-endian, без ведущих нулей).
+    pub(crate) fn from_private(curve: &str, private: &[u8]) -> Result<Self, Box<dyn Error>> {
+        let size = match curve {
+            "nistp256" => 32,
+            "nistp384" => 48,
+            "nistp521" => 66,
+            _ => return Err(format!("Кривая {} не поддерживается", curve).into()),
+        };
+        let invalid = || format!("Некорректный закрытый ключ ECDSA ({})", curve);
+        if private.len() > size {
+            return Err(invalid().into());
+        }
+        let mut bytes = Zeroizing::new(vec![0u8; size]);
+        bytes[size - private.len()..].copy_from_slice(private);
+        // Ноль и числа не меньше порядка группы отвергают сами крейты
+        let key = match curve {
+            "nistp256" => EcdsaKey::P256(p256::ecdsa::SigningKey::from_slice(&bytes).map_err(|_| invalid())?),
+            "nistp384" => EcdsaKey::P384(p384::ecdsa::SigningKey::from_slice(&bytes).map_err(|_| invalid())?),
+            _ => EcdsaKey::P521(p521::ecdsa::SigningKey::from_slice(&bytes).map_err(|_| invalid())?),
+        };
+        Ok(key)
+    }
+
+    /// Имя кривой в SSH.
+    pub(crate) fn curve(&self) -> &'static str {
+        match self {
+            EcdsaKey::P256(_) => CURVES[0],
+            EcdsaKey::P384(_) => CURVES[1],
+            EcdsaKey::P521(_) => CURVES[2],
+        }
+    }
+
+    pub(crate) fn bits(&self) -> usize {
+        match self {
+            EcdsaKey::P256(_) => 256,
+            EcdsaKey::P384(_) => 384,
+            EcdsaKey::P521(_) => 521,
+        }
+    }
+
+    /// Открытый ключ: `04 ‖ x ‖ y`.
+    pub(crate) fn public_key(&self) -> Vec<u8> {
+        match self {
+            EcdsaKey::P256(key) => public_point!(p256, key),
+            EcdsaKey::P384(key) => public_point!(p384, key),
+            EcdsaKey::P521(key) => public_point!(p521, key),
+        }
+    }
+
+    /// Подпись сообщения (хеш — SHA-256, SHA-384 или SHA-512 по кривой):
+    /// числа r и s в big-endian.
+    pub(crate) fn sign(&self, message: &[u8]) -> Result<(Vec<u8>, Vec<u8>), Box<dyn Error>> {
+        Ok(match self {
+            EcdsaKey::P256(key) => sign_checked!(p256, key, message),
+            EcdsaKey::P384(key) => sign_checked!(p384, key, message),
+            EcdsaKey::P521(key) => sign_checked!(p521, key, message),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ssh::unhex;
+
+    // RFC 6979, A.2.6: сообщение «sample», SHA-384
+    #[test]
+    fn p384_matches_rfc6979_vector() {
+        let private = "6B9D3DAD2E1B8C1C05B19875B6659F4DE23C3B667BF297BA9AA47740787137D896D5724E4C70A825F872C9EA60D2EDF5";
+        let key = EcdsaKey::from_private("nistp384", &unhex(private)).unwrap();
+        assert_eq!(key.curve(), "nistp384");
+        let (r, s) = key.sign(b"sample").unwrap();
+        assert_eq!(
+            r,
+            unhex("94EDBB92A5ECB8AAD4736E56C691916B3F88140666CE9FA73D64C4EA95AD133C81A648152E44ACF96E36DD1E80FABE46")
+        );
+        assert_eq!(
+            s,
+            unhex("99EF4AEB15F178CEA1FE40DB2603138F130E740A19624526203B6351D0A3A94FA329C145786E679E7B82C71A38628AC8")
+        );
+    }
+
+    // RFC 6979, A.2.7: у P-521 одноразовое число случайное, поэтому вектор
+    // проверяется открытым ключом, а своя подпись — той же проверкой
+    #[test]
+    fn p521_agrees_with_rfc6979_vector() {
+        let private = "00FAD06DAA62BA3B25D2FB40133DA757205DE67F5BB0018FEE8C86E1B68C7E75CAA896EB32F1F47C70855836A6D16FCC1466F6
+                       D8FBEC67DB89EC0C08B0E996B83538";
+        let key = EcdsaKey::from_private("nistp521", &unhex(private)).unwrap();
+        let EcdsaKey::P521(signing) = &key else {
+            panic!("ожидался ключ P-521");
+        };
+        let verifying = p521::ecdsa::VerifyingKey::from(signing);
+        let verify = |r: &[u8], s: &[u8]| {
+            let signature = p521::ecdsa::Signature::from_slice(&[r, s].concat()).unwrap();
+            verifying.verify(b"sample", &signature).is_ok()
+        };
+
+        let r = unhex(
+            "00C328FAFCBD79DD77850370C46325D987CB525569FB63C5D3BC53950E6D4C5F174E25A1EE9017B5D450606ADD152B534931D7
+             D4E8455CC91F9B15BF05EC36E377FA",
+        );
+        let s = unhex(
+            "00617CCE7CF5064806C467F678D3B4080D6F1CC50AF26CA209417308281B68AF282623EAA63E5B5C0723D8B8C37FF0777B1A20
+             F8CCB1DCCC43997F1EE0E44DA4A67A",
+        );
+        assert!(verify(&r, &s));
+        assert!(!verify(&s, &r));
+
+        let (r, s) = key.sign(b"sample").unwrap();
+        assert_eq!((r.len(), s.len()), (66, 66));
+        assert!(verify(&r, &s));
+    }
+
+    #[test]
+    fn short_private_key_is_left_padded() {
+        // Число SSH без ведущих нулей: у ключа P-521 из RFC 6979 старший байт нулевой
+        let private = unhex(
+            "FAD06DAA62BA3B25D2FB40133DA757205DE67F5BB0018FEE8C86E1B68C7E75CAA896EB32F1F47C70855836A6D16FCC1466F6D8F
+             BEC67DB89EC0C08B0E996B83538",
+        );
+        let key = EcdsaKey::from_private("nistp521", &private).unwrap();
+        assert_eq!(key.bits(), 521);
+        assert_eq!(key.public_key().len(), 1 + 2 * 66);
+        assert_eq!(key.public_key()[0], 0x04);
+    }
+
+    #[test]
+    fn rejects_out_of_range_private_keys() {
+        assert!(EcdsaKey::from_private("nistp256", &[]).is_err());
+        assert!(EcdsaKey::from_private("nistp256", &[0xFF; 32]).is_err());
+        assert!(EcdsaKey::from_private("nistp256", &[1; 33]).is_err());
+        assert!(EcdsaKey::from_private("nistp192", &[1]).is_err());
+    }
+}
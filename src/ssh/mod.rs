@@ -0,0 +1,53 @@
+//! SSH-ключи в хранилище и встроенный ssh-agent.
+//!
+//! Закрытые ключи лежат в записях в формате OpenSSH без парольной фразы:
+//! их защищает шифрование хранилища. Агент hiho открывает рядом со своим
+//! сокетом второй — с протоколом ssh-agent, и отдаёт ключи, пока хранилище
+//! открыто. После блокировки, в том числе автоматической, список ключей
+//! пуст.
+//!
+//! Подписи Ed25519, RSA (ssh-rsa, rsa-sha2-256, rsa-sha2-512) и ECDSA
+//! (nistp256, nistp384, nistp521) считают крейты ed25519-dalek, rsa
+//! и p256/p384/p521. Агент всё равно слушает только сокет с доступом
+//! для своего пользователя.
+
+#[cfg(unix)]
+pub mod agent;
+mod ecdsa;
+pub mod key;
+mod rsa;
+mod wire;
+
+pub use key::PrivateKey;
+
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+
+/// Хеш-функции подписей RSA в SSH.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Hash {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl Hash {
+    pub(crate) fn digest(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Hash::Sha1 => Sha1::digest(data).to_vec(),
+            Hash::Sha256 => Sha256::digest(data).to_vec(),
+            Hash::Sha512 => Sha512::digest(data).to_vec(),
+        }
+    }
+}
+
+/// Байты из шестнадцатеричной строки тестовых векторов; пробелы и переводы
+/// строк пропускаются.
+#[cfg(test)]
+pub(crate) fn unhex(text: &str) -> Vec<u8> {
+    let digits: Vec<u8> = text.bytes().filter(|byte| !byte.is_ascii_whitespace()).collect();
+    digits
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).unwrap(), 16).unwrap())
+        .collect()
+}
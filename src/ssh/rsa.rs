@@ -0,0 +1,56 @@
+//! Подпись RSA PKCS#1 v1.5 (RFC 8017, раздел 8.2) через крейт rsa.
+//! Возведение в степень идёт с ослеплением, чтобы время не зависело
+//! от закрытого ключа.
+
+use super::Hash;
+use ::rsa::{BigUint, Pkcs1v15Sign, RsaPrivateKey};
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+use std::error::Error;
+
+/// Ключ из чисел OpenSSH. Крейт проверяет, что p·q = n и что d подходит к e.
+pub(crate) fn private_key(n: &[u8], e: &[u8], d: &[u8], p: &[u8], q: &[u8]) -> Result<RsaPrivateKey, Box<dyn Error>> {
+    let number = BigUint::from_bytes_be;
+    RsaPrivateKey::from_components(number(n), number(e), number(d), vec![number(p), number(q)])
+        .map_err(|e| format!("Некорректный ключ RSA: {}", e).into())
+}
+
+/// Подпись `message`. Результат проверяется открытым ключом, чтобы не
+/// отдать испорченную подпись.
+pub(crate) fn sign(key: &RsaPrivateKey, hash: Hash, message: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let scheme = || match hash {
+        Hash::Sha1 => Pkcs1v15Sign::new::<Sha1>(),
+        Hash::Sha256 => Pkcs1v15Sign::new::<Sha256>(),
+        Hash::Sha512 => Pkcs1v15Sign::new::<Sha512>(),
+    };
+    let digest = hash.digest(message);
+    let signature = key.sign_with_rng(&mut rand::thread_rng(), scheme(), &digest)?;
+    key.to_public_key()
+        .verify(scheme(), &digest, &signature)
+        .map_err(|_| "Подпись RSA не прошла проверку: ключ повреждён")?;
+    Ok(signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::rsa::traits::{PrivateKeyParts, PublicKeyParts};
+
+    #[test]
+    fn rejects_inconsistent_components() {
+        let key = RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap();
+        let n = key.n().to_bytes_be();
+        let e = key.e().to_bytes_be();
+        let d = key.d().to_bytes_be();
+        let p = key.primes()[0].to_bytes_be();
+        let q = key.primes()[1].to_bytes_be();
+        let restored = private_key(&n, &e, &d, &p, &q).unwrap();
+        let signature = sign(&restored, Hash::Sha256, b"sample").unwrap();
+        assert_eq!(signature.len(), 128);
+
+        // Простые множители от другого модуля
+        let other = RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap();
+        let other_p = other.primes()[0].to_bytes_be();
+        assert!(private_key(&n, &e, &d, &other_p, &q).is_err());
+    }
+}
@@ -0,0 +1,95 @@
+//! Двоичный формат SSH (RFC 4251, раздел 5): uint32 big-endian, строки
+//! с длиной впереди и mpint.
+
+use std::error::Error;
+
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    pub(crate) fn bytes(&mut self, len: usize) -> Result<&'a [u8], Box<dyn Error>> {
+        if self.data.len() < len {
+            return Err("Данные SSH обрываются раньше времени".into());
+        }
+        let (head, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(head)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, Box<dyn Error>> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, Box<dyn Error>> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub(crate) fn string(&mut self) -> Result<&'a [u8], Box<dyn Error>> {
+        let len = self.u32()? as usize;
+        self.bytes(len)
+    }
+
+    pub(crate) fn str(&mut self) -> Result<&'a str, Box<dyn Error>> {
+        Ok(std::str::from_utf8(self.string()?)?)
+    }
+
+    /// Неотрицательный mpint без ведущих нулей.
+    pub(crate) fn mpint(&mut self) -> Result<&'a [u8], Box<dyn Error>> {
+        let value = self.string()?;
+        if value.first().is_some_and(|byte| byte & 0x80 != 0) {
+            return Err("Отрицательное число в ключе SSH".into());
+        }
+        let start = value.iter().position(|byte| *byte != 0).unwrap_or(value.len());
+        Ok(&value[start..])
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Writer {
+    data: Vec<u8>,
+}
+
+impl Writer {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn u8(&mut self, value: u8) -> &mut Self {
+        self.data.push(value);
+        self
+    }
+
+    pub(crate) fn u32(&mut self, value: u32) -> &mut Self {
+        self.data.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    pub(crate) fn string(&mut self, value: &[u8]) -> &mut Self {
+        self.u32(value.len() as u32);
+        self.data.extend_from_slice(value);
+        self
+    }
+
+    /// Неотрицательное число из байтов big-endian.
+    pub(crate) fn mpint(&mut self, value: &[u8]) -> &mut Self {
+        let start = value.iter().position(|byte| *byte != 0).unwrap_or(value.len());
+        let value = &value[start..];
+        if value.first().is_some_and(|byte| byte & 0x80 != 0) {
+            self.u32(value.len() as u32 + 1).u8(0);
+            self.data.extend_from_slice(value);
+            self
+        } else {
+            self.string(value)
+        }
+    }
+
+    pub(crate) fn finish(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.data)
+    }
+}
@@ -0,0 +1,1398 @@
+use chrono::Datelike;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::OnceLock;
+
+// Встроенные словари: порядок строк задаёт ранг (чем выше, тем популярнее)
+const PASSWORDS_LIST: &str = include_str!("dictionaries/passwords.txt");
+const ENGLISH_LIST: &str = include_str!("dictionaries/english.txt");
+const NAMES_LIST: &str = include_str!("dictionaries/names.txt");
+
+const BRUTEFORCE_CARDINALITY: f64 = 10.0;
+const MIN_GUESSES_BEFORE_GROWING_SEQUENCE: f64 = 10_000.0;
+const MIN_SUBMATCH_GUESSES_SINGLE_CHAR: f64 = 10.0;
+const MIN_SUBMATCH_GUESSES_MULTI_CHAR: f64 = 50.0;
+const MIN_YEAR_SPACE: i32 = 20;
+const MAX_SEQUENCE_DELTA: i32 = 5;
+const MAX_L33T_COMBINATIONS: usize = 64;
+
+const QWERTY_LAYOUT: [&str; 4] = [
+    "`~ 1! 2@ 3# 4$ 5% 6^ 7& 8* 9( 0) -_ =+",
+    "qQ wW eE rR tT yY uU iI oO pP [{ ]} \\|",
+    "aA sS dD fF gG hH jJ kK lL ;: '\"",
+    "zZ xX cC vV bB nN mM ,< .> /?",
+];
+
+const KEYPAD_LAYOUT: [&str; 5] = [
+    "_ / * -",
+    "7 8 9 +",
+    "4 5 6",
+    "1 2 3",
+    "_ 0 .",
+];
+
+const L33T_TABLE: &[(char, &[char])] = &[
+    ('a', &['4', '@']),
+    ('b', &['8']),
+    ('c', &['(', '{', '[', '<']),
+    ('e', &['3']),
+    ('g', &['6', '9']),
+    ('i', &['1', '!', '|']),
+    ('l', &['1', '|', '7']),
+    ('o', &['0']),
+    ('s', &['$', '5']),
+    ('t', &['+', '7']),
+    ('x', &['%']),
+    ('z', &['2']),
+];
+
+/// Итоговая оценка пароля: 0 — очень слабый, 4 — очень сильный.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StrengthScore {
+    VeryWeak = 0,
+    Weak = 1,
+    Medium = 2,
+    Strong = 3,
+    VeryStrong = 4,
+}
+
+impl StrengthScore {
+    fn from_guesses(guesses: f64) -> Self {
+        const DELTA: f64 = 5.0;
+        if guesses < 1e3 + DELTA {
+            StrengthScore::VeryWeak
+        } else if guesses < 1e6 + DELTA {
+            StrengthScore::Weak
+        } else if guesses < 1e8 + DELTA {
+            StrengthScore::Medium
+        } else if guesses < 1e10 + DELTA {
+            StrengthScore::Strong
+        } else {
+            StrengthScore::VeryStrong
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            StrengthScore::VeryWeak => "Очень слабый",
+            StrengthScore::Weak => "Слабый",
+            StrengthScore::Medium => "Средний",
+            StrengthScore::Strong => "Сильный",
+            StrengthScore::VeryStrong => "Очень сильный",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dictionary {
+    Passwords,
+    English,
+    Names,
+    UserInputs,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Keyboard {
+    Qwerty,
+    Keypad,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MatchPattern {
+    Dictionary {
+        dictionary: Dictionary,
+        matched_word: String,
+        rank: usize,
+        reversed: bool,
+        l33t: bool,
+        substitutions: Vec<(char, char)>,
+    },
+    Spatial {
+        keyboard: Keyboard,
+        turns: usize,
+        shifted_count: usize,
+    },
+    Repeat {
+        base_token: String,
+        base_guesses: f64,
+        repeat_count: usize,
+    },
+    Sequence {
+        ascending: bool,
+    },
+    Year,
+    Date {
+        year: i32,
+        month: u32,
+        day: u32,
+        separator: Option<char>,
+    },
+    Bruteforce,
+}
+
+/// Найденный фрагмент пароля, позиции считаются в символах (не в байтах).
+#[derive(Debug, Clone)]
+pub struct PasswordMatch {
+    pub start: usize,
+    pub end: usize,
+    pub token: String,
+    pub pattern: MatchPattern,
+    pub guesses: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Feedback {
+    pub warning: Option<String>,
+    pub suggestions: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CrackTimes {
+    pub online_throttled: f64,
+    pub online_unthrottled: f64,
+    pub offline_slow_hashing: f64,
+    pub offline_fast_hashing: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct StrengthReport {
+    pub score: StrengthScore,
+    pub guesses: f64,
+    pub guesses_log10: f64,
+    pub entropy_bits: f64,
+    pub crack_times: CrackTimes,
+    pub sequence: Vec<PasswordMatch>,
+    pub feedback: Feedback,
+}
+
+/// Оценивает стойкость пароля в стиле zxcvbn: ищет словарные слова, клавиатурные
+/// ряды, повторы, последовательности и даты, затем выбирает самое «дешёвое»
+/// для атакующего разбиение пароля на фрагменты.
+///
+/// `user_inputs` — слова, связанные с записью (имя сервиса, логин), которые
+/// атакующий наверняка попробует первыми.
+pub fn estimate_strength(password: &str, user_inputs: &[&str]) -> StrengthReport {
+    let chars: Vec<char> = password.chars().collect();
+    let user_dictionary = build_user_dictionary(user_inputs);
+
+    let matches = omnimatch(&chars, &user_dictionary);
+    let (guesses, sequence) = most_guessable_match_sequence(&chars, matches, false);
+
+    let guesses = guesses.max(1.0);
+    let score = StrengthScore::from_guesses(guesses);
+    let feedback = build_feedback(score, &sequence);
+
+    StrengthReport {
+        score,
+        guesses,
+        guesses_log10: guesses.log10(),
+        entropy_bits: guesses.log2(),
+        crack_times: CrackTimes {
+            online_throttled: guesses / (100.0 / 3600.0),
+            online_unthrottled: guesses / 10.0,
+            offline_slow_hashing: guesses / 1e4,
+            offline_fast_hashing: guesses / 1e10,
+        },
+        sequence,
+        feedback,
+    }
+}
+
+/// Человекочитаемое время подбора.
+pub fn display_crack_time(seconds: f64) -> String {
+    const MINUTE: f64 = 60.0;
+    const HOUR: f64 = MINUTE * 60.0;
+    const DAY: f64 = HOUR * 24.0;
+    const MONTH: f64 = DAY * 31.0;
+    const YEAR: f64 = MONTH * 12.0;
+    const CENTURY: f64 = YEAR * 100.0;
+
+    if seconds < 1.0 {
+        "меньше секунды".to_string()
+    } else if seconds < MINUTE {
+        format!("{} сек", seconds.round())
+    } else if seconds < HOUR {
+        format!("{} мин", (seconds / MINUTE).round())
+    } else if seconds < DAY {
+        format!("{} ч", (seconds / HOUR).round())
+    } else if seconds < MONTH {
+        format!("{} дн", (seconds / DAY).round())
+    } else if seconds < YEAR {
+        format!("{} мес", (seconds / MONTH).round())
+    } else if seconds < CENTURY {
+        format!("{} лет", (seconds / YEAR).round())
+    } else {
+        "столетия".to_string()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Словари и клавиатурные графы
+// ---------------------------------------------------------------------------
+
+struct RankedDictionaries {
+    passwords: HashMap<&'static str, usize>,
+    english: HashMap<&'static str, usize>,
+    names: HashMap<&'static str, usize>,
+    max_word_len: usize,
+}
+
+fn ranked(list: &'static str) -> HashMap<&'static str, usize> {
+    list.lines()
+        .map(str::trim)
+        .filter(|word| !word.is_empty())
+        .enumerate()
+        .map(|(i, word)| (word, i + 1))
+        .collect()
+}
+
+fn dictionaries() -> &'static RankedDictionaries {
+    static DICTIONARIES: OnceLock<RankedDictionaries> = OnceLock::new();
+    DICTIONARIES.get_or_init(|| {
+        let passwords = ranked(PASSWORDS_LIST);
+        let english = ranked(ENGLISH_LIST);
+        let names = ranked(NAMES_LIST);
+        let max_word_len = passwords
+            .keys()
+            .chain(english.keys())
+            .chain(names.keys())
+            .map(|word| word.chars().count())
+            .max()
+            .unwrap_or(0);
+        RankedDictionaries { passwords, english, names, max_word_len }
+    })
+}
+
+fn build_user_dictionary(user_inputs: &[&str]) -> HashMap<String, usize> {
+    let mut dictionary = HashMap::new();
+    let words = user_inputs
+        .iter()
+        .flat_map(|input| {
+            let input = input.to_lowercase();
+            // Логин вида user@example.com разбиваем на части
+            let mut parts: Vec<String> = input
+                .split(|c: char| !c.is_alphanumeric())
+                .filter(|part| part.chars().count() > 1)
+                .map(str::to_string)
+                .collect();
+            parts.push(input);
+            parts
+        })
+        .filter(|word| !word.is_empty());
+
+    for (i, word) in words.enumerate() {
+        dictionary.entry(word).or_insert(i + 1);
+    }
+    dictionary
+}
+
+struct AdjacencyGraph {
+    // Для каждого символа — соседние клавиши по направлениям (None — края клавиатуры)
+    neighbours: HashMap<char, Vec<Option<[char; 2]>>>,
+    starting_positions: f64,
+    average_degree: f64,
+}
+
+fn build_graph(layout: &[&str], slanted: bool) -> AdjacencyGraph {
+    let mut positions: HashMap<(i32, i32), [char; 2]> = HashMap::new();
+    for (y, row) in layout.iter().enumerate() {
+        for (x, token) in row.split(' ').enumerate() {
+            if token == "_" {
+                continue;
+            }
+            let mut chars = token.chars();
+            let unshifted = chars.next().unwrap_or(' ');
+            let shifted = chars.next().unwrap_or(unshifted);
+            // Для наклонной раскладки первая клавиша строк с буквами стоит под «1»
+            let x = if slanted && y > 0 { x + 1 } else { x };
+            positions.insert((x as i32, y as i32), [unshifted, shifted]);
+        }
+    }
+
+    let mut neighbours = HashMap::new();
+    for (&(x, y), key) in &positions {
+        let coords: Vec<(i32, i32)> = if slanted {
+            vec![(x - 1, y), (x, y - 1), (x + 1, y - 1), (x + 1, y), (x, y + 1), (x - 1, y + 1)]
+        } else {
+            vec![
+                (x - 1, y),
+                (x - 1, y - 1),
+                (x, y - 1),
+                (x + 1, y - 1),
+                (x + 1, y),
+                (x + 1, y + 1),
+                (x, y + 1),
+                (x - 1, y + 1),
+            ]
+        };
+        let adjacent: Vec<Option<[char; 2]>> = coords
+            .iter()
+            .map(|coord| positions.get(coord).copied())
+            .collect();
+        for c in key.iter() {
+            neighbours.insert(*c, adjacent.clone());
+        }
+    }
+
+    let starting_positions = neighbours.len() as f64;
+    let total_degree: usize = neighbours
+        .values()
+        .map(|adjacent| adjacent.iter().filter(|n| n.is_some()).count())
+        .sum();
+    let average_degree = total_degree as f64 / starting_positions;
+
+    AdjacencyGraph {
+        neighbours,
+        starting_positions,
+        average_degree,
+    }
+}
+
+fn keyboard_graphs() -> &'static [(Keyboard, AdjacencyGraph); 2] {
+    static GRAPHS: OnceLock<[(Keyboard, AdjacencyGraph); 2]> = OnceLock::new();
+    GRAPHS.get_or_init(|| {
+        [
+            (Keyboard::Qwerty, build_graph(&QWERTY_LAYOUT, true)),
+            (Keyboard::Keypad, build_graph(&KEYPAD_LAYOUT, false)),
+        ]
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Поиск фрагментов
+// ---------------------------------------------------------------------------
+
+fn omnimatch(chars: &[char], user_dictionary: &HashMap<String, usize>) -> Vec<PasswordMatch> {
+    let mut matches = Vec::new();
+    matches.extend(dictionary_match(chars, user_dictionary));
+    matches.extend(reverse_dictionary_match(chars, user_dictionary));
+    matches.extend(l33t_match(chars, user_dictionary));
+    matches.extend(spatial_match(chars));
+    matches.extend(repeat_match(chars, user_dictionary));
+    matches.extend(sequence_match(chars));
+    matches.extend(year_match(chars));
+    matches.extend(date_match(chars));
+    matches.sort_by_key(|m| (m.start, m.end));
+    matches
+}
+
+fn token(chars: &[char], start: usize, end: usize) -> String {
+    chars[start..=end].iter().collect()
+}
+
+fn dictionary_match(chars: &[char], user_dictionary: &HashMap<String, usize>) -> Vec<PasswordMatch> {
+    let lower: Vec<char> = chars
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+    let dicts = dictionaries();
+    let max_len = user_dictionary
+        .keys()
+        .map(|word| word.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(dicts.max_word_len);
+    let mut matches = Vec::new();
+
+    for start in 0..lower.len() {
+        for end in start..lower.len().min(start + max_len) {
+            let word: String = lower[start..=end].iter().collect();
+            let found = [
+                (Dictionary::Passwords, dicts.passwords.get(word.as_str()).copied()),
+                (Dictionary::English, dicts.english.get(word.as_str()).copied()),
+                (Dictionary::Names, dicts.names.get(word.as_str()).copied()),
+                (Dictionary::UserInputs, user_dictionary.get(&word).copied()),
+            ];
+            for (dictionary, rank) in found {
+                if let Some(rank) = rank {
+                    matches.push(PasswordMatch {
+                        start,
+                        end,
+                        token: token(chars, start, end),
+                        pattern: MatchPattern::Dictionary {
+                            dictionary,
+                            matched_word: word.clone(),
+                            rank,
+                            reversed: false,
+                            l33t: false,
+                            substitutions: Vec::new(),
+                        },
+                        guesses: 0.0,
+                    });
+                }
+            }
+        }
+    }
+    matches
+}
+
+fn reverse_dictionary_match(chars: &[char], user_dictionary: &HashMap<String, usize>) -> Vec<PasswordMatch> {
+    let reversed: Vec<char> = chars.iter().rev().copied().collect();
+    let len = chars.len();
+    dictionary_match(&reversed, user_dictionary)
+        .into_iter()
+        .filter(|m| m.end > m.start)
+        .map(|mut m| {
+            let (start, end) = (len - 1 - m.end, len - 1 - m.start);
+            m.start = start;
+            m.end = end;
+            m.token = token(chars, start, end);
+            if let MatchPattern::Dictionary { reversed, .. } = &mut m.pattern {
+                *reversed = true;
+            }
+            m
+        })
+        .collect()
+}
+
+fn l33t_substitution_tables(chars: &[char]) -> Vec<Vec<(char, char)>> {
+    // Для каждого l33t-символа пароля перечисляем возможные буквы
+    let mut candidates: Vec<(char, Vec<char>)> = Vec::new();
+    for &c in chars {
+        if candidates.iter().any(|(sub, _)| *sub == c) {
+            continue;
+        }
+        let letters: Vec<char> = L33T_TABLE
+            .iter()
+            .filter(|(_, subs)| subs.contains(&c))
+            .map(|(letter, _)| *letter)
+            .collect();
+        if !letters.is_empty() {
+            candidates.push((c, letters));
+        }
+    }
+
+    let mut tables: Vec<Vec<(char, char)>> = vec![Vec::new()];
+    for (sub, letters) in candidates {
+        let mut next = Vec::new();
+        for table in &tables {
+            for &letter in &letters {
+                let mut extended = table.clone();
+                extended.push((sub, letter));
+                next.push(extended);
+            }
+        }
+        next.truncate(MAX_L33T_COMBINATIONS);
+        tables = next;
+    }
+    tables.retain(|table| !table.is_empty());
+    tables
+}
+
+fn l33t_match(chars: &[char], user_dictionary: &HashMap<String, usize>) -> Vec<PasswordMatch> {
+    let mut matches: Vec<PasswordMatch> = Vec::new();
+    let mut seen: HashSet<(usize, usize, Dictionary, String)> = HashSet::new();
+
+    for table in l33t_substitution_tables(chars) {
+        let translated: Vec<char> = chars
+            .iter()
+            .map(|c| {
+                table
+                    .iter()
+                    .find(|(sub, _)| sub == c)
+                    .map(|(_, letter)| *letter)
+                    .unwrap_or(*c)
+            })
+            .collect();
+
+        for mut m in dictionary_match(&translated, user_dictionary) {
+            let original = token(chars, m.start, m.end);
+            // Одиночные символы вроде «1» → «i» дают слишком много ложных совпадений
+            if original.chars().count() <= 1 {
+                continue;
+            }
+            let used: Vec<(char, char)> = table
+                .iter()
+                .filter(|(sub, _)| original.contains(*sub))
+                .copied()
+                .collect();
+            if used.is_empty() {
+                continue;
+            }
+            let key = (m.start, m.end, dictionary_of(&m.pattern), matched_word_of(&m.pattern));
+            if !seen.insert(key) {
+                continue;
+            }
+            m.token = original;
+            if let MatchPattern::Dictionary { l33t, substitutions, .. } = &mut m.pattern {
+                *l33t = true;
+                *substitutions = used;
+            }
+            matches.push(m);
+        }
+    }
+    matches
+}
+
+fn dictionary_of(pattern: &MatchPattern) -> Dictionary {
+    match pattern {
+        MatchPattern::Dictionary { dictionary, .. } => *dictionary,
+        _ => Dictionary::Passwords,
+    }
+}
+
+fn matched_word_of(pattern: &MatchPattern) -> String {
+    match pattern {
+        MatchPattern::Dictionary { matched_word, .. } => matched_word.clone(),
+        _ => String::new(),
+    }
+}
+
+fn spatial_match(chars: &[char]) -> Vec<PasswordMatch> {
+    let mut matches = Vec::new();
+    for (keyboard, graph) in keyboard_graphs().iter() {
+        let mut i = 0;
+        while i + 1 < chars.len() {
+            let mut j = i + 1;
+            let mut last_direction: Option<usize> = None;
+            let mut turns = 0;
+            let mut shifted_count = if *keyboard == Keyboard::Qwerty && is_shifted(chars[i]) { 1 } else { 0 };
+
+            loop {
+                let prev = chars[j - 1];
+                let mut found = false;
+                if j < chars.len() {
+                    let current = chars[j];
+                    if let Some(adjacent) = graph.neighbours.get(&prev) {
+                        for (direction, key) in adjacent.iter().enumerate() {
+                            if let Some(key) = key {
+                                if let Some(position) = key.iter().position(|c| *c == current) {
+                                    found = true;
+                                    if position == 1 && key[0] != key[1] {
+                                        shifted_count += 1;
+                                    }
+                                    if last_direction != Some(direction) {
+                                        turns += 1;
+                                        last_direction = Some(direction);
+                                    }
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                }
+
+                if found {
+                    j += 1;
+                } else {
+                    if j - i > 2 {
+                        matches.push(PasswordMatch {
+                            start: i,
+                            end: j - 1,
+                            token: token(chars, i, j - 1),
+                            pattern: MatchPattern::Spatial {
+                                keyboard: *keyboard,
+                                turns,
+                                shifted_count,
+                            },
+                            guesses: 0.0,
+                        });
+                    }
+                    i = j;
+                    break;
+                }
+            }
+        }
+    }
+    matches
+}
+
+fn is_shifted(c: char) -> bool {
+    c.is_uppercase() || "~!@#$%^&*()_+{}|:\"<>?".contains(c)
+}
+
+fn repeat_match(chars: &[char], user_dictionary: &HashMap<String, usize>) -> Vec<PasswordMatch> {
+    let mut matches = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        // Ищем самое длинное повторение, при равной длине — с самой короткой основой
+        let mut best: Option<(usize, usize)> = None;
+        for base_len in 1..=(chars.len() - i) / 2 {
+            let base = &chars[i..i + base_len];
+            let mut count = 1;
+            while i + (count + 1) * base_len <= chars.len()
+                && &chars[i + count * base_len..i + (count + 1) * base_len] == base
+            {
+                count += 1;
+            }
+            if count >= 2 {
+                let total = base_len * count;
+                if best.is_none_or(|(best_len, best_count)| total > best_len * best_count) {
+                    best = Some((base_len, count));
+                }
+            }
+        }
+
+        match best {
+            Some((base_len, count)) => {
+                let end = i + base_len * count - 1;
+                let base: Vec<char> = chars[i..i + base_len].to_vec();
+                let base_matches = omnimatch(&base, user_dictionary);
+                let (base_guesses, _) = most_guessable_match_sequence(&base, base_matches, true);
+                matches.push(PasswordMatch {
+                    start: i,
+                    end,
+                    token: token(chars, i, end),
+                    pattern: MatchPattern::Repeat {
+                        base_token: base.iter().collect(),
+                        base_guesses,
+                        repeat_count: count,
+                    },
+                    guesses: 0.0,
+                });
+                i = end + 1;
+            }
+            None => i += 1,
+        }
+    }
+    matches
+}
+
+fn sequence_match(chars: &[char]) -> Vec<PasswordMatch> {
+    let mut matches = Vec::new();
+    if chars.len() < 2 {
+        return matches;
+    }
+
+    let mut push = |start: usize, end: usize, delta: i32| {
+        if (end - start > 1 || delta.abs() == 1) && delta != 0 && delta.abs() <= MAX_SEQUENCE_DELTA {
+            matches.push(PasswordMatch {
+                start,
+                end,
+                token: token(chars, start, end),
+                pattern: MatchPattern::Sequence { ascending: delta > 0 },
+                guesses: 0.0,
+            });
+        }
+    };
+
+    let mut i = 0;
+    let mut last_delta: Option<i32> = None;
+    for k in 1..chars.len() {
+        let delta = chars[k] as i32 - chars[k - 1] as i32;
+        let last = *last_delta.get_or_insert(delta);
+        if delta == last {
+            continue;
+        }
+        let j = k - 1;
+        push(i, j, last);
+        i = j;
+        last_delta = Some(delta);
+    }
+    push(i, chars.len() - 1, last_delta.unwrap_or(0));
+    matches
+}
+
+fn reference_year() -> i32 {
+    chrono::Local::now().year()
+}
+
+fn year_match(chars: &[char]) -> Vec<PasswordMatch> {
+    let mut matches = Vec::new();
+    for start in 0..chars.len().saturating_sub(3) {
+        let candidate = &chars[start..start + 4];
+        if !candidate.iter().all(|c| c.is_ascii_digit()) {
+            continue;
+        }
+        let before_digit = start > 0 && chars[start - 1].is_ascii_digit();
+        let after_digit = start + 4 < chars.len() && chars[start + 4].is_ascii_digit();
+        if before_digit || after_digit {
+            continue;
+        }
+        let text: String = candidate.iter().collect();
+        if text.starts_with("19") || text.starts_with("20") {
+            matches.push(PasswordMatch {
+                start,
+                end: start + 3,
+                token: text,
+                pattern: MatchPattern::Year,
+                guesses: 0.0,
+            });
+        }
+    }
+    matches
+}
+
+fn two_to_four_digit_year(year: i32) -> i32 {
+    if year > 99 {
+        year
+    } else if year > 50 {
+        year + 1900
+    } else {
+        year + 2000
+    }
+}
+
+fn map_date(a: i32, b: i32, c: i32) -> Option<(i32, u32, u32)> {
+    // Перебираем порядки «год-месяц-день», «день-месяц-год», «месяц-день-год»
+    let orders = [(a, b, c), (c, b, a), (c, a, b), (a, c, b)];
+    let reference = reference_year();
+    let mut best: Option<(i32, u32, u32)> = None;
+    for (year, month, day) in orders {
+        let full_year = two_to_four_digit_year(year);
+        if !(1000..=2050).contains(&full_year) {
+            continue;
+        }
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            continue;
+        }
+        let closer = best.is_none_or(|(best_year, _, _)| {
+            (full_year - reference).abs() < (best_year - reference).abs()
+        });
+        if closer {
+            best = Some((full_year, month as u32, day as u32));
+        }
+    }
+    best
+}
+
+fn date_match(chars: &[char]) -> Vec<PasswordMatch> {
+    let mut matches: Vec<PasswordMatch> = Vec::new();
+
+    // Даты без разделителей: 4–8 цифр подряд
+    for start in 0..chars.len() {
+        for end in start + 3..(start + 8).min(chars.len()) {
+            let digits = &chars[start..=end];
+            if !digits.iter().all(|c| c.is_ascii_digit()) {
+                break;
+            }
+            let text: String = digits.iter().collect();
+            let len = text.len();
+            let splits: &[(usize, usize)] = match len {
+                4 => &[(1, 2), (2, 3)],
+                5 => &[(1, 3), (2, 3)],
+                6 => &[(1, 2), (2, 4), (4, 5)],
+                7 => &[(1, 3), (2, 3), (4, 5), (4, 6)],
+                8 => &[(2, 4), (4, 6)],
+                _ => &[],
+            };
+            let mut best: Option<(i32, u32, u32)> = None;
+            for &(k, l) in splits {
+                let parse = |s: &str| s.parse::<i32>().ok();
+                if let (Some(a), Some(b), Some(c)) = (parse(&text[..k]), parse(&text[k..l]), parse(&text[l..])) {
+                    if let Some(date) = map_date(a, b, c) {
+                        let reference = reference_year();
+                        if best.is_none_or(|(y, _, _)| (date.0 - reference).abs() < (y - reference).abs()) {
+                            best = Some(date);
+                        }
+                    }
+                }
+            }
+            if let Some((year, month, day)) = best {
+                matches.push(PasswordMatch {
+                    start,
+                    end,
+                    token: text,
+                    pattern: MatchPattern::Date { year, month, day, separator: None },
+                    guesses: 0.0,
+                });
+            }
+        }
+    }
+
+    // Даты с разделителями: 1.2.1990, 1990-01-02 и т.п.
+    const SEPARATORS: &str = " /\\_.-";
+    for start in 0..chars.len() {
+        for end in start + 5..(start + 10).min(chars.len()) {
+            let text: String = chars[start..=end].iter().collect();
+            let separator = match text.chars().find(|c| SEPARATORS.contains(*c)) {
+                Some(separator) => separator,
+                None => continue,
+            };
+            let parts: Vec<&str> = text.split(separator).collect();
+            if parts.len() != 3 || parts.iter().any(|p| p.is_empty() || !p.chars().all(|c| c.is_ascii_digit())) {
+                continue;
+            }
+            let lens: Vec<usize> = parts.iter().map(|p| p.len()).collect();
+            if lens[0] > 4 || lens[1] > 2 || lens[2] > 4 {
+                continue;
+            }
+            let values: Vec<i32> = parts.iter().filter_map(|p| p.parse().ok()).collect();
+            if let Some((year, month, day)) = map_date(values[0], values[1], values[2]) {
+                matches.push(PasswordMatch {
+                    start,
+                    end,
+                    token: text,
+                    pattern: MatchPattern::Date { year, month, day, separator: Some(separator) },
+                    guesses: 0.0,
+                });
+            }
+        }
+    }
+
+    // Отбрасываем даты, целиком вложенные в другие даты
+    let spans: Vec<(usize, usize)> = matches.iter().map(|m| (m.start, m.end)).collect();
+    matches.retain(|m| {
+        !spans
+            .iter()
+            .any(|&(start, end)| (start, end) != (m.start, m.end) && start <= m.start && end >= m.end)
+    });
+    matches
+}
+
+// ---------------------------------------------------------------------------
+// Оценка числа попыток
+// ---------------------------------------------------------------------------
+
+fn n_choose_k(n: usize, k: usize) -> f64 {
+    if k > n {
+        return 0.0;
+    }
+    if k == 0 {
+        return 1.0;
+    }
+    let mut result = 1.0;
+    for d in 1..=k {
+        result *= (n - k + d) as f64;
+        result /= d as f64;
+    }
+    result
+}
+
+fn factorial(n: usize) -> f64 {
+    (2..=n).fold(1.0, |acc, i| acc * i as f64)
+}
+
+fn estimate_guesses(m: &PasswordMatch, password_len: usize) -> f64 {
+    let token_len = m.token.chars().count();
+    let min_guesses = if token_len < password_len {
+        if token_len == 1 {
+            MIN_SUBMATCH_GUESSES_SINGLE_CHAR
+        } else {
+            MIN_SUBMATCH_GUESSES_MULTI_CHAR
+        }
+    } else {
+        1.0
+    };
+
+    let guesses = match &m.pattern {
+        MatchPattern::Bruteforce => {
+            let guesses = BRUTEFORCE_CARDINALITY.powi(token_len as i32);
+            let min = if token_len == 1 {
+                MIN_SUBMATCH_GUESSES_SINGLE_CHAR + 1.0
+            } else {
+                MIN_SUBMATCH_GUESSES_MULTI_CHAR + 1.0
+            };
+            if guesses.is_finite() { guesses.max(min) } else { f64::MAX }
+        }
+        MatchPattern::Dictionary { rank, reversed, l33t, substitutions, .. } => {
+            let mut guesses = *rank as f64 * uppercase_variations(&m.token);
+            if *l33t {
+                guesses *= l33t_variations(&m.token, substitutions);
+            }
+            if *reversed {
+                guesses *= 2.0;
+            }
+            guesses
+        }
+        MatchPattern::Spatial { keyboard, turns, shifted_count } => {
+            spatial_guesses(*keyboard, token_len, *turns, *shifted_count)
+        }
+        MatchPattern::Repeat { base_guesses, repeat_count, .. } => base_guesses * *repeat_count as f64,
+        MatchPattern::Sequence { ascending } => {
+            let first = m.token.chars().next().unwrap_or('a');
+            let mut base: f64 = if "aAzZ019".contains(first) {
+                4.0
+            } else if first.is_ascii_digit() {
+                10.0
+            } else {
+                26.0
+            };
+            if !ascending {
+                base *= 2.0;
+            }
+            base * token_len as f64
+        }
+        MatchPattern::Year => {
+            let year: i32 = m.token.parse().unwrap_or(0);
+            (year - reference_year()).abs().max(MIN_YEAR_SPACE) as f64
+        }
+        MatchPattern::Date { year, separator, .. } => {
+            let year_space = (year - reference_year()).abs().max(MIN_YEAR_SPACE) as f64;
+            let mut guesses = year_space * 365.0;
+            if separator.is_some() {
+                guesses *= 4.0;
+            }
+            guesses
+        }
+    };
+    guesses.max(min_guesses)
+}
+
+fn uppercase_variations(word: &str) -> f64 {
+    let chars: Vec<char> = word.chars().collect();
+    let upper = chars.iter().filter(|c| c.is_uppercase()).count();
+    let lower = chars.iter().filter(|c| c.is_lowercase()).count();
+    if upper == 0 {
+        return 1.0;
+    }
+    let first_upper_only = chars.first().is_some_and(|c| c.is_uppercase()) && upper == 1;
+    let last_upper_only = chars.last().is_some_and(|c| c.is_uppercase()) && upper == 1;
+    if first_upper_only || last_upper_only || lower == 0 {
+        return 2.0;
+    }
+    (1..=upper.min(lower)).map(|i| n_choose_k(upper + lower, i)).sum()
+}
+
+fn l33t_variations(word: &str, substitutions: &[(char, char)]) -> f64 {
+    let lower = word.to_lowercase();
+    let mut variations = 1.0;
+    for (sub, letter) in substitutions {
+        let subbed = lower.chars().filter(|c| c == sub).count();
+        let unsubbed = lower.chars().filter(|c| c == letter).count();
+        if subbed == 0 || unsubbed == 0 {
+            variations *= 2.0;
+        } else {
+            let possibilities: f64 = (1..=subbed.min(unsubbed)).map(|i| n_choose_k(subbed + unsubbed, i)).sum();
+            variations *= possibilities;
+        }
+    }
+    variations
+}
+
+fn spatial_guesses(keyboard: Keyboard, length: usize, turns: usize, shifted_count: usize) -> f64 {
+    let graph = &keyboard_graphs()
+        .iter()
+        .find(|(k, _)| *k == keyboard)
+        .expect("граф клавиатуры всегда построен")
+        .1;
+    let (s, d) = (graph.starting_positions, graph.average_degree);
+
+    let mut guesses = 0.0;
+    for i in 2..=length {
+        let possible_turns = turns.min(i - 1);
+        for j in 1..=possible_turns {
+            guesses += n_choose_k(i - 1, j - 1) * s * d.powi(j as i32);
+        }
+    }
+
+    if shifted_count > 0 {
+        let unshifted = length.saturating_sub(shifted_count);
+        if unshifted == 0 {
+            guesses *= 2.0;
+        } else {
+            let variations: f64 = (1..=shifted_count.min(unshifted))
+                .map(|i| n_choose_k(shifted_count + unshifted, i))
+                .sum();
+            guesses *= variations;
+        }
+    }
+    guesses
+}
+
+// ---------------------------------------------------------------------------
+// Поиск оптимального разбиения (динамическое программирование как в zxcvbn)
+// ---------------------------------------------------------------------------
+
+#[derive(Default, Clone)]
+struct Optimal {
+    // Для каждой длины последовательности: последний фрагмент, произведение попыток и итог
+    m: BTreeMap<usize, PasswordMatch>,
+    pi: BTreeMap<usize, f64>,
+    g: BTreeMap<usize, f64>,
+}
+
+fn most_guessable_match_sequence(
+    chars: &[char],
+    matches: Vec<PasswordMatch>,
+    exclude_additive: bool,
+) -> (f64, Vec<PasswordMatch>) {
+    let n = chars.len();
+    if n == 0 {
+        return (1.0, Vec::new());
+    }
+
+    let mut matches_by_end: Vec<Vec<PasswordMatch>> = vec![Vec::new(); n];
+    for m in matches {
+        let end = m.end;
+        matches_by_end[end].push(m);
+    }
+    for list in &mut matches_by_end {
+        list.sort_by_key(|m| m.start);
+    }
+
+    let mut optimal: Vec<Optimal> = vec![Optimal::default(); n];
+
+    let update = |optimal: &mut Vec<Optimal>, mut m: PasswordMatch, length: usize| {
+        let k = m.end;
+        m.guesses = estimate_guesses(&m, n);
+        let mut pi = m.guesses;
+        if length > 1 {
+            pi *= optimal[m.start - 1].pi[&(length - 1)];
+        }
+        let mut g = factorial(length) * pi;
+        if !exclude_additive {
+            g += MIN_GUESSES_BEFORE_GROWING_SEQUENCE.powi(length as i32 - 1);
+        }
+        for (&competing_length, &competing_g) in &optimal[k].g {
+            if competing_length > length {
+                continue;
+            }
+            if competing_g <= g {
+                return;
+            }
+        }
+        optimal[k].g.insert(length, g);
+        optimal[k].m.insert(length, m);
+        optimal[k].pi.insert(length, pi);
+    };
+
+    let bruteforce = |start: usize, end: usize| PasswordMatch {
+        start,
+        end,
+        token: token(chars, start, end),
+        pattern: MatchPattern::Bruteforce,
+        guesses: 0.0,
+    };
+
+    for (k, ending_here) in matches_by_end.iter().enumerate() {
+        for m in ending_here.iter().cloned() {
+            if m.start > 0 {
+                let lengths: Vec<usize> = optimal[m.start - 1].m.keys().copied().collect();
+                for length in lengths {
+                    update(&mut optimal, m.clone(), length + 1);
+                }
+            } else {
+                update(&mut optimal, m, 1);
+            }
+        }
+
+        update(&mut optimal, bruteforce(0, k), 1);
+        for start in 1..=k {
+            let previous: Vec<(usize, bool)> = optimal[start - 1]
+                .m
+                .iter()
+                .map(|(&length, m)| (length, m.pattern == MatchPattern::Bruteforce))
+                .collect();
+            for (length, is_bruteforce) in previous {
+                // Два перебора подряд не имеют смысла — их покрывает один более длинный
+                if is_bruteforce {
+                    continue;
+                }
+                update(&mut optimal, bruteforce(start, k), length + 1);
+            }
+        }
+    }
+
+    // Восстанавливаем последовательность с конца
+    let mut sequence = Vec::new();
+    let last = &optimal[n - 1];
+    let (mut length, guesses) = last
+        .g
+        .iter()
+        .map(|(&l, &g)| (l, g))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .expect("перебор всегда покрывает пароль целиком");
+    let mut k = n as isize - 1;
+    while k >= 0 {
+        let m = optimal[k as usize].m[&length].clone();
+        k = m.start as isize - 1;
+        length -= 1;
+        sequence.push(m);
+    }
+    sequence.reverse();
+
+    (guesses.min(f64::MAX), sequence)
+}
+
+// ---------------------------------------------------------------------------
+// Подсказки
+// ---------------------------------------------------------------------------
+
+fn build_feedback(score: StrengthScore, sequence: &[PasswordMatch]) -> Feedback {
+    if sequence.is_empty() {
+        return Feedback {
+            warning: None,
+            suggestions: vec![
+                "Используйте несколько слов, избегайте распространённых фраз".to_string(),
+                "Не обязательно использовать символы, цифры или заглавные буквы".to_string(),
+            ],
+        };
+    }
+
+    if score > StrengthScore::Medium {
+        return Feedback::default();
+    }
+
+    let longest = sequence
+        .iter()
+        .max_by_key(|m| m.token.chars().count())
+        .expect("последовательность не пуста");
+
+    let mut feedback = match_feedback(longest, sequence.len() == 1);
+    feedback
+        .suggestions
+        .insert(0, "Добавьте ещё одно-два слова. Необычные слова лучше".to_string());
+    feedback
+}
+
+fn match_feedback(m: &PasswordMatch, is_sole_match: bool) -> Feedback {
+    let mut suggestions = Vec::new();
+    let warning = match &m.pattern {
+        MatchPattern::Dictionary { dictionary, rank, reversed, l33t, .. } => {
+            let starts_upper = m.token.chars().next().is_some_and(|c| c.is_uppercase());
+            let all_upper = m.token.chars().filter(|c| c.is_alphabetic()).all(|c| c.is_uppercase());
+            if starts_upper {
+                suggestions.push("Заглавная первая буква почти не помогает".to_string());
+            } else if all_upper && m.token.chars().any(|c| c.is_alphabetic()) {
+                suggestions.push("Пароль из одних заглавных так же легко угадать, как из строчных".to_string());
+            }
+            if *reversed && m.token.chars().count() >= 4 {
+                suggestions.push("Слова задом наперёд угадываются не сложнее обычных".to_string());
+            }
+            if *l33t {
+                suggestions.push("Замены вроде «@» вместо «a» почти не помогают".to_string());
+            }
+
+            match dictionary {
+                Dictionary::Passwords if is_sole_match && !l33t && !reversed => {
+                    if *rank <= 10 {
+                        Some("Это один из 10 самых распространённых паролей".to_string())
+                    } else if *rank <= 100 {
+                        Some("Это один из 100 самых распространённых паролей".to_string())
+                    } else {
+                        Some("Это очень распространённый пароль".to_string())
+                    }
+                }
+                Dictionary::Passwords => Some("Пароль похож на распространённый".to_string()),
+                Dictionary::English if is_sole_match => Some("Одно слово легко угадать".to_string()),
+                Dictionary::Names => Some("Имена и фамилии легко угадать".to_string()),
+                Dictionary::UserInputs => Some("Пароль содержит имя сервиса или логин".to_string()),
+                Dictionary::English => None,
+            }
+        }
+        MatchPattern::Spatial { turns, .. } => {
+            suggestions.push("Используйте более длинный клавиатурный узор с поворотами".to_string());
+            if *turns == 1 {
+                Some("Прямые ряды клавиш легко угадать".to_string())
+            } else {
+                Some("Короткие клавиатурные узоры легко угадать".to_string())
+            }
+        }
+        MatchPattern::Repeat { base_token, .. } => {
+            suggestions.push("Избегайте повторяющихся слов и символов".to_string());
+            if base_token.chars().count() == 1 {
+                Some("Повторы вида «aaa» легко угадать".to_string())
+            } else {
+                Some("Повторы вида «abcabcabc» угадать лишь немногим сложнее, чем «abc»".to_string())
+            }
+        }
+        MatchPattern::Sequence { .. } => {
+            suggestions.push("Избегайте последовательностей".to_string());
+            Some("Последовательности вида «abc» или «6543» легко угадать".to_string())
+        }
+        MatchPattern::Year => {
+            suggestions.push("Избегайте недавних лет".to_string());
+            suggestions.push("Избегайте лет, связанных с вами".to_string());
+            Some("Недавние годы легко угадать".to_string())
+        }
+        MatchPattern::Date { .. } => {
+            suggestions.push("Избегайте дат и лет, связанных с вами".to_string());
+            Some("Даты часто легко угадать".to_string())
+        }
+        MatchPattern::Bruteforce => None,
+    };
+
+    Feedback { warning, suggestions }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(text: &str) -> Vec<char> {
+        text.chars().collect()
+    }
+
+    fn spans(matches: &[PasswordMatch]) -> Vec<(usize, usize)> {
+        matches.iter().map(|m| (m.start, m.end)).collect()
+    }
+
+    #[test]
+    fn dictionary_match_finds_ranked_words_in_any_case() {
+        let matches = dictionary_match(&chars("xPassWordx"), &HashMap::new());
+        let password = matches
+            .iter()
+            .find(|m| m.pattern == MatchPattern::Dictionary {
+                dictionary: Dictionary::Passwords,
+                matched_word: "password".to_string(),
+                rank: 2,
+                reversed: false,
+                l33t: false,
+                substitutions: Vec::new(),
+            })
+            .unwrap();
+        assert_eq!((password.start, password.end), (1, 8));
+        assert_eq!(password.token, "PassWord");
+    }
+
+    #[test]
+    fn reverse_dictionary_match_maps_back_positions() {
+        let matches = reverse_dictionary_match(&chars("drowssap1"), &HashMap::new());
+        let found = matches.iter().find(|m| m.token == "drowssap").unwrap();
+        assert_eq!((found.start, found.end), (0, 7));
+        assert!(matches!(found.pattern, MatchPattern::Dictionary { reversed: true, .. }));
+    }
+
+    #[test]
+    fn l33t_match_records_substitutions() {
+        let matches = l33t_match(&chars("p@ssw0rd"), &HashMap::new());
+        let found = matches.iter().find(|m| matched_word_of(&m.pattern) == "password").unwrap();
+        assert_eq!(found.token, "p@ssw0rd");
+        let MatchPattern::Dictionary { l33t, substitutions, .. } = &found.pattern else {
+            panic!("ожидалось словарное совпадение");
+        };
+        assert!(l33t);
+        assert!(substitutions.contains(&('@', 'a')));
+        assert!(substitutions.contains(&('0', 'o')));
+    }
+
+    #[test]
+    fn spatial_match_finds_keyboard_rows_and_keypad() {
+        let matches = spatial_match(&chars("qwerty"));
+        let row = matches.iter().find(|m| m.token == "qwerty").unwrap();
+        assert!(matches!(row.pattern, MatchPattern::Spatial { keyboard: Keyboard::Qwerty, turns: 1, shifted_count: 0 }));
+
+        let matches = spatial_match(&chars("QWErty"));
+        assert!(matches
+            .iter()
+            .any(|m| matches!(m.pattern, MatchPattern::Spatial { shifted_count: 3, .. })));
+
+        let matches = spatial_match(&chars("7896"));
+        assert!(matches
+            .iter()
+            .any(|m| matches!(m.pattern, MatchPattern::Spatial { keyboard: Keyboard::Keypad, .. })));
+        assert!(spatial_match(&chars("qp")).is_empty());
+    }
+
+    #[test]
+    fn repeat_match_prefers_longest_repetition() {
+        let matches = repeat_match(&chars("abcabcabcx"), &HashMap::new());
+        assert_eq!(spans(&matches), [(0, 8)]);
+        let MatchPattern::Repeat { base_token, repeat_count, base_guesses } = &matches[0].pattern else {
+            panic!("ожидался повтор");
+        };
+        assert_eq!(base_token, "abc");
+        assert_eq!(*repeat_count, 3);
+        assert!(*base_guesses > 1.0);
+
+        let matches = repeat_match(&chars("zzzz"), &HashMap::new());
+        assert!(matches!(&matches[0].pattern, MatchPattern::Repeat { base_token, repeat_count: 4, .. } if base_token == "z"));
+    }
+
+    #[test]
+    fn sequence_match_finds_runs_in_both_directions() {
+        let matches = sequence_match(&chars("abcd"));
+        assert_eq!(spans(&matches), [(0, 3)]);
+        assert_eq!(matches[0].pattern, MatchPattern::Sequence { ascending: true });
+
+        let matches = sequence_match(&chars("9753"));
+        assert_eq!(matches[0].pattern, MatchPattern::Sequence { ascending: false });
+
+        // Шаг больше MAX_SEQUENCE_DELTA последовательностью не считается
+        assert!(sequence_match(&chars("aks")).is_empty());
+        assert!(sequence_match(&chars("a")).is_empty());
+    }
+
+    #[test]
+    fn year_match_skips_years_inside_longer_numbers() {
+        assert_eq!(spans(&year_match(&chars("abc1987"))), [(3, 6)]);
+        assert_eq!(year_match(&chars("2024x"))[0].token, "2024");
+        assert!(year_match(&chars("119870")).is_empty());
+        assert!(year_match(&chars("1787")).is_empty());
+    }
+
+    #[test]
+    fn date_match_parses_with_and_without_separators() {
+        let matches = date_match(&chars("13.05.1991"));
+        assert_eq!(matches.len(), 1);
+        assert_eq!(
+            matches[0].pattern,
+            MatchPattern::Date { year: 1991, month: 5, day: 13, separator: Some('.') }
+        );
+
+        let matches = date_match(&chars("19910513"));
+        assert!(matches
+            .iter()
+            .any(|m| m.pattern == MatchPattern::Date { year: 1991, month: 5, day: 13, separator: None }));
+
+        assert!(date_match(&chars("55.55.55")).is_empty());
+    }
+
+    #[test]
+    fn bruteforce_covers_unmatched_characters() {
+        let report = estimate_strength("#k%", &[]);
+        assert!(report.sequence.iter().any(|m| m.pattern == MatchPattern::Bruteforce));
+        let covered: usize = report.sequence.iter().map(|m| m.end - m.start + 1).sum();
+        assert_eq!(covered, 3);
+    }
+
+    #[test]
+    fn crack_times_follow_guesses() {
+        let report = estimate_strength("correcthorsebatterystaple", &[]);
+        let times = &report.crack_times;
+        assert_eq!(times.online_throttled, report.guesses * 36.0);
+        assert_eq!(times.online_unthrottled, report.guesses / 10.0);
+        assert_eq!(times.offline_slow_hashing, report.guesses / 1e4);
+        assert_eq!(times.offline_fast_hashing, report.guesses / 1e10);
+        assert!((report.guesses_log10 - report.guesses.log10()).abs() < 1e-9);
+        assert!((report.entropy_bits - report.guesses.log2()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn scores_common_and_random_passwords() {
+        let weak = estimate_strength("password", &[]);
+        assert_eq!(weak.score, StrengthScore::VeryWeak);
+        assert_eq!(
+            weak.feedback.warning.as_deref(),
+            Some("Это один из 10 самых распространённых паролей")
+        );
+
+        let strong = estimate_strength("t9#Vq!2mZ$w8Lr@x", &[]);
+        assert_eq!(strong.score, StrengthScore::VeryStrong);
+        assert!(strong.feedback.warning.is_none());
+    }
+
+    #[test]
+    fn user_inputs_are_penalised() {
+        let password = "zhukovskiy1988";
+        let plain = estimate_strength(password, &[]);
+        let known = estimate_strength(password, &["Zhukovskiy", "mail@example.com"]);
+        assert!(known.guesses < plain.guesses);
+        assert!(known
+            .sequence
+            .iter()
+            .any(|m| matches!(m.pattern, MatchPattern::Dictionary { dictionary: Dictionary::UserInputs, .. })));
+
+        let dictionary = build_user_dictionary(&["alice@example.com"]);
+        assert!(dictionary.contains_key("alice"));
+        assert!(dictionary.contains_key("example"));
+        assert!(dictionary.contains_key("alice@example.com"));
+    }
+
+    #[test]
+    fn length_is_counted_in_characters() {
+        // 4 символа кириллицы — 8 байт в UTF-8
+        let report = estimate_strength("жщыф", &[]);
+        let end = report.sequence.iter().map(|m| m.end).max().unwrap();
+        assert_eq!(end, 3);
+        let ascii = estimate_strength("#k%&", &[]);
+        assert_eq!(report.guesses, ascii.guesses);
+    }
+
+    #[test]
+    fn empty_password() {
+        let report = estimate_strength("", &[]);
+        assert_eq!(report.score, StrengthScore::VeryWeak);
+        assert_eq!(report.guesses, 1.0);
+        assert!(report.sequence.is_empty());
+        assert_eq!(report.feedback.suggestions.len(), 2);
+    }
+
+    #[test]
+    fn crack_time_display() {
+        assert_eq!(display_crack_time(0.5), "меньше секунды");
+        assert_eq!(display_crack_time(30.0), "30 сек");
+        assert_eq!(display_crack_time(7200.0), "2 ч");
+        assert_eq!(display_crack_time(1e12), "столетия");
+    }
+}
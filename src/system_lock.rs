@@ -0,0 +1,149 @@
+//! Сигналы системы, по которым хранилище нужно закрыть: переход в сон и
+//! блокировка экрана.
+//!
+//! В Linux сигналы приходят по D-Bus: `PrepareForSleep` и `Lock` от logind
+//! на системной шине, `ActiveChanged` от хранителя экрана (freedesktop,
+//! GNOME) на сессионной. Если шины или службы нет, источник просто не
+//! подключается. На других платформах сигналы пока не поддерживаются.
+
+use crate::auto_lock::AutoLockManager;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockSignal {
+    /// Система уходит в сон
+    Sleep,
+    /// Экран заблокирован
+    ScreenLock,
+}
+
+impl LockSignal {
+    pub fn label(&self) -> &'static str {
+        match self {
+            LockSignal::Sleep => "переход в сон",
+            LockSignal::ScreenLock => "блокировка экрана",
+        }
+    }
+}
+
+/// Подписывается на сигналы системы в фоновых потоках и вызывает
+/// `on_signal`, если блокировка по этому сигналу включена в настройках.
+/// Возвращает число подключённых источников.
+pub fn watch<F>(on_signal: F) -> usize
+where
+    F: Fn(LockSignal) + Send + Sync + 'static,
+{
+    let on_signal: Arc<dyn Fn(LockSignal) + Send + Sync> = Arc::new(move |signal| {
+        // Настройки читаем в момент сигнала, чтобы изменения действовали сразу
+        if AutoLockManager::get_config().unwrap_or_default().locks_on(signal) {
+            on_signal(signal);
+        }
+    });
+    platform::watch(on_signal)
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::LockSignal;
+    use std::sync::Arc;
+    use std::thread;
+    use zbus::blocking::{Connection, MessageIterator};
+    use zbus::{MatchRule, MessageType};
+
+    type Callback = Arc<dyn Fn(LockSignal) + Send + Sync>;
+
+    // Сигнал D-Bus и условие на его аргумент
+    struct Source {
+        interface: &'static str,
+        member: &'static str,
+        signal: LockSignal,
+        // У сигналов с bool-аргументом реагируем только на `true`
+        bool_arg: bool,
+    }
+
+    const SYSTEM_SOURCES: &[Source] = &[
+        Source {
+            interface: "org.freedesktop.login1.Manager",
+            member: "PrepareForSleep",
+            signal: LockSignal::Sleep,
+            bool_arg: true,
+        },
+        // `loginctl lock-session`; приходит для любой сессии, но лишняя
+        // блокировка безопаснее пропущенной
+        Source {
+            interface: "org.freedesktop.login1.Session",
+            member: "Lock",
+            signal: LockSignal::ScreenLock,
+            bool_arg: false,
+        },
+    ];
+
+    const SESSION_SOURCES: &[Source] = &[
+        Source {
+            interface: "org.freedesktop.ScreenSaver",
+            member: "ActiveChanged",
+            signal: LockSignal::ScreenLock,
+            bool_arg: true,
+        },
+        Source {
+            interface: "org.gnome.ScreenSaver",
+            member: "ActiveChanged",
+            signal: LockSignal::ScreenLock,
+            bool_arg: true,
+        },
+    ];
+
+    pub fn watch(on_signal: Callback) -> usize {
+        let mut connected = 0;
+        if let Ok(connection) = Connection::system() {
+            connected += listen(&connection, SYSTEM_SOURCES, &on_signal);
+        }
+        if let Ok(connection) = Connection::session() {
+            connected += listen(&connection, SESSION_SOURCES, &on_signal);
+        }
+        connected
+    }
+
+    fn listen(connection: &Connection, sources: &'static [Source], on_signal: &Callback) -> usize {
+        let mut connected = 0;
+        for source in sources {
+            let Ok(messages) = subscribe(connection, source) else {
+                continue;
+            };
+            let on_signal = Arc::clone(on_signal);
+            let spawned = thread::Builder::new()
+                .name(format!("hiho-{}", source.member))
+                .spawn(move || {
+                    for message in messages.flatten() {
+                        let fire = !source.bool_arg || message.body::<bool>().unwrap_or(false);
+                        if fire {
+                            on_signal(source.signal);
+                        }
+                    }
+                });
+            if spawned.is_ok() {
+                connected += 1;
+            }
+        }
+        connected
+    }
+
+    fn subscribe(connection: &Connection, source: &Source) -> zbus::Result<MessageIterator> {
+        let rule = MatchRule::builder()
+            .msg_type(MessageType::Signal)
+            .interface(source.interface)?
+            .member(source.member)?
+            .build();
+        MessageIterator::for_match_rule(rule, connection, None)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod platform {
+    use super::LockSignal;
+    use std::sync::Arc;
+
+    pub fn watch(_on_signal: Arc<dyn Fn(LockSignal) + Send + Sync>) -> usize {
+        0
+    }
+}
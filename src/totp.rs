@@ -0,0 +1,140 @@
+//! Одноразовые коды TOTP (RFC 6238) из секрета записи.
+//!
+//! Секрет хранится либо как строка base32, либо как ссылка
+//! `otpauth://totp/...?secret=...&digits=...&period=...&algorithm=...`.
+
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+use std::error::Error;
+use std::time::{SystemTime, UNIX_EPOCH};
+use zeroize::Zeroizing;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+pub struct Totp {
+    secret: Zeroizing<Vec<u8>>,
+    pub digits: u32,
+    /// Период смены кода в секундах
+    pub period: u64,
+    pub algorithm: Algorithm,
+}
+
+impl Totp {
+    /// Разбирает секрет base32 или ссылку otpauth://.
+    pub fn parse(value: &str) -> Result<Self, Box<dyn Error>> {
+        let value = value.trim();
+        let mut totp = Totp {
+            secret: Zeroizing::new(Vec::new()),
+            digits: 6,
+            period: 30,
+            algorithm: Algorithm::Sha1,
+        };
+
+        let Some(rest) = value.strip_prefix("otpauth://") else {
+            totp.secret = decode_base32(value)?;
+            return Ok(totp);
+        };
+        if !rest.to_lowercase().starts_with("totp/") {
+            return Err("Поддерживаются только ссылки otpauth://totp/".into());
+        }
+        let query = rest.split_once('?').map(|(_, query)| query).unwrap_or("");
+        for pair in query.split('&') {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            match key.to_lowercase().as_str() {
+                "secret" => totp.secret = decode_base32(&value.replace("%3D", "="))?,
+                "digits" => {
+                    totp.digits = value
+                        .parse()
+                        .ok()
+                        .filter(|digits| (6..=10).contains(digits))
+                        .ok_or_else(|| format!("Некорректное число цифр TOTP: {}", value))?
+                }
+                "period" => {
+                    totp.period = value
+                        .parse()
+                        .ok()
+                        .filter(|period| *period > 0)
+                        .ok_or_else(|| format!("Некорректный период TOTP: {}", value))?
+                }
+                "algorithm" => {
+                    totp.algorithm = match value.to_uppercase().as_str() {
+                        "SHA1" => Algorithm::Sha1,
+                        "SHA256" => Algorithm::Sha256,
+                        "SHA512" => Algorithm::Sha512,
+                        _ => return Err(format!("Неизвестный алгоритм TOTP: {}", value).into()),
+                    }
+                }
+                _ => {}
+            }
+        }
+        if totp.secret.is_empty() {
+            return Err("В ссылке otpauth нет секрета".into());
+        }
+        Ok(totp)
+    }
+
+    /// Код для момента `unix_time` (секунды с 1970 года).
+    pub fn code_at(&self, unix_time: u64) -> String {
+        let counter = (unix_time / self.period).to_be_bytes();
+        let digest = match self.algorithm {
+            Algorithm::Sha1 => hmac::<Hmac<Sha1>>(&self.secret, &counter),
+            Algorithm::Sha256 => hmac::<Hmac<Sha256>>(&self.secret, &counter),
+            Algorithm::Sha512 => hmac::<Hmac<Sha512>>(&self.secret, &counter),
+        };
+        // Динамическое усечение из RFC 4226
+        let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+        let binary = u32::from_be_bytes([
+            digest[offset] & 0x7f,
+            digest[offset + 1],
+            digest[offset + 2],
+            digest[offset + 3],
+        ]);
+        let code = u64::from(binary) % 10u64.pow(self.digits);
+        format!("{:0width$}", code, width = self.digits as usize)
+    }
+
+    /// Текущий код и сколько секунд он ещё действует.
+    pub fn current(&self) -> (String, u64) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        (self.code_at(now), self.period - now % self.period)
+    }
+}
+
+fn hmac<M: Mac + hmac::digest::KeyInit>(key: &[u8], message: &[u8]) -> Zeroizing<Vec<u8>> {
+    let mut mac = <M as Mac>::new_from_slice(key).expect("HMAC принимает ключ любой длины");
+    mac.update(message);
+    Zeroizing::new(mac.finalize().into_bytes().to_vec())
+}
+
+/// Декодирует base32 (RFC 4648) без учёта регистра, пробелов и `=`.
+fn decode_base32(value: &str) -> Result<Zeroizing<Vec<u8>>, Box<dyn Error>> {
+    let mut bytes = Zeroizing::new(Vec::with_capacity(value.len() * 5 / 8));
+    let mut buffer: u64 = 0;
+    let mut bits = 0;
+    for c in value.chars().filter(|c| !c.is_whitespace() && *c != '=' && *c != '-') {
+        let digit = match c.to_ascii_uppercase() {
+            c @ 'A'..='Z' => c as u64 - 'A' as u64,
+            c @ '2'..='7' => c as u64 - '2' as u64 + 26,
+            _ => return Err("Секрет TOTP должен быть в base32".into()),
+        };
+        buffer = (buffer << 5) | digit;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+    if bytes.is_empty() {
+        return Err("Пустой секрет TOTP".into());
+    }
+    Ok(bytes)
+}
@@ -0,0 +1,318 @@
+//! Получение мастер-пароля и открытие хранилища для команд CLI.
+//!
+//! Источники по приоритету: `--password-file`, `--password-fd`,
+//! `--password-stdin`, переменная `HIHO_PASSWORD`, запрос в терминале.
+//! Из файла, дескриптора и stdin берётся первая строка без перевода строки.
+//!
+//! Если запущен агент (`hiho agent start`), хранилище открывает он: пароль
+//! спрашивается только когда агент заблокирован, а записи и изменения
+//! передаются через его сокет.
+//!
+//! Если хранилище запомнено (`hiho remember enable`) и пароль не задан
+//! явно, вместо запроса пароля берётся ключ из хранилища ключей ОС.
+
+use crate::cli::PasswordArgs;
+#[cfg(unix)]
+use hiho::agent::AgentClient;
+use hiho::keystore;
+use hiho::remember::RememberManager;
+use hiho::Vault;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader, IsTerminal, Read};
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use zeroize::Zeroizing;
+
+pub const PASSWORD_ENV: &str = "HIHO_PASSWORD";
+
+/// Мастер-пароль из первого заданного источника.
+pub fn master_password(args: &PasswordArgs, prompt: &str) -> Result<Zeroizing<String>, Box<dyn Error>> {
+    match password_source(args, env_password()) {
+        PasswordSource::File(path) => {
+            let file = File::open(path).map_err(|e| format!("Не удалось открыть файл пароля {}: {}", path.display(), e))?;
+            first_line(file)
+        }
+        PasswordSource::Fd(fd) => first_line(open_fd(fd)?),
+        PasswordSource::Stdin => {
+            let mut line = Zeroizing::new(String::new());
+            std::io::stdin().lock().read_line(&mut line)?;
+            Ok(trim_newline(line))
+        }
+        PasswordSource::Env(password) => {
+            eprintln!(
+                "⚠️  Мастер-пароль взят из {}: переменные окружения видны другим процессам пользователя",
+                PASSWORD_ENV
+            );
+            Ok(password)
+        }
+        PasswordSource::Prompt => Ok(Zeroizing::new(rpassword::prompt_password(prompt)?)),
+    }
+}
+
+// Откуда брать мастер-пароль
+#[derive(Debug, PartialEq)]
+enum PasswordSource<'a> {
+    File(&'a Path),
+    Fd(i32),
+    Stdin,
+    Env(Zeroizing<String>),
+    Prompt,
+}
+
+fn password_source(args: &PasswordArgs, env: Option<Zeroizing<String>>) -> PasswordSource<'_> {
+    if let Some(path) = &args.password_file {
+        PasswordSource::File(path)
+    } else if let Some(fd) = args.password_fd {
+        PasswordSource::Fd(fd)
+    } else if args.password_stdin {
+        PasswordSource::Stdin
+    } else if let Some(password) = env {
+        PasswordSource::Env(password)
+    } else {
+        PasswordSource::Prompt
+    }
+}
+
+fn env_password() -> Option<Zeroizing<String>> {
+    std::env::var(PASSWORD_ENV).ok().map(Zeroizing::new)
+}
+
+/// Секрет, который не должен попасть в историю команд и `ps`: первая
+/// строка stdin, если он перенаправлен, иначе запрос без отображения.
+pub fn secret_input(prompt: &str) -> Result<Zeroizing<String>, Box<dyn Error>> {
+    if std::io::stdin().is_terminal() {
+        Ok(Zeroizing::new(rpassword::prompt_password(prompt)?))
+    } else {
+        first_line(std::io::stdin().lock())
+    }
+}
+
+/// Открытое хранилище и место, куда сохранять изменения.
+pub struct OpenVault {
+    vault: Vault,
+    target: SaveTarget,
+}
+
+enum SaveTarget {
+    File(PathBuf),
+    #[cfg(unix)]
+    Agent(AgentClient, PathBuf),
+}
+
+impl OpenVault {
+    pub fn save(&mut self) -> Result<(), Box<dyn Error>> {
+        match &mut self.target {
+            SaveTarget::File(path) => self.vault.save_to_file(path),
+            #[cfg(unix)]
+            SaveTarget::Agent(client, path) => client.store(path, self.vault.get_entries().clone()),
+        }
+    }
+}
+
+impl Deref for OpenVault {
+    type Target = Vault;
+
+    fn deref(&self) -> &Vault {
+        &self.vault
+    }
+}
+
+impl DerefMut for OpenVault {
+    fn deref_mut(&mut self) -> &mut Vault {
+        &mut self.vault
+    }
+}
+
+/// Открывает хранилище через агент или, если его нет, запрашивает
+/// мастер-пароль и расшифровывает файл сам.
+pub fn unlock_vault(args: &PasswordArgs, vault_file: &str) -> Result<OpenVault, Box<dyn Error>> {
+    #[cfg(unix)]
+    if !args.no_agent {
+        if let Some(client) = AgentClient::connect() {
+            return unlock_with_agent(args, client, vault_file);
+        }
+    }
+    if let Some(key) = remembered_key(args, Path::new(vault_file)) {
+        match Vault::open_with_key(Path::new(vault_file), *key) {
+            Ok(vault) => {
+                return Ok(OpenVault {
+                    vault,
+                    target: SaveTarget::File(PathBuf::from(vault_file)),
+                })
+            }
+            Err(e) => eprintln!("⚠️  Запомненный ключ не подошёл ({}), нужен мастер-пароль", e),
+        }
+    }
+    let password = master_password(args, "Введите мастер-пароль: ")?;
+    Ok(OpenVault {
+        vault: Vault::open(Path::new(vault_file), &password)?,
+        target: SaveTarget::File(PathBuf::from(vault_file)),
+    })
+}
+
+#[cfg(unix)]
+fn unlock_with_agent(args: &PasswordArgs, mut client: AgentClient, vault_file: &str) -> Result<OpenVault, Box<dyn Error>> {
+    // Агент работает в другом каталоге, поэтому путь передаём абсолютный
+    let path = std::path::absolute(vault_file)?;
+    let entries = match client.entries(&path)? {
+        Some(entries) => entries,
+        None => {
+            unlock_agent(args, &mut client, &path, "Введите мастер-пароль (агент): ")?;
+            client.entries(&path)?.ok_or("Агент не открыл хранилище")?
+        }
+    };
+    Ok(OpenVault {
+        vault: Vault::detached(entries),
+        target: SaveTarget::Agent(client, path),
+    })
+}
+
+/// Открывает хранилище в агенте запомненным ключом или мастер-паролем.
+#[cfg(unix)]
+pub fn unlock_agent(args: &PasswordArgs, client: &mut AgentClient, path: &Path, prompt: &str) -> Result<(), Box<dyn Error>> {
+    if let Some(key) = remembered_key(args, path) {
+        match client.unlock_with_key(path, &key) {
+            Ok(()) => return Ok(()),
+            Err(e) => eprintln!("⚠️  Запомненный ключ не подошёл ({}), нужен мастер-пароль", e),
+        }
+    }
+    let password = master_password(args, prompt)?;
+    client.unlock(path, &password)
+}
+
+/// Запомненный ключ хранилища, если пароль не задан явно. Недоступное
+/// хранилище ключей — не ошибка: тогда спрашивается мастер-пароль.
+fn remembered_key(args: &PasswordArgs, vault: &Path) -> Option<Zeroizing<[u8; 32]>> {
+    if password_source(args, env_password()) != PasswordSource::Prompt {
+        return None;
+    }
+    let vault = std::path::absolute(vault).ok()?;
+    // Без запомненного ключа хранилище ключей ОС не трогаем
+    if !matches!(RememberManager::find(&vault), Ok(Some(_))) {
+        return None;
+    }
+    match keystore::platform().and_then(|store| RememberManager::vault_key(store.as_ref(), &vault)) {
+        Ok(key) => key,
+        Err(e) => {
+            eprintln!("⚠️  Запомненный ключ недоступен: {}", e);
+            None
+        }
+    }
+}
+
+fn first_line<R: Read>(reader: R) -> Result<Zeroizing<String>, Box<dyn Error>> {
+    let mut line = Zeroizing::new(String::new());
+    BufReader::new(reader).read_line(&mut line)?;
+    Ok(trim_newline(line))
+}
+
+fn trim_newline(mut line: Zeroizing<String>) -> Zeroizing<String> {
+    while line.ends_with(['\n', '\r']) {
+        line.pop();
+    }
+    line
+}
+
+#[cfg(unix)]
+fn open_fd(fd: i32) -> Result<File, Box<dyn Error>> {
+    // /dev/fd/N открывает тот же файл, что и унаследованный дескриптор
+    File::open(format!("/dev/fd/{}", fd))
+        .map_err(|e| format!("Не удалось прочитать дескриптор {}: {}", fd, e).into())
+}
+
+#[cfg(not(unix))]
+fn open_fd(_fd: i32) -> Result<File, Box<dyn Error>> {
+    Err("--password-fd поддерживается только в Unix".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Cli;
+    use clap::Parser;
+    use std::io::Write;
+
+    fn args() -> PasswordArgs {
+        PasswordArgs {
+            password_file: None,
+            password_fd: None,
+            password_stdin: false,
+            no_agent: true,
+        }
+    }
+
+    fn env(password: &str) -> Option<Zeroizing<String>> {
+        Some(Zeroizing::new(password.to_string()))
+    }
+
+    #[test]
+    fn sources_follow_priority() {
+        let file = PathBuf::from("pw.txt");
+        let mut args = PasswordArgs {
+            password_file: Some(file.clone()),
+            password_fd: Some(3),
+            password_stdin: true,
+            ..args()
+        };
+        assert_eq!(password_source(&args, env("env")), PasswordSource::File(&file));
+        args.password_file = None;
+        assert_eq!(password_source(&args, env("env")), PasswordSource::Fd(3));
+        args.password_fd = None;
+        assert_eq!(password_source(&args, env("env")), PasswordSource::Stdin);
+        args.password_stdin = false;
+        assert_eq!(password_source(&args, env("env")), PasswordSource::Env(Zeroizing::new("env".to_string())));
+        assert_eq!(password_source(&args, None), PasswordSource::Prompt);
+    }
+
+    #[test]
+    fn password_file_wins_and_only_first_line_is_used() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        write!(file, "первый пароль\r\nвторая строка\n").unwrap();
+        let args = PasswordArgs {
+            password_file: Some(file.path().to_path_buf()),
+            password_stdin: true,
+            ..args()
+        };
+        assert_eq!(master_password(&args, "").unwrap().as_str(), "первый пароль");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn password_fd_is_read() {
+        use std::os::unix::io::AsRawFd;
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(file, "from-fd").unwrap();
+        let reader = File::open(file.path()).unwrap();
+        let args = PasswordArgs {
+            password_fd: Some(reader.as_raw_fd()),
+            password_stdin: true,
+            ..args()
+        };
+        assert_eq!(master_password(&args, "").unwrap().as_str(), "from-fd");
+    }
+
+    #[test]
+    fn missing_password_file_is_an_error() {
+        let args = PasswordArgs {
+            password_file: Some(PathBuf::from("/nonexistent/hiho-password")),
+            ..args()
+        };
+        assert!(master_password(&args, "").is_err());
+    }
+
+    #[test]
+    fn conflicting_flags_are_rejected() {
+        for flags in [
+            &["--password-file", "pw", "--password-stdin"][..],
+            &["--password-file", "pw", "--password-fd", "3"],
+            &["--password-fd", "3", "--password-stdin"],
+        ] {
+            let argv = ["hiho"].iter().chain(flags).chain(&["list"]);
+            let error = Cli::try_parse_from(argv).err().expect("флаги должны конфликтовать");
+            assert_eq!(error.kind(), clap::error::ErrorKind::ArgumentConflict);
+        }
+        assert!(Cli::try_parse_from(["hiho", "--password-file", "pw", "--no-agent", "list"]).is_ok());
+    }
+}
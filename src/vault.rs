@@ -1,96 +1,204 @@
 use std::fs;
 use std::path::Path;
-use crate::entry::Entry;
-use crate::crypto::{encrypt, decrypt, derive_key};
+use crate::entry::{CustomField, Entry};
+use crate::crypto::{encrypt, decrypt, derive_key, EncryptedData};
+use crate::kdbx::{self, KdbxSettings};
+use crate::merge::{self, MergeReport, MergeStrategy};
+use crate::second_factor::SecondFactorManager;
 use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use zeroize::{Zeroize, Zeroizing};
 
 #[derive(Serialize, Deserialize, Debug)]
 struct VaultData {
     entries: Vec<Entry>,
 }
 
+/// Ключ контейнера хранилища из пароля.
+pub fn derive_vault_key(password: &str) -> Result<[u8; 32], Box<dyn std::error::Error>> {
+    derive_key(password, "hiho_salt_2024")
+}
+
+/// Ключ хранилища `path`: из пароля и, если он включён, второго фактора.
+pub fn derive_vault_key_for(path: &Path, password: &str) -> Result<[u8; 32], Box<dyn std::error::Error>> {
+    let key = Zeroizing::new(derive_vault_key(password)?);
+    match SecondFactorManager::find(&std::path::absolute(path)?)? {
+        Some(factor) => factor.mix(&key),
+        None => Ok(*key),
+    }
+}
+
+/// Шифрует записи в контейнер хранилища: JSON, AES-256-CBC, bincode.
+pub fn seal_entries(entries: &[Entry], key: &[u8; 32]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let vault_data = VaultData {
+        entries: entries.to_vec(),
+    };
+    let mut json_data = serde_json::to_vec(&vault_data)?;
+    let encrypted = encrypt(&json_data, key)?;
+    json_data.zeroize();
+    
+    Ok(bincode::serialize(&encrypted)
+        .map_err(|e| format!("Serialization error: {}", e))?)
+}
+
+/// Расшифровывает контейнер хранилища.
+pub fn open_entries(data: &[u8], key: &[u8; 32]) -> Result<Vec<Entry>, Box<dyn std::error::Error>> {
+    let encrypted: EncryptedData = bincode::deserialize(data)
+        .map_err(|e| format!("Deserialization error: {}", e))?;
+    let mut plaintext = decrypt(&encrypted, key)?;
+    let vault_data: Result<VaultData, _> = serde_json::from_slice(&plaintext);
+    plaintext.zeroize();
+    
+    Ok(vault_data.map_err(|e| format!("JSON parsing error: {}", e))?.entries)
+}
+
+/// Похож ли файл на контейнер хранилища: в начале длина шифротекста,
+/// за ним сам шифротекст и IV.
+pub fn is_container(header: &[u8], file_len: u64) -> bool {
+    header
+        .get(..8)
+        .and_then(|len| len.try_into().ok())
+        .map(u64::from_le_bytes)
+        .is_some_and(|len| len % 16 == 0 && len.checked_add(8 + 16) == Some(file_len))
+}
+
+/// Хранилище не расшифровалось: неверный мастер-пароль или повреждённый файл.
+#[derive(Debug)]
+pub struct UnlockError;
+
+impl std::fmt::Display for UnlockError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Неверный мастер-пароль или файл хранилища повреждён")
+    }
+}
+
+impl std::error::Error for UnlockError {}
+
 pub struct Vault {
     entries: Vec<Entry>,
-    master_password: String,
+    // None у копии записей, полученной от агента (её нельзя сохранить),
+    // и у хранилища, открытого запомненным ключом (сохраняется этим ключом)
+    master_password: Option<String>,
+    // Ключ vault.enc (Argon2 и второй фактор) выводится один раз на всё время жизни
+    key: OnceLock<[u8; 32]>,
+    // Параметры открытого файла .kdbx, чтобы сохранить его в том же виде
+    kdbx_settings: Option<KdbxSettings>,
 }
 
 impl Vault {
     pub fn new(password: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        println!("Vault::new: creating new vault");
         Ok(Vault {
             entries: Vec::new(),
-            master_password: password.to_string(),
+            master_password: Some(password.to_string()),
+            key: OnceLock::new(),
+            kdbx_settings: None,
         })
     }
 
-pub fn load_from_file(&mut self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
-    println!("Vault::load_from_file: path = {:?}", path);
-    if !path.exists() {
-        println!("Vault::load_from_file: file does not exist");
-        return Ok(());
+    /// Копия записей без мастер-пароля (например, полученная от агента).
+    /// Сохранить её в файл нельзя.
+    pub fn detached(entries: Vec<Entry>) -> Self {
+        Vault {
+            entries,
+            master_password: None,
+            key: OnceLock::new(),
+            kdbx_settings: None,
+        }
     }
 
-    println!("Vault::load_from_file: reading file");
-    let data = fs::read(path)?;
-    println!("Vault::load_from_file: file read, size = {}", data.len());
-    
-    let salt = "hiho_salt_2024";
-    println!("Vault::load_from_file: deriving key");
-    let key = derive_key(&self.master_password, salt)?;
-    println!("Vault::load_from_file: key derived");
-    
-    let encrypted: crate::crypto::EncryptedData = bincode::deserialize(&data)
-        .map_err(|e| format!("Deserialization error: {}", e))?;
-    println!("Vault::load_from_file: data deserialized");
-    
-    let plaintext = decrypt(&encrypted, &key)?;
-    println!("Vault::load_from_file: data decrypted, size = {}", plaintext.len());
-    
-    let vault_data: VaultData = serde_json::from_slice(&plaintext)
-        .map_err(|e| format!("JSON parsing error: {}", e))?;
-    println!("Vault::load_from_file: JSON parsed, entries count = {}", vault_data.entries.len());
-    
-    self.entries = vault_data.entries;
-    Ok(())
-}
+    /// Открывает хранилище; если файла ещё нет, оно будет пустым.
+    pub fn open(path: &Path, password: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut vault = Vault::new(password)?;
+        vault.load_from_file(path)?;
+        Ok(vault)
+    }
 
-    pub fn save_to_file(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
-        println!("Vault::save_to_file: path = {:?}", path);
-        let vault_data = VaultData {
-            entries: self.entries.clone(),
+    /// Открывает vault.enc готовым ключом (`derive_vault_key_for`) без
+    /// мастер-пароля — для входа по запомненному ключу. Файлы KeePass так
+    /// не открываются: их ключ выводится из пароля иначе.
+    pub fn open_with_key(path: &Path, key: [u8; 32]) -> Result<Self, Box<dyn std::error::Error>> {
+        if kdbx::is_kdbx(path) {
+            return Err("Файл KeePass открывается только мастер-паролем".into());
+        }
+        let mut vault = Vault {
+            entries: Vec::new(),
+            master_password: None,
+            key: OnceLock::from(key),
+            kdbx_settings: None,
         };
-        println!("Vault::save_to_file: vault data prepared, entries count = {}", vault_data.entries.len());
-        
-        let json_data = serde_json::to_vec(&vault_data)?;
-        println!("Vault::save_to_file: JSON serialized, size = {}", json_data.len());
+        vault.load_from_file(path)?;
+        Ok(vault)
+    }
+
+    pub fn load_from_file(&mut self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if !path.exists() {
+            return Ok(());
+        }
+
+        let data = fs::read(path)?;
         
-        let salt = "hiho_salt_2024";
-        println!("Vault::save_to_file: deriving key");
-        let key = derive_key(&self.master_password, salt)?;
-        println!("Vault::save_to_file: key derived");
+        if kdbx::is_kdbx(path) {
+            let database = kdbx::read(&data, self.password()?)?;
+            self.entries = database.entries;
+            self.kdbx_settings = Some(database.settings);
+            return Ok(());
+        }
         
-        let encrypted = encrypt(&json_data, &key)?;
-        println!("Vault::save_to_file: data encrypted");
+        let key = self.vault_key(path)?;
+        self.entries = open_entries(&data, &key).map_err(|_| UnlockError)?;
+        Ok(())
+    }
+
+    pub fn save_to_file(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if kdbx::is_kdbx(path) {
+            let settings = self.kdbx_settings.clone().unwrap_or_default();
+            let data = kdbx::write(&self.entries, self.password()?, &settings)?;
+            fs::write(path, data)?;
+            return Ok(());
+        }
+        let key = self.vault_key(path)?;
         
-        let serialized = bincode::serialize(&encrypted)
-            .map_err(|e| format!("Serialization error: {}", e))?;
-        println!("Vault::save_to_file: data serialized, size = {}", serialized.len());
+        let serialized = seal_entries(&self.entries, &key)?;
         
         fs::write(path, serialized)?;
-        println!("Vault::save_to_file: file written");
         Ok(())
     }
 
+    fn password(&self) -> Result<&str, Box<dyn std::error::Error>> {
+        self.master_password
+            .as_deref()
+            .ok_or_else(|| "Хранилище открыто без мастер-пароля и не может быть сохранено".into())
+    }
+
+    fn vault_key(&self, path: &Path) -> Result<[u8; 32], Box<dyn std::error::Error>> {
+        if let Some(key) = self.key.get() {
+            return Ok(*key);
+        }
+        let key = derive_vault_key_for(path, self.password()?)?;
+        let _ = self.key.set(key);
+        Ok(key)
+    }
+
+    /// Заменяет все записи (например, изменённой копией от клиента агента).
+    pub fn replace_entries(&mut self, entries: Vec<Entry>) {
+        for entry in &mut self.entries {
+            entry.zeroize_secrets();
+        }
+        self.entries = entries;
+    }
+
     pub fn add_entry(&mut self, entry: Entry) {
-        println!("Vault::add_entry: adding entry '{}'", entry.name);
         self.entries.push(entry);
     }
 
+    /// Добавляет импортированные записи, разрешая дубликаты по стратегии.
+    pub fn merge_entries(&mut self, incoming: Vec<Entry>, strategy: MergeStrategy) -> MergeReport {
+        merge::merge_entries(&mut self.entries, incoming, strategy)
+    }
+
     pub fn remove_entry(&mut self, index: usize) -> Option<Entry> {
         if index < self.entries.len() {
-            let entry = self.entries.remove(index);
-            println!("Vault::remove_entry: removed entry '{}'", entry.name);
-            Some(entry)
+            Some(self.entries.remove(index))
         } else {
             None
         }
@@ -101,20 +209,89 @@ pub fn load_from_file(&mut self, path: &Path) -> Result<(), Box<dyn std::error::
             return Err("Запись не найдена");
         }
         
+        let now = chrono::Utc::now();
+        let entry = &self.entries[index];
+        let changed = username.as_ref().is_some_and(|u| *u != entry.username)
+            || password.as_ref().is_some_and(|p| *p != entry.password);
+        if changed {
+            self.entries[index].push_history();
+        }
+        
         if let Some(new_username) = username {
-            println!("Vault::edit_entry: updating username for entry '{}'", self.entries[index].name);
             self.entries[index].username = new_username;
         }
         
         if let Some(new_password) = password {
-            println!("Vault::edit_entry: updating password for entry '{}'", self.entries[index].name);
+            // Дату смены пароля двигаем только при реальном изменении
+            if self.entries[index].password != new_password {
+                self.entries[index].password_changed_at = Some(now);
+            }
             self.entries[index].password = new_password;
         }
         
+        self.entries[index].updated_at = Some(now);
+        Ok(())
+    }
+
+    /// Задаёт последовательность автонабора; `None` — по умолчанию.
+    pub fn set_auto_type(&mut self, index: usize, sequence: Option<String>) -> Result<(), &'static str> {
+        let entry = self.entries.get_mut(index).ok_or("Запись не найдена")?;
+        if entry.auto_type != sequence {
+            entry.push_history();
+            entry.auto_type = sequence;
+            entry.updated_at = Some(chrono::Utc::now());
+        }
+        Ok(())
+    }
+
+    /// Включает или выключает подтверждение подписей SSH-ключом записи.
+    pub fn set_ssh_confirm(&mut self, index: usize, confirm: bool) -> Result<(), &'static str> {
+        let entry = self.entries.get_mut(index).ok_or("Запись не найдена")?;
+        let current = entry.ssh_key.as_ref().ok_or("В записи нет SSH-ключа")?.confirm;
+        if current != confirm {
+            entry.push_history();
+            if let Some(key) = &mut entry.ssh_key {
+                key.confirm = confirm;
+            }
+            entry.updated_at = Some(chrono::Utc::now());
+        }
+        Ok(())
+    }
+
+    pub fn rename_entry(&mut self, index: usize, name: String) -> Result<(), &'static str> {
+        let entry = self.entries.get_mut(index).ok_or("Запись не найдена")?;
+        if entry.name != name {
+            entry.push_history();
+            entry.name = name;
+            entry.updated_at = Some(chrono::Utc::now());
+        }
+        Ok(())
+    }
+
+    /// Заменяет дополнительные поля записи.
+    pub fn set_custom_fields(&mut self, index: usize, fields: Vec<CustomField>) -> Result<(), &'static str> {
+        let entry = self.entries.get_mut(index).ok_or("Запись не найдена")?;
+        if entry.custom_fields != fields {
+            entry.push_history();
+            entry.custom_fields = fields;
+            entry.updated_at = Some(chrono::Utc::now());
+        }
         Ok(())
     }
 
     pub fn get_entries(&self) -> &Vec<Entry> {
         &self.entries
     }
+}
+
+impl Drop for Vault {
+    fn drop(&mut self) {
+        self.master_password.zeroize();
+        if let Some(key) = self.key.get_mut() {
+            key.zeroize();
+        }
+        for entry in &mut self.entries {
+            entry.zeroize_secrets();
+        }
+    }
 }
\ No newline at end of file
@@ -31,7 +31,7 @@ goto :eof
 
 :: Тест 1: Инициализация
 echo 🔐 Тест 1: Инициализация хранилища
-echo %TEST_PASSWORD% | hiho.exe init >nul 2>&1
+echo %TEST_PASSWORD%| hiho.exe --password-stdin init >nul 2>&1
 if %errorlevel% equ 0 (
     call :log_result PASS "Инициализация хранилища"
 ) else (
@@ -40,14 +40,14 @@ if %errorlevel% equ 0 (
 
 :: Тест 2: Добавление записей
 echo ➕ Тест 2: Добавление записей
-echo %TEST_PASSWORD% | hiho.exe add -n "github.com" -u "dev@github.com" -p "MyGitHubPass123!" >nul 2>&1
+echo %TEST_PASSWORD%| hiho.exe --password-stdin add -n "github.com" -u "dev@github.com" -p "MyGitHubPass123!" >nul 2>&1
 if %errorlevel% equ 0 (
     call :log_result PASS "Добавление записи github.com"
 ) else (
     call :log_result FAIL "Добавление записи github.com"
 )
 
-echo %TEST_PASSWORD% | hiho.exe add -n "google.com" -u "user@gmail.com" --length 16 >nul 2>&1
+echo %TEST_PASSWORD%| hiho.exe --password-stdin add -n "google.com" -u "user@gmail.com" --length 16 >nul 2>&1
 if %errorlevel% equ 0 (
     call :log_result PASS "Добавление записи google.com с автогенерацией"
 ) else (
@@ -56,7 +56,7 @@ if %errorlevel% equ 0 (
 
 :: Тест 3: Просмотр записей
 echo 📋 Тест 3: Просмотр записей
-echo %TEST_PASSWORD% | hiho.exe list > test_output.txt 2>&1
+echo %TEST_PASSWORD%| hiho.exe --password-stdin list > test_output.txt 2>&1
 findstr /C:"github.com" test_output.txt >nul
 if %errorlevel% equ 0 (
     call :log_result PASS "Просмотр записей"
@@ -66,7 +66,7 @@ if %errorlevel% equ 0 (
 
 :: Тест 4: Поиск записей
 echo 🔍 Тест 4: Поиск записей
-echo %TEST_PASSWORD% | hiho.exe search "git" > test_output.txt 2>&1
+echo %TEST_PASSWORD%| hiho.exe --password-stdin search "git" > test_output.txt 2>&1
 findstr /C:"github.com" test_output.txt >nul
 if %errorlevel% equ 0 (
     call :log_result PASS "Поиск записей"
@@ -92,7 +92,7 @@ if %errorlevel% equ 0 (
 
 :: Тест 6: Экспорт данных
 echo 📤 Тест 6: Экспорт данных
-echo %TEST_PASSWORD% | hiho.exe export --file test_export.json >nul 2>&1
+echo %TEST_PASSWORD%| hiho.exe --password-stdin export --format json --plaintext --file test_export.json >nul 2>&1
 if exist test_export.json (
     call :log_result PASS "Экспорт в JSON"
 ) else (
@@ -108,6 +108,16 @@ if %errorlevel% equ 0 (
     call :log_result FAIL "Настройка автоблокировки"
 )
 
+:: Тест 8: Оценка стойкости пароля
+echo 🛡️ Тест 8: Оценка стойкости пароля
+hiho.exe strength "Password1!Password1!" > test_output.txt 2>&1
+findstr /C:"Слабый" test_output.txt >nul
+if %errorlevel% equ 0 (
+    call :log_result PASS "Оценка стойкости пароля"
+) else (
+    call :log_result FAIL "Оценка стойкости пароля"
+)
+
 :: Вывод результатов
 echo.
 echo ╔════════════════════════════════════════════════╗